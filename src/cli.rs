@@ -1,6 +1,10 @@
 use clap::{Parser as ClapParser, Subcommand};
 use std::path::PathBuf;
 
+use crate::ranking::{StemLanguage, RERANKERS};
+use crate::search::command_reranker::COMMAND_RERANKER_PREFIX;
+use crate::search::fuzzy::MAX_FUZZY_DISTANCE;
+
 #[derive(ClapParser, Debug)]
 #[command(
     author,
@@ -31,10 +35,56 @@ pub struct Args {
     #[arg(short = 'n', long = "exclude-filenames")]
     pub exclude_filenames: bool,
 
-    /// BM25 ranking for search results
-    #[arg(short = 'r', long = "reranker", default_value = "bm25", value_parser = ["bm25"])]
+    /// Ranking algorithm for search results: bm25, tfidf, bm25f (field-weighted BM25),
+    /// hybrid (BM25 blended with file-level match statistics) or cmd:<program> (an external
+    /// program that scores the candidates it reads as JSON from stdin)
+    #[arg(short = 'r', long = "reranker", default_value = "bm25", value_parser = parse_reranker)]
     pub reranker: String,
 
+    /// Score multiplier for blocks that define a searched symbol (e.g. the struct or function
+    /// named by the query) over blocks that only mention it; 1.0 disables the boost
+    #[arg(long = "definition-boost", default_value = "1.5")]
+    pub definition_boost: f64,
+
+    /// Boost blocks in files that changed recently or often in git. Each commit's weight
+    /// halves after HALF_LIFE_DAYS (default 30), e.g. --boost-recent=7
+    #[arg(long = "boost-recent", value_name = "HALF_LIFE_DAYS", num_args = 0..=1, require_equals = true, default_missing_value = "30", value_parser = parse_half_life)]
+    pub boost_recent: Option<f64>,
+
+    /// Push down results that are similar to better-ranked ones, from 0 (ranked order)
+    /// to 1 (most diverse)
+    #[arg(long = "diversity", default_value = "0", value_parser = parse_diversity)]
+    pub diversity: f64,
+
+    /// Maximum number of results from any one file
+    #[arg(long = "max-per-file", value_parser = parse_max_per_file)]
+    pub max_per_file: Option<usize>,
+
+    /// Boost blocks in files that are central in the import graph (imported by many
+    /// files); scores are multiplied by 1 + WEIGHT * centrality. 0 disables the prior
+    #[arg(long = "centrality-weight", value_name = "WEIGHT", default_value = "0", value_parser = parse_centrality_weight)]
+    pub centrality_weight: f64,
+
+    /// Show why each result ranked where it did: the BM25 breakdown per query term plus
+    /// any boosts and merged blocks
+    #[arg(long = "explain")]
+    pub explain: bool,
+
+    /// BM25 term frequency saturation (default: 1.2, or `ranking.bm25_k1` in .probe.json);
+    /// higher values give repeated terms more weight
+    #[arg(long = "bm25-k1", value_name = "K1")]
+    pub bm25_k1: Option<f64>,
+
+    /// BM25 length normalization from 0 to 1 (default: 0.75, or `ranking.bm25_b` in
+    /// .probe.json); higher values penalize long blocks more
+    #[arg(long = "bm25-b", value_name = "B")]
+    pub bm25_b: Option<f64>,
+
+    /// How much a match in the file name counts compared to one in the code (default: 1,
+    /// or `ranking.filename_weight` in .probe.json)
+    #[arg(long = "filename-weight", value_name = "WEIGHT")]
+    pub filename_weight: Option<f64>,
+
     /// Use frequency-based search with stemming and stopword removal (enabled by default)
     #[arg(short = 's', long = "frequency", default_value = "true")]
     pub frequency_search: bool,
@@ -43,6 +93,48 @@ pub struct Args {
     #[arg(short = 'e', long = "exact")]
     pub exact: bool,
 
+    /// Match every query word of 4 or more characters up to DISTANCE edits away (default 1,
+    /// at most 2), like writing `word~DISTANCE` for each, e.g. --fuzzy=2
+    #[arg(long = "fuzzy", value_name = "DISTANCE", num_args = 0..=1, require_equals = true, default_missing_value = "1", value_parser = parse_fuzzy_distance)]
+    pub fuzzy: Option<usize>,
+
+    /// Synonyms file with `word = synonym, ...` lines to expand query words with (default:
+    /// `synonyms.file` in .probe.json)
+    #[arg(long = "synonyms", value_name = "FILE")]
+    pub synonyms: Option<PathBuf>,
+
+    /// Also expand query words with the built-in abbreviations (`cfg` => `config`), like
+    /// `synonyms.builtin` in .probe.json
+    #[arg(long = "builtin-synonyms")]
+    pub builtin_synonyms: bool,
+
+    /// Don't expand query words at all, even with configured synonyms
+    #[arg(long = "no-synonyms")]
+    pub no_synonyms: bool,
+
+    /// Comma-separated words to ignore like stop words, in addition to the built-in ones
+    /// and `vocabulary.stop_words` in .probe.json
+    #[arg(long = "stop-words", value_name = "WORDS", value_delimiter = ',')]
+    pub stop_words: Vec<String>,
+
+    /// Comma-separated built-in stop words to search for after all
+    #[arg(long = "not-stop-words", value_name = "WORDS", value_delimiter = ',')]
+    pub not_stop_words: Vec<String>,
+
+    /// Comma-separated words that are never split into parts, e.g. --exception-terms=ledger,tenant
+    #[arg(long = "exception-terms", value_name = "WORDS", value_delimiter = ',')]
+    pub exception_terms: Vec<String>,
+
+    /// Comma-separated words that compound words are split into, e.g. --compound-words=multi
+    #[arg(long = "compound-words", value_name = "WORDS", value_delimiter = ',')]
+    pub compound_words: Vec<String>,
+
+    /// Language to stem query and code words in: arabic, danish, dutch, english (default),
+    /// finnish, french, german, greek, hungarian, italian, norwegian, portuguese, romanian,
+    /// russian, spanish, swedish, tamil, turkish, or none to match words unstemmed
+    #[arg(long = "stem-language", value_name = "LANG")]
+    pub stem_language: Option<StemLanguage>,
+
     /// Maximum number of results to return
     #[arg(long = "max-results")]
     pub max_results: Option<usize>,
@@ -80,15 +172,20 @@ pub struct Args {
     #[arg(long = "session")]
     pub session: Option<String>,
 
-    /// Timeout in seconds for search operation (default: 30)
+    /// Timeout in seconds for search operation; partial results are shown when it is reached (default: 30)
     #[arg(long = "timeout", default_value = "30")]
     pub timeout: u64,
 
+    /// Stream progress and per-file results as JSON Lines, followed by the ranked results
+    #[arg(long = "stream")]
+    pub stream: bool,
+
     #[command(subcommand)]
     pub command: Option<Commands>,
 }
 
 #[derive(Subcommand, Debug)]
+#[allow(clippy::large_enum_variant)] // Parsed once per run
 pub enum Commands {
     /// Search code using patterns with intelligent ranking
     ///
@@ -101,7 +198,7 @@ pub enum Commands {
         #[arg(value_name = "PATTERN")]
         pattern: String,
 
-        /// Files or directories to search (defaults to current directory)
+        /// Files or directories to search, ranked together (defaults to current directory)
         #[arg(value_name = "PATH", default_value = ".")]
         paths: Vec<PathBuf>,
 
@@ -117,10 +214,56 @@ pub enum Commands {
         #[arg(short = 'n', long = "exclude-filenames")]
         exclude_filenames: bool,
 
-        /// BM25 ranking for search results
-        #[arg(short = 'r', long = "reranker", default_value = "bm25", value_parser = ["bm25"])]
+        /// Ranking algorithm for search results: bm25, tfidf, bm25f (field-weighted BM25),
+        /// hybrid (BM25 blended with file-level match statistics) or cmd:<program> (an external
+        /// program that scores the candidates it reads as JSON from stdin)
+        #[arg(short = 'r', long = "reranker", default_value = "bm25", value_parser = parse_reranker)]
         reranker: String,
 
+        /// Score multiplier for blocks that define a searched symbol (e.g. the struct or function
+        /// named by the query) over blocks that only mention it; 1.0 disables the boost
+        #[arg(long = "definition-boost", default_value = "1.5")]
+        definition_boost: f64,
+
+        /// Boost blocks in files that changed recently or often in git. Each commit's weight
+        /// halves after HALF_LIFE_DAYS (default 30), e.g. --boost-recent=7
+        #[arg(long = "boost-recent", value_name = "HALF_LIFE_DAYS", num_args = 0..=1, require_equals = true, default_missing_value = "30", value_parser = parse_half_life)]
+        boost_recent: Option<f64>,
+
+        /// Push down results that are similar to better-ranked ones, from 0 (ranked order)
+        /// to 1 (most diverse)
+        #[arg(long = "diversity", default_value = "0", value_parser = parse_diversity)]
+        diversity: f64,
+
+        /// Maximum number of results from any one file
+        #[arg(long = "max-per-file", value_parser = parse_max_per_file)]
+        max_per_file: Option<usize>,
+
+        /// Boost blocks in files that are central in the import graph (imported by many
+        /// files); scores are multiplied by 1 + WEIGHT * centrality. 0 disables the prior
+        #[arg(long = "centrality-weight", value_name = "WEIGHT", default_value = "0", value_parser = parse_centrality_weight)]
+        centrality_weight: f64,
+
+        /// Show why each result ranked where it did: the BM25 breakdown per query term plus
+        /// any boosts and merged blocks
+        #[arg(long = "explain")]
+        explain: bool,
+
+        /// BM25 term frequency saturation (default: 1.2, or `ranking.bm25_k1` in .probe.json);
+        /// higher values give repeated terms more weight
+        #[arg(long = "bm25-k1", value_name = "K1")]
+        bm25_k1: Option<f64>,
+
+        /// BM25 length normalization from 0 to 1 (default: 0.75, or `ranking.bm25_b` in
+        /// .probe.json); higher values penalize long blocks more
+        #[arg(long = "bm25-b", value_name = "B")]
+        bm25_b: Option<f64>,
+
+        /// How much a match in the file name counts compared to one in the code (default: 1,
+        /// or `ranking.filename_weight` in .probe.json)
+        #[arg(long = "filename-weight", value_name = "WEIGHT")]
+        filename_weight: Option<f64>,
+
         /// Use frequency-based search with stemming and stopword removal (enabled by default)
         #[arg(short = 's', long = "frequency", default_value = "true")]
         frequency_search: bool,
@@ -129,6 +272,48 @@ pub enum Commands {
         #[arg(short = 'e', long = "exact")]
         exact: bool,
 
+        /// Match every query word of 4 or more characters up to DISTANCE edits away (default 1,
+        /// at most 2), like writing `word~DISTANCE` for each, e.g. --fuzzy=2
+        #[arg(long = "fuzzy", value_name = "DISTANCE", num_args = 0..=1, require_equals = true, default_missing_value = "1", value_parser = parse_fuzzy_distance)]
+        fuzzy: Option<usize>,
+
+        /// Synonyms file with `word = synonym, ...` lines to expand query words with (default:
+        /// `synonyms.file` in .probe.json)
+        #[arg(long = "synonyms", value_name = "FILE")]
+        synonyms: Option<PathBuf>,
+
+        /// Also expand query words with the built-in abbreviations (`cfg` => `config`), like
+        /// `synonyms.builtin` in .probe.json
+        #[arg(long = "builtin-synonyms")]
+        builtin_synonyms: bool,
+
+        /// Don't expand query words at all, even with configured synonyms
+        #[arg(long = "no-synonyms")]
+        no_synonyms: bool,
+
+        /// Comma-separated words to ignore like stop words, in addition to the built-in ones
+        /// and `vocabulary.stop_words` in .probe.json
+        #[arg(long = "stop-words", value_name = "WORDS", value_delimiter = ',')]
+        stop_words: Vec<String>,
+
+        /// Comma-separated built-in stop words to search for after all
+        #[arg(long = "not-stop-words", value_name = "WORDS", value_delimiter = ',')]
+        not_stop_words: Vec<String>,
+
+        /// Comma-separated words that are never split into parts, e.g. --exception-terms=ledger,tenant
+        #[arg(long = "exception-terms", value_name = "WORDS", value_delimiter = ',')]
+        exception_terms: Vec<String>,
+
+        /// Comma-separated words that compound words are split into, e.g. --compound-words=multi
+        #[arg(long = "compound-words", value_name = "WORDS", value_delimiter = ',')]
+        compound_words: Vec<String>,
+
+        /// Language to stem query and code words in: arabic, danish, dutch, english (default),
+        /// finnish, french, german, greek, hungarian, italian, norwegian, portuguese, romanian,
+        /// russian, spanish, swedish, tamil, turkish, or none to match words unstemmed
+        #[arg(long = "stem-language", value_name = "LANG")]
+        stem_language: Option<StemLanguage>,
+
         /// Programming language to limit search to specific file extensions
         #[arg(short = 'l', long = "language", value_parser = [
             "rust", "rs",
@@ -183,9 +368,13 @@ pub enum Commands {
         #[arg(long = "session")]
         session: Option<String>,
 
-        /// Timeout in seconds for search operation (default: 30)
+        /// Timeout in seconds for search operation; partial results are shown when it is reached (default: 30)
         #[arg(long = "timeout", default_value = "30")]
         timeout: u64,
+
+        /// Stream progress and per-file results as JSON Lines, followed by the ranked results
+        #[arg(long = "stream")]
+        stream: bool,
     },
 
     /// Extract code blocks from files
@@ -249,6 +438,70 @@ pub enum Commands {
         instructions: Option<String>,
     },
 
+    /// Build a persistent search index for faster repeat searches
+    ///
+    /// This command walks each path once and stores a word-to-line postings index together
+    /// with per-file token statistics in the probe cache directory. Later searches under
+    /// an indexed path read matches for unchanged files from the index and only scan files
+    /// that changed since. Run it again to refresh the index.
+    Index {
+        /// Directories to index (defaults to current directory)
+        #[arg(value_name = "PATH", default_value = ".")]
+        paths: Vec<PathBuf>,
+    },
+
+    /// Export the import graph of a codebase with the centrality of each file
+    ///
+    /// This command resolves the imports of every supported file (use, import, require,
+    /// #include, ...) to files under PATH and ranks files by PageRank, so modules that
+    /// many others depend on come first. The graph is printed as JSON or as Graphviz DOT.
+    Graph {
+        /// Directory to build the graph for (defaults to current directory)
+        #[arg(value_name = "PATH", default_value = ".")]
+        path: PathBuf,
+
+        /// Output format: json or dot
+        #[arg(short = 'o', long = "format", default_value = "json", value_parser = ["json", "dot"])]
+        format: String,
+
+        /// Include test files
+        #[arg(long = "allow-tests")]
+        allow_tests: bool,
+
+        /// Custom patterns to ignore (in addition to .gitignore and common patterns)
+        #[arg(short, long)]
+        ignore: Vec<String>,
+    },
+
+    /// Run a Model Context Protocol server over stdio
+    ///
+    /// This command reads MCP JSON-RPC requests from stdin and writes responses to stdout,
+    /// one message per line. It offers the search_code, query_code and extract_code tools
+    /// and runs them in-process, so caches stay warm between calls.
+    Mcp,
+
+    /// Run a Language Server Protocol server over stdio
+    ///
+    /// This command lets editors use probe directly. It answers workspace/symbol and
+    /// textDocument/definition from the tree-sitter symbols of the workspace, and a custom
+    /// probe/search request that returns ranked search results as locations.
+    Lsp,
+
+    /// Run a local HTTP server exposing search, query and extract as JSON endpoints
+    ///
+    /// The server keeps the tree cache, the file-list cache and the tokenizer resident
+    /// between requests. Endpoints: GET /health, POST /search, POST /query and
+    /// POST /extract, each taking a JSON body with the same fields as the MCP tools.
+    Serve {
+        /// Port to listen on
+        #[arg(short, long, default_value = "8080")]
+        port: u16,
+
+        /// Address to bind to
+        #[arg(long, default_value = "127.0.0.1")]
+        host: String,
+    },
+
     /// Search code using AST patterns for precise structural matching
     ///
     /// This command uses ast-grep to search for structural patterns in code.
@@ -300,3 +553,60 @@ pub enum Commands {
         format: String,
     },
 }
+
+/// Accept fuzzy distances up to `fuzzy::MAX_FUZZY_DISTANCE`
+fn parse_fuzzy_distance(value: &str) -> Result<usize, String> {
+    let distance: usize = value.parse().map_err(|e| format!("{}", e))?;
+    if distance > MAX_FUZZY_DISTANCE {
+        return Err(format!("expected at most {}", MAX_FUZZY_DISTANCE));
+    }
+    Ok(distance)
+}
+
+/// Accept half-lives that are a finite number of days above 0
+fn parse_half_life(value: &str) -> Result<f64, String> {
+    let days: f64 = value.parse().map_err(|e| format!("{}", e))?;
+    if !(days > 0.0 && days.is_finite()) {
+        return Err("expected a number of days above 0".to_string());
+    }
+    Ok(days)
+}
+
+/// Accept diversity weights from 0 to 1
+fn parse_diversity(value: &str) -> Result<f64, String> {
+    let diversity: f64 = value.parse().map_err(|e| format!("{}", e))?;
+    if !(0.0..=1.0).contains(&diversity) {
+        return Err("expected a number from 0 to 1".to_string());
+    }
+    Ok(diversity)
+}
+
+/// Accept per-file limits of at least one result
+fn parse_max_per_file(value: &str) -> Result<usize, String> {
+    let max_per_file: usize = value.parse().map_err(|e| format!("{}", e))?;
+    if max_per_file == 0 {
+        return Err("expected at least 1".to_string());
+    }
+    Ok(max_per_file)
+}
+
+/// Accept centrality weights that are a finite number of 0 or more
+fn parse_centrality_weight(value: &str) -> Result<f64, String> {
+    let weight: f64 = value.parse().map_err(|e| format!("{}", e))?;
+    if !(weight >= 0.0 && weight.is_finite()) {
+        return Err("expected a number of 0 or more".to_string());
+    }
+    Ok(weight)
+}
+
+/// Accept the built-in rerankers and `cmd:<program>` external rerankers
+fn parse_reranker(value: &str) -> Result<String, String> {
+    if value.starts_with(COMMAND_RERANKER_PREFIX) || RERANKERS.contains(&value) {
+        Ok(value.to_string())
+    } else {
+        Err(format!(
+            "expected one of {} or cmd:<program>",
+            RERANKERS.join(", ")
+        ))
+    }
+}
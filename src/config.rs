@@ -0,0 +1,180 @@
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+use crate::ranking::Bm25Params;
+use crate::search::synonyms::SynonymDictionary;
+use crate::search::tokenization::VocabularyOverrides;
+use crate::search::SearchRequest;
+
+/// Name of the project configuration file
+pub const CONFIG_FILE_NAME: &str = ".probe.json";
+
+/// Per-project settings, read from the nearest `.probe.json` in the searched directory or
+/// one of its parents. Command line flags take precedence over them.
+///
+/// ```json
+/// {"ranking": {"bm25_k1": 1.5, "bm25_b": 0.9, "filename_weight": 2.0},
+///  "synonyms": {"file": "synonyms.txt"},
+///  "vocabulary": {"exception_terms": ["ledger", "tenant"], "not_stop_words": ["type"]}}
+/// ```
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProjectConfig {
+    pub ranking: RankingConfig,
+    pub synonyms: SynonymsConfig,
+    /// Additions to the stop words, exception terms and compound word vocabulary, and the
+    /// stem language
+    pub vocabulary: VocabularyOverrides,
+}
+
+/// The `ranking` section of the project configuration
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RankingConfig {
+    pub bm25_k1: Option<f64>,
+    pub bm25_b: Option<f64>,
+    pub filename_weight: Option<f64>,
+}
+
+/// The `synonyms` section of the project configuration
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SynonymsConfig {
+    /// Expand query words with the built-in abbreviations (`cfg`, `db`, ...); off unless set
+    pub builtin: bool,
+    /// Synonyms file with `word = synonym, ...` lines, relative to the configuration file
+    pub file: Option<PathBuf>,
+}
+
+/// Settings given with a search (on the command line, say) that take precedence over the
+/// project configuration
+#[derive(Debug, Default, Clone)]
+pub struct ConfigOverrides {
+    pub bm25_k1: Option<f64>,
+    pub bm25_b: Option<f64>,
+    pub filename_weight: Option<f64>,
+    /// Synonyms file to use instead of the configured one
+    pub synonyms_file: Option<PathBuf>,
+    /// Use the built-in abbreviations even if the configuration doesn't
+    pub builtin_synonyms: bool,
+    /// Expand no query words at all
+    pub no_synonyms: bool,
+    /// Word lists added to the configured ones; a stem language replaces the configured one
+    pub vocabulary: VocabularyOverrides,
+}
+
+/// Apply the configuration of the first searched path, with `overrides` on top, to
+/// `request`: its BM25 parameters, synonyms and vocabulary. The CLI and the MCP, HTTP and
+/// LSP servers all go through this, so a project is searched the same way from each of
+/// them. The built-in abbreviations are off unless the configuration or `overrides` turn
+/// them on.
+pub fn configure_search(
+    mut request: SearchRequest,
+    overrides: &ConfigOverrides,
+) -> Result<SearchRequest> {
+    let config = match request.paths.first() {
+        Some(path) => ProjectConfig::for_path(path)?,
+        None => ProjectConfig::default(),
+    };
+    config.apply(&mut request, overrides)?;
+    Ok(request)
+}
+
+impl ProjectConfig {
+    /// Find the configuration file that applies to `path`, looking in it (or its directory,
+    /// for a file) and then in each parent directory
+    pub fn find(path: &Path) -> Option<PathBuf> {
+        let path = path.canonicalize().ok()?;
+        let start = if path.is_file() {
+            path.parent()?
+        } else {
+            &path
+        };
+        start
+            .ancestors()
+            .map(|dir| dir.join(CONFIG_FILE_NAME))
+            .find(|candidate| candidate.is_file())
+    }
+
+    /// Read a configuration file
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let mut config: Self =
+            serde_json::from_str(&text).with_context(|| format!("Invalid {}", path.display()))?;
+        if let (Some(dir), Some(file)) = (path.parent(), config.synonyms.file.as_mut()) {
+            *file = dir.join(&*file);
+        }
+        Ok(config)
+    }
+
+    /// Load the configuration that applies to `path`, or the defaults if there is none
+    pub fn for_path(path: &Path) -> Result<Self> {
+        match Self::find(path) {
+            Some(config_path) => Self::load(&config_path),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// BM25 parameters from the given command line values, falling back to this
+    /// configuration and then to the defaults
+    pub fn bm25_params(
+        &self,
+        k1: Option<f64>,
+        b: Option<f64>,
+        filename_weight: Option<f64>,
+    ) -> Result<Bm25Params> {
+        let defaults = Bm25Params::default();
+        let params = Bm25Params {
+            k1: k1.or(self.ranking.bm25_k1).unwrap_or(defaults.k1),
+            b: b.or(self.ranking.bm25_b).unwrap_or(defaults.b),
+            filename_weight: filename_weight
+                .or(self.ranking.filename_weight)
+                .unwrap_or(defaults.filename_weight),
+        };
+        params.validate().map_err(|e| anyhow!(e))?;
+        Ok(params)
+    }
+
+    /// The synonyms to expand query words with: the built-in abbreviations if enabled here
+    /// or with `builtin`, plus those of the synonyms file given on the command line or
+    /// configured here
+    pub fn synonym_dictionary(
+        &self,
+        file: Option<&Path>,
+        builtin: bool,
+    ) -> Result<SynonymDictionary> {
+        let mut dictionary = if builtin || self.synonyms.builtin {
+            SynonymDictionary::builtin().clone()
+        } else {
+            SynonymDictionary::default()
+        };
+        if let Some(file) = file.or(self.synonyms.file.as_deref()) {
+            dictionary.add_file(file)?;
+        }
+        Ok(dictionary)
+    }
+
+    /// Set the BM25 parameters, synonyms and vocabulary of `request` from this
+    /// configuration with `overrides` on top
+    pub fn apply(&self, request: &mut SearchRequest, overrides: &ConfigOverrides) -> Result<()> {
+        request.ranking.bm25 = self.bm25_params(
+            overrides.bm25_k1,
+            overrides.bm25_b,
+            overrides.filename_weight,
+        )?;
+        request.synonyms = if overrides.no_synonyms {
+            None
+        } else {
+            Some(self.synonym_dictionary(
+                overrides.synonyms_file.as_deref(),
+                overrides.builtin_synonyms,
+            )?)
+        };
+        let mut vocabulary = self.vocabulary.clone();
+        vocabulary.extend(overrides.vocabulary.clone());
+        request.vocabulary = Some(vocabulary);
+        Ok(())
+    }
+}
@@ -28,12 +28,17 @@ pub use prompts::PromptTemplate;
 use crate::extract::file_paths::{set_custom_ignores, FilePathInfo};
 use crate::models::SearchResult;
 use anyhow::Result;
+use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 use std::io::Read;
 #[allow(unused_imports)]
 use std::path::PathBuf;
 
 /// Options for the extract command
+///
+/// Defaults match the CLI, and missing fields take their default when deserializing.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ExtractOptions {
     /// Files to extract from
     pub files: Vec<String>,
@@ -63,6 +68,88 @@ pub struct ExtractOptions {
     pub instructions: Option<String>,
 }
 
+impl Default for ExtractOptions {
+    fn default() -> Self {
+        Self {
+            files: Vec::new(),
+            custom_ignores: Vec::new(),
+            context_lines: 0,
+            format: "color".to_string(),
+            from_clipboard: false,
+            input_file: None,
+            to_clipboard: false,
+            dry_run: false,
+            diff: false,
+            allow_tests: false,
+            keep_input: false,
+            prompt: None,
+            instructions: None,
+        }
+    }
+}
+
+#[allow(dead_code)]
+impl ExtractOptions {
+    /// Create options extracting `files` (e.g. `file.rs:10` or `file.rs#symbol`) with the CLI defaults
+    pub fn new<S: Into<String>>(files: impl IntoIterator<Item = S>) -> Self {
+        Self {
+            files: files.into_iter().map(Into::into).collect(),
+            ..Self::default()
+        }
+    }
+
+    /// Add a pattern to ignore in addition to .gitignore and common patterns
+    pub fn ignore(mut self, pattern: impl Into<String>) -> Self {
+        self.custom_ignores.push(pattern.into());
+        self
+    }
+
+    pub fn context_lines(mut self, context_lines: usize) -> Self {
+        self.context_lines = context_lines;
+        self
+    }
+
+    pub fn format(mut self, format: impl Into<String>) -> Self {
+        self.format = format.into();
+        self
+    }
+
+    pub fn input_file(mut self, input_file: impl Into<String>) -> Self {
+        self.input_file = Some(input_file.into());
+        self
+    }
+
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    pub fn diff(mut self, diff: bool) -> Self {
+        self.diff = diff;
+        self
+    }
+
+    pub fn allow_tests(mut self, allow_tests: bool) -> Self {
+        self.allow_tests = allow_tests;
+        self
+    }
+
+    pub fn keep_input(mut self, keep_input: bool) -> Self {
+        self.keep_input = keep_input;
+        self
+    }
+
+    pub fn prompt(mut self, prompt: prompts::PromptTemplate) -> Self {
+        self.prompt = Some(prompt);
+        self
+    }
+
+    pub fn instructions(mut self, instructions: impl Into<String>) -> Self {
+        self.instructions = Some(instructions.into());
+        self
+    }
+}
+
 /// Handle the extract command
 pub fn handle_extract(options: ExtractOptions) -> Result<()> {
     use arboard::Clipboard;
@@ -413,6 +500,123 @@ pub fn handle_extract(options: ExtractOptions) -> Result<()> {
         None
     };
 
+    let (results, errors) = extract_file_paths(
+        file_paths,
+        options.allow_tests,
+        options.context_lines,
+        &options.format,
+    );
+
+    if debug_mode {
+        println!("[DEBUG] After deduplication: {} results", results.len());
+    }
+
+    if debug_mode {
+        println!("\n[DEBUG] ===== Extraction Summary =====");
+        println!("[DEBUG] Total results: {}", results.len());
+        println!("[DEBUG] Total errors: {}", errors.len());
+        println!("[DEBUG] Output format: {}", options.format);
+        println!("[DEBUG] Dry run: {}", options.dry_run);
+    }
+
+    // Format the results
+    let res = {
+        // Temporarily disable colors if writing to clipboard
+        let colors_enabled = if options.to_clipboard {
+            let was_enabled = colored::control::SHOULD_COLORIZE.should_colorize();
+            colored::control::set_override(false);
+            was_enabled
+        } else {
+            false
+        };
+
+        // Format the results
+        let result = if options.dry_run {
+            formatter::format_extraction_dry_run(
+                &results,
+                &options.format,
+                original_input.as_deref(),
+                system_prompt.as_deref(),
+                options.instructions.as_deref(),
+            )
+        } else {
+            formatter::format_extraction_results(
+                &results,
+                &options.format,
+                original_input.as_deref(),
+                system_prompt.as_deref(),
+                options.instructions.as_deref(),
+            )
+        };
+
+        // Restore color settings if they were changed
+        if options.to_clipboard && colors_enabled {
+            colored::control::set_override(true);
+        }
+
+        result
+    };
+    match res {
+        Ok(formatted_output) => {
+            if options.to_clipboard {
+                // Write to clipboard
+                let mut clipboard = Clipboard::new()?;
+                clipboard.set_text(&formatted_output)?;
+                println!("{}", "Results copied to clipboard.".green().bold());
+
+                if debug_mode {
+                    println!(
+                        "[DEBUG] Wrote {} bytes to clipboard",
+                        formatted_output.len()
+                    );
+                }
+            } else {
+                // Print to stdout
+                println!("{}", formatted_output);
+            }
+        }
+        Err(e) => {
+            // Only print error messages for non-JSON/XML formats
+            if options.format != "json" && options.format != "xml" {
+                eprintln!("{}", format!("Error formatting results: {}", e).red());
+            }
+            if debug_mode {
+                println!("[DEBUG] Error formatting results: {}", e);
+            }
+        }
+    }
+
+    // Print summary of errors if any (only for non-JSON/XML formats)
+    if !errors.is_empty() && options.format != "json" && options.format != "xml" {
+        println!();
+        println!(
+            "{} {} {}",
+            "Encountered".red().bold(),
+            errors.len(),
+            if errors.len() == 1 { "error" } else { "errors" }
+        );
+    }
+
+    if debug_mode {
+        println!("[DEBUG] ===== Extract Command Completed =====");
+    }
+
+    Ok(())
+}
+
+/// Extract the code for every parsed file path in parallel, then drop exact duplicates and
+/// blocks nested inside another extracted block of the same file.
+/// Returns the extracted blocks and a message for every file that could not be processed.
+pub fn extract_file_paths(
+    file_paths: Vec<FilePathInfo>,
+    allow_tests: bool,
+    context_lines: usize,
+    format: &str,
+) -> (Vec<SearchResult>, Vec<String>) {
+    use colored::*;
+
+    let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
+
     // Process files in parallel using Rayon
     use rayon::prelude::*;
     use std::sync::{Arc, Mutex};
@@ -432,13 +636,6 @@ pub fn handle_extract(options: ExtractOptions) -> Result<()> {
         context_lines: usize,
         debug_mode: bool,
         format: String,
-
-        #[allow(dead_code)]
-        original_input: Option<String>,
-        #[allow(dead_code)]
-        system_prompt: Option<String>,
-        #[allow(dead_code)]
-        user_instructions: Option<String>,
     }
 
     // Collect all file parameters
@@ -451,13 +648,10 @@ pub fn handle_extract(options: ExtractOptions) -> Result<()> {
                 end_line,
                 symbol,
                 specific_lines,
-                allow_tests: options.allow_tests,
-                context_lines: options.context_lines,
+                allow_tests,
+                context_lines,
                 debug_mode,
-                format: options.format.clone(),
-                original_input: original_input.clone(),
-                system_prompt: system_prompt.clone(),
-                user_instructions: options.instructions.clone(),
+                format: format.to_string(),
             },
         )
         .collect();
@@ -659,99 +853,5 @@ pub fn handle_extract(options: ExtractOptions) -> Result<()> {
 
     results = new_results;
 
-    if debug_mode {
-        println!("[DEBUG] After deduplication: {} results", results.len());
-    }
-
-    if debug_mode {
-        println!("\n[DEBUG] ===== Extraction Summary =====");
-        println!("[DEBUG] Total results: {}", results.len());
-        println!("[DEBUG] Total errors: {}", errors.len());
-        println!("[DEBUG] Output format: {}", options.format);
-        println!("[DEBUG] Dry run: {}", options.dry_run);
-    }
-
-    // Format the results
-    let res = {
-        // Temporarily disable colors if writing to clipboard
-        let colors_enabled = if options.to_clipboard {
-            let was_enabled = colored::control::SHOULD_COLORIZE.should_colorize();
-            colored::control::set_override(false);
-            was_enabled
-        } else {
-            false
-        };
-
-        // Format the results
-        let result = if options.dry_run {
-            formatter::format_extraction_dry_run(
-                &results,
-                &options.format,
-                original_input.as_deref(),
-                system_prompt.as_deref(),
-                options.instructions.as_deref(),
-            )
-        } else {
-            formatter::format_extraction_results(
-                &results,
-                &options.format,
-                original_input.as_deref(),
-                system_prompt.as_deref(),
-                options.instructions.as_deref(),
-            )
-        };
-
-        // Restore color settings if they were changed
-        if options.to_clipboard && colors_enabled {
-            colored::control::set_override(true);
-        }
-
-        result
-    };
-    match res {
-        Ok(formatted_output) => {
-            if options.to_clipboard {
-                // Write to clipboard
-                let mut clipboard = Clipboard::new()?;
-                clipboard.set_text(&formatted_output)?;
-                println!("{}", "Results copied to clipboard.".green().bold());
-
-                if debug_mode {
-                    println!(
-                        "[DEBUG] Wrote {} bytes to clipboard",
-                        formatted_output.len()
-                    );
-                }
-            } else {
-                // Print to stdout
-                println!("{}", formatted_output);
-            }
-        }
-        Err(e) => {
-            // Only print error messages for non-JSON/XML formats
-            if options.format != "json" && options.format != "xml" {
-                eprintln!("{}", format!("Error formatting results: {}", e).red());
-            }
-            if debug_mode {
-                println!("[DEBUG] Error formatting results: {}", e);
-            }
-        }
-    }
-
-    // Print summary of errors if any (only for non-JSON/XML formats)
-    if !errors.is_empty() && options.format != "json" && options.format != "xml" {
-        println!();
-        println!(
-            "{} {} {}",
-            "Encountered".red().bold(),
-            errors.len(),
-            if errors.len() == 1 { "error" } else { "errors" }
-        );
-    }
-
-    if debug_mode {
-        println!("[DEBUG] ===== Extract Command Completed =====");
-    }
-
-    Ok(())
+    (results, errors)
 }
@@ -189,6 +189,12 @@ pub fn process_file_for_extraction(
                     block_id: None,
                     matched_keywords: None,
                     tokenized_content: Some(tokenized_content),
+                    search_root: None,
+                    symbol_name: None,
+                    definition_boost: None,
+                    recency_boost: None,
+                    centrality: None,
+                    explanation: None,
                 })
             }
             _ => {
@@ -234,6 +240,12 @@ pub fn process_file_for_extraction(
                     block_id: None,
                     matched_keywords: None,
                     tokenized_content: Some(tokenized_content),
+                    search_root: None,
+                    symbol_name: None,
+                    definition_boost: None,
+                    recency_boost: None,
+                    centrality: None,
+                    explanation: None,
                 })
             }
         }
@@ -311,7 +323,7 @@ pub fn process_file_for_extraction(
                 let tokenized_content =
                     crate::ranking::preprocess_text_with_filename(&merged_content, &filename);
 
-                return Ok(SearchResult {
+                Ok(SearchResult {
                     file: path.to_string_lossy().to_string(),
                     lines: (merged_start, merged_end),
                     node_type: "merged_ast_line".to_string(),
@@ -335,7 +347,13 @@ pub fn process_file_for_extraction(
                     block_id: None,
                     matched_keywords: None,
                     tokenized_content: Some(tokenized_content),
-                });
+                    search_root: None,
+                    symbol_name: None,
+                    definition_boost: None,
+                    recency_boost: None,
+                    centrality: None,
+                    explanation: None,
+                })
             }
             _ => {
                 // If no AST block found, fallback to the line + context
@@ -368,7 +386,7 @@ pub fn process_file_for_extraction(
                 let tokenized_content =
                     crate::ranking::preprocess_text_with_filename(&context_code, &filename);
 
-                return Ok(SearchResult {
+                Ok(SearchResult {
                     file: path.to_string_lossy().to_string(),
                     lines: (start_ctx, end_ctx),
                     node_type: "context".to_string(),
@@ -392,7 +410,13 @@ pub fn process_file_for_extraction(
                     block_id: None,
                     matched_keywords: None,
                     tokenized_content: Some(tokenized_content),
-                });
+                    search_root: None,
+                    symbol_name: None,
+                    definition_boost: None,
+                    recency_boost: None,
+                    centrality: None,
+                    explanation: None,
+                })
             }
         }
     } else if let Some(lines_set) = specific_lines {
@@ -438,6 +462,12 @@ pub fn process_file_for_extraction(
                 block_id: None,
                 matched_keywords: None,
                 tokenized_content: Some(tokenized_content),
+                search_root: None,
+                symbol_name: None,
+                definition_boost: None,
+                recency_boost: None,
+                centrality: None,
+                explanation: None,
             });
         }
 
@@ -511,7 +541,7 @@ pub fn process_file_for_extraction(
                 let tokenized_content =
                     crate::ranking::preprocess_text_with_filename(&merged_content, &filename);
 
-                return Ok(SearchResult {
+                Ok(SearchResult {
                     file: path.to_string_lossy().to_string(),
                     lines: (merged_start, merged_end),
                     node_type: "merged_ast_specific_lines".to_string(),
@@ -535,7 +565,13 @@ pub fn process_file_for_extraction(
                     block_id: None,
                     matched_keywords: None,
                     tokenized_content: Some(tokenized_content),
-                });
+                    search_root: None,
+                    symbol_name: None,
+                    definition_boost: None,
+                    recency_boost: None,
+                    centrality: None,
+                    explanation: None,
+                })
             }
             _ => {
                 // Fallback to literal extraction of the specific lines
@@ -569,7 +605,7 @@ pub fn process_file_for_extraction(
                 let tokenized_content =
                     crate::ranking::preprocess_text_with_filename(&range_content, &filename);
 
-                return Ok(SearchResult {
+                Ok(SearchResult {
                     file: path.to_string_lossy().to_string(),
                     lines: (start, end),
                     node_type: "specific_lines".to_string(),
@@ -593,7 +629,13 @@ pub fn process_file_for_extraction(
                     block_id: None,
                     matched_keywords: None,
                     tokenized_content: Some(tokenized_content),
-                });
+                    search_root: None,
+                    symbol_name: None,
+                    definition_boost: None,
+                    recency_boost: None,
+                    centrality: None,
+                    explanation: None,
+                })
             }
         }
     } else {
@@ -633,6 +675,12 @@ pub fn process_file_for_extraction(
             block_id: None,
             matched_keywords: None,
             tokenized_content: Some(tokenized_content),
+            search_root: None,
+            symbol_name: None,
+            definition_boost: None,
+            recency_boost: None,
+            centrality: None,
+            explanation: None,
         })
     }
 }
@@ -4,6 +4,7 @@
 //! for use with LLM models. It supports built-in templates and loading from files.
 
 use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::Path;
 
@@ -53,7 +54,10 @@ Brief code snippets may be included for clarity, but do not produce a full imple
 Your analysis should strictly cover the technical implementation plan, excluding deployment, testing, or validation unless explicitly tied to architectural impact."#;
 
 /// Enum representing different prompt template sources
-#[derive(Debug, Clone)]
+///
+/// Serialized as `"engineer"`, `"architect"` or `{"custom": "path/to/file"}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum PromptTemplate {
     /// Built-in engineer template
     Engineer,
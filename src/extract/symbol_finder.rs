@@ -474,6 +474,12 @@ pub fn find_symbol_in_file(
             block_id: None,
             matched_keywords: None,
             tokenized_content: Some(tokenized_content),
+            search_root: None,
+            symbol_name: None,
+            definition_boost: None,
+            recency_boost: None,
+            centrality: None,
+            explanation: None,
         });
     }
 
@@ -588,6 +594,12 @@ pub fn find_symbol_in_file(
             block_id: None,
             matched_keywords: None,
             tokenized_content: Some(tokenized_content),
+            search_root: None,
+            symbol_name: None,
+            definition_boost: None,
+            recency_boost: None,
+            centrality: None,
+            explanation: None,
         });
     }
 
@@ -604,3 +616,154 @@ pub fn find_symbol_in_file(
         path
     ))
 }
+
+/// A named symbol (function, struct, class, etc.) declared in a file
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileSymbol {
+    /// The symbol's name
+    pub name: String,
+    /// The tree-sitter node type of the declaration (e.g. `function_item`)
+    pub kind: String,
+    /// Name of the enclosing symbol, if any (e.g. the class of a method)
+    pub container: Option<String>,
+    /// Start of the declaration as a zero-based (row, byte column)
+    pub start: (usize, usize),
+    /// End of the declaration as a zero-based (row, byte column)
+    pub end: (usize, usize),
+    /// Start of the symbol's name as a zero-based (row, byte column)
+    pub name_start: (usize, usize),
+    /// End of the symbol's name as a zero-based (row, byte column)
+    pub name_end: (usize, usize),
+}
+
+/// Find the node holding the name of a declaration
+pub(crate) fn declaration_name_node(node: tree_sitter::Node) -> Option<tree_sitter::Node> {
+    if let Some(name) = node.child_by_field_name("name") {
+        return Some(name);
+    }
+
+    // C and C++ functions keep their name inside a chain of declarators
+    let mut declarator = node.child_by_field_name("declarator");
+    while let Some(current) = declarator {
+        if matches!(
+            current.kind(),
+            "identifier" | "field_identifier" | "qualified_identifier"
+        ) {
+            return Some(current);
+        }
+        declarator = current.child_by_field_name("declarator");
+    }
+
+    // Rust impl blocks only mention the type they implement
+    if node.child_by_field_name("type").is_some() {
+        return None;
+    }
+
+    // Fall back to the first identifier-like child, as find_symbol_in_file does
+    let mut cursor = node.walk();
+    let name = node.children(&mut cursor).find(|child| {
+        matches!(
+            child.kind(),
+            "identifier" | "field_identifier" | "type_identifier" | "property_identifier"
+        )
+    });
+    name
+}
+
+/// List the symbols declared in a file
+///
+/// Every node the language treats as an acceptable parent (function, struct, class, etc.)
+/// and that has a name becomes a symbol. Nested declarations record the name of the
+/// enclosing symbol as their container.
+///
+/// # Arguments
+///
+/// * `path` - The path of the file, used to detect the language
+/// * `content` - The content of the file
+/// * `allow_tests` - Whether to include test code blocks
+#[allow(dead_code)]
+pub fn list_symbols_in_file(
+    path: &Path,
+    content: &str,
+    allow_tests: bool,
+) -> Result<Vec<FileSymbol>> {
+    let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or("");
+    let language_impl = crate::language::factory::get_language_impl(extension)
+        .ok_or_else(|| anyhow::anyhow!("Unsupported language extension: {}", extension))?;
+
+    let mut parser = tree_sitter::Parser::new();
+    parser
+        .set_language(&language_impl.get_tree_sitter_language())
+        .map_err(|e| anyhow::anyhow!("Failed to set language: {}", e))?;
+    let tree = parser
+        .parse(content.as_bytes(), None)
+        .ok_or_else(|| anyhow::anyhow!("Failed to parse file"))?;
+
+    fn collect_symbols(
+        node: tree_sitter::Node,
+        container: Option<&str>,
+        language_impl: &dyn crate::language::language_trait::LanguageImpl,
+        content: &[u8],
+        allow_tests: bool,
+        symbols: &mut Vec<FileSymbol>,
+    ) {
+        let mut child_container = container.map(str::to_string);
+
+        if language_impl.is_acceptable_parent(&node) {
+            if !allow_tests && language_impl.is_test_node(&node, content) {
+                return;
+            }
+
+            if let Some(name_node) = declaration_name_node(node) {
+                if let Ok(name) = name_node.utf8_text(content) {
+                    symbols.push(FileSymbol {
+                        name: name.to_string(),
+                        kind: node.kind().to_string(),
+                        container: container.map(str::to_string),
+                        start: (node.start_position().row, node.start_position().column),
+                        end: (node.end_position().row, node.end_position().column),
+                        name_start: (
+                            name_node.start_position().row,
+                            name_node.start_position().column,
+                        ),
+                        name_end: (
+                            name_node.end_position().row,
+                            name_node.end_position().column,
+                        ),
+                    });
+                    child_container = Some(name.to_string());
+                }
+            } else if let Some(type_node) = node.child_by_field_name("type") {
+                // Rust impl blocks have no name of their own, but name their methods' owner
+                if let Ok(type_name) = type_node.utf8_text(content) {
+                    child_container = Some(type_name.to_string());
+                }
+            }
+        }
+
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            collect_symbols(
+                child,
+                child_container.as_deref(),
+                language_impl,
+                content,
+                allow_tests,
+                symbols,
+            );
+        }
+    }
+
+    let mut symbols = Vec::new();
+    collect_symbols(
+        tree.root_node(),
+        None,
+        language_impl.as_ref(),
+        content.as_bytes(),
+        allow_tests,
+        &mut symbols,
+    );
+
+    Ok(symbols)
+}
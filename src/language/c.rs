@@ -1,4 +1,5 @@
-use super::language_trait::LanguageImpl;
+use super::common::string_literal_text;
+use super::language_trait::{Import, LanguageImpl};
 use tree_sitter::{Language as TSLanguage, Node};
 
 /// Implementation of LanguageImpl for C
@@ -59,4 +60,20 @@ impl LanguageImpl for CLanguage {
 
         false
     }
+
+    fn find_imports(&self, node: &Node, source: &[u8]) -> Vec<Import> {
+        c_include(node, source)
+    }
+}
+
+/// `#include "file.h"` of C and C++; system includes (`<stdio.h>`) are not project files
+pub fn c_include(node: &Node, source: &[u8]) -> Vec<Import> {
+    if node.kind() != "preproc_include" {
+        return Vec::new();
+    }
+    node.child_by_field_name("path")
+        .filter(|path| path.kind() == "string_literal")
+        .and_then(|path| string_literal_text(&path, source))
+        .map(|path| vec![Import::Path(path)])
+        .unwrap_or_default()
 }
@@ -1,6 +1,8 @@
 use std::collections::HashSet;
 use tree_sitter::Node;
 
+use super::language_trait::Import;
+
 /// Helper function to collect all node types in the AST
 pub fn collect_node_types(node: Node, node_types: &mut HashSet<String>) {
     node_types.insert(node.kind().to_string());
@@ -10,3 +12,30 @@ pub fn collect_node_types(node: Node, node_types: &mut HashSet<String>) {
         collect_node_types(child, node_types);
     }
 }
+
+/// Get the text of a string literal node without its quotes (or the `<>` of a C system
+/// include)
+pub fn string_literal_text(node: &Node, source: &[u8]) -> Option<String> {
+    let text = node.utf8_text(source).ok()?;
+    let text = text.trim_matches(|c| matches!(c, '"' | '\'' | '`' | '<' | '>'));
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+/// Turn a JavaScript-style import specifier into an import: relative specifiers are
+/// paths, anything else (`react`, `@scope/pkg/sub`) is a module
+pub fn path_or_module_import(specifier: &str) -> Import {
+    if specifier.starts_with('.') {
+        Import::Path(specifier.to_string())
+    } else {
+        Import::Module(specifier.split('/').map(str::to_string).collect())
+    }
+}
+
+/// Split a module path such as `os.path` or `std::fs` into its segments
+pub fn module_segments(path: &str, separator: &str) -> Vec<String> {
+    path.split(separator)
+        .map(str::trim)
+        .filter(|segment| !segment.is_empty())
+        .map(str::to_string)
+        .collect()
+}
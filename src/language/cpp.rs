@@ -1,4 +1,4 @@
-use super::language_trait::LanguageImpl;
+use super::language_trait::{Import, LanguageImpl};
 use tree_sitter::{Language as TSLanguage, Node};
 
 /// Implementation of LanguageImpl for C++
@@ -64,4 +64,8 @@ impl LanguageImpl for CppLanguage {
 
         false
     }
+
+    fn find_imports(&self, node: &Node, source: &[u8]) -> Vec<Import> {
+        super::c::c_include(node, source)
+    }
 }
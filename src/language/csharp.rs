@@ -1,4 +1,5 @@
-use super::language_trait::LanguageImpl;
+use super::common::module_segments;
+use super::language_trait::{Import, LanguageImpl};
 use tree_sitter::{Language as TSLanguage, Node};
 
 /// Implementation of LanguageImpl for C#
@@ -136,4 +137,16 @@ impl LanguageImpl for CSharpLanguage {
 
         None
     }
+
+    fn find_imports(&self, node: &Node, source: &[u8]) -> Vec<Import> {
+        if node.kind() != "using_directive" {
+            return Vec::new();
+        }
+        // using [static] System.Text; / using Alias = System.Text;
+        node.utf8_text(source)
+            .ok()
+            .and_then(|text| text.trim_end_matches(';').split_whitespace().last())
+            .map(|namespace| vec![Import::Module(module_segments(namespace, "."))])
+            .unwrap_or_default()
+    }
 }
@@ -1,4 +1,5 @@
-use super::language_trait::LanguageImpl;
+use super::common::string_literal_text;
+use super::language_trait::{Import, LanguageImpl};
 use tree_sitter::{Language as TSLanguage, Node};
 
 /// Implementation of LanguageImpl for Go
@@ -90,4 +91,19 @@ impl LanguageImpl for GoLanguage {
 
         None
     }
+
+    fn find_imports(&self, node: &Node, source: &[u8]) -> Vec<Import> {
+        // Go imports whole packages, i.e. directories
+        if node.kind() != "import_spec" {
+            return Vec::new();
+        }
+        node.child_by_field_name("path")
+            .and_then(|path| string_literal_text(&path, source))
+            .map(|path| {
+                vec![Import::Module(
+                    path.split('/').map(str::to_string).collect(),
+                )]
+            })
+            .unwrap_or_default()
+    }
 }
@@ -1,4 +1,5 @@
-use super::language_trait::LanguageImpl;
+use super::common::module_segments;
+use super::language_trait::{Import, LanguageImpl};
 use tree_sitter::{Language as TSLanguage, Node};
 
 /// Implementation of LanguageImpl for Java
@@ -63,4 +64,18 @@ impl LanguageImpl for JavaLanguage {
 
         false
     }
+
+    fn find_imports(&self, node: &Node, source: &[u8]) -> Vec<Import> {
+        if node.kind() != "import_declaration" {
+            return Vec::new();
+        }
+        // import [static] com.example.Foo[.*];
+        let mut cursor = node.walk();
+        let name = node
+            .named_children(&mut cursor)
+            .find(|child| matches!(child.kind(), "scoped_identifier" | "identifier"));
+        name.and_then(|name| name.utf8_text(source).ok())
+            .map(|name| vec![Import::Module(module_segments(name, "."))])
+            .unwrap_or_default()
+    }
 }
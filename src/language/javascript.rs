@@ -1,4 +1,5 @@
-use super::language_trait::LanguageImpl;
+use super::common::{path_or_module_import, string_literal_text};
+use super::language_trait::{Import, LanguageImpl};
 use tree_sitter::{Language as TSLanguage, Node};
 
 /// Implementation of LanguageImpl for JavaScript
@@ -87,4 +88,31 @@ impl LanguageImpl for JavaScriptLanguage {
 
         false
     }
+
+    fn find_imports(&self, node: &Node, source: &[u8]) -> Vec<Import> {
+        javascript_imports(node, source)
+    }
+}
+
+/// Imports of JavaScript and TypeScript: `import ... from 'x'`, `export ... from 'x'`
+/// and `require('x')`
+pub fn javascript_imports(node: &Node, source: &[u8]) -> Vec<Import> {
+    let specifier = match node.kind() {
+        "import_statement" | "export_statement" => node.child_by_field_name("source"),
+        "call_expression" => {
+            let is_require = node
+                .child_by_field_name("function")
+                .and_then(|function| function.utf8_text(source).ok())
+                .is_some_and(|function| function == "require" || function == "import");
+            node.child_by_field_name("arguments")
+                .filter(|_| is_require)
+                .and_then(|arguments| arguments.named_child(0))
+                .filter(|argument| argument.kind() == "string")
+        }
+        _ => None,
+    };
+    specifier
+        .and_then(|specifier| string_literal_text(&specifier, source))
+        .map(|specifier| vec![path_or_module_import(&specifier)])
+        .unwrap_or_default()
 }
@@ -1,5 +1,20 @@
+use serde::{Deserialize, Serialize};
 use tree_sitter::{Language as TSLanguage, Node};
 
+/// Something a source file imports, as found by `LanguageImpl::find_imports`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Import {
+    /// A path relative to the importing file's directory, with or without extension
+    /// (`./utils`, `../lib/helper`, `config.h`)
+    Path(String),
+    /// A module path split into segments (`os.path`, `com.example.Foo`). Trailing
+    /// segments may name items inside the module rather than files.
+    Module(Vec<String>),
+    /// A module path that is known to be inside the project (Rust `crate::`), so even a
+    /// single matching segment identifies the file
+    LocalModule(Vec<String>),
+}
+
 /// Trait that defines the interface for all language implementations.
 pub trait LanguageImpl {
     /// Get the tree-sitter language for parsing
@@ -21,4 +36,9 @@ pub trait LanguageImpl {
         // Default implementation returns None
         None
     }
+
+    /// Get what a node imports if it is an import (`use`, `import`, `#include`, ...)
+    fn find_imports(&self, _node: &Node, _source: &[u8]) -> Vec<Import> {
+        Vec::new()
+    }
 }
@@ -1,6 +1,7 @@
 use anyhow::{Context, Result};
 use dashmap::DashMap;
 use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
 use std::collections::hash_map::DefaultHasher;
 use std::collections::{HashMap, HashSet};
 use std::hash::{Hash, Hasher};
@@ -11,8 +12,11 @@ use crate::language::language_trait::LanguageImpl;
 use crate::language::tree_cache;
 use crate::models::CodeBlock;
 
+/// Line-to-node map for a whole file, as kept between searches
+pub(crate) type LineMap = Vec<Option<CachedNodeInfo>>;
+
 // Define a static cache for line maps
-static LINE_MAP_CACHE: Lazy<DashMap<String, Vec<Option<CachedNodeInfo>>>> = Lazy::new(DashMap::new);
+static LINE_MAP_CACHE: Lazy<DashMap<String, LineMap>> = Lazy::new(DashMap::new);
 
 /// Calculate a hash of the content for cache validation
 fn calculate_content_hash(content: &str) -> u64 {
@@ -22,8 +26,8 @@ fn calculate_content_hash(content: &str) -> u64 {
 }
 
 /// A version of NodeInfo without lifetimes for caching
-#[derive(Clone, Debug)]
-struct CachedNodeInfo {
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct CachedNodeInfo {
     // Original node info
     start_byte: usize,
     end_byte: usize,
@@ -915,6 +919,35 @@ fn process_cached_line_map(
 } // Added missing closing brace for process_cached_line_map
   // Removed unexpected closing brace that was here
 
+/// Build the key of a file's line map in `LINE_MAP_CACHE`
+fn line_map_cache_key(content: &str, extension: &str, allow_tests: bool) -> String {
+    format!(
+        "{}_{}_{}",
+        extension,
+        calculate_content_hash(content),
+        allow_tests
+    )
+}
+
+/// Get a copy of the line map computed for this content, if any
+pub(crate) fn cached_line_map(
+    content: &str,
+    extension: &str,
+    allow_tests: bool,
+) -> Option<LineMap> {
+    LINE_MAP_CACHE
+        .get(&line_map_cache_key(content, extension, allow_tests))
+        .map(|entry| entry.value().clone())
+}
+
+/// Seed the line map cache with a map computed for the same content in an earlier run
+pub(crate) fn seed_line_map(content: &str, extension: &str, allow_tests: bool, line_map: LineMap) {
+    LINE_MAP_CACHE.insert(
+        line_map_cache_key(content, extension, allow_tests),
+        line_map,
+    );
+}
+
 /// Function to parse a file and extract code blocks for the given line numbers
 pub fn parse_file_for_code_blocks(
     content: &str,
@@ -938,8 +971,7 @@ pub fn parse_file_for_code_blocks(
     let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
 
     // Calculate content hash for cache key
-    let content_hash = calculate_content_hash(content);
-    let cache_key = format!("{}_{}_{}", extension, content_hash, allow_tests);
+    let cache_key = line_map_cache_key(content, extension, allow_tests);
 
     // Check if we have a cached line map
     if let Some(cached_entry) = LINE_MAP_CACHE.get(&cache_key) {
@@ -1480,7 +1512,7 @@ pub fn parse_file_for_code_blocks(
     // ====================================================================
 
     // Convert the original line_map to a cacheable format with representative node info
-    let cacheable_line_map: Vec<Option<CachedNodeInfo>> = line_map
+    let cacheable_line_map: LineMap = line_map
         .iter()
         .map(|opt_node_info| {
             opt_node_info.map(|node_info| {
@@ -1,4 +1,5 @@
-use super::language_trait::LanguageImpl;
+use super::common::{module_segments, string_literal_text};
+use super::language_trait::{Import, LanguageImpl};
 use tree_sitter::{Language as TSLanguage, Node};
 
 /// Implementation of LanguageImpl for PHP
@@ -66,4 +67,29 @@ impl LanguageImpl for PhpLanguage {
 
         false
     }
+
+    fn find_imports(&self, node: &Node, source: &[u8]) -> Vec<Import> {
+        match node.kind() {
+            // use App\Models\User;
+            "namespace_use_clause" => {
+                let mut cursor = node.walk();
+                let name = node
+                    .named_children(&mut cursor)
+                    .find(|child| matches!(child.kind(), "qualified_name" | "name"));
+                name.and_then(|name| name.utf8_text(source).ok())
+                    .map(|name| vec![Import::Module(module_segments(name, "\\"))])
+                    .unwrap_or_default()
+            }
+            "require_expression"
+            | "require_once_expression"
+            | "include_expression"
+            | "include_once_expression" => node
+                .named_child(0)
+                .filter(|path| matches!(path.kind(), "string" | "encapsed_string"))
+                .and_then(|path| string_literal_text(&path, source))
+                .map(|path| vec![Import::Path(path)])
+                .unwrap_or_default(),
+            _ => Vec::new(),
+        }
+    }
 }
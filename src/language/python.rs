@@ -1,4 +1,5 @@
-use super::language_trait::LanguageImpl;
+use super::common::module_segments;
+use super::language_trait::{Import, LanguageImpl};
 use tree_sitter::{Language as TSLanguage, Node};
 
 /// Implementation of LanguageImpl for Python
@@ -51,4 +52,65 @@ impl LanguageImpl for PythonLanguage {
 
         false
     }
+
+    fn find_imports(&self, node: &Node, source: &[u8]) -> Vec<Import> {
+        let text = |node: Node| node.utf8_text(source).ok().map(str::to_string);
+        match node.kind() {
+            // import a.b, c as d
+            "import_statement" => {
+                let mut cursor = node.walk();
+                node.children_by_field_name("name", &mut cursor)
+                    .filter_map(|name| {
+                        let name = name.child_by_field_name("name").unwrap_or(name);
+                        text(name).map(|name| Import::Module(module_segments(&name, ".")))
+                    })
+                    .collect()
+            }
+            // from a.b import c, d / from .a import b
+            "import_from_statement" => {
+                let Some(module) = node.child_by_field_name("module_name").and_then(text) else {
+                    return Vec::new();
+                };
+                let mut cursor = node.walk();
+                let names: Vec<String> = node
+                    .children_by_field_name("name", &mut cursor)
+                    .filter_map(|name| text(name.child_by_field_name("name").unwrap_or(name)))
+                    .collect();
+
+                let dots = module.chars().take_while(|&c| c == '.').count();
+                if dots == 0 {
+                    // The names may be submodules; otherwise the module itself matches
+                    let segments = module_segments(&module, ".");
+                    if names.is_empty() {
+                        return vec![Import::Module(segments)];
+                    }
+                    return names
+                        .iter()
+                        .map(|name| {
+                            let mut segments = segments.clone();
+                            segments.extend(module_segments(name, "."));
+                            Import::Module(segments)
+                        })
+                        .collect();
+                }
+
+                let base = if dots == 1 {
+                    ".".to_string()
+                } else {
+                    vec![".."; dots - 1].join("/")
+                };
+                let rest = module_segments(&module[dots..], ".").join("/");
+                if rest.is_empty() {
+                    // from . import a, b imports sibling modules
+                    names
+                        .iter()
+                        .map(|name| Import::Path(format!("{}/{}", base, name)))
+                        .collect()
+                } else {
+                    vec![Import::Path(format!("{}/{}", base, rest))]
+                }
+            }
+            _ => Vec::new(),
+        }
+    }
 }
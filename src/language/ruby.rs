@@ -1,4 +1,5 @@
-use super::language_trait::LanguageImpl;
+use super::common::{path_or_module_import, string_literal_text};
+use super::language_trait::{Import, LanguageImpl};
 use tree_sitter::{Language as TSLanguage, Node};
 
 /// Implementation of LanguageImpl for Ruby
@@ -68,4 +69,30 @@ impl LanguageImpl for RubyLanguage {
 
         false
     }
+
+    fn find_imports(&self, node: &Node, source: &[u8]) -> Vec<Import> {
+        if node.kind() != "call" {
+            return Vec::new();
+        }
+        let method = node
+            .child_by_field_name("method")
+            .and_then(|method| method.utf8_text(source).ok());
+        let Some(method @ ("require" | "require_relative" | "load")) = method else {
+            return Vec::new();
+        };
+        let Some(path) = node
+            .child_by_field_name("arguments")
+            .and_then(|arguments| arguments.named_child(0))
+            .filter(|argument| argument.kind() == "string")
+            .and_then(|argument| string_literal_text(&argument, source))
+        else {
+            return Vec::new();
+        };
+
+        if method == "require_relative" && !path.starts_with('.') {
+            vec![Import::Path(format!("./{}", path))]
+        } else {
+            vec![path_or_module_import(&path)]
+        }
+    }
 }
@@ -1,4 +1,5 @@
-use super::language_trait::LanguageImpl;
+use super::common::module_segments;
+use super::language_trait::{Import, LanguageImpl};
 use tree_sitter::{Language as TSLanguage, Node};
 
 /// Implementation of LanguageImpl for Rust
@@ -139,4 +140,80 @@ impl LanguageImpl for RustLanguage {
 
         false
     }
+
+    fn find_imports(&self, node: &Node, source: &[u8]) -> Vec<Import> {
+        match node.kind() {
+            "use_declaration" => {
+                let Some(path) = node
+                    .child_by_field_name("argument")
+                    .and_then(|argument| argument.utf8_text(source).ok())
+                else {
+                    return Vec::new();
+                };
+                use_paths(path)
+                    .into_iter()
+                    .filter_map(|path| {
+                        let segments = module_segments(&path, "::");
+                        match segments.first().map(String::as_str) {
+                            // Paths from the crate root or relative to this module are local
+                            Some("crate" | "self" | "super") if segments.len() > 1 => {
+                                Some(Import::LocalModule(segments[1..].to_vec()))
+                            }
+                            Some("crate" | "self" | "super") => None,
+                            Some(_) => Some(Import::Module(segments)),
+                            None => None,
+                        }
+                    })
+                    .collect()
+            }
+            // `mod name;` pulls in name.rs or name/mod.rs next to the declaring file
+            "mod_item" if node.child_by_field_name("body").is_none() => node
+                .child_by_field_name("name")
+                .and_then(|name| name.utf8_text(source).ok())
+                .map(|name| vec![Import::Path(format!("./{}", name))])
+                .unwrap_or_default(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Expand the use list of a `use` path: `a::{b, c::d}` becomes `a::b` and `a::c::d`
+fn use_paths(path: &str) -> Vec<String> {
+    let (Some(open), Some(close)) = (path.find('{'), path.rfind('}')) else {
+        return vec![strip_alias(path).to_string()];
+    };
+    let prefix = &path[..open];
+    let mut paths = Vec::new();
+    let mut depth = 0;
+    let mut start = open + 1;
+    for (i, c) in path[..close].char_indices().skip_while(|(i, _)| *i <= open) {
+        match c {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            ',' if depth == 0 => {
+                paths.push(&path[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    paths.push(&path[start..close]);
+
+    paths
+        .into_iter()
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .flat_map(|item| match item {
+            "self" => vec![prefix.trim_end_matches("::").to_string()],
+            _ => use_paths(item)
+                .into_iter()
+                .map(|item| format!("{}{}", prefix, item))
+                .collect(),
+        })
+        .collect()
+}
+
+/// Drop the `as name` of a renamed import
+fn strip_alias(path: &str) -> &str {
+    path.split(" as ").next().unwrap_or(path).trim()
 }
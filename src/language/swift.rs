@@ -1,4 +1,5 @@
-use super::language_trait::LanguageImpl;
+use super::common::module_segments;
+use super::language_trait::{Import, LanguageImpl};
 use tree_sitter::{Language as TSLanguage, Node};
 
 /// Implementation of LanguageImpl for Swift
@@ -109,4 +110,16 @@ impl LanguageImpl for SwiftLanguage {
 
         None
     }
+
+    fn find_imports(&self, node: &Node, source: &[u8]) -> Vec<Import> {
+        if node.kind() != "import_declaration" {
+            return Vec::new();
+        }
+        // import Foundation / import struct Module.Type
+        node.utf8_text(source)
+            .ok()
+            .and_then(|text| text.split_whitespace().last())
+            .map(|module| vec![Import::Module(module_segments(module, "."))])
+            .unwrap_or_default()
+    }
 }
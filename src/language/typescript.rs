@@ -1,4 +1,4 @@
-use super::language_trait::LanguageImpl;
+use super::language_trait::{Import, LanguageImpl};
 use tree_sitter::{Language as TSLanguage, Node};
 
 /// Implementation of LanguageImpl for TypeScript
@@ -96,4 +96,8 @@ impl LanguageImpl for TypeScriptLanguage {
 
         false
     }
+
+    fn find_imports(&self, node: &Node, source: &[u8]) -> Vec<Import> {
+        super::javascript::javascript_imports(node, source)
+    }
 }
@@ -6,21 +6,29 @@
 // Make the library available as `probe` within itself
 extern crate self as probe;
 
+pub mod config;
 pub mod extract;
 pub mod language;
+pub mod lsp;
+pub mod mcp;
 pub mod models;
 pub mod path_resolver;
 pub mod query;
 pub mod ranking;
 pub mod search;
+pub mod serve;
 
 // Re-export commonly used types for convenience
 pub use extract::{
     format_and_print_extraction_results, handle_extract, process_file_for_extraction,
+    ExtractOptions,
 };
 pub use models::{CodeBlock, LimitedSearchResults, SearchLimits, SearchResult};
 pub use path_resolver::resolve_path;
-pub use query::{format_and_print_query_results, perform_query, AstMatch, QueryOptions};
-pub use search::perform_probe;
+pub use query::{
+    format_and_print_query_results, format_query_results, perform_query, AsQueryOptions, AstMatch,
+    QueryOptions, QueryRequest,
+};
+pub use search::{perform_probe, AsSearchOptions, SearchOptions, SearchRequest};
 
 // Tests are defined in their respective modules with #[cfg(test)]
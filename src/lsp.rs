@@ -0,0 +1,587 @@
+//! Language Server Protocol server.
+//!
+//! `probe lsp` speaks LSP over stdio using `Content-Length` framed JSON-RPC messages.
+//! It answers `workspace/symbol` and `textDocument/definition` from the tree-sitter
+//! symbols found by `extract::symbol_finder`, and a custom `probe/search` request that
+//! runs a ranked search and returns the results as `Location`s.
+
+use anyhow::{Context, Result};
+use rayon::prelude::*;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+
+use crate::config::{configure_search, ConfigOverrides};
+use crate::extract::symbol_finder::{list_symbols_in_file, FileSymbol};
+use crate::language::factory::get_language_impl;
+use crate::search::cache::file_mtime_nanos;
+use crate::search::file_list_cache::get_file_list;
+use crate::search::timeout::CancellationToken;
+use crate::search::{perform_probe, SearchRequest};
+
+/// Maximum number of symbols returned for a `workspace/symbol` request
+const MAX_WORKSPACE_SYMBOLS: usize = 500;
+
+// JSON-RPC error codes
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const INTERNAL_ERROR: i64 = -32603;
+
+/// Serve LSP requests from stdin until the client sends `exit` or closes stdin
+pub fn handle_lsp() -> Result<()> {
+    let stdin = io::stdin();
+    let mut reader = stdin.lock();
+    let mut stdout = io::stdout();
+    let mut server = LspServer::new(std::env::current_dir()?);
+
+    while let Some(body) = read_message(&mut reader)? {
+        let response = match serde_json::from_slice::<Value>(&body) {
+            Ok(message) => {
+                if message.get("method").and_then(Value::as_str) == Some("exit") {
+                    break;
+                }
+                server.handle_message(&message)
+            }
+            Err(e) => Some(error_response(
+                Value::Null,
+                PARSE_ERROR,
+                &format!("Parse error: {}", e),
+            )),
+        };
+
+        if let Some(response) = response {
+            write_message(&mut stdout, &response)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Read one `Content-Length` framed message. Returns `None` when the input is closed.
+fn read_message(reader: &mut impl BufRead) -> Result<Option<Vec<u8>>> {
+    let mut content_length = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+
+        let line = line.trim_end();
+        if line.is_empty() {
+            if content_length.is_some() {
+                break;
+            }
+            continue;
+        }
+
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("Content-Length") {
+                content_length = Some(
+                    value
+                        .trim()
+                        .parse::<usize>()
+                        .context("Invalid Content-Length header")?,
+                );
+            }
+        }
+    }
+
+    let mut body = vec![0; content_length.unwrap_or(0)];
+    reader.read_exact(&mut body)?;
+    Ok(Some(body))
+}
+
+fn write_message(writer: &mut impl Write, message: &Value) -> Result<()> {
+    let body = message.to_string();
+    write!(writer, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    writer.flush()?;
+    Ok(())
+}
+
+fn error_response(id: Value, code: i64, message: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message }
+    })
+}
+
+/// A symbol together with its LSP ranges
+#[derive(Debug, Clone)]
+struct LspSymbol {
+    symbol: FileSymbol,
+    range: Value,
+    selection_range: Value,
+}
+
+/// Symbols of one file, with the stamp they were computed for
+struct CachedSymbols {
+    /// Modification time of the file on disk, or `None` for an open document's text
+    mtime: Option<u64>,
+    symbols: Vec<LspSymbol>,
+}
+
+/// State of one LSP session
+pub struct LspServer {
+    root: PathBuf,
+    /// Text of the documents the client has open, which may differ from the disk
+    documents: HashMap<PathBuf, String>,
+    symbols: HashMap<PathBuf, CachedSymbols>,
+    shutdown: bool,
+}
+
+impl LspServer {
+    /// Create a server for the workspace at `root`; `initialize` may replace the root
+    pub fn new(root: PathBuf) -> Self {
+        Self {
+            root,
+            documents: HashMap::new(),
+            symbols: HashMap::new(),
+            shutdown: false,
+        }
+    }
+
+    /// Handle one JSON-RPC message. Notifications (messages without an id) get no response.
+    pub fn handle_message(&mut self, message: &Value) -> Option<Value> {
+        let id = message.get("id").cloned();
+        let Some(method) = message.get("method").and_then(Value::as_str) else {
+            return id.map(|id| error_response(id, INVALID_REQUEST, "Invalid request"));
+        };
+        let params = message.get("params").cloned().unwrap_or(Value::Null);
+
+        let Some(id) = id else {
+            self.handle_notification(method, &params);
+            return None;
+        };
+
+        if self.shutdown {
+            return Some(error_response(
+                id,
+                INVALID_REQUEST,
+                "Server is shutting down",
+            ));
+        }
+
+        let result = match method {
+            "initialize" => Ok(self.initialize(&params)),
+            "shutdown" => {
+                self.shutdown = true;
+                Ok(Value::Null)
+            }
+            "workspace/symbol" => self.workspace_symbol(&params),
+            "textDocument/definition" => self.definition(&params),
+            "probe/search" => self.search(&params),
+            _ => Err((METHOD_NOT_FOUND, format!("Method not found: {}", method))),
+        };
+
+        Some(match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, message)) => error_response(id, code, &message),
+        })
+    }
+
+    fn handle_notification(&mut self, method: &str, params: &Value) {
+        let Some(path) = params
+            .pointer("/textDocument/uri")
+            .and_then(Value::as_str)
+            .and_then(uri_to_path)
+        else {
+            return;
+        };
+
+        match method {
+            "textDocument/didOpen" => {
+                if let Some(text) = params.pointer("/textDocument/text").and_then(Value::as_str) {
+                    self.documents.insert(path.clone(), text.to_string());
+                }
+            }
+            "textDocument/didChange" => {
+                // We only ask for full document sync, so the last change holds the whole text
+                if let Some(text) = params
+                    .get("contentChanges")
+                    .and_then(Value::as_array)
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change.get("text"))
+                    .and_then(Value::as_str)
+                {
+                    self.documents.insert(path.clone(), text.to_string());
+                }
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(&path);
+            }
+            _ => return,
+        }
+
+        self.symbols.remove(&path);
+    }
+
+    fn initialize(&mut self, params: &Value) -> Value {
+        let root = params
+            .get("rootUri")
+            .and_then(Value::as_str)
+            .and_then(uri_to_path)
+            .or_else(|| {
+                params
+                    .pointer("/workspaceFolders/0/uri")
+                    .and_then(Value::as_str)
+                    .and_then(uri_to_path)
+            })
+            .or_else(|| {
+                params
+                    .get("rootPath")
+                    .and_then(Value::as_str)
+                    .map(PathBuf::from)
+            });
+        if let Some(root) = root {
+            self.root = root;
+        }
+
+        json!({
+            "capabilities": {
+                // Full document sync
+                "textDocumentSync": 1,
+                "workspaceSymbolProvider": true,
+                "definitionProvider": true,
+                "experimental": { "probeSearch": true }
+            },
+            "serverInfo": {
+                "name": "probe",
+                "version": env!("CARGO_PKG_VERSION")
+            }
+        })
+    }
+
+    /// Get the text of a file, preferring the client's unsaved copy
+    fn document_text(&self, path: &Path) -> Result<String> {
+        match self.documents.get(path) {
+            Some(text) => Ok(text.clone()),
+            None => Ok(fs::read_to_string(path)?),
+        }
+    }
+
+    /// Make sure the symbols of every given file are cached and current
+    fn refresh_symbols(&mut self, files: &[PathBuf]) {
+        let stale: Vec<(PathBuf, Option<u64>)> = files
+            .iter()
+            .filter(|path| {
+                path.extension()
+                    .and_then(|ext| ext.to_str())
+                    .and_then(get_language_impl)
+                    .is_some()
+            })
+            .filter_map(|path| {
+                let mtime = if self.documents.contains_key(path) {
+                    None
+                } else {
+                    Some(fs::metadata(path).ok().map(|m| file_mtime_nanos(&m))?)
+                };
+                match self.symbols.get(path) {
+                    Some(cached) if cached.mtime == mtime => None,
+                    _ => Some((path.clone(), mtime)),
+                }
+            })
+            .collect();
+
+        let computed: Vec<(PathBuf, CachedSymbols)> = stale
+            .into_par_iter()
+            .filter_map(|(path, mtime)| {
+                let text = self.document_text(&path).ok()?;
+                let symbols = lsp_symbols(&path, &text);
+                Some((path, CachedSymbols { mtime, symbols }))
+            })
+            .collect();
+
+        self.symbols.extend(computed);
+    }
+
+    fn workspace_symbol(&mut self, params: &Value) -> std::result::Result<Value, (i64, String)> {
+        let query = params
+            .get("query")
+            .and_then(Value::as_str)
+            .unwrap_or("")
+            .to_lowercase();
+
+        let file_list = get_file_list(&self.root, true, &[], &CancellationToken::new())
+            .map_err(|e| (INTERNAL_ERROR, e.to_string()))?;
+        self.refresh_symbols(&file_list.files);
+
+        let mut matches: Vec<(&PathBuf, &LspSymbol)> = file_list
+            .files
+            .iter()
+            .filter_map(|path| Some((path, self.symbols.get(path)?)))
+            .flat_map(|(path, cached)| cached.symbols.iter().map(move |s| (path, s)))
+            .filter(|(_, s)| s.symbol.name.to_lowercase().contains(&query))
+            .collect();
+
+        // Exact matches first, then prefix matches, then shorter names
+        matches.sort_by_key(|(path, s)| {
+            let name = s.symbol.name.to_lowercase();
+            (name != query, !name.starts_with(&query), name.len(), *path)
+        });
+
+        let symbols: Vec<Value> = matches
+            .into_iter()
+            .take(MAX_WORKSPACE_SYMBOLS)
+            .map(|(path, s)| {
+                let mut info = json!({
+                    "name": s.symbol.name,
+                    "kind": symbol_kind(&s.symbol),
+                    "location": { "uri": path_to_uri(path), "range": s.range }
+                });
+                if let Some(container) = &s.symbol.container {
+                    info["containerName"] = json!(container);
+                }
+                info
+            })
+            .collect();
+
+        Ok(json!(symbols))
+    }
+
+    /// Best-effort definition lookup: find declarations named like the word under the
+    /// cursor, first in the same file and then across the workspace.
+    fn definition(&mut self, params: &Value) -> std::result::Result<Value, (i64, String)> {
+        let path = params
+            .pointer("/textDocument/uri")
+            .and_then(Value::as_str)
+            .and_then(uri_to_path)
+            .ok_or((INVALID_PARAMS, "Missing textDocument.uri".to_string()))?;
+        let line = params.pointer("/position/line").and_then(Value::as_u64);
+        let character = params
+            .pointer("/position/character")
+            .and_then(Value::as_u64);
+        let (Some(line), Some(character)) = (line, character) else {
+            return Err((INVALID_PARAMS, "Missing position".to_string()));
+        };
+
+        let text = self
+            .document_text(&path)
+            .map_err(|e| (INTERNAL_ERROR, e.to_string()))?;
+        let Some(word) = word_at(&text, line as usize, character as usize) else {
+            return Ok(Value::Null);
+        };
+
+        self.refresh_symbols(std::slice::from_ref(&path));
+        let mut locations = self.locations_named(&path, word);
+
+        if locations.is_empty() {
+            let file_list = get_file_list(&self.root, true, &[], &CancellationToken::new())
+                .map_err(|e| (INTERNAL_ERROR, e.to_string()))?;
+            self.refresh_symbols(&file_list.files);
+            for file in &file_list.files {
+                locations.extend(self.locations_named(file, word));
+            }
+        }
+
+        if locations.is_empty() {
+            Ok(Value::Null)
+        } else {
+            Ok(json!(locations))
+        }
+    }
+
+    fn locations_named(&self, path: &Path, name: &str) -> Vec<Value> {
+        self.symbols
+            .get(path)
+            .map(|cached| {
+                cached
+                    .symbols
+                    .iter()
+                    .filter(|s| s.symbol.name == name)
+                    .map(|s| json!({ "uri": path_to_uri(path), "range": s.selection_range }))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Run a ranked search and return the results as `Location`s, best first
+    fn search(&mut self, params: &Value) -> std::result::Result<Value, (i64, String)> {
+        let query = params
+            .get("query")
+            .and_then(Value::as_str)
+            .filter(|q| !q.trim().is_empty())
+            .ok_or((INVALID_PARAMS, "Query is required".to_string()))?;
+        let path = params
+            .get("path")
+            .and_then(Value::as_str)
+            .map(|p| uri_to_path(p).unwrap_or_else(|| PathBuf::from(p)))
+            .unwrap_or_else(|| self.root.clone());
+        let usize_param = |key: &str| params.get(key).and_then(Value::as_u64).map(|n| n as usize);
+        let bool_param = |key: &str| params.get(key).and_then(Value::as_bool).unwrap_or(false);
+
+        let request = SearchRequest {
+            paths: vec![path],
+            queries: vec![query.to_string()],
+            exclude_filenames: bool_param("excludeFilenames"),
+            reranker: params
+                .get("reranker")
+                .and_then(Value::as_str)
+                .unwrap_or("hybrid")
+                .to_string(),
+            exact: bool_param("exact"),
+            language: params
+                .get("language")
+                .and_then(Value::as_str)
+                .map(str::to_string),
+            max_results: usize_param("maxResults"),
+            max_bytes: usize_param("maxBytes"),
+            max_tokens: usize_param("maxTokens"),
+            allow_tests: bool_param("allowTests"),
+            timeout: usize_param("timeout").unwrap_or(30) as u64,
+            ..SearchRequest::default()
+        };
+        let request = configure_search(request, &ConfigOverrides::default())
+            .map_err(|e| (INTERNAL_ERROR, e.to_string()))?;
+
+        let limited_results =
+            perform_probe(&request).map_err(|e| (INTERNAL_ERROR, e.to_string()))?;
+
+        let locations: Vec<Value> = limited_results
+            .results
+            .iter()
+            .map(|result| {
+                let file = PathBuf::from(&result.file);
+                let file = fs::canonicalize(&file).unwrap_or(file);
+                let (start, end) = result.lines;
+                let last_line = result.code.lines().last().unwrap_or("");
+                json!({
+                    "uri": path_to_uri(&file),
+                    "range": {
+                        "start": { "line": start.saturating_sub(1), "character": 0 },
+                        "end": {
+                            "line": end.saturating_sub(1),
+                            "character": last_line.encode_utf16().count()
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        Ok(json!(locations))
+    }
+}
+
+/// List the symbols of a file and compute their LSP ranges
+fn lsp_symbols(path: &Path, text: &str) -> Vec<LspSymbol> {
+    let Ok(symbols) = list_symbols_in_file(path, text, true) else {
+        return Vec::new();
+    };
+    let lines: Vec<&str> = text.lines().collect();
+    symbols
+        .into_iter()
+        .map(|symbol| LspSymbol {
+            range: lsp_range(&lines, symbol.start, symbol.end),
+            selection_range: lsp_range(&lines, symbol.name_start, symbol.name_end),
+            symbol,
+        })
+        .collect()
+}
+
+/// Convert tree-sitter (row, byte column) points to an LSP range in UTF-16 code units
+fn lsp_range(lines: &[&str], start: (usize, usize), end: (usize, usize)) -> Value {
+    let position = |(row, column): (usize, usize)| {
+        let line = lines.get(row).copied().unwrap_or("");
+        let prefix = line.get(..column).unwrap_or(line);
+        json!({ "line": row, "character": prefix.encode_utf16().count() })
+    };
+    json!({ "start": position(start), "end": position(end) })
+}
+
+/// Map a declaration's node type to an LSP `SymbolKind`
+fn symbol_kind(symbol: &FileSymbol) -> u32 {
+    let kind = symbol.kind.as_str();
+    if kind.contains("method") || (kind.contains("function") && symbol.container.is_some()) {
+        6 // Method
+    } else if kind.contains("function") || kind.contains("macro") {
+        12 // Function
+    } else if kind.contains("struct") {
+        23 // Struct
+    } else if kind.contains("class") {
+        5 // Class
+    } else if kind.contains("interface") || kind.contains("trait") || kind.contains("protocol") {
+        11 // Interface
+    } else if kind.contains("enum") {
+        10 // Enum
+    } else if kind.contains("mod") || kind.contains("namespace") {
+        3 // Namespace
+    } else if kind.contains("type") {
+        5 // Class
+    } else {
+        13 // Variable
+    }
+}
+
+/// Find the identifier around an LSP position (UTF-16 character offset)
+fn word_at(text: &str, line: usize, character: usize) -> Option<&str> {
+    let line = text.lines().nth(line)?;
+
+    // Convert the UTF-16 offset to a byte offset
+    let mut units = 0;
+    let mut offset = line.len();
+    for (index, c) in line.char_indices() {
+        if units >= character {
+            offset = index;
+            break;
+        }
+        units += c.len_utf16();
+    }
+
+    let is_word = |c: char| c.is_alphanumeric() || c == '_' || c == '$';
+    let start = line[..offset]
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| is_word(*c))
+        .last()
+        .map_or(offset, |(index, _)| index);
+    let end = line[offset..]
+        .char_indices()
+        .find(|(_, c)| !is_word(*c))
+        .map_or(line.len(), |(index, _)| offset + index);
+
+    if start == end {
+        None
+    } else {
+        Some(&line[start..end])
+    }
+}
+
+/// Convert a `file://` URI to a path
+fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    let encoded = uri.strip_prefix("file://")?;
+    let bytes = encoded.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            if let Some(Ok(byte)) = encoded
+                .get(i + 1..i + 3)
+                .map(|hex| u8::from_str_radix(hex, 16))
+            {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    Some(PathBuf::from(String::from_utf8(decoded).ok()?))
+}
+
+/// Convert a path to a `file://` URI
+fn path_to_uri(path: &Path) -> String {
+    let mut uri = String::from("file://");
+    for byte in path.to_string_lossy().bytes() {
+        if byte.is_ascii_alphanumeric() || b"/-._~".contains(&byte) {
+            uri.push(byte as char);
+        } else {
+            uri.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    uri
+}
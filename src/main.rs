@@ -1,10 +1,11 @@
 use anyhow::Result;
 use clap::{CommandFactory, Parser as ClapParser};
 use colored::*;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::Instant;
 
 mod cli;
+mod config;
 mod extract;
 mod language;
 mod models;
@@ -13,7 +14,12 @@ mod ranking;
 mod search;
 
 use cli::{Args, Commands};
-use search::{format_and_print_search_results, perform_probe, SearchOptions};
+use ranking::StemLanguage;
+use search::tokenization;
+use search::{
+    format_and_print_search_results, format_search_event, perform_probe, perform_probe_with_events,
+    AsSearchOptions, RankingOptions, SearchRequest,
+};
 
 struct SearchParams {
     pattern: String,
@@ -22,8 +28,26 @@ struct SearchParams {
     ignore: Vec<String>,
     exclude_filenames: bool,
     reranker: String,
+    definition_boost: f64,
+    boost_recent: Option<f64>,
+    diversity: f64,
+    max_per_file: Option<usize>,
+    centrality_weight: f64,
+    explain: bool,
+    bm25_k1: Option<f64>,
+    bm25_b: Option<f64>,
+    filename_weight: Option<f64>,
     frequency_search: bool,
     exact: bool,
+    fuzzy: Option<usize>,
+    synonyms: Option<PathBuf>,
+    builtin_synonyms: bool,
+    no_synonyms: bool,
+    stop_words: Vec<String>,
+    not_stop_words: Vec<String>,
+    exception_terms: Vec<String>,
+    compound_words: Vec<String>,
+    stem_language: Option<StemLanguage>,
     language: Option<String>,
     max_results: Option<usize>,
     max_bytes: Option<usize>,
@@ -35,16 +59,90 @@ struct SearchParams {
     format: String,
     session: Option<String>,
     timeout: u64,
+    stream: bool,
+}
+
+/// What the command line sets that takes precedence over the project configuration
+fn config_overrides(params: &SearchParams) -> config::ConfigOverrides {
+    config::ConfigOverrides {
+        bm25_k1: params.bm25_k1,
+        bm25_b: params.bm25_b,
+        filename_weight: params.filename_weight,
+        synonyms_file: params.synonyms.clone(),
+        builtin_synonyms: params.builtin_synonyms,
+        no_synonyms: params.no_synonyms,
+        vocabulary: tokenization::VocabularyOverrides {
+            stop_words: params.stop_words.clone(),
+            not_stop_words: params.not_stop_words.clone(),
+            exception_terms: params.exception_terms.clone(),
+            compound_words: params.compound_words.clone(),
+            stem_language: params.stem_language,
+        },
+    }
+}
+
+/// The search the command line asks for, with the project configuration applied
+fn search_request(params: &SearchParams) -> Result<SearchRequest> {
+    let request = SearchRequest {
+        paths: params.paths.clone(),
+        queries: vec![params.pattern.clone()],
+        files_only: params.files_only,
+        custom_ignores: params.ignore.clone(),
+        exclude_filenames: params.exclude_filenames,
+        reranker: params.reranker.clone(),
+        frequency_search: params.frequency_search,
+        exact: params.exact,
+        fuzzy: params.fuzzy,
+        synonyms: None,
+        vocabulary: None,
+        language: params.language.clone(),
+        max_results: params.max_results,
+        max_bytes: params.max_bytes,
+        max_tokens: params.max_tokens,
+        allow_tests: params.allow_tests,
+        no_merge: params.no_merge,
+        merge_threshold: params.merge_threshold,
+        dry_run: params.dry_run,
+        session: params.session.clone(),
+        timeout: params.timeout,
+        ranking: RankingOptions {
+            definition_boost: params.definition_boost,
+            recency_half_life_days: params.boost_recent,
+            diversity: params.diversity,
+            max_per_file: params.max_per_file,
+            centrality_weight: params.centrality_weight,
+            explain: params.explain,
+            bm25: ranking::Bm25Params::default(),
+        },
+    };
+    config::configure_search(request, &config_overrides(params))
 }
 
 fn handle_search(params: SearchParams) -> Result<()> {
+    if params.stream {
+        return handle_stream_search(params);
+    }
+
     let use_frequency = params.frequency_search;
+    let request = search_request(&params)?;
+    let bm25 = request.ranking.bm25;
+    let vocabulary = request.vocabulary.clone().unwrap_or_default();
 
     println!("{} {}", "Pattern:".bold().green(), params.pattern);
+    let path_label = if params.paths.len() > 1 {
+        "Paths:"
+    } else {
+        "Path:"
+    };
     println!(
         "{} {}",
-        "Path:".bold().green(),
-        params.paths.first().unwrap().display()
+        path_label.bold().green(),
+        params
+            .paths
+            .iter()
+            .map(|p| p.display().to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
     );
 
     // Show advanced options if they differ from defaults
@@ -55,12 +153,59 @@ fn handle_search(params: SearchParams) -> Result<()> {
     if params.exclude_filenames {
         advanced_options.push("Exclude filenames".to_string());
     }
-    if params.reranker != "hybrid" {
+    if params.reranker != "bm25" {
         advanced_options.push(format!("Reranker: {}", params.reranker));
     }
+    if params.definition_boost != RankingOptions::default().definition_boost {
+        advanced_options.push(format!("Definition boost: {}", params.definition_boost));
+    }
+    if let Some(half_life) = params.boost_recent {
+        advanced_options.push(format!("Boost recent: {} day half-life", half_life));
+    }
+    if params.diversity > 0.0 {
+        advanced_options.push(format!("Diversity: {}", params.diversity));
+    }
+    if let Some(max_per_file) = params.max_per_file {
+        advanced_options.push(format!("Max per file: {}", max_per_file));
+    }
+    if params.centrality_weight > 0.0 {
+        advanced_options.push(format!("Centrality weight: {}", params.centrality_weight));
+    }
+    if params.explain {
+        advanced_options.push("Explain ranking".to_string());
+    }
+    if bm25 != ranking::Bm25Params::default() {
+        advanced_options.push(format!(
+            "BM25: k1 {}, b {}, filename weight {}",
+            bm25.k1, bm25.b, bm25.filename_weight
+        ));
+    }
     if !use_frequency {
         advanced_options.push("Frequency search disabled".to_string());
     }
+    if let Some(distance) = params.fuzzy {
+        advanced_options.push(format!("Fuzzy: {} edit(s)", distance));
+    }
+    if params.no_synonyms {
+        advanced_options.push("Synonyms disabled".to_string());
+    } else {
+        if params.builtin_synonyms {
+            advanced_options.push("Built-in synonyms".to_string());
+        }
+        if let Some(file) = &params.synonyms {
+            advanced_options.push(format!("Synonyms: {}", file.display()));
+        }
+    }
+    let word_lists = tokenization::VocabularyOverrides {
+        stem_language: None,
+        ..vocabulary.clone()
+    };
+    if !word_lists.is_empty() {
+        advanced_options.push("Custom vocabulary".to_string());
+    }
+    if let Some(language) = vocabulary.stem_language {
+        advanced_options.push(format!("Stemming: {}", language));
+    }
     if let Some(lang) = &params.language {
         advanced_options.push(format!("Language: {}", lang));
     }
@@ -95,50 +240,33 @@ fn handle_search(params: SearchParams) -> Result<()> {
 
     let start_time = Instant::now();
 
-    // Create a vector with the pattern
-    let query = vec![params.pattern.clone()];
-
-    let search_options = SearchOptions {
-        path: params.paths.first().unwrap(),
-        queries: &query,
-        files_only: params.files_only,
-        custom_ignores: &params.ignore,
-        exclude_filenames: params.exclude_filenames,
-        reranker: &params.reranker,
-        frequency_search: use_frequency,
-        exact: params.exact,
-        language: params.language.as_deref(),
-        max_results: params.max_results,
-        max_bytes: params.max_bytes,
-        max_tokens: params.max_tokens,
-        allow_tests: params.allow_tests,
-        no_merge: params.no_merge,
-        merge_threshold: params.merge_threshold,
-        dry_run: params.dry_run,
-        session: params.session.as_deref(),
-        timeout: params.timeout,
-    };
+    let limited_results = perform_probe(&request)?;
 
-    let limited_results = perform_probe(&search_options)?;
+    // Print the session ID to the console if it was generated or provided
+    if let Some(session_id) = &limited_results.session_id {
+        if limited_results.session_generated {
+            println!(
+                "Session ID: {} (generated - ALWAYS USE IT in future sessions for caching)",
+                session_id
+            );
+        } else {
+            println!("Session ID: {}", session_id);
+        }
+    }
 
     // Calculate search time
     let duration = start_time.elapsed();
 
-    // Create the query plan regardless of whether we have results
-    let query_plan = if search_options.queries.len() > 1 {
-        // Join multiple queries with AND
-        let combined_query = search_options.queries.join(" AND ");
-        crate::search::query::create_query_plan(&combined_query, false).ok()
-    } else {
-        crate::search::query::create_query_plan(&search_options.queries[0], false).ok()
-    };
+    // Create the query plan regardless of whether we have results, from the queries as
+    // searched for (after synonym expansion and `--fuzzy`)
+    let query_plan = request.as_search_options().query_plan();
 
     if limited_results.results.is_empty() {
         // For JSON and XML formats, still call format_and_print_search_results
         if params.format == "json" || params.format == "xml" {
             format_and_print_search_results(
                 &limited_results.results,
-                search_options.dry_run,
+                request.dry_run,
                 &params.format,
                 query_plan.as_ref(),
             );
@@ -156,7 +284,7 @@ fn handle_search(params: SearchParams) -> Result<()> {
 
         format_and_print_search_results(
             &limited_results.results,
-            search_options.dry_run,
+            request.dry_run,
             &params.format,
             query_plan.as_ref(),
         );
@@ -197,6 +325,84 @@ fn handle_search(params: SearchParams) -> Result<()> {
         }
     }
 
+    // Partial results were printed above; still report the timeout and fail
+    if limited_results.timed_out {
+        eprintln!(
+            "{}",
+            format!(
+                "Search operation timed out after {} seconds, results are partial",
+                params.timeout
+            )
+            .red()
+            .bold()
+        );
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
+
+/// Run a search printing every progress event and per-file batch as a line of JSON,
+/// followed by the ranked results
+fn handle_stream_search(params: SearchParams) -> Result<()> {
+    let request = search_request(&params)?;
+
+    let limited_results = perform_probe_with_events(&request, &mut |event| {
+        println!("{}", format_search_event(event));
+    })?;
+
+    if limited_results.timed_out {
+        eprintln!(
+            "{}",
+            format!(
+                "Search operation timed out after {} seconds, results are partial",
+                params.timeout
+            )
+            .red()
+            .bold()
+        );
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
+
+fn handle_index(paths: &[PathBuf]) -> Result<()> {
+    for path in paths {
+        println!("{} {}", "Indexing:".bold().green(), path.display());
+
+        let start_time = Instant::now();
+        // Tokenize with the project's vocabulary, so searches with it can use the statistics
+        let vocabulary = config::ProjectConfig::for_path(path)?.vocabulary;
+        let index =
+            tokenization::with_vocabulary(&vocabulary, || search::index::SearchIndex::build(path))?;
+        let index_file = index.save()?;
+
+        println!(
+            "Indexed {} files ({} distinct words, {:.1} tokens per file on average) in {:.2}s",
+            index.files.len(),
+            index.postings.len(),
+            index.average_doc_length(),
+            start_time.elapsed().as_secs_f64()
+        );
+        println!("{} {}", "Index written to:".bold(), index_file.display());
+    }
+
+    Ok(())
+}
+
+fn handle_graph(path: &Path, format: &str, allow_tests: bool, ignore: &[String]) -> Result<()> {
+    let graph = search::import_graph::ImportGraph::build(
+        path,
+        allow_tests,
+        ignore,
+        &search::timeout::CancellationToken::new(),
+    )?;
+    if format == "dot" {
+        print!("{}", graph.to_dot());
+    } else {
+        println!("{}", serde_json::to_string_pretty(&graph.to_json())?);
+    }
     Ok(())
 }
 
@@ -229,8 +435,26 @@ async fn main() -> Result<()> {
                 ignore: args.ignore,
                 exclude_filenames: args.exclude_filenames,
                 reranker: args.reranker,
+                definition_boost: args.definition_boost,
+                boost_recent: args.boost_recent,
+                diversity: args.diversity,
+                max_per_file: args.max_per_file,
+                centrality_weight: args.centrality_weight,
+                explain: args.explain,
+                bm25_k1: args.bm25_k1,
+                bm25_b: args.bm25_b,
+                filename_weight: args.filename_weight,
                 frequency_search: args.frequency_search,
                 exact: args.exact,
+                fuzzy: args.fuzzy,
+                synonyms: args.synonyms,
+                builtin_synonyms: args.builtin_synonyms,
+                no_synonyms: args.no_synonyms,
+                stop_words: args.stop_words,
+                not_stop_words: args.not_stop_words,
+                exception_terms: args.exception_terms,
+                compound_words: args.compound_words,
+                stem_language: args.stem_language,
                 language: None, // Default to None for the no-subcommand case
                 max_results: args.max_results,
                 max_bytes: args.max_bytes,
@@ -242,6 +466,7 @@ async fn main() -> Result<()> {
                 format: args.format,
                 session: args.session,
                 timeout: args.timeout,
+                stream: args.stream,
             })?
         }
         Some(Commands::Search {
@@ -251,8 +476,26 @@ async fn main() -> Result<()> {
             ignore,
             exclude_filenames,
             reranker,
+            definition_boost,
+            boost_recent,
+            diversity,
+            max_per_file,
+            centrality_weight,
+            explain,
+            bm25_k1,
+            bm25_b,
+            filename_weight,
             frequency_search,
             exact,
+            fuzzy,
+            synonyms,
+            builtin_synonyms,
+            no_synonyms,
+            stop_words,
+            not_stop_words,
+            exception_terms,
+            compound_words,
+            stem_language,
             language,
             max_results,
             max_bytes,
@@ -264,6 +507,7 @@ async fn main() -> Result<()> {
             format,
             session,
             timeout,
+            stream,
         }) => handle_search(SearchParams {
             pattern,
             paths,
@@ -271,8 +515,26 @@ async fn main() -> Result<()> {
             ignore,
             exclude_filenames,
             reranker,
+            definition_boost,
+            boost_recent,
+            diversity,
+            max_per_file,
+            centrality_weight,
+            explain,
+            bm25_k1,
+            bm25_b,
+            filename_weight,
             frequency_search,
             exact,
+            fuzzy,
+            synonyms,
+            builtin_synonyms,
+            no_synonyms,
+            stop_words,
+            not_stop_words,
+            exception_terms,
+            compound_words,
+            stem_language,
             language,
             max_results,
             max_bytes,
@@ -284,6 +546,7 @@ async fn main() -> Result<()> {
             format,
             session,
             timeout,
+            stream,
         })?,
         Some(Commands::Extract {
             files,
@@ -319,6 +582,16 @@ async fn main() -> Result<()> {
             }),
             instructions,
         })?,
+        Some(Commands::Index { paths }) => handle_index(&paths)?,
+        Some(Commands::Graph {
+            path,
+            format,
+            allow_tests,
+            ignore,
+        }) => handle_graph(&path, &format, allow_tests, &ignore)?,
+        Some(Commands::Mcp) => probe::mcp::handle_mcp()?,
+        Some(Commands::Lsp) => probe::lsp::handle_lsp()?,
+        Some(Commands::Serve { port, host }) => probe::serve::handle_serve(&host, port)?,
         Some(Commands::Query {
             pattern,
             path,
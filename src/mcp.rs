@@ -0,0 +1,434 @@
+//! Model Context Protocol server.
+//!
+//! `probe mcp` speaks MCP JSON-RPC over stdio: one JSON message per line on stdin, one
+//! response per line on stdout. Tool calls run the search, query and extract pipelines
+//! in-process, so the parse and file-list caches stay warm between calls.
+
+use anyhow::{anyhow, Result};
+use serde_json::{json, Value};
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+
+use crate::config::{configure_search, ConfigOverrides};
+use crate::extract::{extract_file_paths, format_extraction_results, parse_file_with_line};
+use crate::models::SearchResult;
+use crate::query::{format_query_results, perform_query, QueryOptions};
+use crate::search::{format_search_results, perform_probe, AsSearchOptions, SearchRequest};
+
+/// Protocol version answered when the client does not ask for one
+const PROTOCOL_VERSION: &str = "2024-11-05";
+
+// JSON-RPC error codes
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// Serve MCP requests from stdin until it is closed
+pub fn handle_mcp() -> Result<()> {
+    // Responses are read by a program, never by a terminal
+    colored::control::set_override(false);
+
+    let stdin = io::stdin();
+    let mut stdout = io::stdout();
+
+    for line in stdin.lock().lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = match serde_json::from_str::<Value>(&line) {
+            Ok(message) => handle_message(&message),
+            Err(e) => Some(error_response(
+                Value::Null,
+                PARSE_ERROR,
+                &format!("Parse error: {}", e),
+            )),
+        };
+
+        if let Some(response) = response {
+            writeln!(stdout, "{}", response)?;
+            stdout.flush()?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Handle one JSON-RPC message. Notifications (messages without an id) get no response.
+pub fn handle_message(message: &Value) -> Option<Value> {
+    let id = message.get("id").cloned();
+    let Some(method) = message.get("method").and_then(Value::as_str) else {
+        return id.map(|id| error_response(id, INVALID_REQUEST, "Invalid request"));
+    };
+    let id = id?;
+    let params = message.get("params").cloned().unwrap_or(Value::Null);
+
+    let result = match method {
+        "initialize" => Ok(initialize_result(&params)),
+        "ping" => Ok(json!({})),
+        "tools/list" => Ok(json!({ "tools": tool_definitions() })),
+        "tools/call" => call_tool(&params),
+        _ => Err((METHOD_NOT_FOUND, format!("Method not found: {}", method))),
+    };
+
+    Some(match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err((code, message)) => error_response(id, code, &message),
+    })
+}
+
+fn error_response(id: Value, code: i64, message: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message }
+    })
+}
+
+fn initialize_result(params: &Value) -> Value {
+    let protocol_version = params
+        .get("protocolVersion")
+        .and_then(Value::as_str)
+        .unwrap_or(PROTOCOL_VERSION);
+
+    json!({
+        "protocolVersion": protocol_version,
+        "capabilities": { "tools": {} },
+        "serverInfo": {
+            "name": "probe",
+            "version": env!("CARGO_PKG_VERSION")
+        }
+    })
+}
+
+fn tool_definitions() -> Value {
+    json!([
+        {
+            "name": "search_code",
+            "description": "Search code in the repository using ElasticSearch. Use this tool first for any code-related questions.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "Absolute path to the directory to search in (e.g., \"/Users/username/projects/myproject\")."
+                    },
+                    "query": {
+                        "type": "string",
+                        "description": "Elastic search query. Supports logical operators (AND, OR, NOT), and grouping with parentheses. Examples: \"config\", \"(term1 OR term2) AND term3\". Use quotes for exact matches, like function or type names."
+                    },
+                    "filesOnly": {
+                        "type": "boolean",
+                        "description": "Skip AST parsing and just output unique files"
+                    },
+                    "ignore": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Custom patterns to ignore (in addition to .gitignore and common patterns)"
+                    },
+                    "excludeFilenames": {
+                        "type": "boolean",
+                        "description": "Exclude filenames from being used for matching"
+                    },
+                    "allowTests": {
+                        "type": "boolean",
+                        "description": "Allow test files and test code blocks in results (disabled by default)"
+                    },
+                    "maxResults": {
+                        "type": "number",
+                        "description": "Maximum number of results to return"
+                    },
+                    "maxTokens": {
+                        "type": "number",
+                        "description": "Maximum number of tokens to return",
+                        "default": 10000
+                    },
+                    "session": {
+                        "type": "string",
+                        "description": "Session identifier for caching. Set to \"new\" if unknown, or want to reset cache. Re-use session ID returned from previous searches",
+                        "default": "new"
+                    },
+                    "timeout": {
+                        "type": "number",
+                        "description": "Seconds to search for before returning the results found so far",
+                        "default": 30
+                    }
+                },
+                "required": ["query"]
+            }
+        },
+        {
+            "name": "query_code",
+            "description": "Search code using ast-grep structural pattern matching. Use this tool to find specific code structures like functions, classes, or methods.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "Absolute path to the directory to search in (e.g., \"/Users/username/projects/myproject\")."
+                    },
+                    "pattern": {
+                        "type": "string",
+                        "description": "The ast-grep pattern to search for. Examples: \"fn $NAME($$$PARAMS) $$$BODY\" for Rust functions, \"def $NAME($$$PARAMS): $$$BODY\" for Python functions."
+                    },
+                    "language": {
+                        "type": "string",
+                        "description": "The programming language to search in. If not specified, the tool will try to infer the language from file extensions. Supported languages: rust, javascript, typescript, python, go, c, cpp, java, ruby, php, swift, csharp."
+                    },
+                    "ignore": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Custom patterns to ignore (in addition to common patterns)"
+                    },
+                    "allowTests": {
+                        "type": "boolean",
+                        "description": "Allow test files and test code blocks in results (disabled by default)"
+                    },
+                    "maxResults": {
+                        "type": "number",
+                        "description": "Maximum number of results to return"
+                    },
+                    "format": {
+                        "type": "string",
+                        "enum": ["markdown", "plain", "json", "color"],
+                        "description": "Output format for the query results"
+                    }
+                },
+                "required": ["pattern"]
+            }
+        },
+        {
+            "name": "extract_code",
+            "description": "Extract code blocks from files based on line number, or symbol name. Fetch full file when line number is not provided.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "Absolute path to the directory to search in (e.g., \"/Users/username/projects/myproject\")."
+                    },
+                    "files": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Files and lines or symbols to extract from: /path/to/file.rs:10, /path/to/file.rs#func_name. Relative paths are resolved against path."
+                    },
+                    "allowTests": {
+                        "type": "boolean",
+                        "description": "Allow test files and test code blocks in results (disabled by default)"
+                    },
+                    "contextLines": {
+                        "type": "number",
+                        "description": "Number of context lines to include before and after the extracted block when AST parsing fails to find a suitable node",
+                        "default": 0
+                    },
+                    "format": {
+                        "type": "string",
+                        "enum": ["markdown", "plain", "json"],
+                        "description": "Output format for the extracted code",
+                        "default": "markdown"
+                    }
+                },
+                "required": ["path", "files"]
+            }
+        }
+    ])
+}
+
+/// Run a tool. Failures of the tool itself are reported as an `isError` result, so the
+/// model sees them; only an unknown tool is a protocol error.
+fn call_tool(params: &Value) -> std::result::Result<Value, (i64, String)> {
+    let name = params
+        .get("name")
+        .and_then(Value::as_str)
+        .ok_or((INVALID_PARAMS, "Missing tool name".to_string()))?;
+    let arguments = params.get("arguments").cloned().unwrap_or(json!({}));
+
+    let output = match name {
+        "search_code" | "probe" => search_code(&arguments),
+        "query_code" | "query" => query_code(&arguments),
+        "extract_code" | "extract" => extract_code(&arguments),
+        _ => return Err((INVALID_PARAMS, format!("Unknown tool: {}", name))),
+    };
+
+    Ok(match output {
+        Ok(text) => json!({ "content": [{ "type": "text", "text": text }] }),
+        Err(e) => json!({
+            "content": [{ "type": "text", "text": format!("Error executing {}: {}", name, e) }],
+            "isError": true
+        }),
+    })
+}
+
+pub(crate) fn str_arg<'a>(arguments: &'a Value, key: &str) -> Option<&'a str> {
+    arguments
+        .get(key)
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+}
+
+pub(crate) fn bool_arg(arguments: &Value, key: &str) -> bool {
+    arguments.get(key).and_then(Value::as_bool).unwrap_or(false)
+}
+
+pub(crate) fn usize_arg(arguments: &Value, key: &str) -> Option<usize> {
+    arguments
+        .get(key)
+        .and_then(Value::as_f64)
+        .filter(|n| *n >= 0.0)
+        .map(|n| n as usize)
+}
+
+pub(crate) fn strings_arg(arguments: &Value, key: &str) -> Vec<String> {
+    arguments
+        .get(key)
+        .and_then(Value::as_array)
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(Value::as_str)
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+pub(crate) fn path_arg(arguments: &Value) -> PathBuf {
+    PathBuf::from(str_arg(arguments, "path").unwrap_or("."))
+}
+
+fn search_code(arguments: &Value) -> Result<String> {
+    let query = str_arg(arguments, "query").ok_or_else(|| anyhow!("Query is required"))?;
+    let session = str_arg(arguments, "session").unwrap_or("new");
+
+    let request = SearchRequest {
+        paths: vec![path_arg(arguments)],
+        queries: vec![query.to_string()],
+        files_only: bool_arg(arguments, "filesOnly"),
+        custom_ignores: strings_arg(arguments, "ignore"),
+        exclude_filenames: bool_arg(arguments, "excludeFilenames"),
+        reranker: "hybrid".to_string(),
+        max_results: usize_arg(arguments, "maxResults"),
+        max_tokens: Some(usize_arg(arguments, "maxTokens").unwrap_or(10000)),
+        allow_tests: bool_arg(arguments, "allowTests"),
+        session: Some(session.to_string()),
+        timeout: usize_arg(arguments, "timeout").unwrap_or(30) as u64,
+        ..SearchRequest::default()
+    };
+    let request = configure_search(request, &ConfigOverrides::default())?;
+
+    let limited_results = perform_probe(&request)?;
+
+    let mut text = String::new();
+    if let Some(session_id) = &limited_results.session_id {
+        if limited_results.session_generated {
+            text.push_str(&format!(
+                "Session ID: {} (generated - ALWAYS USE IT in future sessions for caching)\n",
+                session_id
+            ));
+        } else {
+            text.push_str(&format!("Session ID: {}\n", session_id));
+        }
+    }
+
+    if limited_results.results.is_empty() {
+        text.push_str("No results found.\n");
+    } else {
+        let query_plan = request.as_search_options().query_plan();
+        text.push_str(&format_search_results(
+            &limited_results.results,
+            false,
+            "terminal",
+            query_plan.as_ref(),
+        ));
+    }
+
+    if limited_results.timed_out {
+        text.push_str(&format!(
+            "\nSearch operation timed out after {} seconds, results are partial\n",
+            request.timeout
+        ));
+    }
+
+    Ok(text)
+}
+
+fn query_code(arguments: &Value) -> Result<String> {
+    let pattern = str_arg(arguments, "pattern").ok_or_else(|| anyhow!("Pattern is required"))?;
+    let path = path_arg(arguments);
+    let ignore = strings_arg(arguments, "ignore");
+    let format = str_arg(arguments, "format").unwrap_or("plain");
+
+    let options = QueryOptions {
+        path: &path,
+        pattern,
+        language: str_arg(arguments, "language"),
+        ignore: &ignore,
+        allow_tests: bool_arg(arguments, "allowTests"),
+        max_results: usize_arg(arguments, "maxResults"),
+        format,
+    };
+
+    let matches = perform_query(&options)?;
+    if matches.is_empty() && format != "json" {
+        return Ok("No results found.\n".to_string());
+    }
+
+    format_query_results(&matches, format)
+}
+
+fn extract_code(arguments: &Value) -> Result<String> {
+    let format = str_arg(arguments, "format").unwrap_or("markdown");
+    let (results, errors) = run_extract(arguments, format)?;
+
+    let mut text = if results.is_empty() {
+        String::from("No results found.\n")
+    } else {
+        format_extraction_results(&results, format, None, None, None)?
+    };
+
+    if !errors.is_empty() {
+        text.push_str(&format!("\nEncountered {} error(s):\n", errors.len()));
+        for error in &errors {
+            text.push_str(&format!("  {}\n", error));
+        }
+    }
+
+    Ok(text)
+}
+
+/// Extract the `files` of an extract_code call, resolving relative paths against `path`.
+/// Returns the extracted blocks and a message for every file that could not be processed.
+pub(crate) fn run_extract(
+    arguments: &Value,
+    format: &str,
+) -> Result<(Vec<SearchResult>, Vec<String>)> {
+    let files = strings_arg(arguments, "files");
+    if files.is_empty() {
+        return Err(anyhow!("Files array is required and must not be empty"));
+    }
+    let path = path_arg(arguments);
+    let allow_tests = bool_arg(arguments, "allowTests");
+    let context_lines = usize_arg(arguments, "contextLines").unwrap_or(0);
+
+    let file_paths = files
+        .iter()
+        .flat_map(|file| {
+            let file = if Path::new(file).is_absolute() {
+                file.clone()
+            } else {
+                path.join(file).to_string_lossy().into_owned()
+            };
+            parse_file_with_line(&file, allow_tests)
+        })
+        .collect::<Vec<_>>();
+
+    Ok(extract_file_paths(
+        file_paths,
+        allow_tests,
+        context_lines,
+        format,
+    ))
+}
@@ -1,3 +1,5 @@
+use serde::Serialize;
+
 // Structure to hold both limited search results and skipped files
 #[derive(Debug)]
 pub struct LimitedSearchResults {
@@ -5,6 +7,12 @@ pub struct LimitedSearchResults {
     pub skipped_files: Vec<SearchResult>,
     pub limits_applied: Option<SearchLimits>,
     pub cached_blocks_skipped: Option<usize>,
+    // Whether the search hit its timeout, in which case the results are partial
+    pub timed_out: bool,
+    // The session ID used for caching, if any
+    pub session_id: Option<String>,
+    // Whether the session ID was generated for this search (and should be reused)
+    pub session_generated: bool,
 }
 
 // Structure to track which limits were applied
@@ -21,7 +29,7 @@ pub struct SearchLimits {
 }
 
 // Structure to hold search results
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct SearchResult {
     pub file: String,
     pub lines: (usize, usize),
@@ -67,6 +75,71 @@ pub struct SearchResult {
     /// Tokenized version of the code block with filename prepended
     #[allow(dead_code)]
     pub tokenized_content: Option<Vec<String>>,
+    // The search root (as given on the command line) this result was found under
+    pub search_root: Option<String>,
+    // Name the block itself declares (function, struct, class, ...), from tree-sitter
+    pub symbol_name: Option<String>,
+    // Boost applied to the score because the block defines a searched symbol
+    pub definition_boost: Option<f64>,
+    // Boost applied to the score because the file changed recently or often in git
+    pub recency_boost: Option<f64>,
+    // Import-graph centrality of the file relative to the most central file (0 to 1)
+    pub centrality: Option<f64>,
+    // Why the result ranked where it did (only filled in with --explain)
+    pub explanation: Option<RankingExplanation>,
+}
+
+// Breakdown of how a result's score came about, for --explain
+#[derive(Debug, Clone, Serialize)]
+pub struct RankingExplanation {
+    // The query as written; the BM25 terms include its synonym and fuzzy expansions
+    pub query: String,
+    // Reranker that scored the result, and its score before any boosts
+    pub reranker: String,
+    pub reranker_score: f64,
+    // BM25 breakdown of the block, whichever reranker was used
+    pub bm25: Option<Bm25Explanation>,
+    // Multipliers applied to the reranker score
+    pub definition_boost: Option<f64>,
+    pub recency_boost: Option<f64>,
+    pub centrality_boost: Option<f64>,
+    // Score and rank after the boosts, before merging
+    pub score: f64,
+    pub rank: usize,
+    // Blocks that were merged into this result after ranking (empty if none)
+    pub merged_blocks: Vec<MergedBlock>,
+}
+
+// BM25 statistics of one block
+#[derive(Debug, Clone, Serialize)]
+pub struct Bm25Explanation {
+    pub score: f64,
+    // Number of tokens in the block (filename included, weighted by filename_weight) and
+    // the average over all blocks, or over all indexed files when a search index is used
+    pub doc_length: f64,
+    pub avgdl: f64,
+    pub k1: f64,
+    pub b: f64,
+    pub filename_weight: f64,
+    pub terms: Vec<TermExplanation>,
+}
+
+// Contribution of one query term to a block's BM25 score
+#[derive(Debug, Clone, Serialize)]
+pub struct TermExplanation {
+    pub term: String,
+    // Occurrences in the block, with filename matches weighted by filename_weight
+    pub tf: f64,
+    pub idf: f64,
+    pub score: f64,
+}
+
+// A block that went into a merged result
+#[derive(Debug, Clone, Serialize)]
+pub struct MergedBlock {
+    pub lines: (usize, usize),
+    pub rank: Option<usize>,
+    pub score: Option<f64>,
 }
 
 // Structure to hold node information for merging
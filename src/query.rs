@@ -5,10 +5,16 @@ use colored::*;
 use ignore::Walk;
 use probe::path_resolver::resolve_path;
 use rayon::prelude::*; // Added import
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 use std::time::Instant;
 
+// The panic hook is process-wide, so queries that swap it run one at a time
+static PANIC_HOOK_LOCK: Mutex<()> = Mutex::new(());
+
 /// Represents a match found by ast-grep
 pub struct AstMatch {
     pub file_path: PathBuf,
@@ -20,6 +26,7 @@ pub struct AstMatch {
 }
 
 /// Options for the ast-grep query
+#[derive(Debug, Clone, Copy)]
 pub struct QueryOptions<'a> {
     pub path: &'a Path,
     pub pattern: &'a str,
@@ -31,6 +38,102 @@ pub struct QueryOptions<'a> {
     pub format: &'a str,
 }
 
+/// Anything `perform_query` can run: the borrowed `QueryOptions` or an owned `QueryRequest`
+pub trait AsQueryOptions {
+    fn as_query_options(&self) -> QueryOptions<'_>;
+}
+
+impl AsQueryOptions for QueryOptions<'_> {
+    fn as_query_options(&self) -> QueryOptions<'_> {
+        *self
+    }
+}
+
+/// Owned counterpart of `QueryOptions` that can be stored, sent across threads and
+/// (de)serialized. Defaults match the CLI.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+#[allow(dead_code)]
+pub struct QueryRequest {
+    pub path: PathBuf,
+    pub pattern: String,
+    pub language: Option<String>,
+    pub ignore: Vec<String>,
+    pub allow_tests: bool,
+    pub max_results: Option<usize>,
+    pub format: String,
+}
+
+impl Default for QueryRequest {
+    fn default() -> Self {
+        Self {
+            path: PathBuf::from("."),
+            pattern: String::new(),
+            language: None,
+            ignore: Vec::new(),
+            allow_tests: false,
+            max_results: None,
+            format: "color".to_string(),
+        }
+    }
+}
+
+#[allow(dead_code)]
+impl QueryRequest {
+    /// Create a request for `pattern` in the current directory with the CLI defaults
+    pub fn new(pattern: impl Into<String>) -> Self {
+        Self {
+            pattern: pattern.into(),
+            ..Self::default()
+        }
+    }
+
+    pub fn path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.path = path.into();
+        self
+    }
+
+    pub fn language(mut self, language: impl Into<String>) -> Self {
+        self.language = Some(language.into());
+        self
+    }
+
+    /// Add a pattern to ignore in addition to .gitignore and common patterns
+    pub fn ignore(mut self, pattern: impl Into<String>) -> Self {
+        self.ignore.push(pattern.into());
+        self
+    }
+
+    pub fn allow_tests(mut self, allow_tests: bool) -> Self {
+        self.allow_tests = allow_tests;
+        self
+    }
+
+    pub fn max_results(mut self, max_results: usize) -> Self {
+        self.max_results = Some(max_results);
+        self
+    }
+
+    pub fn format(mut self, format: impl Into<String>) -> Self {
+        self.format = format.into();
+        self
+    }
+}
+
+impl AsQueryOptions for QueryRequest {
+    fn as_query_options(&self) -> QueryOptions<'_> {
+        QueryOptions {
+            path: &self.path,
+            pattern: &self.pattern,
+            language: self.language.as_deref(),
+            ignore: &self.ignore,
+            allow_tests: self.allow_tests,
+            max_results: self.max_results,
+            format: &self.format,
+        }
+    }
+}
+
 /// Convert a language string to the corresponding SupportLang
 fn get_language(lang: &str) -> Option<SupportLang> {
     match lang.to_lowercase().as_str() {
@@ -214,11 +317,16 @@ fn query_file(file_path: &Path, options: &QueryOptions) -> Result<Vec<AstMatch>>
     Ok(ast_matches)
 }
 
-pub fn perform_query(options: &QueryOptions) -> Result<Vec<AstMatch>> {
+/// Run an ast-grep query. Accepts either borrowed `&QueryOptions` or an owned `&QueryRequest`.
+pub fn perform_query(options: &(impl AsQueryOptions + ?Sized)) -> Result<Vec<AstMatch>> {
+    let options = &options.as_query_options();
+
     // Suppress panic output if language is not specified
     let suppress_output = options.language.is_none();
 
     // Set a custom panic hook to suppress panic messages if needed
+    let _hook_guard =
+        suppress_output.then(|| PANIC_HOOK_LOCK.lock().unwrap_or_else(|e| e.into_inner()));
     let original_hook = if suppress_output {
         let original_hook = std::panic::take_hook();
         std::panic::set_hook(Box::new(|_| {
@@ -304,10 +412,18 @@ fn escape_xml(s: &str) -> String {
 
 /// Format and print the query results
 pub fn format_and_print_query_results(matches: &[AstMatch], format: &str) -> Result<()> {
+    print!("{}", format_query_results(matches, format)?);
+    Ok(())
+}
+
+/// Format the query results, returning the text `format_and_print_query_results` would print
+pub fn format_query_results(matches: &[AstMatch], format: &str) -> Result<String> {
+    let mut out = String::new();
     match format {
         "color" | "terminal" => {
             for m in matches {
-                println!(
+                writeln!(
+                    out,
                     "{}",
                     format!(
                         "{}:{}:{}",
@@ -316,31 +432,33 @@ pub fn format_and_print_query_results(matches: &[AstMatch], format: &str) -> Res
                         m.column_start
                     )
                     .cyan()
-                );
-                println!("{}", m.matched_text.trim());
-                println!();
+                )?;
+                writeln!(out, "{}", m.matched_text.trim())?;
+                writeln!(out)?;
             }
         }
         "plain" => {
             for m in matches {
-                println!(
+                writeln!(
+                    out,
                     "{}:{}:{}",
                     m.file_path.display(),
                     m.line_start,
                     m.column_start
-                );
-                println!("{}", m.matched_text.trim());
-                println!();
+                )?;
+                writeln!(out, "{}", m.matched_text.trim())?;
+                writeln!(out)?;
             }
         }
         "markdown" => {
             for m in matches {
-                println!(
+                writeln!(
+                    out,
                     "**{}:{}:{}**",
                     m.file_path.display(),
                     m.line_start,
                     m.column_start
-                );
+                )?;
 
                 // Determine language for code block
                 let lang = m
@@ -349,10 +467,10 @@ pub fn format_and_print_query_results(matches: &[AstMatch], format: &str) -> Res
                     .and_then(|e| e.to_str())
                     .unwrap_or("");
 
-                println!("```{}", lang);
-                println!("{}", m.matched_text.trim());
-                println!("```");
-                println!();
+                writeln!(out, "```{}", lang)?;
+                writeln!(out, "{}", m.matched_text.trim())?;
+                writeln!(out, "```")?;
+                writeln!(out)?;
             }
         }
         "json" => {
@@ -388,54 +506,61 @@ pub fn format_and_print_query_results(matches: &[AstMatch], format: &str) -> Res
                 }
             });
 
-            println!("{}", serde_json::to_string_pretty(&wrapper)?);
+            writeln!(out, "{}", serde_json::to_string_pretty(&wrapper)?)?;
         }
         "xml" => {
-            println!("<?xml version=\"1.0\" encoding=\"UTF-8\"?>");
-            println!("<probe_results>");
+            writeln!(out, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+            writeln!(out, "<probe_results>")?;
 
             for m in matches {
-                println!("  <result>");
-                println!(
+                writeln!(out, "  <result>")?;
+                writeln!(
+                    out,
                     "    <file>{}</file>",
                     escape_xml(&m.file_path.to_string_lossy())
-                );
-                println!("    <lines>{}-{}</lines>", m.line_start, m.line_end);
-                println!("    <node_type>match</node_type>");
-                println!("    <column_start>{}</column_start>", m.column_start);
-                println!("    <column_end>{}</column_end>", m.column_end);
-                println!("    <code><![CDATA[{}]]></code>", m.matched_text.trim());
-                println!("  </result>");
+                )?;
+                writeln!(out, "    <lines>{}-{}</lines>", m.line_start, m.line_end)?;
+                writeln!(out, "    <node_type>match</node_type>")?;
+                writeln!(out, "    <column_start>{}</column_start>", m.column_start)?;
+                writeln!(out, "    <column_end>{}</column_end>", m.column_end)?;
+                writeln!(
+                    out,
+                    "    <code><![CDATA[{}]]></code>",
+                    m.matched_text.trim()
+                )?;
+                writeln!(out, "  </result>")?;
             }
 
             // Add summary section
-            println!("  <summary>");
-            println!("    <count>{}</count>", matches.len());
-            println!(
+            writeln!(out, "  <summary>")?;
+            writeln!(out, "    <count>{}</count>", matches.len())?;
+            writeln!(
+                out,
                 "    <total_bytes>{}</total_bytes>",
                 matches.iter().map(|m| m.matched_text.len()).sum::<usize>()
-            );
+            )?;
 
             // Import the count_tokens function locally to avoid unused import warning
             use crate::search::search_tokens::count_tokens;
-            println!(
+            writeln!(
+                out,
                 "    <total_tokens>{}</total_tokens>",
                 matches
                     .iter()
                     .map(|m| count_tokens(&m.matched_text))
                     .sum::<usize>()
-            );
-            println!("  </summary>");
+            )?;
+            writeln!(out, "  </summary>")?;
 
-            println!("</probe_results>");
+            writeln!(out, "</probe_results>")?;
         }
         _ => {
             // Default to color format
-            format_and_print_query_results(matches, "color")?;
+            out = format_query_results(matches, "color")?;
         }
     }
 
-    Ok(())
+    Ok(out)
 }
 
 /// Handle the query command
@@ -1,8 +1,14 @@
-use crate::search::elastic_query::Expr;
+use crate::models::{Bm25Explanation, SearchResult, TermExplanation};
+use crate::search::command_reranker::{CommandReranker, COMMAND_RERANKER_PREFIX};
+use crate::search::elastic_query::{proximity_span, Expr};
+use crate::search::index::SearchIndex;
 use crate::search::tokenization;
 use ahash::{AHashMap, AHashSet};
 use rust_stemmers::{Algorithm, Stemmer};
-use std::sync::OnceLock;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::path::Path;
+use std::sync::Arc;
 
 // Replace standard collections with ahash versions for better performance
 type HashMap<K, V> = AHashMap<K, V>;
@@ -29,12 +35,213 @@ pub struct RankingParams<'a> {
     pub query: &'a str,
     /// Pre-tokenized content (optional)
     pub pre_tokenized: Option<&'a [Vec<String>]>,
+    /// File name of each document, whose tokens are weighted by `bm25.filename_weight`
+    /// (optional; without them every token counts once)
+    pub filenames: Option<&'a [&'a str]>,
+    /// BM25 parameters
+    pub bm25: Bm25Params,
+    /// Statistics from the search index to use instead of the documents' own (optional)
+    pub index: Option<&'a IndexCorpus>,
 }
 
-/// Returns a reference to the global stemmer instance
-pub fn get_stemmer() -> &'static Stemmer {
-    static STEMMER: OnceLock<Stemmer> = OnceLock::new();
-    STEMMER.get_or_init(|| Stemmer::create(Algorithm::English))
+/// Statistics of the search index (see `probe index`) that BM25 uses when every ranked
+/// document comes from an unchanged indexed file: the IDF and average document length of
+/// all indexed files, and the term frequencies of documents that span a whole file
+pub struct IndexCorpus {
+    pub index: Arc<SearchIndex>,
+    /// For each document, the id of the indexed file it spans entirely, if it does
+    pub whole_files: Vec<Option<usize>>,
+}
+
+/// Tunable BM25 parameters
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Bm25Params {
+    /// Term frequency saturation: higher values give repeated terms more weight
+    pub k1: f64,
+    /// Document length normalization, from 0 (none) to 1 (full): higher values penalize
+    /// long documents more
+    pub b: f64,
+    /// How many times a term in the file name counts compared to one in the code
+    pub filename_weight: f64,
+}
+
+impl Default for Bm25Params {
+    // These values are standard defaults for BM25 as established in academic literature:
+    // k1=1.2 controls term frequency saturation (higher values give more weight to term frequency)
+    // b=0.75 controls document length normalization (higher values give more penalty to longer documents)
+    // See: Robertson, S. E., & Zaragoza, H. (2009). The Probabilistic Relevance Framework: BM25 and Beyond
+    fn default() -> Self {
+        Self {
+            k1: 1.2,
+            b: 0.75,
+            filename_weight: 1.0,
+        }
+    }
+}
+
+impl Bm25Params {
+    /// Check that the parameters are in range
+    pub fn validate(&self) -> Result<(), String> {
+        if !(self.k1 >= 0.0 && self.k1.is_finite()) {
+            return Err(format!("BM25 k1 must be 0 or more, got {}", self.k1));
+        }
+        if !(0.0..=1.0).contains(&self.b) {
+            return Err(format!("BM25 b must be between 0 and 1, got {}", self.b));
+        }
+        if !(self.filename_weight >= 0.0 && self.filename_weight.is_finite()) {
+            return Err(format!(
+                "Filename weight must be 0 or more, got {}",
+                self.filename_weight
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Language whose stemmer reduces query and code words to their stems, chosen with
+/// `--stem-language` or `stem_language` in the `vocabulary` section of `.probe.json`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StemLanguage {
+    Arabic,
+    Danish,
+    Dutch,
+    #[default]
+    English,
+    Finnish,
+    French,
+    German,
+    Greek,
+    Hungarian,
+    Italian,
+    Norwegian,
+    Portuguese,
+    Romanian,
+    Russian,
+    Spanish,
+    Swedish,
+    Tamil,
+    Turkish,
+    /// Words are matched as written, apart from lowercasing and splitting
+    None,
+}
+
+impl StemLanguage {
+    /// Every language, in the order `--stem-language` lists them
+    pub const ALL: &'static [StemLanguage] = &[
+        StemLanguage::Arabic,
+        StemLanguage::Danish,
+        StemLanguage::Dutch,
+        StemLanguage::English,
+        StemLanguage::Finnish,
+        StemLanguage::French,
+        StemLanguage::German,
+        StemLanguage::Greek,
+        StemLanguage::Hungarian,
+        StemLanguage::Italian,
+        StemLanguage::Norwegian,
+        StemLanguage::Portuguese,
+        StemLanguage::Romanian,
+        StemLanguage::Russian,
+        StemLanguage::Spanish,
+        StemLanguage::Swedish,
+        StemLanguage::Tamil,
+        StemLanguage::Turkish,
+        StemLanguage::None,
+    ];
+
+    /// Name the language is selected by
+    pub fn name(self) -> &'static str {
+        match self {
+            StemLanguage::Arabic => "arabic",
+            StemLanguage::Danish => "danish",
+            StemLanguage::Dutch => "dutch",
+            StemLanguage::English => "english",
+            StemLanguage::Finnish => "finnish",
+            StemLanguage::French => "french",
+            StemLanguage::German => "german",
+            StemLanguage::Greek => "greek",
+            StemLanguage::Hungarian => "hungarian",
+            StemLanguage::Italian => "italian",
+            StemLanguage::Norwegian => "norwegian",
+            StemLanguage::Portuguese => "portuguese",
+            StemLanguage::Romanian => "romanian",
+            StemLanguage::Russian => "russian",
+            StemLanguage::Spanish => "spanish",
+            StemLanguage::Swedish => "swedish",
+            StemLanguage::Tamil => "tamil",
+            StemLanguage::Turkish => "turkish",
+            StemLanguage::None => "none",
+        }
+    }
+
+    fn algorithm(self) -> Option<Algorithm> {
+        Some(match self {
+            StemLanguage::Arabic => Algorithm::Arabic,
+            StemLanguage::Danish => Algorithm::Danish,
+            StemLanguage::Dutch => Algorithm::Dutch,
+            StemLanguage::English => Algorithm::English,
+            StemLanguage::Finnish => Algorithm::Finnish,
+            StemLanguage::French => Algorithm::French,
+            StemLanguage::German => Algorithm::German,
+            StemLanguage::Greek => Algorithm::Greek,
+            StemLanguage::Hungarian => Algorithm::Hungarian,
+            StemLanguage::Italian => Algorithm::Italian,
+            StemLanguage::Norwegian => Algorithm::Norwegian,
+            StemLanguage::Portuguese => Algorithm::Portuguese,
+            StemLanguage::Romanian => Algorithm::Romanian,
+            StemLanguage::Russian => Algorithm::Russian,
+            StemLanguage::Spanish => Algorithm::Spanish,
+            StemLanguage::Swedish => Algorithm::Swedish,
+            StemLanguage::Tamil => Algorithm::Tamil,
+            StemLanguage::Turkish => Algorithm::Turkish,
+            StemLanguage::None => return None,
+        })
+    }
+}
+
+impl std::fmt::Display for StemLanguage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl std::str::FromStr for StemLanguage {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        StemLanguage::ALL
+            .iter()
+            .copied()
+            .find(|language| language.name().eq_ignore_ascii_case(value))
+            .ok_or_else(|| {
+                let names: Vec<&str> = StemLanguage::ALL.iter().map(|l| l.name()).collect();
+                format!("expected one of {}", names.join(", "))
+            })
+    }
+}
+
+/// Stemmer of a [`StemLanguage`]; [`StemLanguage::None`] leaves words as they are
+pub struct LanguageStemmer(Option<Stemmer>);
+
+impl LanguageStemmer {
+    pub fn new(language: StemLanguage) -> Self {
+        Self(language.algorithm().map(Stemmer::create))
+    }
+
+    pub fn stem<'a>(&self, word: &'a str) -> Cow<'a, str> {
+        match &self.0 {
+            Some(stemmer) => stemmer.stem(word),
+            None => Cow::Borrowed(word),
+        }
+    }
+}
+
+/// Returns the stemmer of the stem language in effect (see `tokenization::with_vocabulary`),
+/// which queries and code are both stemmed with
+pub fn get_stemmer() -> LanguageStemmer {
+    LanguageStemmer::new(tokenization::active_vocabulary().stem_language)
 }
 
 /// Tokenizes text into lowercase words by splitting on whitespace and non-alphanumeric characters,
@@ -71,8 +278,12 @@ pub fn compute_avgdl(lengths: &[usize]) -> f64 {
 pub struct PrecomputedBm25Params<'a> {
     /// Document term frequencies using u8 indices
     pub doc_tf: &'a HashMap<u8, usize>,
-    /// Document length
-    pub doc_len: usize,
+    /// Term frequencies of the document's file name, which are part of `doc_tf` and count
+    /// `filename_weight` times in total
+    pub filename_tf: Option<&'a HashMap<u8, usize>>,
+    pub filename_weight: f64,
+    /// Document length, with file name tokens weighted
+    pub doc_len: f64,
     /// Average document length
     pub avgdl: f64,
     /// Precomputed IDF values for query terms (remains String-based)
@@ -83,6 +294,8 @@ pub struct PrecomputedBm25Params<'a> {
     pub k1: f64,
     /// BM25 b parameter
     pub b: f64,
+    /// Positioned tokens of the document's code, if the query has proximity terms
+    pub positions: Option<&'a [(usize, String)]>,
 }
 
 /// Extracts unique terms from a query expression
@@ -91,6 +304,8 @@ pub fn extract_query_terms(expr: &Expr) -> HashSet<String> {
     let mut terms = HashSet::new();
 
     match expr {
+        // path:/lang:/type: values are filters, not document terms
+        Term { .. } if expr.is_filter_term() => {}
         Term { keywords, .. } => {
             terms.extend(keywords.iter().cloned());
         }
@@ -180,7 +395,7 @@ fn bm25_single_token_optimized(token: &str, params: &PrecomputedBm25Params) -> f
     };
 
     // Get frequency using the u8 index
-    let freq_in_doc = *params.doc_tf.get(&token_index).unwrap_or(&0) as f64;
+    let freq_in_doc = weighted_tf(token_index, params);
     if freq_in_doc <= 0.0 {
         return 0.0;
     }
@@ -188,11 +403,57 @@ fn bm25_single_token_optimized(token: &str, params: &PrecomputedBm25Params) -> f
     // Use precomputed IDF value (still using string for IDF lookup)
     let idf = *params.idfs.get(token).unwrap_or(&0.0);
 
-    let tf_part = (freq_in_doc * (params.k1 + 1.0))
-        / (freq_in_doc
-            + params.k1 * (1.0 - params.b + params.b * (params.doc_len as f64 / params.avgdl)));
+    idf * bm25_tf_part(freq_in_doc, params)
+}
+
+/// Frequency of a query token in a document, counting file name matches `filename_weight` times
+fn weighted_tf(token_index: u8, params: &PrecomputedBm25Params) -> f64 {
+    let freq = *params.doc_tf.get(&token_index).unwrap_or(&0) as f64;
+    let filename_freq = params
+        .filename_tf
+        .and_then(|tf| tf.get(&token_index))
+        .copied()
+        .unwrap_or(0) as f64;
+    freq + (params.filename_weight - 1.0) * filename_freq
+}
 
-    idf * tf_part
+/// The saturated, length-normalized term frequency part of BM25
+fn bm25_tf_part(freq_in_doc: f64, params: &PrecomputedBm25Params) -> f64 {
+    (freq_in_doc * (params.k1 + 1.0))
+        / (freq_in_doc + params.k1 * (1.0 - params.b + params.b * (params.doc_len / params.avgdl)))
+}
+
+/// Break a document's BM25 score down per query term, in the order of the token map
+fn explain_bm25_terms(params: &PrecomputedBm25Params) -> Vec<TermExplanation> {
+    let mut terms: Vec<(&String, &u8)> = params.query_token_map.iter().collect();
+    terms.sort_by_key(|&(_, index)| *index);
+
+    terms
+        .into_iter()
+        .map(|(term, index)| {
+            let tf = weighted_tf(*index, params);
+            let idf = *params.idfs.get(term).unwrap_or(&0.0);
+            let score = if tf > 0.0 {
+                idf * bm25_tf_part(tf, params)
+            } else {
+                0.0
+            };
+            TermExplanation {
+                term: term.clone(),
+                tf,
+                idf,
+                score,
+            }
+        })
+        .collect()
+}
+
+/// Recursively compute a doc's "ES-like BM25 bool query" score from the AST using precomputed IDF values.
+/// See `score_expr_with` for how required, excluded and optional terms are combined.
+pub fn score_expr_bm25_optimized(expr: &Expr, params: &PrecomputedBm25Params) -> Option<f64> {
+    score_expr_with(expr, params.positions, &|keywords| {
+        score_term_bm25_optimized(keywords, params)
+    })
 }
 
 /// Sum BM25 for all keywords in a single "Term" node using precomputed IDF values
@@ -204,23 +465,48 @@ fn score_term_bm25_optimized(keywords: &[String], params: &PrecomputedBm25Params
     total
 }
 
-/// Recursively compute a doc's "ES-like BM25 bool query" score from the AST using precomputed IDF values:
+/// Recursively compute a doc's bool query score from the AST, scoring the keywords of each
+/// "Term" node with `score_term`:
 /// - If it fails a must or matches a must_not => return None (exclude doc)
 /// - Otherwise sum up matched subclause scores
 /// - For "OR," doc must match at least one side
 /// - For "AND," doc must match both sides
-/// - For a "should" term, we add the BM25 if it matches; if the entire query has no must, then
+/// - For a "should" term, we add the score if it matches; if the entire query has no must, then
 ///   at least one "should" must match in order to include the doc.
-pub fn score_expr_bm25_optimized(expr: &Expr, params: &PrecomputedBm25Params) -> Option<f64> {
+/// - A proximity term only matches if its keywords occur within its distance in `positions`,
+///   the positioned tokens of the document's code, and scores higher the closer they are.
+///   Without `positions` it scores like an ordinary term.
+/// - A boosted term (`term^n`) has its score multiplied by `n`.
+pub fn score_expr_with(
+    expr: &Expr,
+    positions: Option<&[(usize, String)]>,
+    score_term: &dyn Fn(&[String]) -> f64,
+) -> Option<f64> {
     use Expr::*;
     match expr {
+        // Filters were applied before ranking; they neither score nor exclude
+        _ if expr.is_filter_only() => Some(0.0),
+        And(left, right) | Or(left, right) if left.is_filter_only() => {
+            score_expr_with(right, positions, score_term)
+        }
+        And(left, right) | Or(left, right) if right.is_filter_only() => {
+            score_expr_with(left, positions, score_term)
+        }
         Term {
             keywords,
             required,
             excluded,
+            proximity,
+            boost,
             ..
         } => {
-            let score = score_term_bm25_optimized(keywords, params);
+            let score = match (proximity, positions) {
+                (Some(distance), Some(positions)) => match proximity_span(positions, keywords) {
+                    Some(span) if span <= *distance => score_term(keywords) * proximity_boost(span),
+                    _ => 0.0,
+                },
+                _ => score_term(keywords),
+            } * boost.unwrap_or(1.0);
 
             if *excluded {
                 // must_not => doc out if doc_score > 0
@@ -244,13 +530,13 @@ pub fn score_expr_bm25_optimized(expr: &Expr, params: &PrecomputedBm25Params) ->
             }
         }
         And(left, right) => {
-            let lscore = score_expr_bm25_optimized(left, params)?;
-            let rscore = score_expr_bm25_optimized(right, params)?;
+            let lscore = score_expr_with(left, positions, score_term)?;
+            let rscore = score_expr_with(right, positions, score_term)?;
             Some(lscore + rscore)
         }
         Or(left, right) => {
-            let l = score_expr_bm25_optimized(left, params);
-            let r = score_expr_bm25_optimized(right, params);
+            let l = score_expr_with(left, positions, score_term);
+            let r = score_expr_with(right, positions, score_term);
             match (l, r) {
                 (None, None) => None,
                 (None, Some(rs)) => Some(rs),
@@ -261,97 +547,307 @@ pub fn score_expr_bm25_optimized(expr: &Expr, params: &PrecomputedBm25Params) ->
     }
 }
 
-// -------------------------------------------------------------------------
-// This is your main entry point for ranking. It now does "pure BM25 like ES."
-// -------------------------------------------------------------------------
-pub fn rank_documents(params: &RankingParams) -> Vec<(usize, f64)> {
-    use rayon::prelude::*;
-    use std::cmp::Ordering;
+/// Score multiplier for a proximity term whose keywords are `span` tokens apart: 2 when
+/// they are adjacent, falling towards 1 as they get further apart
+fn proximity_boost(span: usize) -> f64 {
+    1.0 + 1.0 / (1.0 + span as f64)
+}
 
-    let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
+/// Positioned tokens of each document's code, for scoring proximity terms; `None` when the
+/// query has none
+fn code_positions<'a>(
+    expr: &Expr,
+    code: impl Iterator<Item = &'a str>,
+) -> Option<Vec<Vec<(usize, String)>>> {
+    expr.has_proximity()
+        .then(|| code.map(tokenization::token_positions).collect())
+}
 
-    // 1) Parse the user query into an AST (Expr)
-    //    If your code uses parse_query(...) from `elastic_query.rs`, do:
-    let parsed_expr = match crate::search::elastic_query::parse_query(params.query, false) {
-        Ok(expr) => expr,
-        Err(e) => {
-            if debug_mode {
-                eprintln!("DEBUG: parse_query failed: {:?}", e);
+/// A query parsed for ranking: the AST, its unique terms and their u8 token indices
+pub struct PreparedQuery {
+    pub expr: Expr,
+    pub terms: HashSet<String>,
+    pub token_map: QueryTokenMap,
+}
+
+impl PreparedQuery {
+    /// Parse `query` for ranking. Problems are reported as warnings and yield `None`,
+    /// which callers treat as "nothing matches".
+    pub fn parse(query: &str) -> Option<Self> {
+        Self::parse_for(query, std::iter::empty())
+    }
+
+    /// Like `parse`, but wildcard and regex terms are replaced by the tokens they match in
+    /// `code`, the code of the documents being ranked (see `expand_pattern_terms`).
+    pub fn parse_for<'a>(query: &str, code: impl Iterator<Item = &'a str>) -> Option<Self> {
+        let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
+
+        let mut parsed_expr = match crate::search::elastic_query::parse_query(query, false) {
+            Ok(expr) => expr,
+            Err(e) => {
+                if debug_mode {
+                    eprintln!("DEBUG: parse_query failed: {:?}", e);
+                }
+                // Instead of silently returning empty results, log a warning even in non-debug mode
+                // to ensure errors are visible and can be addressed
+                eprintln!(
+                    "WARNING: Query parsing failed: {:?}. Returning empty results.",
+                    e
+                );
+                return None;
             }
-            // Instead of silently returning empty results, log a warning even in non-debug mode
-            // to ensure errors are visible and can be addressed
-            eprintln!(
-                "WARNING: Query parsing failed: {:?}. Returning empty results.",
-                e
-            );
-            // In a future version, consider changing the return type to Result<Vec<(usize, f64)>, QueryError>
-            // to properly propagate errors to the caller
-            return vec![];
+        };
+        if parsed_expr.has_pattern() {
+            let code: Vec<&str> = code.collect();
+            expand_pattern_terms(&mut parsed_expr, &code);
         }
-    };
 
-    // 3) Extract query terms, create token mapping
-    let query_terms = extract_query_terms(&parsed_expr);
+        let query_terms = extract_query_terms(&parsed_expr);
 
-    // Generate query token map (maps each unique query term to a unique u8 index)
-    let query_token_map = match generate_query_token_map(&query_terms) {
-        Ok(map) => map,
-        Err(e) => {
-            if debug_mode {
-                eprintln!("DEBUG: Failed to generate query token map: {}", e);
+        // Generate query token map (maps each unique query term to a unique u8 index)
+        let query_token_map = match generate_query_token_map(&query_terms) {
+            Ok(map) => map,
+            Err(e) => {
+                if debug_mode {
+                    eprintln!("DEBUG: Failed to generate query token map: {}", e);
+                }
+                eprintln!("WARNING: {}", e);
+                return None;
             }
-            eprintln!("WARNING: {}", e);
-            return vec![];
+        };
+
+        if debug_mode {
+            println!(
+                "DEBUG: Generated query token map with {} entries",
+                query_token_map.len()
+            );
         }
-    };
 
-    if debug_mode {
-        println!(
-            "DEBUG: Generated query token map with {} entries",
-            query_token_map.len()
-        );
+        Some(Self {
+            expr: parsed_expr,
+            terms: query_terms,
+            token_map: query_token_map,
+        })
     }
+}
 
-    // 2) Precompute TF/DF for docs
-    let tf_df_result = if let Some(pre_tokenized) = &params.pre_tokenized {
-        // Use pre-tokenized content if available
-        if debug_mode {
-            println!("DEBUG: Using pre-tokenized content for ranking");
+/// Most tokens a wildcard or regex term is expanded into, keeping the query within the
+/// token map's limit
+const MAX_PATTERN_EXPANSIONS: usize = 50;
+
+/// Replace the keywords of wildcard and regex terms with the tokens they match in `code`,
+/// the most common first. A term's score is the sum over its keywords, so the expanded
+/// term matches a document containing any of them.
+fn expand_pattern_terms(expr: &mut Expr, code: &[&str]) {
+    match expr {
+        Expr::Term {
+            keywords,
+            pattern: pattern @ Some(_),
+            ..
+        } => {
+            let term_pattern = pattern.take().unwrap();
+            let mut counts: HashMap<String, usize> = HashMap::new();
+            for keyword in keywords.iter() {
+                let compiled = term_pattern.compile(keyword);
+                for text in code {
+                    for token in compiled.matched_tokens(text) {
+                        *counts.entry(token).or_insert(0) += 1;
+                    }
+                }
+            }
+            let mut tokens: Vec<(String, usize)> = counts.into_iter().collect();
+            tokens.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+            *keywords = tokens
+                .into_iter()
+                .take(MAX_PATTERN_EXPANSIONS)
+                .map(|(token, _)| token)
+                .collect();
         }
-        compute_tf_df_from_tokenized(pre_tokenized, &query_token_map)
-    } else {
-        // Fallback to tokenizing the documents
-        if debug_mode {
-            println!("DEBUG: Tokenizing documents for ranking");
+        Expr::Term { .. } => {}
+        Expr::And(left, right) | Expr::Or(left, right) => {
+            expand_pattern_terms(left, code);
+            expand_pattern_terms(right, code);
         }
-        // Tokenize documents on the fly
-        let tokenized_docs: Vec<Vec<String>> =
-            params.documents.iter().map(|doc| tokenize(doc)).collect();
-        compute_tf_df_from_tokenized(&tokenized_docs, &query_token_map)
-    };
+    }
+}
 
-    let n_docs = params.documents.len();
-    let avgdl = compute_avgdl(&tf_df_result.document_lengths);
+/// Everything BM25 needs to know about the documents being ranked: the parsed query, the
+/// term and document frequencies, the (filename-weighted) document lengths and the IDF of
+/// each term
+struct Bm25Corpus {
+    query: PreparedQuery,
+    tf_df: TfDfResult,
+    /// Term frequencies of the file names, if they are weighted differently from the code
+    filename_tf: Option<Vec<HashMap<u8, usize>>>,
+    doc_lengths: Vec<f64>,
+    avgdl: f64,
+    idfs: HashMap<String, f64>,
+    bm25: Bm25Params,
+    /// Positioned code tokens of each document, if the query has proximity terms
+    positions: Option<Vec<Vec<(usize, String)>>>,
+}
 
-    // Precompute IDF values
+impl Bm25Corpus {
+    /// Parse the query and compute the statistics of the documents; `None` if the query
+    /// does not parse
+    fn new(params: &RankingParams) -> Option<Self> {
+        let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
+
+        // 1) Parse the user query into an AST (Expr), extract query terms, create token mapping
+        let query = PreparedQuery::parse_for(
+            params.query,
+            params.documents.iter().map(|doc| document_code(doc)),
+        )?;
+
+        // 2) Precompute TF/DF for docs
+        let whole_file = |i: usize| {
+            let corpus = params.index?;
+            Some(&corpus.index.files[corpus.whole_files[i]?])
+        };
+        let mut tf_df = if let Some(pre_tokenized) = &params.pre_tokenized {
+            // Use pre-tokenized content if available
+            if debug_mode {
+                println!("DEBUG: Using pre-tokenized content for ranking");
+            }
+            compute_tf_df_from_tokenized(pre_tokenized, &query.token_map)
+        } else {
+            // Fallback to tokenizing the documents
+            if debug_mode {
+                println!("DEBUG: Tokenizing documents for ranking");
+            }
+            // Tokenize documents on the fly; whole indexed files are counted below
+            let tokenized_docs: Vec<Vec<String>> = params
+                .documents
+                .iter()
+                .enumerate()
+                .map(|(i, doc)| match whole_file(i) {
+                    Some(_) => Vec::new(),
+                    None => tokenize(doc),
+                })
+                .collect();
+            compute_tf_df_from_tokenized(&tokenized_docs, &query.token_map)
+        };
+        for (i, file) in (0..params.documents.len()).filter_map(|i| Some((i, whole_file(i)?))) {
+            // The file's tokens from the index plus those of its name, like other documents
+            let filename_tokens = params
+                .filenames
+                .map(|filenames| tokenize(filenames[i]))
+                .unwrap_or_default();
+            let mut tf = HashMap::new();
+            for (token, &count) in &file.term_frequencies {
+                if let Some(&token_index) = query.token_map.get(token) {
+                    *tf.entry(token_index).or_insert(0) += count;
+                }
+            }
+            for token in &filename_tokens {
+                if let Some(&token_index) = query.token_map.get(token) {
+                    *tf.entry(token_index).or_insert(0) += 1;
+                }
+            }
+            tf_df.term_frequencies[i] = tf;
+            tf_df.document_lengths[i] = file.doc_length + filename_tokens.len();
+        }
+
+        // File name tokens are already part of each document, once
+        let filename_tf_df = params
+            .filenames
+            .filter(|_| params.bm25.filename_weight != 1.0)
+            .map(|filenames| {
+                let tokens: Vec<Vec<String>> =
+                    filenames.iter().map(|file| tokenize(file)).collect();
+                compute_tf_df_from_tokenized(&tokens, &query.token_map)
+            });
+        let doc_lengths: Vec<f64> = tf_df
+            .document_lengths
+            .iter()
+            .enumerate()
+            .map(|(i, &len)| {
+                let filename_len = filename_tf_df
+                    .as_ref()
+                    .map_or(0, |stats| stats.document_lengths[i]);
+                len as f64 + (params.bm25.filename_weight - 1.0) * filename_len as f64
+            })
+            .collect();
+
+        // 3) Precompute IDF values, over all indexed files when there is an index
+        let (avgdl, idfs) = match params.index {
+            Some(corpus) => {
+                let dfs: HashMap<String, usize> = query
+                    .terms
+                    .iter()
+                    .map(|term| (term.clone(), corpus.index.document_frequency(term)))
+                    .collect();
+                (
+                    corpus.index.average_doc_length(),
+                    precompute_idfs(&query.terms, &dfs, corpus.index.files.len()),
+                )
+            }
+            None => {
+                let avgdl = if doc_lengths.is_empty() {
+                    0.0
+                } else {
+                    doc_lengths.iter().sum::<f64>() / doc_lengths.len() as f64
+                };
+                let n_docs = params.documents.len();
+                let idfs = precompute_idfs(&query.terms, &tf_df.document_frequencies, n_docs);
+                (avgdl, idfs)
+            }
+        };
 
-    let precomputed_idfs =
-        precompute_idfs(&query_terms, &tf_df_result.document_frequencies, n_docs);
+        if debug_mode {
+            println!(
+                "DEBUG: Precomputed IDF values for {} unique query terms",
+                idfs.len()
+            );
+        }
 
-    if debug_mode {
-        println!(
-            "DEBUG: Precomputed IDF values for {} unique query terms",
-            precomputed_idfs.len()
+        let positions = code_positions(
+            &query.expr,
+            params.documents.iter().map(|doc| document_code(doc)),
         );
+
+        Some(Self {
+            query,
+            tf_df,
+            filename_tf: filename_tf_df.map(|stats| stats.term_frequencies),
+            doc_lengths,
+            avgdl,
+            idfs,
+            bm25: params.bm25,
+            positions,
+        })
     }
 
-    // 4) BM25 parameters
-    // These values are standard defaults for BM25 as established in academic literature:
-    // k1=1.2 controls term frequency saturation (higher values give more weight to term frequency)
-    // b=0.75 controls document length normalization (higher values give more penalty to longer documents)
-    // See: Robertson, S. E., & Zaragoza, H. (2009). The Probabilistic Relevance Framework: BM25 and Beyond
-    let k1 = 1.2;
-    let b = 0.75;
+    /// BM25 parameters for document `i`
+    fn params(&self, i: usize) -> PrecomputedBm25Params<'_> {
+        PrecomputedBm25Params {
+            doc_tf: &self.tf_df.term_frequencies[i],
+            filename_tf: self.filename_tf.as_ref().map(|tf| &tf[i]),
+            filename_weight: self.bm25.filename_weight,
+            doc_len: self.doc_lengths[i],
+            avgdl: self.avgdl,
+            idfs: &self.idfs,
+            query_token_map: &self.query.token_map,
+            k1: self.bm25.k1,
+            b: self.bm25.b,
+            positions: self.positions.as_ref().map(|p| p[i].as_slice()),
+        }
+    }
+}
+
+// -------------------------------------------------------------------------
+// This is your main entry point for ranking. It now does "pure BM25 like ES."
+// -------------------------------------------------------------------------
+pub fn rank_documents(params: &RankingParams) -> Vec<(usize, f64)> {
+    use rayon::prelude::*;
+    use std::cmp::Ordering;
+
+    let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
+
+    let Some(corpus) = Bm25Corpus::new(params) else {
+        return vec![];
+    };
+    let n_docs = params.documents.len();
 
     if debug_mode {
         println!(
@@ -360,28 +856,14 @@ pub fn rank_documents(params: &RankingParams) -> Vec<(usize, f64)> {
         );
     }
 
-    // 5) Compute BM25 bool logic score for each doc in parallel
+    // 4) Compute BM25 bool logic score for each doc in parallel
     // Use a stable collection method to ensure deterministic ordering
-    let scored_docs: Vec<(usize, Option<f64>)> = (0..tf_df_result.term_frequencies.len())
+    let scored_docs: Vec<(usize, Option<f64>)> = (0..corpus.tf_df.term_frequencies.len())
         .collect::<Vec<_>>() // Collect indices first to ensure stable ordering
         .par_iter() // Then parallelize
         .map(|&i| {
-            let doc_tf = &tf_df_result.term_frequencies[i];
-            let doc_len = tf_df_result.document_lengths[i];
-
-            // Create optimized BM25 parameters with precomputed IDF values
-            let precomputed_bm25_params = PrecomputedBm25Params {
-                doc_tf,
-                doc_len,
-                avgdl,
-                idfs: &precomputed_idfs,
-                query_token_map: &query_token_map,
-                k1,
-                b,
-            };
-
             // Evaluate doc's BM25 sum or None if excluded using optimized function
-            let bm25_score_opt = score_expr_bm25_optimized(&parsed_expr, &precomputed_bm25_params);
+            let bm25_score_opt = score_expr_bm25_optimized(&corpus.query.expr, &corpus.params(i));
 
             (i, bm25_score_opt)
         })
@@ -397,7 +879,7 @@ pub fn rank_documents(params: &RankingParams) -> Vec<(usize, f64)> {
         .filter_map(|(i, score_opt)| score_opt.map(|score| (i, score)))
         .collect();
 
-    // 6) Sort in descending order by BM25 score, with a stable secondary sort by document index
+    // 5) Sort in descending order by BM25 score, with a stable secondary sort by document index
     filtered_docs.sort_by(|a, b| {
         // First compare by score (descending)
         // Note: unwrap_or(Ordering::Equal) handles NaN cases by treating them as equal
@@ -422,6 +904,489 @@ pub fn rank_documents(params: &RankingParams) -> Vec<(usize, f64)> {
     filtered_docs
 }
 
+/// Explain the BM25 score of every document the query matches, by index; documents the
+/// query excludes get `None`
+pub fn explain_documents(params: &RankingParams) -> Vec<Option<Bm25Explanation>> {
+    let Some(corpus) = Bm25Corpus::new(params) else {
+        return vec![None; params.documents.len()];
+    };
+
+    (0..corpus.tf_df.term_frequencies.len())
+        .map(|i| {
+            let params = corpus.params(i);
+            let score = score_expr_bm25_optimized(&corpus.query.expr, &params)?;
+            Some(Bm25Explanation {
+                score,
+                doc_length: params.doc_len,
+                avgdl: params.avgdl,
+                k1: params.k1,
+                b: params.b,
+                filename_weight: params.filename_weight,
+                terms: explain_bm25_terms(&params),
+            })
+        })
+        .collect()
+}
+
+// -------------------------------------------------------------------------
+// Rerankers: the scoring models selectable with `--reranker`
+// -------------------------------------------------------------------------
+
+/// Names accepted by `reranker_by_name`
+#[allow(dead_code)]
+pub const RERANKERS: &[&str] = &["bm25", "tfidf", "bm25f", "hybrid"];
+
+/// A scoring model that orders search results for a query
+pub trait Reranker: Send + Sync {
+    /// Name the reranker is selected by
+    fn name(&self) -> &str;
+
+    /// Score `results` for `query`. Returns `(index, score)` for every result the query
+    /// matches, honouring required and excluded terms; the order does not matter.
+    fn score(&self, query: &str, results: &[SearchResult]) -> Vec<(usize, f64)>;
+
+    /// Store a score and its 1-based rank in the result fields this reranker owns
+    fn record(&self, result: &mut SearchResult, score: f64, rank: usize);
+}
+
+/// Look up a built-in reranker by name (see `RERANKERS`), or an external one given as
+/// `cmd:<program> [args...]` (see `CommandReranker`)
+#[allow(dead_code)]
+pub fn reranker_by_name(name: &str) -> Option<Box<dyn Reranker>> {
+    reranker_by_name_with(name, Bm25Params::default())
+}
+
+/// Like `reranker_by_name`, with the given BM25 parameters for the rerankers that use BM25.
+/// BM25F takes k1 and b but keeps its own field weights.
+pub fn reranker_by_name_with(name: &str, bm25: Bm25Params) -> Option<Box<dyn Reranker>> {
+    if let Some(command) = name.strip_prefix(COMMAND_RERANKER_PREFIX) {
+        return Some(Box::new(CommandReranker::new(command).bm25(bm25)));
+    }
+    match name.to_lowercase().as_str() {
+        "bm25" => Some(Box::new(Bm25Reranker { params: bm25 })),
+        "tfidf" | "tf-idf" => Some(Box::new(TfIdfReranker)),
+        "bm25f" => Some(Box::new(Bm25fReranker {
+            k1: bm25.k1,
+            b: bm25.b,
+            ..Default::default()
+        })),
+        "hybrid" => Some(Box::new(HybridReranker {
+            bm25,
+            ..Default::default()
+        })),
+        _ => None,
+    }
+}
+
+/// Header line `filename_documents` puts before the code
+const FILENAME_HEADER: &str = "// Filename: ";
+
+/// Documents as BM25 and TF-IDF see them: the filename followed by the code
+fn filename_documents(results: &[SearchResult]) -> Vec<String> {
+    results
+        .iter()
+        .map(|r| format!("{}{}\n{}", FILENAME_HEADER, r.file, r.code))
+        .collect()
+}
+
+/// The code of a document, without the header added by `filename_documents`
+fn document_code(document: &str) -> &str {
+    document
+        .strip_prefix(FILENAME_HEADER)
+        .and_then(|rest| rest.split_once('\n'))
+        .map_or(document, |(_, code)| code)
+}
+
+/// Rank results with BM25 over their filename and code documents
+fn bm25_results<T>(
+    results: &[SearchResult],
+    query: &str,
+    bm25: Bm25Params,
+    rank: impl FnOnce(&RankingParams) -> T,
+) -> T {
+    let documents = filename_documents(results);
+    let documents_refs: Vec<&str> = documents.iter().map(|s| s.as_str()).collect();
+    let filenames: Vec<&str> = results.iter().map(|r| r.file.as_str()).collect();
+    let pre_tokenized = result_tokens(results);
+    let index = index_corpus(results);
+
+    rank(&RankingParams {
+        documents: &documents_refs,
+        query,
+        pre_tokenized: pre_tokenized.as_deref(),
+        filenames: Some(&filenames),
+        bm25,
+        index: index.as_ref(),
+    })
+}
+
+/// The search index statistics for `results`, if one index covers all their files, none of
+/// them changed since it was built and it was built with the vocabulary in effect
+fn index_corpus(results: &[SearchResult]) -> Option<IndexCorpus> {
+    let index = SearchIndex::load_for(Path::new(&results.first()?.file))?;
+    if index.vocabulary != tokenization::vocabulary_fingerprint()
+        || index.average_doc_length() == 0.0
+    {
+        return None;
+    }
+    let whole_files = results
+        .iter()
+        .map(|result| {
+            let id = index.fresh_file_id(Path::new(&result.file))?;
+            let (start, end) = result.lines;
+            Some((start <= 1 && end >= index.files[id].lines).then_some(id))
+        })
+        .collect::<Option<Vec<_>>>()?;
+    Some(IndexCorpus { index, whole_files })
+}
+
+/// Tokens of every result, reusing the tokenization from file processing when all results have it
+fn result_tokens(results: &[SearchResult]) -> Option<Vec<Vec<String>>> {
+    results
+        .iter()
+        .map(|r| r.tokenized_content.clone())
+        .collect()
+}
+
+/// Okapi BM25 over the filename and code of each block (the default)
+#[derive(Default)]
+pub struct Bm25Reranker {
+    pub params: Bm25Params,
+}
+
+impl Reranker for Bm25Reranker {
+    fn name(&self) -> &str {
+        "bm25"
+    }
+
+    fn score(&self, query: &str, results: &[SearchResult]) -> Vec<(usize, f64)> {
+        bm25_results(results, query, self.params, rank_documents)
+    }
+
+    fn record(&self, result: &mut SearchResult, score: f64, rank: usize) {
+        result.bm25_score = Some(score);
+        result.bm25_rank = Some(rank);
+    }
+}
+
+impl Bm25Reranker {
+    /// Break the BM25 score of each result down per query term (see `explain_documents`)
+    pub fn explain(&self, query: &str, results: &[SearchResult]) -> Vec<Option<Bm25Explanation>> {
+        bm25_results(results, query, self.params, explain_documents)
+    }
+}
+
+/// TF-IDF with length-normalized term frequency and smoothed IDF
+pub struct TfIdfReranker;
+
+impl Reranker for TfIdfReranker {
+    fn name(&self) -> &str {
+        "tfidf"
+    }
+
+    fn score(&self, query: &str, results: &[SearchResult]) -> Vec<(usize, f64)> {
+        let Some(prepared) =
+            PreparedQuery::parse_for(query, results.iter().map(|r| r.code.as_str()))
+        else {
+            return vec![];
+        };
+        let tokens = result_tokens(results).unwrap_or_else(|| {
+            filename_documents(results)
+                .iter()
+                .map(|doc| tokenize(doc))
+                .collect()
+        });
+        let tf_df = compute_tf_df_from_tokenized(&tokens, &prepared.token_map);
+        let positions = code_positions(&prepared.expr, results.iter().map(|r| r.code.as_str()));
+
+        // idf = ln((1 + N) / (1 + df)) + 1
+        let n_docs = tokens.len() as f64;
+        let idfs: HashMap<String, f64> = prepared
+            .terms
+            .iter()
+            .filter_map(|term| {
+                let df = *tf_df.document_frequencies.get(term)? as f64;
+                Some((term.clone(), ((1.0 + n_docs) / (1.0 + df)).ln() + 1.0))
+            })
+            .collect();
+
+        (0..tokens.len())
+            .filter_map(|i| {
+                let doc_tf = &tf_df.term_frequencies[i];
+                let doc_len = tf_df.document_lengths[i].max(1) as f64;
+                let score_token = |token: &String| {
+                    let Some(index) = prepared.token_map.get(token) else {
+                        return 0.0;
+                    };
+                    let freq = *doc_tf.get(index).unwrap_or(&0) as f64;
+                    freq / doc_len * idfs.get(token).unwrap_or(&0.0)
+                };
+                let doc_positions = positions.as_ref().map(|p| p[i].as_slice());
+                let score = score_expr_with(&prepared.expr, doc_positions, &|keywords| {
+                    keywords.iter().map(score_token).sum()
+                })?;
+                Some((i, score))
+            })
+            .collect()
+    }
+
+    fn record(&self, result: &mut SearchResult, score: f64, rank: usize) {
+        result.tfidf_score = Some(score);
+        result.tfidf_rank = Some(rank);
+    }
+}
+
+/// Per-field weights for BM25F
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bm25fWeights {
+    /// Path of the file the block is in
+    pub filename: f64,
+    /// Declaration line of the block (e.g. `fn name(...)`), standing in for its symbol name
+    pub symbol: f64,
+    /// Comment lines inside the block
+    pub comments: f64,
+    /// Remaining code
+    pub body: f64,
+}
+
+impl Default for Bm25fWeights {
+    fn default() -> Self {
+        Self {
+            filename: 2.0,
+            symbol: 3.0,
+            comments: 0.75,
+            body: 1.0,
+        }
+    }
+}
+
+/// BM25F: term frequencies are length-normalized and weighted per field before a single
+/// BM25 saturation, so a match in the filename or symbol name counts more than one in the body
+pub struct Bm25fReranker {
+    pub weights: Bm25fWeights,
+    pub k1: f64,
+    pub b: f64,
+}
+
+impl Default for Bm25fReranker {
+    fn default() -> Self {
+        Self {
+            weights: Bm25fWeights::default(),
+            k1: 1.2,
+            b: 0.75,
+        }
+    }
+}
+
+/// Number of fields BM25F splits a block into
+const BM25F_FIELDS: usize = 4;
+
+/// Whether a trimmed line is a comment in any of the supported languages
+fn is_comment_line(line: &str) -> bool {
+    line.starts_with("//")
+        || line.starts_with("/*")
+        || line.starts_with('*')
+        || line.starts_with("--")
+        || line.starts_with("\"\"\"")
+        || line.starts_with("'''")
+        || line == "#"
+        || line.starts_with("# ")
+}
+
+/// Split a result into filename, symbol, comment and body tokens
+fn bm25f_fields(result: &SearchResult) -> [Vec<String>; BM25F_FIELDS] {
+    let mut symbol = String::new();
+    let mut comments = String::new();
+    let mut body = String::new();
+
+    for line in result.code.lines() {
+        let trimmed = line.trim();
+        if is_comment_line(trimmed) {
+            comments.push_str(trimmed);
+            comments.push('\n');
+        } else if symbol.is_empty()
+            && !trimmed.is_empty()
+            && !trimmed.starts_with('@')
+            && !trimmed.starts_with("#[")
+        {
+            // The first code line after any decorators declares the block
+            symbol.push_str(trimmed);
+        } else {
+            body.push_str(line);
+            body.push('\n');
+        }
+    }
+
+    [
+        tokenize(&result.file),
+        tokenize(&symbol),
+        tokenize(&comments),
+        tokenize(&body),
+    ]
+}
+
+impl Reranker for Bm25fReranker {
+    fn name(&self) -> &str {
+        "bm25f"
+    }
+
+    fn score(&self, query: &str, results: &[SearchResult]) -> Vec<(usize, f64)> {
+        use rayon::prelude::*;
+
+        let Some(prepared) =
+            PreparedQuery::parse_for(query, results.iter().map(|r| r.code.as_str()))
+        else {
+            return vec![];
+        };
+
+        let fields: Vec<[Vec<String>; BM25F_FIELDS]> =
+            results.par_iter().map(bm25f_fields).collect();
+        let field_stats: Vec<TfDfResult> = (0..BM25F_FIELDS)
+            .map(|f| {
+                let tokens: Vec<Vec<String>> = fields.iter().map(|doc| doc[f].clone()).collect();
+                compute_tf_df_from_tokenized(&tokens, &prepared.token_map)
+            })
+            .collect();
+        let avg_lengths: Vec<f64> = field_stats
+            .iter()
+            .map(|stats| compute_avgdl(&stats.document_lengths))
+            .collect();
+        let weights = [
+            self.weights.filename,
+            self.weights.symbol,
+            self.weights.comments,
+            self.weights.body,
+        ];
+
+        // A document contains a term if any of its fields does
+        let n_docs = results.len();
+        let dfs: HashMap<String, usize> = prepared
+            .token_map
+            .iter()
+            .map(|(term, index)| {
+                let df = (0..n_docs)
+                    .filter(|&i| {
+                        field_stats
+                            .iter()
+                            .any(|stats| stats.term_frequencies[i].contains_key(index))
+                    })
+                    .count();
+                (term.clone(), df)
+            })
+            .collect();
+        let idfs = precompute_idfs(&prepared.terms, &dfs, n_docs);
+        let positions = code_positions(&prepared.expr, results.iter().map(|r| r.code.as_str()));
+
+        (0..n_docs)
+            .into_par_iter()
+            .filter_map(|i| {
+                let score_token = |token: &String| {
+                    let Some(index) = prepared.token_map.get(token) else {
+                        return 0.0;
+                    };
+                    let weighted_tf: f64 = (0..BM25F_FIELDS)
+                        .map(|f| {
+                            let stats = &field_stats[f];
+                            let freq = *stats.term_frequencies[i].get(index).unwrap_or(&0) as f64;
+                            if freq == 0.0 {
+                                return 0.0;
+                            }
+                            let length_ratio = stats.document_lengths[i] as f64 / avg_lengths[f];
+                            weights[f] * freq / (1.0 - self.b + self.b * length_ratio)
+                        })
+                        .sum();
+                    let idf = idfs.get(token).unwrap_or(&0.0);
+                    idf * weighted_tf * (self.k1 + 1.0) / (weighted_tf + self.k1)
+                };
+                let doc_positions = positions.as_ref().map(|p| p[i].as_slice());
+                let score = score_expr_with(&prepared.expr, doc_positions, &|keywords| {
+                    keywords.iter().map(score_token).sum()
+                })?;
+                Some((i, score))
+            })
+            .collect()
+    }
+
+    fn record(&self, result: &mut SearchResult, score: f64, rank: usize) {
+        result.bm25_score = Some(score);
+        result.bm25_rank = Some(rank);
+    }
+}
+
+/// BM25 blended with file-level match statistics: how many of the query terms the file
+/// matches and how often, so blocks from files that are about the query rank higher
+pub struct HybridReranker {
+    /// Weight of the BM25 score, normalized to the best result
+    pub bm25_weight: f64,
+    /// Weight of the share of query terms matched anywhere in the file
+    pub coverage_weight: f64,
+    /// Weight of the file's total matches, log-scaled and normalized to the busiest file
+    pub frequency_weight: f64,
+    /// Parameters of the BM25 part
+    pub bm25: Bm25Params,
+}
+
+impl Default for HybridReranker {
+    fn default() -> Self {
+        Self {
+            bm25_weight: 0.7,
+            coverage_weight: 0.2,
+            frequency_weight: 0.1,
+            bm25: Bm25Params::default(),
+        }
+    }
+}
+
+impl Reranker for HybridReranker {
+    fn name(&self) -> &str {
+        "hybrid"
+    }
+
+    fn score(&self, query: &str, results: &[SearchResult]) -> Vec<(usize, f64)> {
+        let bm25_scores = Bm25Reranker { params: self.bm25 }.score(query, results);
+        let query_terms = PreparedQuery::parse(query)
+            .map(|prepared| prepared.terms.len())
+            .unwrap_or(0)
+            .max(1) as f64;
+
+        let max_bm25 = bm25_scores
+            .iter()
+            .map(|&(_, score)| score)
+            .fold(0.0, f64::max);
+        let max_matches = results
+            .iter()
+            .filter_map(|r| r.file_total_matches)
+            .max()
+            .unwrap_or(0) as f64;
+
+        bm25_scores
+            .into_iter()
+            .map(|(i, bm25)| {
+                let result = &results[i];
+                let bm25_part = if max_bm25 > 0.0 { bm25 / max_bm25 } else { 0.0 };
+                let coverage =
+                    (result.file_unique_terms.unwrap_or(0) as f64 / query_terms).min(1.0);
+                let frequency = if max_matches > 0.0 {
+                    (1.0 + result.file_total_matches.unwrap_or(0) as f64).ln()
+                        / (1.0 + max_matches).ln()
+                } else {
+                    0.0
+                };
+
+                let score = self.bm25_weight * bm25_part
+                    + self.coverage_weight * coverage
+                    + self.frequency_weight * frequency;
+                (i, score)
+            })
+            .collect()
+    }
+
+    fn record(&self, result: &mut SearchResult, score: f64, rank: usize) {
+        result.new_score = Some(score);
+        result.hybrid2_rank = Some(rank);
+    }
+}
+
 /// Computes term frequencies (TF) for each document, document frequencies (DF) for each term,
 /// and document lengths from pre-tokenized content.
 ///
@@ -548,6 +1513,9 @@ mod tests {
             documents: &docs,
             query,
             pre_tokenized: None,
+            filenames: None,
+            bm25: Bm25Params::default(),
+            index: None,
         };
 
         let results = rank_documents(&params);
@@ -583,6 +1551,9 @@ mod tests {
             documents: &docs,
             query,
             pre_tokenized: Some(&pre_tokenized),
+            filenames: None,
+            bm25: Bm25Params::default(),
+            index: None,
         };
 
         let results = rank_documents(&params);
@@ -610,6 +1581,9 @@ mod tests {
             documents: &docs,
             query,
             pre_tokenized: None,
+            filenames: None,
+            bm25: Bm25Params::default(),
+            index: None,
         };
 
         let results = rank_documents(&params);
@@ -793,12 +1767,15 @@ mod tests {
         // Create BM25 parameters
         let params = PrecomputedBm25Params {
             doc_tf: &doc_tf,
-            doc_len: 3,
+            filename_tf: None,
+            filename_weight: 1.0,
+            doc_len: 3.0,
             avgdl: 3.0,
             idfs: &idfs,
             query_token_map: &query_token_map,
             k1: 1.2,
             b: 0.75,
+            positions: None,
         };
 
         // Test bm25_single_token_optimized
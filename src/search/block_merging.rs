@@ -1,4 +1,4 @@
-use crate::models::SearchResult;
+use crate::models::{MergedBlock, RankingExplanation, SearchResult};
 use std::collections::HashMap;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
@@ -115,29 +115,47 @@ pub fn merge_ranked_blocks(
                         let merged_end = current_block.lines.1.max(next_block.lines.1);
                         let merged_code = merge_block_content(&current_block, next_block);
 
-                        // Use node type from the highest-ranked block
-                        let merged_node_type = if current_block.rank.unwrap_or(usize::MAX)
-                            <= next_block.rank.unwrap_or(usize::MAX)
-                        {
-                            current_block.node_type.clone()
-                        } else {
-                            next_block.node_type.clone()
-                        };
+                        // Use node type and declared name from the highest-ranked block
+                        let current_is_best = current_block.rank.unwrap_or(usize::MAX)
+                            <= next_block.rank.unwrap_or(usize::MAX);
+                        let (merged_node_type, merged_symbol_name, merged_definition_boost) =
+                            if current_is_best {
+                                (
+                                    current_block.node_type.clone(),
+                                    current_block.symbol_name.clone(),
+                                    current_block.definition_boost,
+                                )
+                            } else {
+                                (
+                                    next_block.node_type.clone(),
+                                    next_block.symbol_name.clone(),
+                                    next_block.definition_boost,
+                                )
+                            };
 
                         // Combine scores and term statistics
                         let merged_score = merge_scores(&current_block, next_block);
                         let merged_term_stats = merge_term_statistics(&current_block, next_block);
+                        let merged_explanation =
+                            merge_explanations(&current_block, next_block, current_is_best);
 
                         // Update the current block
                         current_block.lines = (merged_start, merged_end);
                         current_block.code = merged_code;
                         current_block.node_type = merged_node_type;
+                        current_block.rank = match (current_block.rank, next_block.rank) {
+                            (Some(a), Some(b)) => Some(a.min(b)),
+                            (a, b) => a.or(b),
+                        };
+                        current_block.symbol_name = merged_symbol_name;
+                        current_block.definition_boost = merged_definition_boost;
                         current_block.score = merged_score.0;
                         current_block.tfidf_score = merged_score.1;
                         current_block.bm25_score = merged_score.2;
                         current_block.new_score = merged_score.3;
                         current_block.block_unique_terms = merged_term_stats.0;
                         current_block.block_total_matches = merged_term_stats.1;
+                        current_block.explanation = merged_explanation;
 
                         // Mark this block as processed
                         processed_indices.insert(j);
@@ -155,6 +173,9 @@ pub fn merge_ranked_blocks(
         merged_results.extend(merged_blocks);
     }
 
+    // Files were processed in arbitrary order; put the best-ranked blocks first again
+    merged_results.sort_by_key(|block| block.rank.unwrap_or(usize::MAX));
+
     if debug_mode {
         println!(
             "DEBUG: Post-rank merging complete. Merged {} blocks into {} blocks",
@@ -453,6 +474,43 @@ fn merge_scores(
     (score, tfidf_score, bm25_score, new_score)
 }
 
+/// Helper function to merge the ranking explanations (`--explain`) of two blocks: the
+/// merged block keeps the explanation of the better-ranked one and lists every block that
+/// went into it
+fn merge_explanations(
+    block1: &SearchResult,
+    block2: &SearchResult,
+    block1_is_best: bool,
+) -> Option<RankingExplanation> {
+    let (best, other) = if block1_is_best {
+        (block1, block2)
+    } else {
+        (block2, block1)
+    };
+    let mut explanation = best
+        .explanation
+        .clone()
+        .or_else(|| other.explanation.clone())?;
+
+    let mut merged_blocks = Vec::new();
+    for block in [block1, block2] {
+        match &block.explanation {
+            Some(e) if !e.merged_blocks.is_empty() => {
+                merged_blocks.extend(e.merged_blocks.iter().cloned())
+            }
+            _ => merged_blocks.push(MergedBlock {
+                lines: block.lines,
+                rank: block.rank,
+                score: block.score,
+            }),
+        }
+    }
+    merged_blocks.sort_by_key(|block| block.lines.0);
+    explanation.merged_blocks = merged_blocks;
+
+    Some(explanation)
+}
+
 /// Helper function to merge term statistics from two blocks
 ///
 /// # Arguments
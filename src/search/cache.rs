@@ -18,6 +18,28 @@ pub fn hash_query(query: &str) -> String {
     format!("{:x}", hasher.finish())
 }
 
+/// Get the directory probe keeps its persistent caches in (`~/.cache/probe`)
+pub fn probe_cache_dir() -> PathBuf {
+    let home_dir = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
+    home_dir.join(".cache").join("probe")
+}
+
+/// Check whether persistent caches may be read and written.
+/// Setting `PROBE_NO_CACHE=1` turns them off, e.g. for one-off runs over temporary files.
+pub fn persistent_cache_enabled() -> bool {
+    std::env::var("PROBE_NO_CACHE").unwrap_or_default() != "1"
+}
+
+/// Get a file's modification time in nanoseconds since the Unix epoch (0 if unavailable)
+pub fn file_mtime_nanos(metadata: &std::fs::Metadata) -> u64 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|duration| duration.as_nanos() as u64)
+        .unwrap_or(0)
+}
+
 /// Structure to hold cache data for a session
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SessionCache {
@@ -171,10 +193,7 @@ impl SessionCache {
 
     /// Get the path to the cache file
     pub fn get_cache_path(session_id: &str, query_hash: &str) -> PathBuf {
-        let home_dir = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
-        home_dir
-            .join(".cache")
-            .join("probe")
+        probe_cache_dir()
             .join("sessions")
             .join(format!("{}_{}.json", session_id, query_hash))
     }
@@ -571,25 +590,7 @@ mod tests {
             lines: (10, 20),
             node_type: "function".to_string(),
             code: "".to_string(),
-            matched_by_filename: None,
-            rank: None,
-            score: None,
-            tfidf_score: None,
-            bm25_score: None,
-            tfidf_rank: None,
-            bm25_rank: None,
-            new_score: None,
-            hybrid2_rank: None,
-            combined_score_rank: None,
-            file_unique_terms: None,
-            file_total_matches: None,
-            file_match_rank: None,
-            block_unique_terms: None,
-            block_total_matches: None,
-            parent_file_id: None,
-            block_id: None,
-            matched_keywords: None,
-            tokenized_content: None,
+            ..Default::default()
         };
 
         let result2 = SearchResult {
@@ -597,25 +598,7 @@ mod tests {
             lines: (10, 20),
             node_type: "function".to_string(),
             code: "".to_string(),
-            matched_by_filename: None,
-            rank: None,
-            score: None,
-            tfidf_score: None,
-            bm25_score: None,
-            tfidf_rank: None,
-            bm25_rank: None,
-            new_score: None,
-            hybrid2_rank: None,
-            combined_score_rank: None,
-            file_unique_terms: None,
-            file_total_matches: None,
-            file_match_rank: None,
-            block_unique_terms: None,
-            block_total_matches: None,
-            parent_file_id: None,
-            block_id: None,
-            matched_keywords: None,
-            tokenized_content: None,
+            ..Default::default()
         };
 
         // Generate cache keys for both results
@@ -0,0 +1,267 @@
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::models::SearchResult;
+use crate::ranking::{Bm25Params, Bm25Reranker, Reranker};
+use crate::search::timeout::CancellationToken;
+
+/// Prefix that selects an external reranker: `--reranker cmd:<program> [args...]`
+pub const COMMAND_RERANKER_PREFIX: &str = "cmd:";
+
+/// How long the program may take unless the search ends first
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How often a running program is checked for having exited, timed out or been cancelled
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Reranker that hands the candidates to an external program, so model-based
+/// reranking (e.g. a local cross-encoder) can be plugged in without probe depending
+/// on it.
+///
+/// The candidates are the blocks BM25 matches, so required and excluded terms still
+/// apply. The program gets one JSON document on stdin, with the query as the user wrote
+/// it (without synonym or fuzzy expansions):
+///
+/// ```json
+/// {"query": "...", "candidates": [{"id": 0, "file": "src/lib.rs", "lines": [10, 24],
+///   "node_type": "function_item", "code": "...", "scores": {"bm25": 3.1}}]}
+/// ```
+///
+/// and writes either `{"scores": [...]}`, one score per candidate in the same order, or
+/// `{"order": [...]}`, candidate ids from best to worst, to stdout. Candidates left out of
+/// an ordering follow the ordered ones. If the program fails, or is still running when the
+/// timeout passes or the search is cancelled, it is killed and the BM25 order is kept.
+pub struct CommandReranker {
+    command: String,
+    bm25: Bm25Params,
+    timeout: Duration,
+    cancel: CancellationToken,
+    /// Query the program gets, as the user wrote it (default: the query being scored)
+    query: Option<String>,
+}
+
+#[derive(Serialize)]
+struct RerankRequest<'a> {
+    query: &'a str,
+    candidates: Vec<Candidate<'a>>,
+}
+
+#[derive(Serialize)]
+struct Candidate<'a> {
+    id: usize,
+    file: &'a str,
+    lines: (usize, usize),
+    node_type: &'a str,
+    code: &'a str,
+    scores: HashMap<&'static str, f64>,
+}
+
+#[derive(Deserialize)]
+struct RerankResponse {
+    scores: Option<Vec<f64>>,
+    order: Option<Vec<usize>>,
+}
+
+impl CommandReranker {
+    /// `command` is the program followed by its arguments, split like a POSIX shell
+    /// would (without expanding anything), so arguments with spaces can be quoted:
+    /// `rerank.py --model "my model"`
+    pub fn new(command: &str) -> Self {
+        Self {
+            command: command.trim().to_string(),
+            bm25: Bm25Params::default(),
+            timeout: DEFAULT_TIMEOUT,
+            cancel: CancellationToken::new(),
+            query: None,
+        }
+    }
+
+    /// Set the BM25 parameters used to pick and pre-score the candidates
+    pub fn bm25(mut self, bm25: Bm25Params) -> Self {
+        self.bm25 = bm25;
+        self
+    }
+
+    /// Set how long the program may take before it is killed
+    #[allow(dead_code)]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Kill the program when the search this token belongs to is cancelled or times out
+    pub fn cancel(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// Give the program `query` instead of the query being scored, which has synonyms and
+    /// fuzzy terms expanded for BM25 to pick and weight the candidates with
+    pub fn query(mut self, query: &str) -> Self {
+        self.query = Some(query.to_string());
+        self
+    }
+
+    /// Run the program on the candidates and return `(candidate id, score)` pairs
+    fn rerank(&self, request: &RerankRequest) -> Result<Vec<(usize, f64)>> {
+        let deadline = Instant::now() + self.timeout;
+        let words = shell_words::split(&self.command)
+            .with_context(|| format!("Invalid reranker command '{}'", self.command))?;
+        let Some((program, args)) = words.split_first() else {
+            bail!(
+                "No reranker program given after '{}'",
+                COMMAND_RERANKER_PREFIX
+            );
+        };
+
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .with_context(|| format!("Failed to start '{}'", program))?;
+
+        // Write and read from other threads so a program that answers before reading all
+        // of its input cannot deadlock on a full pipe, and the wait below can give up
+        let input = serde_json::to_vec(request)?;
+        let mut stdin = child
+            .stdin
+            .take()
+            .context("Failed to open reranker stdin")?;
+        let mut stdout = child
+            .stdout
+            .take()
+            .context("Failed to open reranker stdout")?;
+        let writer = thread::spawn(move || stdin.write_all(&input));
+        let reader = thread::spawn(move || {
+            let mut output = Vec::new();
+            stdout.read_to_end(&mut output).map(|_| output)
+        });
+
+        let status = loop {
+            if let Some(status) = child.try_wait()? {
+                break status;
+            }
+            let cancelled = self.cancel.is_cancelled();
+            if cancelled || Instant::now() >= deadline {
+                // The pipes close with the program, which ends the writer and reader
+                let _ = child.kill();
+                let _ = child.wait();
+                if cancelled {
+                    bail!("The search was cancelled before '{}' answered", program);
+                }
+                bail!("'{}' did not answer within {:?}", program, self.timeout);
+            }
+            thread::sleep(POLL_INTERVAL);
+        };
+        // The program may exit without reading everything; only its answer matters
+        let _ = writer.join();
+        let output = reader
+            .join()
+            .map_err(|_| anyhow::anyhow!("Failed to read the output of '{}'", program))??;
+        if !status.success() {
+            bail!("'{}' exited with {}", program, status);
+        }
+
+        let response: RerankResponse = serde_json::from_slice(&output)
+            .with_context(|| format!("Invalid JSON from '{}'", program))?;
+        let count = request.candidates.len();
+        match (response.scores, response.order) {
+            (Some(scores), None) => {
+                if scores.len() != count {
+                    bail!("Got {} scores for {} candidates", scores.len(), count);
+                }
+                Ok(scores.into_iter().enumerate().collect())
+            }
+            (None, Some(order)) => {
+                if let Some(id) = order.iter().find(|&&id| id >= count) {
+                    bail!("Unknown candidate id {} in order", id);
+                }
+                // Turn positions into descending scores; the first mention of an id wins and
+                // ids left out follow in their BM25 order
+                let mut seen = vec![false; count];
+                let mentioned: Vec<usize> = order
+                    .into_iter()
+                    .filter(|&id| !std::mem::replace(&mut seen[id], true))
+                    .collect();
+                let rest = (0..count).filter(|&id| !seen[id]);
+                Ok(mentioned
+                    .into_iter()
+                    .chain(rest)
+                    .enumerate()
+                    .map(|(position, id)| (id, (count - position) as f64))
+                    .collect())
+            }
+            _ => bail!("Expected exactly one of \"scores\" or \"order\""),
+        }
+    }
+}
+
+impl Reranker for CommandReranker {
+    fn name(&self) -> &str {
+        &self.command
+    }
+
+    fn score(&self, query: &str, results: &[SearchResult]) -> Vec<(usize, f64)> {
+        let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
+        let start_time = Instant::now();
+
+        // BM25 picks the candidates and provides a score the program can use or ignore
+        let matched = Bm25Reranker { params: self.bm25 }.score(query, results);
+        let request = RerankRequest {
+            query: self.query.as_deref().unwrap_or(query),
+            candidates: matched
+                .iter()
+                .enumerate()
+                .map(|(id, &(index, bm25))| {
+                    let result = &results[index];
+                    Candidate {
+                        id,
+                        file: &result.file,
+                        lines: result.lines,
+                        node_type: &result.node_type,
+                        code: &result.code,
+                        scores: HashMap::from([("bm25", bm25)]),
+                    }
+                })
+                .collect(),
+        };
+        if request.candidates.is_empty() {
+            return matched;
+        }
+
+        match self.rerank(&request) {
+            Ok(scores) => {
+                if debug_mode {
+                    println!(
+                        "DEBUG: Reranker '{}' scored {} candidates in {:?}",
+                        self.command,
+                        scores.len(),
+                        start_time.elapsed()
+                    );
+                }
+                scores
+                    .into_iter()
+                    .map(|(id, score)| (matched[id].0, score))
+                    .collect()
+            }
+            Err(e) => {
+                eprintln!(
+                    "Error running reranker '{}', keeping BM25 order: {:#}",
+                    self.command, e
+                );
+                matched
+            }
+        }
+    }
+
+    fn record(&self, _result: &mut SearchResult, _score: f64, _rank: usize) {
+        // The external score is only reported as the combined score
+    }
+}
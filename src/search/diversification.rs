@@ -0,0 +1,165 @@
+use crate::models::SearchResult;
+use std::collections::{HashMap, HashSet};
+
+/// Only the best-ranked candidates are re-ordered; comparing every pair of a large
+/// result set would cost more than the search itself
+const DIVERSITY_WINDOW: usize = 500;
+
+/// Re-order ranked results with maximal marginal relevance (MMR) and cap the number of
+/// results per file.
+///
+/// Results are picked one at a time, each time taking the candidate with the best
+/// `(1 - diversity) * relevance - diversity * similarity`, where relevance is the score
+/// relative to the best result and similarity is the highest token overlap (Jaccard)
+/// with an already picked result. A `diversity` of 0 keeps the ranked order; higher
+/// values push near-duplicates of earlier results down. With `max_per_file`, results
+/// from a file that already has that many are dropped.
+///
+/// Picked results get new 1-based ranks so that `apply_limits` keeps this order.
+pub fn diversify_results(
+    results: Vec<SearchResult>,
+    diversity: f64,
+    max_per_file: Option<usize>,
+) -> Vec<SearchResult> {
+    let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
+    let diversity = diversity.clamp(0.0, 1.0);
+
+    let mut results = results;
+    results.sort_by_key(|r| r.rank.unwrap_or(usize::MAX));
+
+    // Results the ranking marked as excluded stay behind the ranked ones untouched
+    let split = results
+        .iter()
+        .position(|r| r.rank.is_none_or(|rank| rank == usize::MAX))
+        .unwrap_or(results.len());
+    let unranked = results.split_off(split);
+    let rest = if results.len() > DIVERSITY_WINDOW && diversity > 0.0 {
+        results.split_off(DIVERSITY_WINDOW)
+    } else {
+        Vec::new()
+    };
+
+    let original_count = results.len() + rest.len();
+    let mut picked = if diversity > 0.0 {
+        pick_diverse(results, diversity, max_per_file)
+    } else {
+        cap_per_file(results, max_per_file, &mut HashMap::new())
+    };
+
+    // Candidates outside the window keep their order, still subject to the cap
+    let mut per_file = HashMap::new();
+    for result in &picked {
+        *per_file.entry(result.file.clone()).or_insert(0) += 1;
+    }
+    picked.extend(cap_per_file(rest, max_per_file, &mut per_file));
+
+    if debug_mode {
+        println!(
+            "DEBUG: Diversified {} ranked results (diversity {}, max per file {:?}) into {}",
+            original_count,
+            diversity,
+            max_per_file,
+            picked.len()
+        );
+    }
+
+    for (index, result) in picked.iter_mut().enumerate() {
+        result.rank = Some(index + 1);
+    }
+    picked.extend(unranked);
+    picked
+}
+
+/// Keep results in order, dropping those from files that already have `max_per_file`
+fn cap_per_file(
+    results: Vec<SearchResult>,
+    max_per_file: Option<usize>,
+    per_file: &mut HashMap<String, usize>,
+) -> Vec<SearchResult> {
+    let Some(max_per_file) = max_per_file else {
+        return results;
+    };
+    results
+        .into_iter()
+        .filter(|result| {
+            let count = per_file.entry(result.file.clone()).or_insert(0);
+            *count += 1;
+            *count <= max_per_file
+        })
+        .collect()
+}
+
+/// Greedy MMR selection over ranked results
+fn pick_diverse(
+    results: Vec<SearchResult>,
+    diversity: f64,
+    max_per_file: Option<usize>,
+) -> Vec<SearchResult> {
+    let max_score = results
+        .iter()
+        .filter_map(|r| r.score)
+        .fold(0.0f64, f64::max);
+    let relevance: Vec<f64> = results
+        .iter()
+        .map(|r| match r.score {
+            Some(score) if max_score > 0.0 => score / max_score,
+            _ => 0.0,
+        })
+        .collect();
+    let tokens: Vec<HashSet<String>> = results.iter().map(block_token_set).collect();
+
+    let mut remaining: Vec<usize> = (0..results.len()).collect();
+    // Highest similarity of each candidate to any picked result
+    let mut max_similarity = vec![0.0f64; results.len()];
+    let mut per_file: HashMap<&str, usize> = HashMap::new();
+    let mut order = Vec::with_capacity(results.len());
+
+    while !remaining.is_empty() {
+        if let Some(max_per_file) = max_per_file {
+            remaining.retain(|&i| {
+                per_file.get(results[i].file.as_str()).copied().unwrap_or(0) < max_per_file
+            });
+        }
+
+        // Ties go to the better-ranked candidate, which comes first in `remaining`
+        let mut best: Option<(usize, f64)> = None;
+        for (position, &i) in remaining.iter().enumerate() {
+            let value = (1.0 - diversity) * relevance[i] - diversity * max_similarity[i];
+            if best.is_none_or(|(_, best_value)| value > best_value) {
+                best = Some((position, value));
+            }
+        }
+        let Some((position, _)) = best else {
+            break;
+        };
+
+        let chosen = remaining.remove(position);
+        *per_file.entry(results[chosen].file.as_str()).or_insert(0) += 1;
+        for &i in &remaining {
+            let similarity = jaccard(&tokens[chosen], &tokens[i]);
+            if similarity > max_similarity[i] {
+                max_similarity[i] = similarity;
+            }
+        }
+        order.push(chosen);
+    }
+
+    let mut slots: Vec<Option<SearchResult>> = results.into_iter().map(Some).collect();
+    order.into_iter().filter_map(|i| slots[i].take()).collect()
+}
+
+/// Tokens of a block, reusing the ones computed during the search when available
+fn block_token_set(result: &SearchResult) -> HashSet<String> {
+    match &result.tokenized_content {
+        Some(tokens) => tokens.iter().cloned().collect(),
+        None => crate::ranking::tokenize(&result.code).into_iter().collect(),
+    }
+}
+
+fn jaccard(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    if a.is_empty() && b.is_empty() {
+        return 0.0;
+    }
+    let intersection = a.intersection(b).count();
+    intersection as f64 / (a.len() + b.len() - intersection) as f64
+}
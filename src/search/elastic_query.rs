@@ -1,23 +1,244 @@
+use regex::Regex;
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::iter::Peekable;
 use std::str::Chars;
 
+/// Field qualifiers understood in `field:term` queries.
+///
+/// `path:`, `lang:` and `type:` are filters: they restrict which files and blocks
+/// may match instead of matching text themselves. `comment:` and `code:` are ordinary
+/// search terms that only count when found in comment or non-comment text respectively.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum QueryField {
+    /// `path:` / `file:` => substring or glob match on the file path
+    Path,
+    /// `lang:` / `language:` => the file's programming language
+    Lang,
+    /// `type:` => the tree-sitter node type of the block
+    Type,
+    /// `comment:` => keyword must appear inside a comment
+    Comment,
+    /// `code:` => keyword must appear outside comments
+    Code,
+}
+
+impl QueryField {
+    /// Resolve a field name (including aliases) to a known field.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "path" | "file" => Some(QueryField::Path),
+            "lang" | "language" => Some(QueryField::Lang),
+            "type" => Some(QueryField::Type),
+            "comment" => Some(QueryField::Comment),
+            "code" => Some(QueryField::Code),
+            _ => None,
+        }
+    }
+
+    /// Canonical name, as stored in `Expr::Term::field`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QueryField::Path => "path",
+            QueryField::Lang => "lang",
+            QueryField::Type => "type",
+            QueryField::Comment => "comment",
+            QueryField::Code => "code",
+        }
+    }
+
+    /// Returns `true` for fields that filter files/blocks rather than match text.
+    pub fn is_filter(&self) -> bool {
+        matches!(self, QueryField::Path | QueryField::Lang | QueryField::Type)
+    }
+
+    /// Key under which a keyword is stored in a plan's `term_indices`.
+    /// Field-scoped text terms get their own entry (`comment:foo`) so they can be
+    /// matched independently of the same keyword used without a field.
+    pub fn term_key(field: Option<&str>, keyword: &str) -> String {
+        match field.and_then(QueryField::from_name) {
+            Some(f) if !f.is_filter() => format!("{}:{}", f.as_str(), keyword),
+            _ => keyword.to_string(),
+        }
+    }
+
+    /// Inverse of [`QueryField::term_key`]: split a term key into its field and keyword.
+    pub fn split_key(key: &str) -> (Option<QueryField>, &str) {
+        if let Some((name, keyword)) = key.split_once(':') {
+            if let Some(f) = QueryField::from_name(name).filter(|f| !f.is_filter()) {
+                return (Some(f), keyword);
+            }
+        }
+        (None, key)
+    }
+}
+
+/// How a wildcard, regex or fuzzy term is matched. The term's only keyword holds the
+/// pattern as written, without the slashes around a regex or the `~n` after a fuzzy word.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TermPattern {
+    /// `auth*`, `*Handler` => `*` stands for any run of letters and digits, and the
+    /// pattern must match a whole word part (`getAuthToken` has the parts `get`, `auth`
+    /// and `token`)
+    Wildcard,
+    /// `/re(try|tries)/` => a regular expression matched anywhere in the text
+    Regex,
+    /// `recieve~1` => any word or word part at most `n` edits away (see
+    /// `fuzzy::edit_distance`)
+    Fuzzy(usize),
+}
+
+impl TermPattern {
+    /// Regex that finds the term in a line of text, for the file search. For a fuzzy term
+    /// that is only the word as written; the words of the corpus within its distance are
+    /// searched for as well (see `query::create_fuzzy_patterns`).
+    pub fn line_pattern(&self, source: &str) -> String {
+        match self {
+            TermPattern::Wildcard => source
+                .split('*')
+                .map(crate::search::query::regex_escape)
+                .collect::<Vec<_>>()
+                .join(r"\w*"),
+            TermPattern::Regex => source.to_string(),
+            TermPattern::Fuzzy(_) => crate::search::query::regex_escape(source),
+        }
+    }
+
+    /// Compile the pattern, or `None` if `source` is not a valid regex
+    pub fn regex(&self, source: &str) -> Option<Regex> {
+        let pattern = match self {
+            TermPattern::Wildcard | TermPattern::Fuzzy(_) => {
+                format!("(?i)^{}$", self.line_pattern(source))
+            }
+            TermPattern::Regex => format!("(?i){}", source),
+        };
+        Regex::new(&pattern).ok()
+    }
+
+    /// Compile the pattern for `source` once, to check many texts against it
+    pub fn compile(&self, source: &str) -> CompiledPattern {
+        CompiledPattern {
+            pattern: *self,
+            source: source.to_string(),
+            regex: self.regex(source),
+        }
+    }
+}
+
+/// A wildcard, regex or fuzzy term with its regex compiled, as `QueryPlan` keeps them
+#[derive(Debug, Clone)]
+pub struct CompiledPattern {
+    pub pattern: TermPattern,
+    /// The pattern as written (see `TermPattern`)
+    pub source: String,
+    /// `None` for an invalid regex, which matches nothing
+    regex: Option<Regex>,
+}
+
+impl CompiledPattern {
+    /// Returns `true` if the term matches anywhere in `text`.
+    pub fn matches(&self, text: &str) -> bool {
+        match self.pattern {
+            TermPattern::Wildcard => self
+                .regex
+                .as_ref()
+                .is_some_and(|regex| word_parts(text).any(|part| regex.is_match(&part))),
+            TermPattern::Regex => self
+                .regex
+                .as_ref()
+                .is_some_and(|regex| regex.is_match(text)),
+            TermPattern::Fuzzy(distance) => {
+                fuzzy_words(text).any(|word| is_fuzzy_match(&self.source, &word, distance))
+            }
+        }
+    }
+
+    /// The tokens the term matches in `text`, as `tokenization::tokenize` produces them,
+    /// so that the term can be scored like the keywords it stands for.
+    pub fn matched_tokens(&self, text: &str) -> Vec<String> {
+        match self.pattern {
+            TermPattern::Wildcard => self.regex.as_ref().map_or_else(Vec::new, |regex| {
+                word_parts(text)
+                    .filter(|part| regex.is_match(part))
+                    .flat_map(|part| custom_tokenize(&part))
+                    .collect()
+            }),
+            // Widen each match to whole words, so that the tokens are the document's own
+            TermPattern::Regex => self.regex.as_ref().map_or_else(Vec::new, |regex| {
+                regex
+                    .find_iter(text)
+                    .flat_map(|m| {
+                        let start = text[..m.start()]
+                            .char_indices()
+                            .rev()
+                            .find(|(_, c)| !c.is_alphanumeric())
+                            .map_or(0, |(i, c)| i + c.len_utf8());
+                        let end = text[m.end()..]
+                            .find(|c: char| !c.is_alphanumeric())
+                            .map_or(text.len(), |i| m.end() + i);
+                        custom_tokenize(&text[start..end])
+                    })
+                    .collect()
+            }),
+            TermPattern::Fuzzy(distance) => fuzzy_words(text)
+                .filter(|word| is_fuzzy_match(&self.source, word, distance))
+                .flat_map(|word| custom_tokenize(&word))
+                .collect(),
+        }
+    }
+}
+
+/// Words a fuzzy term is compared with: each word of `text` and, for camelCase and
+/// snake_case identifiers, their parts, all lowercase
+fn fuzzy_words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|word| !word.is_empty())
+        .flat_map(|word| {
+            let mut words = vec![word.to_lowercase()];
+            if word.contains('_') || word.chars().any(|c| c.is_uppercase()) {
+                words.extend(word_parts(word));
+            }
+            words
+        })
+}
+
+/// Returns `true` if `word` is within `distance` edits of the fuzzy term `source`
+fn is_fuzzy_match(source: &str, word: &str, distance: usize) -> bool {
+    crate::search::fuzzy::edit_distance(&source.to_lowercase(), word, distance).is_some()
+}
+
+/// The lowercase words of `text`, with camelCase and PascalCase identifiers split into
+/// their parts
+fn word_parts(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .flat_map(crate::search::tokenization::split_camel_case)
+        .map(|part| part.to_lowercase())
+}
+
 /// The AST representing a parsed query.
 #[derive(Debug, Clone, PartialEq)]
 pub enum Expr {
     /// A search term, which can represent multiple keywords.
     /// `keywords` => a list of keywords (possibly tokenized/split)
-    /// `field` => optional field specifier (e.g. `Some("title")` for `title:foo`)
+    /// `field` => optional field specifier (e.g. `Some("path")` for `path:foo`), see [`QueryField`]
     /// `required` => a leading `+`
     /// `excluded` => a leading `-`
     /// `exact` => if originally quoted, meaning "no tokenization/splitting"
+    /// `proximity` => `Some(n)` for `"a b"~n` and `a NEAR/n b`: the keywords must occur
+    /// with at most `n` other tokens between them
+    /// `pattern` => set for wildcard (`auth*`), regex (`/re(try|tries)/`) and fuzzy
+    /// (`recieve~1`) terms, see [`TermPattern`]
+    /// `boost` => `Some(n)` for `term^n`: the term's ranking score is multiplied by `n`
     Term {
         keywords: Vec<String>,
         field: Option<String>,
         required: bool,
         excluded: bool,
         exact: bool,
+        proximity: Option<usize>,
+        pattern: Option<TermPattern>,
+        boost: Option<f64>,
     },
 
     /// Logical AND of two sub-expressions.
@@ -28,6 +249,55 @@ pub enum Expr {
 }
 
 impl Expr {
+    /// The field qualifier of a term, if it names a known field.
+    pub fn query_field(&self) -> Option<QueryField> {
+        match self {
+            Expr::Term {
+                field: Some(name), ..
+            } => QueryField::from_name(name),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if this is a `path:`/`lang:`/`type:` filter term. Filters are applied
+    /// separately (see `QueryPlan::matches_file`), so boolean evaluation and scoring treat
+    /// them as neutral.
+    pub fn is_filter_term(&self) -> bool {
+        self.query_field().is_some_and(|f| f.is_filter())
+    }
+
+    /// Returns `true` if the expression is a filter term or a group made only of filter
+    /// terms (`lang:rust OR lang:go`). Such a group filters files like the same filters
+    /// written outside it, so it is as neutral as a lone filter.
+    pub fn is_filter_only(&self) -> bool {
+        match self {
+            Expr::Term { .. } => self.is_filter_term(),
+            Expr::And(left, right) | Expr::Or(left, right) => {
+                left.is_filter_only() && right.is_filter_only()
+            }
+        }
+    }
+
+    /// Returns `true` if the expression contains a `"a b"~n` or `a NEAR/n b` term.
+    pub fn has_proximity(&self) -> bool {
+        match self {
+            Expr::Term { proximity, .. } => proximity.is_some(),
+            Expr::And(left, right) | Expr::Or(left, right) => {
+                left.has_proximity() || right.has_proximity()
+            }
+        }
+    }
+
+    /// Returns `true` if the expression contains a wildcard or regex term.
+    pub fn has_pattern(&self) -> bool {
+        match self {
+            Expr::Term { pattern, .. } => pattern.is_some(),
+            Expr::And(left, right) | Expr::Or(left, right) => {
+                left.has_pattern() || right.has_pattern()
+            }
+        }
+    }
+
     /// Extract required and optional terms from the AST, excluding negative terms
     #[cfg(test)]
     pub fn extract_terms(&self) -> (Vec<String>, Vec<String>) {
@@ -70,7 +340,7 @@ impl Expr {
     /// Returns `true` if this expression contains at least one `required=true` term.
     fn has_required_term(&self) -> bool {
         match self {
-            Expr::Term { required, .. } => *required,
+            Expr::Term { required, .. } => *required && !self.is_filter_term(),
             Expr::And(left, right) | Expr::Or(left, right) => {
                 left.has_required_term() || right.has_required_term()
             }
@@ -85,40 +355,54 @@ impl Expr {
         term_indices: &HashMap<String, usize>,
         ignore_negatives: bool,
         has_required_anywhere: bool,
+        tokens: Option<&[(usize, String)]>,
     ) -> bool {
         let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
 
+        if self.is_filter_only() {
+            // Filters are enforced outside the expression; a lone filter or a group of
+            // them is neutral
+            return true;
+        }
+
         match self {
             Expr::Term {
                 keywords,
+                field,
                 required,
                 excluded,
+                proximity,
                 ..
             } => {
                 if keywords.is_empty() {
                     // Empty term => if excluded, trivially true, otherwise false
                     return *excluded;
                 }
-                // Are all keywords present?
-                let all_present = keywords.iter().all(|kw| {
+                let is_present = |kw: &String| {
                     term_indices
-                        .get(kw)
+                        .get(&QueryField::term_key(field.as_deref(), kw))
                         .map(|idx| matched_terms.contains(idx))
                         .unwrap_or(false)
-                });
+                };
+                // Are all keywords present, and close enough together for proximity terms?
+                let all_present = keywords.iter().all(is_present)
+                    && match (proximity, tokens) {
+                        (Some(distance), Some(tokens)) => {
+                            proximity_span(tokens, keywords).is_some_and(|span| span <= *distance)
+                        }
+                        _ => true,
+                    };
 
                 if *excluded {
                     if ignore_negatives {
                         // Negative ignored => always true
                         true
+                    } else if proximity.is_some() {
+                        // Excluded proximity term => the keywords must not occur close together
+                        !all_present
                     } else {
                         // Excluded => none should be present
-                        !keywords.iter().any(|kw| {
-                            term_indices
-                                .get(kw)
-                                .map(|idx| matched_terms.contains(idx))
-                                .unwrap_or(false)
-                        })
+                        !keywords.iter().any(is_present)
                     }
                 } else if *required && ignore_negatives {
                     // If ignoring negatives, we've already enforced required terms up front.
@@ -138,12 +422,7 @@ impl Expr {
                         // ["jwt", "middleware"], both parts must be present.
 
                         // Check if any keywords are present
-                        let any_present = keywords.iter().any(|kw| {
-                            term_indices
-                                .get(kw)
-                                .map(|idx| matched_terms.contains(idx))
-                                .unwrap_or(false)
-                        });
+                        let any_present = keywords.iter().any(is_present);
 
                         // If no keywords are present, the term doesn't match
                         if !any_present {
@@ -156,18 +435,36 @@ impl Expr {
                     }
                 }
             }
+            Expr::And(left, right) | Expr::Or(left, right) if left.is_filter_only() => right
+                .evaluate_with_has_required(
+                    matched_terms,
+                    term_indices,
+                    ignore_negatives,
+                    has_required_anywhere,
+                    tokens,
+                ),
+            Expr::And(left, right) | Expr::Or(left, right) if right.is_filter_only() => left
+                .evaluate_with_has_required(
+                    matched_terms,
+                    term_indices,
+                    ignore_negatives,
+                    has_required_anywhere,
+                    tokens,
+                ),
             Expr::And(left, right) => {
                 let lval = left.evaluate_with_has_required(
                     matched_terms,
                     term_indices,
                     ignore_negatives,
                     has_required_anywhere,
+                    tokens,
                 );
                 let rval = right.evaluate_with_has_required(
                     matched_terms,
                     term_indices,
                     ignore_negatives,
                     has_required_anywhere,
+                    tokens,
                 );
                 if debug_mode {
                     println!(
@@ -188,12 +485,14 @@ impl Expr {
                     term_indices,
                     ignore_negatives,
                     has_required_anywhere,
+                    tokens,
                 );
                 let rval = right.evaluate_with_has_required(
                     matched_terms,
                     term_indices,
                     ignore_negatives,
                     has_required_anywhere,
+                    tokens,
                 );
 
                 if debug_mode {
@@ -216,12 +515,37 @@ impl Expr {
     /// - AND => both sides must match.
     /// - OR => at least one side must match.
     /// - `ignore_negatives` => if true, excluded terms are basically ignored (they don’t exclude).
-    /// - Field is **ignored** in evaluation, per request.
+    /// - `comment:`/`code:` terms are looked up under their field-scoped key.
+    /// - `path:`/`lang:`/`type:` filters are neutral here; they are applied separately.
     pub fn evaluate(
         &self,
         matched_terms: &HashSet<usize>,
         term_indices: &HashMap<String, usize>,
         ignore_negatives: bool,
+    ) -> bool {
+        self.evaluate_tokens(matched_terms, term_indices, ignore_negatives, None)
+    }
+
+    /// Like [`Expr::evaluate`], but proximity terms (`"a b"~n`, `a NEAR/n b`) only match
+    /// when their keywords occur close enough together in `tokens`, the positioned tokens
+    /// of the block (see `tokenization::token_positions`). `evaluate` only checks that
+    /// their keywords are present.
+    pub fn evaluate_with_tokens(
+        &self,
+        matched_terms: &HashSet<usize>,
+        term_indices: &HashMap<String, usize>,
+        ignore_negatives: bool,
+        tokens: &[(usize, String)],
+    ) -> bool {
+        self.evaluate_tokens(matched_terms, term_indices, ignore_negatives, Some(tokens))
+    }
+
+    fn evaluate_tokens(
+        &self,
+        matched_terms: &HashSet<usize>,
+        term_indices: &HashMap<String, usize>,
+        ignore_negatives: bool,
+        tokens: Option<&[(usize, String)]>,
     ) -> bool {
         let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
 
@@ -232,12 +556,16 @@ impl Expr {
                 match expr {
                     Expr::Term {
                         keywords,
+                        field,
                         required,
                         excluded,
                         ..
                     } => {
-                        if *required && !*excluded {
-                            keywords.clone()
+                        if *required && !*excluded && !expr.is_filter_term() {
+                            keywords
+                                .iter()
+                                .map(|kw| QueryField::term_key(field.as_deref(), kw))
+                                .collect()
                         } else {
                             vec![]
                         }
@@ -295,10 +623,60 @@ impl Expr {
             term_indices,
             ignore_negatives,
             has_required_anywhere,
+            tokens,
         )
     }
 }
 
+/// The fewest other tokens between the keywords in any stretch of `tokens` that contains
+/// all of them, or `None` if some keyword does not occur. `tokens` are positioned tokens
+/// as returned by `tokenization::token_positions`.
+pub fn proximity_span(tokens: &[(usize, String)], keywords: &[String]) -> Option<usize> {
+    let mut wanted: Vec<&String> = keywords.iter().collect();
+    wanted.sort();
+    wanted.dedup();
+    if wanted.is_empty() {
+        return None;
+    }
+
+    // Occurrences of the keywords in document order, as (position, keyword index)
+    let hits: Vec<(usize, usize)> = tokens
+        .iter()
+        .filter_map(|(position, token)| {
+            wanted
+                .binary_search(&token)
+                .ok()
+                .map(|keyword| (*position, keyword))
+        })
+        .collect();
+
+    // Slide a window over the hits, shrinking it from the left while it still covers
+    // every keyword
+    let mut counts = vec![0usize; wanted.len()];
+    let mut covered = 0;
+    let mut start = 0;
+    let mut best: Option<usize> = None;
+    for end in 0..hits.len() {
+        if counts[hits[end].1] == 0 {
+            covered += 1;
+        }
+        counts[hits[end].1] += 1;
+
+        while covered == wanted.len() {
+            let words = hits[end].0 - hits[start].0 + 1;
+            let span = words.saturating_sub(wanted.len());
+            best = Some(best.map_or(span, |b| b.min(span)));
+
+            counts[hits[start].1] -= 1;
+            if counts[hits[start].1] == 0 {
+                covered -= 1;
+            }
+            start += 1;
+        }
+    }
+    best
+}
+
 impl std::fmt::Display for Expr {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -308,6 +686,9 @@ impl std::fmt::Display for Expr {
                 required,
                 excluded,
                 exact,
+                proximity,
+                pattern,
+                boost,
             } => {
                 let prefix = if *required {
                     "+"
@@ -323,13 +704,41 @@ impl std::fmt::Display for Expr {
                 };
                 // If there's exactly one keyword and it's exact => show it quoted
                 // If multiple or not exact => "quoted" with joined keywords
-                if keywords.len() == 1 && *exact {
-                    write!(f, "{}{}\"{}\"", prefix, field_prefix, keywords[0])
+                // Proximity terms => "quoted"~n
+                // Regex terms => /regex/
+                // Fuzzy terms => term~n
+                // Boosted terms => term^n
+                if let Some(TermPattern::Fuzzy(distance)) = pattern {
+                    write!(
+                        f,
+                        "{}{}{}~{}",
+                        prefix,
+                        field_prefix,
+                        keywords.join(" "),
+                        distance
+                    )?;
+                } else if *pattern == Some(TermPattern::Regex) {
+                    write!(f, "{}{}/{}/", prefix, field_prefix, keywords.join(" "))?;
+                } else if let Some(distance) = proximity {
+                    write!(
+                        f,
+                        "{}{}\"{}\"~{}",
+                        prefix,
+                        field_prefix,
+                        keywords.join(" "),
+                        distance
+                    )?;
+                } else if keywords.len() == 1 && *exact {
+                    write!(f, "{}{}\"{}\"", prefix, field_prefix, keywords[0])?;
                 } else if keywords.len() == 1 {
-                    write!(f, "{}{}{}", prefix, field_prefix, keywords[0])
+                    write!(f, "{}{}{}", prefix, field_prefix, keywords[0])?;
                 } else {
-                    write!(f, "{}{}\"{}\"", prefix, field_prefix, keywords.join(" "))
+                    write!(f, "{}{}\"{}\"", prefix, field_prefix, keywords.join(" "))?;
+                }
+                if let Some(boost) = boost {
+                    write!(f, "^{}", boost)?;
                 }
+                Ok(())
             }
             Expr::And(left, right) => write!(f, "({} AND {})", left, right),
             Expr::Or(left, right) => write!(f, "({} OR {})", left, right),
@@ -348,6 +757,11 @@ pub enum Token {
     Colon,                // ':'
     Ident(String),        // alphanumeric / underscore / dot
     QuotedString(String), // raw string inside quotes
+    Slop(usize),          // '~N' right after a quoted string
+    Near(usize),          // 'NEAR/N'
+    Regex(String),        // raw regex between slashes
+    Boost(f64),           // '^N'
+    Fuzzy(usize),         // '~N' right after a word
 }
 
 /// A simple error type for parsing/tokenizing.
@@ -403,22 +817,85 @@ fn tokenize(input: &str) -> Result<Vec<Token>, ParseError> {
                 tokens.push(Token::Colon);
                 chars.next();
             }
+            '^' => {
+                chars.next();
+                let boost = lex_boost(&mut chars).ok_or(ParseError::Generic(
+                    "Expected a positive number after '^'".to_string(),
+                ))?;
+                tokens.push(Token::Boost(boost));
+            }
+            '/' => {
+                chars.next(); // consume the opening slash
+                              // Without a closing slash it is not a regex, just an unknown character
+                let mut lookahead = chars.clone();
+                if let Some(regex) = lex_regex(&mut lookahead) {
+                    tokens.push(Token::Regex(regex));
+                    chars = lookahead;
+                } else if debug_mode {
+                    println!("DEBUG: Skipping unterminated regex at '/'");
+                }
+            }
             '"' => {
                 chars.next(); // consume the opening quote
                 let quoted_string = lex_quoted_string(&mut chars)?;
                 tokens.push(Token::QuotedString(quoted_string));
+                // `"a b"~N` => the words may be up to N tokens apart
+                if chars.peek() == Some(&'~') {
+                    chars.next();
+                    let slop = lex_number(&mut chars).ok_or(ParseError::Generic(
+                        "Expected a number after '~'".to_string(),
+                    ))?;
+                    tokens.push(Token::Slop(slop));
+                }
             }
             _ => {
-                // If it starts with alphanumeric, underscore, or dot => parse identifier
-                if ch.is_alphanumeric() || ch == '_' || ch == '.' {
+                // If it starts with alphanumeric, underscore, dot or a wildcard => parse identifier
+                if ch.is_alphanumeric() || ch == '_' || ch == '.' || ch == '*' {
                     let ident = lex_identifier(&mut chars);
                     let ident_upper = ident.to_ascii_uppercase();
-                    if ident_upper == "AND" {
+                    if ident.chars().all(|c| c == '*') {
+                        // A bare wildcard would match everything
+                        if debug_mode {
+                            println!("DEBUG: Skipping bare wildcard '{}'", ident);
+                        }
+                    } else if ident_upper == "AND" {
                         tokens.push(Token::And);
                     } else if ident_upper == "OR" {
                         tokens.push(Token::Or);
+                    } else if ident_upper == "NEAR" && chars.peek() == Some(&'/') {
+                        chars.next();
+                        let distance = lex_number(&mut chars).ok_or(ParseError::Generic(
+                            "Expected a number after 'NEAR/'".to_string(),
+                        ))?;
+                        tokens.push(Token::Near(distance));
+                    } else if chars.peek() == Some(&':')
+                        && QueryField::from_name(&ident).is_some_and(|f| f.is_filter())
+                    {
+                        // Filter values such as `path:src/search/*.rs` are taken verbatim
+                        tokens.push(Token::Ident(ident));
+                        tokens.push(Token::Colon);
+                        chars.next();
+                        if let Some(value) = lex_field_value(&mut chars) {
+                            tokens.push(Token::Ident(value));
+                        }
                     } else {
                         tokens.push(Token::Ident(ident));
+                        // `word~N` => words up to N edits away match too
+                        if chars.peek() == Some(&'~') {
+                            chars.next();
+                            let distance = lex_number(&mut chars).ok_or(ParseError::Generic(
+                                "Expected a number after '~'".to_string(),
+                            ))?;
+                            // Larger distances match most words, so they are capped
+                            tokens.push(Token::Fuzzy(
+                                distance.min(crate::search::fuzzy::MAX_FUZZY_DISTANCE),
+                            ));
+                        }
+                    }
+                    // A slash straight after a word separates path segments (`src/search`)
+                    // rather than starting a regex
+                    if chars.peek() == Some(&'/') {
+                        chars.next();
                     }
                 } else {
                     // Skip unknown characters
@@ -464,10 +941,51 @@ fn lex_quoted_string(chars: &mut Peekable<Chars>) -> Result<String, ParseError>
     Err(ParseError::UnexpectedEndOfInput)
 }
 
+/// Lex the factor of a `^N` boost, which may have a fractional part (`^0.5`)
+fn lex_boost(chars: &mut Peekable<Chars>) -> Option<f64> {
+    let mut buf = String::new();
+    while let Some(&ch) = chars.peek() {
+        if ch.is_ascii_digit() || ch == '.' {
+            buf.push(ch);
+            chars.next();
+        } else {
+            break;
+        }
+    }
+    buf.parse::<f64>()
+        .ok()
+        .filter(|boost| boost.is_finite() && *boost > 0.0)
+}
+
+/// Lex a `/regex/`, allowing `\/` to escape slashes; other escapes are kept for the regex.
+/// Returns `None` if there is no closing slash.
+fn lex_regex(chars: &mut Peekable<Chars>) -> Option<String> {
+    let mut buf = String::new();
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\\' if chars.peek() == Some(&'/') => {
+                buf.push('/');
+                chars.next();
+            }
+            '\\' => {
+                buf.push(ch);
+                if let Some(escaped) = chars.next() {
+                    buf.push(escaped);
+                }
+            }
+            '/' => return Some(buf),
+            _ => buf.push(ch),
+        }
+    }
+    // If we get here, we ran out of characters before finding a closing slash
+    None
+}
+
 fn lex_identifier(chars: &mut Peekable<Chars>) -> String {
     let mut buf = String::new();
     while let Some(&ch) = chars.peek() {
-        if ch.is_alphanumeric() || ch == '_' || ch == '.' {
+        if ch.is_alphanumeric() || ch == '_' || ch == '.' || ch == '*' {
             buf.push(ch);
             chars.next();
         } else {
@@ -477,6 +995,38 @@ fn lex_identifier(chars: &mut Peekable<Chars>) -> String {
     buf
 }
 
+/// Lex a non-negative decimal number, as in `~5` or `NEAR/3`
+fn lex_number(chars: &mut Peekable<Chars>) -> Option<usize> {
+    let mut buf = String::new();
+    while let Some(&ch) = chars.peek() {
+        if ch.is_ascii_digit() {
+            buf.push(ch);
+            chars.next();
+        } else {
+            break;
+        }
+    }
+    buf.parse().ok()
+}
+
+/// Lex the raw value of a filter field up to the next whitespace or closing parenthesis.
+/// Returns `None` when the value is quoted or missing, leaving it to the regular lexer.
+fn lex_field_value(chars: &mut Peekable<Chars>) -> Option<String> {
+    let mut buf = String::new();
+    while let Some(&ch) = chars.peek() {
+        if ch.is_whitespace() || ch == ')' || (buf.is_empty() && (ch == '"' || ch == '(')) {
+            break;
+        }
+        buf.push(ch);
+        chars.next();
+    }
+    if buf.is_empty() {
+        None
+    } else {
+        Some(buf)
+    }
+}
+
 // Adjust paths to match your project structure
 use crate::search::tokenization::{add_special_term, tokenize as custom_tokenize};
 
@@ -539,14 +1089,14 @@ impl Parser {
             println!("DEBUG: parse_and_expr => pos={}", self.pos);
         }
 
-        let mut left = self.parse_factor()?;
+        let mut left = self.parse_near_expr()?;
 
         while let Some(token) = self.peek() {
             match token {
                 // Explicit "AND"
                 Token::And => {
                     self.next(); // consume 'AND'
-                    let right = self.parse_factor()?;
+                    let right = self.parse_near_expr()?;
                     left = Expr::And(Box::new(left), Box::new(right));
                     if debug_mode {
                         println!("DEBUG: AND => {:?}", left);
@@ -558,15 +1108,15 @@ impl Parser {
                 }
                 // If next token is a plus or minus, interpret as an AND
                 Token::Plus | Token::Minus => {
-                    let right = self.parse_factor()?;
+                    let right = self.parse_near_expr()?;
                     left = Expr::And(Box::new(left), Box::new(right));
                     if debug_mode {
                         println!("DEBUG: forced AND => {:?}", left);
                     }
                 }
                 // Otherwise (Ident, QuotedString, LParen) => implicit combos
-                Token::Ident(_) | Token::QuotedString(_) | Token::LParen => {
-                    let right = self.parse_factor()?;
+                Token::Ident(_) | Token::QuotedString(_) | Token::Regex(_) | Token::LParen => {
+                    let right = self.parse_near_expr()?;
                     // Use OR for implicit combinations (space-separated terms) - Elasticsearch standard behavior
                     left = Expr::Or(Box::new(left), Box::new(right));
                     if debug_mode {
@@ -579,13 +1129,30 @@ impl Parser {
         Ok(left)
     }
 
+    /// `a NEAR/n b` binds tighter than AND/OR and joins its operands into one proximity
+    /// term. In a chain (`a NEAR/2 b NEAR/5 c`) the largest distance applies.
+    fn parse_near_expr(&mut self) -> Result<Expr, ParseError> {
+        let mut left = self.parse_factor()?;
+
+        while let Some(&Token::Near(distance)) = self.peek() {
+            self.next(); // consume 'NEAR/n'
+            let right = self.parse_factor()?;
+            left = join_near(left, right, distance)?;
+        }
+        Ok(left)
+    }
+
     fn parse_factor(&mut self) -> Result<Expr, ParseError> {
         match self.peek() {
             Some(Token::LParen) => {
                 self.next(); // consume '('
                 let expr = self.parse_expr()?;
                 self.expect(&Token::RParen)?;
-                Ok(expr)
+                // `(a OR b)^n` boosts every term in the group
+                Ok(match self.parse_boost() {
+                    Some(boost) => boost_terms(expr, boost),
+                    None => expr,
+                })
             }
             _ => self.parse_prefixed_term(),
         }
@@ -609,6 +1176,7 @@ impl Parser {
         }
 
         let primary_expr = self.parse_primary()?;
+        let boost = self.parse_boost();
         // If it's a Term => update its required/excluded
         if let Expr::Term {
             keywords,
@@ -616,10 +1184,22 @@ impl Parser {
             required: _,
             excluded: _,
             exact,
+            proximity,
+            pattern,
+            boost: _,
         } = primary_expr
         {
+            let is_filter = field
+                .as_deref()
+                .and_then(QueryField::from_name)
+                .is_some_and(|f| f.is_filter());
+
             // If exact or excluded => skip further tokenization
-            let final_keywords = if exact || excluded {
+            let final_keywords = if is_filter || pattern.is_some() {
+                // Filter values are matched against paths, languages and node types, not text,
+                // and patterns against the text as written
+                keywords
+            } else if (exact || excluded) && proximity.is_none() {
                 // Mark them special (no splitting)
                 for kw in &keywords {
                     add_special_term(kw);
@@ -651,6 +1231,9 @@ impl Parser {
                 required,
                 excluded,
                 exact,
+                proximity,
+                pattern,
+                boost,
             })
         } else {
             // If it's a sub-expression in parentheses or something else, just return it
@@ -658,6 +1241,39 @@ impl Parser {
         }
     }
 
+    /// Consume the `~n` after a word, if any
+    fn parse_fuzzy(&mut self) -> Option<usize> {
+        match self.peek() {
+            Some(&Token::Fuzzy(distance)) => {
+                self.next();
+                Some(distance)
+            }
+            _ => None,
+        }
+    }
+
+    /// Consume the `^n` after a term or group, if any
+    fn parse_boost(&mut self) -> Option<f64> {
+        match self.peek() {
+            Some(&Token::Boost(boost)) => {
+                self.next();
+                Some(boost)
+            }
+            _ => None,
+        }
+    }
+
+    /// Consume the `~n` after a quoted string, if any
+    fn parse_slop(&mut self) -> Option<usize> {
+        match self.peek() {
+            Some(&Token::Slop(slop)) => {
+                self.next();
+                Some(slop)
+            }
+            _ => None,
+        }
+    }
+
     fn parse_primary(&mut self) -> Result<Expr, ParseError> {
         let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
 
@@ -669,14 +1285,25 @@ impl Parser {
                 if debug_mode {
                     println!("DEBUG: QuotedString => {}", val);
                 }
+                let proximity = self.parse_slop();
                 Ok(Expr::Term {
                     keywords: vec![val],
                     field: None,
                     required: false,
                     excluded: false,
-                    exact: true,
+                    exact: proximity.is_none(),
+                    proximity,
+                    pattern: None,
+                    boost: None,
                 })
             }
+            // Slash-delimited => regex
+            Some(Token::Regex(_)) => {
+                let Token::Regex(source) = self.next().unwrap() else {
+                    unreachable!()
+                };
+                regex_term(source, None)
+            }
             // Possibly field:term
             Some(Token::Ident(_)) => {
                 let Token::Ident(first) = self.next().unwrap() else {
@@ -688,48 +1315,51 @@ impl Parser {
                 if let Some(Token::Colon) = self.peek() {
                     // We have "field:"
                     self.next(); // consume colon
-                                 // Next could be ident or quoted
+                                 // Known fields are stored under their canonical name; unknown ones are dropped
+                                 // so that `foo:bar` behaves like a plain `bar`
+                    let first = QueryField::from_name(&first).map(|f| f.as_str().to_string());
+                    // Next could be ident or quoted
                     match self.peek() {
                         Some(Token::Ident(ident2)) => {
                             let val2 = ident2.clone();
                             self.next();
-                            Ok(Expr::Term {
-                                keywords: vec![val2],
-                                field: Some(first),
-                                required: false,
-                                excluded: false,
-                                exact: false,
-                            })
+                            Ok(ident_term(val2, first, self.parse_fuzzy()))
+                        }
+                        Some(Token::Regex(source)) => {
+                            let source = source.clone();
+                            self.next();
+                            regex_term(source, first)
                         }
                         Some(Token::QuotedString(qs)) => {
                             let qval = qs.clone();
                             self.next();
+                            let proximity = self.parse_slop();
                             Ok(Expr::Term {
                                 keywords: vec![qval],
-                                field: Some(first),
+                                field: first,
                                 required: false,
                                 excluded: false,
-                                exact: true,
+                                exact: proximity.is_none(),
+                                proximity,
+                                pattern: None,
+                                boost: None,
                             })
                         }
                         // If nothing or other token => empty term
                         _ => Ok(Expr::Term {
                             keywords: vec![],
-                            field: Some(first),
+                            field: first,
                             required: false,
                             excluded: false,
                             exact: false,
+                            proximity: None,
+                            pattern: None,
+                            boost: None,
                         }),
                     }
                 } else {
                     // Just a plain ident
-                    Ok(Expr::Term {
-                        keywords: vec![first],
-                        field: None,
-                        required: false,
-                        excluded: false,
-                        exact: false,
-                    })
+                    Ok(ident_term(first, None, self.parse_fuzzy()))
                 }
             }
             Some(t) => Err(ParseError::UnexpectedToken(t.clone())),
@@ -738,6 +1368,252 @@ impl Parser {
     }
 }
 
+/// Multiply the boost of every term in `expr` by `boost`
+fn boost_terms(expr: Expr, boost: f64) -> Expr {
+    match expr {
+        Expr::Term {
+            keywords,
+            field,
+            required,
+            excluded,
+            exact,
+            proximity,
+            pattern,
+            boost: term_boost,
+        } => Expr::Term {
+            keywords,
+            field,
+            required,
+            excluded,
+            exact,
+            proximity,
+            pattern,
+            boost: Some(term_boost.unwrap_or(1.0) * boost),
+        },
+        Expr::And(left, right) => Expr::And(
+            Box::new(boost_terms(*left, boost)),
+            Box::new(boost_terms(*right, boost)),
+        ),
+        Expr::Or(left, right) => Expr::Or(
+            Box::new(boost_terms(*left, boost)),
+            Box::new(boost_terms(*right, boost)),
+        ),
+    }
+}
+
+/// A term for an identifier, which is a wildcard pattern if it contains `*` and a fuzzy
+/// one if it was followed by `~n` (unless it is shorter than `fuzzy::MIN_FUZZY_LENGTH`)
+fn ident_term(ident: String, field: Option<String>, fuzzy: Option<usize>) -> Expr {
+    let is_filter = field
+        .as_deref()
+        .and_then(QueryField::from_name)
+        .is_some_and(|f| f.is_filter());
+    let pattern = if is_filter {
+        None
+    } else if ident.contains('*') {
+        Some(TermPattern::Wildcard)
+    } else if ident.chars().count() < crate::search::fuzzy::MIN_FUZZY_LENGTH {
+        None
+    } else {
+        fuzzy.map(TermPattern::Fuzzy)
+    };
+    Expr::Term {
+        keywords: vec![ident],
+        field,
+        required: false,
+        excluded: false,
+        exact: false,
+        proximity: None,
+        pattern,
+        boost: None,
+    }
+}
+
+/// A `/regex/` term; the regex must compile
+fn regex_term(source: String, field: Option<String>) -> Result<Expr, ParseError> {
+    if source.is_empty() || TermPattern::Regex.regex(&source).is_none() {
+        return Err(ParseError::Generic(format!("Invalid regex '/{}/'", source)));
+    }
+    Ok(Expr::Term {
+        keywords: vec![source],
+        field,
+        required: false,
+        excluded: false,
+        exact: false,
+        proximity: None,
+        pattern: Some(TermPattern::Regex),
+        boost: None,
+    })
+}
+
+/// Join the operands of `NEAR/n` into a single proximity term. Both must be text terms
+/// on the same field; quoted operands are split into words like any other keyword.
+fn join_near(left: Expr, right: Expr, distance: usize) -> Result<Expr, ParseError> {
+    let (
+        Expr::Term {
+            keywords: left_keywords,
+            field,
+            required: left_required,
+            excluded: false,
+            exact: left_exact,
+            proximity: left_proximity,
+            pattern: None,
+            boost: left_boost,
+        },
+        Expr::Term {
+            keywords: right_keywords,
+            field: right_field,
+            required: right_required,
+            excluded: false,
+            exact: right_exact,
+            pattern: None,
+            boost: right_boost,
+            ..
+        },
+    ) = (left, right)
+    else {
+        return Err(ParseError::Generic(
+            "NEAR can only join search terms without wildcards".to_string(),
+        ));
+    };
+    if field
+        .as_deref()
+        .and_then(QueryField::from_name)
+        .is_some_and(|f| f.is_filter())
+        || field != right_field
+    {
+        return Err(ParseError::Generic(
+            "NEAR operands must be text terms on the same field".to_string(),
+        ));
+    }
+
+    let words = |keywords: Vec<String>, exact: bool| -> Vec<String> {
+        if exact {
+            keywords
+                .iter()
+                .flat_map(|kw| custom_tokenize(kw))
+                .filter(|s| !s.is_empty())
+                .collect()
+        } else {
+            keywords
+        }
+    };
+    let mut keywords = words(left_keywords, left_exact);
+    keywords.extend(words(right_keywords, right_exact));
+
+    Ok(Expr::Term {
+        keywords,
+        field,
+        required: left_required || right_required,
+        excluded: false,
+        exact: false,
+        proximity: Some(left_proximity.map_or(distance, |d| d.max(distance))),
+        pattern: None,
+        // A boost on either operand boosts the joined term
+        boost: match (left_boost, right_boost) {
+            (Some(l), Some(r)) => Some(l.max(r)),
+            (l, r) => l.or(r),
+        },
+    })
+}
+
+/// A plain word of a query, as passed to the callback of [`rewrite_words`]
+pub struct QueryWord<'a> {
+    pub word: &'a str,
+    /// The `comment:`/`code:` field the word is scoped to, if any
+    pub field: Option<&'a str>,
+    /// The word has a leading `+`
+    pub required: bool,
+    /// The word has a leading `-`
+    pub excluded: bool,
+}
+
+/// Rewrite the plain words of `query` with `rewrite`, which returns the text to put in place
+/// of a word or `None` to keep it. Quoted phrases, wildcards, regexes, field names, filter
+/// values, `NEAR` operands and words that already have a `~n` are not plain words. A query
+/// that does not lex is returned unchanged.
+pub fn rewrite_words(query: &str, mut rewrite: impl FnMut(&QueryWord) -> Option<String>) -> String {
+    let Ok(tokens) = tokenize(query) else {
+        return query.to_string();
+    };
+
+    let mut out = String::new();
+    for (i, token) in tokens.iter().enumerate() {
+        let prev = i.checked_sub(1).map(|j| &tokens[j]);
+        let next = tokens.get(i + 1);
+        let text = match token {
+            Token::Plus => "+".to_string(),
+            Token::Minus => "-".to_string(),
+            Token::And => "AND".to_string(),
+            Token::Or => "OR".to_string(),
+            Token::LParen => "(".to_string(),
+            Token::RParen => ")".to_string(),
+            Token::Colon => ":".to_string(),
+            Token::Ident(word) => {
+                let is_field_name = next == Some(&Token::Colon);
+                let field = match (prev, i.checked_sub(2).map(|j| &tokens[j])) {
+                    (Some(Token::Colon), Some(Token::Ident(name))) => Some(name.as_str()),
+                    _ => None,
+                };
+                let is_filter_value = field
+                    .is_some_and(|name| QueryField::from_name(name).is_some_and(|f| f.is_filter()));
+                // `NEAR` does not take pattern terms
+                let is_near_operand =
+                    matches!(prev, Some(Token::Near(_))) || matches!(next, Some(Token::Near(_)));
+                let is_plain = !is_field_name
+                    && !is_filter_value
+                    && !is_near_operand
+                    && !word.contains('*')
+                    && !matches!(next, Some(Token::Fuzzy(_)));
+                // A field's prefix comes before its name
+                let prefix = match field {
+                    Some(_) => i.checked_sub(3).map(|j| &tokens[j]),
+                    None => prev,
+                };
+                let query_word = QueryWord {
+                    word,
+                    field,
+                    required: prefix == Some(&Token::Plus),
+                    excluded: prefix == Some(&Token::Minus),
+                };
+                is_plain
+                    .then(|| rewrite(&query_word))
+                    .flatten()
+                    .unwrap_or_else(|| word.clone())
+            }
+            Token::QuotedString(phrase) => format!("\"{}\"", phrase.replace('"', "\\\"")),
+            Token::Slop(slop) => format!("~{}", slop),
+            Token::Near(near) => format!("NEAR/{}", near),
+            Token::Regex(regex) => format!("/{}/", regex.replace('/', "\\/")),
+            Token::Boost(boost) => format!("^{}", boost),
+            Token::Fuzzy(fuzzy) => format!("~{}", fuzzy),
+        };
+
+        // Prefixes and suffixes stay attached to their term
+        let attached = matches!(
+            token,
+            Token::RParen | Token::Colon | Token::Slop(_) | Token::Boost(_) | Token::Fuzzy(_)
+        ) || matches!(
+            prev,
+            Some(Token::Plus | Token::Minus | Token::LParen | Token::Colon)
+        );
+        if !out.is_empty() && !attached {
+            out.push(' ');
+        }
+        out.push_str(&text);
+    }
+    out
+}
+
+/// Rewrite `query` for `--fuzzy`: every plain word (see [`rewrite_words`]) of at least
+/// `fuzzy::MIN_FUZZY_LENGTH` characters becomes a fuzzy term (`word~distance`).
+pub fn with_fuzzy(query: &str, distance: usize) -> String {
+    rewrite_words(query, |query_word| {
+        (query_word.word.chars().count() >= crate::search::fuzzy::MIN_FUZZY_LENGTH)
+            .then(|| format!("{}~{}", query_word.word, distance))
+    })
+}
+
 /// Parse the query string into an AST
 pub fn parse_query(input: &str, exact: bool) -> Result<Expr, ParseError> {
     let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
@@ -757,6 +1633,9 @@ pub fn parse_query(input: &str, exact: bool) -> Result<Expr, ParseError> {
             required: false,
             excluded: false,
             exact: true,
+            proximity: None,
+            pattern: None,
+            boost: None,
         });
     }
 
@@ -787,6 +1666,9 @@ pub fn parse_query(input: &str, exact: bool) -> Result<Expr, ParseError> {
                 required: false,
                 excluded: false,
                 exact: false,
+                proximity: None,
+                pattern: None,
+                boost: None,
             });
         }
     };
@@ -816,6 +1698,9 @@ pub fn parse_query(input: &str, exact: bool) -> Result<Expr, ParseError> {
             required: false,
             excluded: false,
             exact: false,
+            proximity: None,
+            pattern: None,
+            boost: None,
         });
     }
 
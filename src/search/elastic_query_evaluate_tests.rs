@@ -11,6 +11,9 @@ fn create_term(keyword: &str) -> Expr {
         required: false,
         excluded: false,
         exact: false,
+        proximity: None,
+        pattern: None,
+        boost: None,
     }
 }
 
@@ -23,6 +26,9 @@ fn create_required_term(keyword: &str) -> Expr {
         required: true,
         excluded: false,
         exact: false,
+        proximity: None,
+        pattern: None,
+        boost: None,
     }
 }
 
@@ -35,6 +41,9 @@ fn create_excluded_term(keyword: &str) -> Expr {
         required: false,
         excluded: true,
         exact: false,
+        proximity: None,
+        pattern: None,
+        boost: None,
     }
 }
 
@@ -47,6 +56,9 @@ fn create_exact_term(keyword: &str) -> Expr {
         required: false,
         excluded: false,
         exact: true,
+        proximity: None,
+        pattern: None,
+        boost: None,
     }
 }
 
@@ -387,6 +399,9 @@ fn test_evaluate_exact_terms() {
         required: false,
         excluded: false,
         exact: false,
+        proximity: None,
+        pattern: None,
+        boost: None,
     };
     
     // The term is "run", so it won't match "running" directly
@@ -415,6 +430,9 @@ fn test_evaluate_exact_terms() {
             required: false,
             excluded: false,
             exact: false,
+            proximity: None,
+            pattern: None,
+            boost: None,
         }),
         Box::new(create_exact_term("whitelist"))
     );
@@ -426,4 +444,72 @@ fn test_evaluate_exact_terms() {
     // No match when the exact term is only present as stemmed parts
     let matched_terms = create_matched_terms(&[0, 3, 4]); // "running", "white", "list"
     assert!(!expr.evaluate(&matched_terms, &term_indices, false));
-}
\ No newline at end of file
+}
+fn create_field_term(field: &str, keyword: &str, excluded: bool) -> Expr {
+    Expr::Term {
+        keywords: vec![keyword.to_string()],
+        field: Some(field.to_string()),
+        required: false,
+        excluded,
+        exact: false,
+        proximity: None,
+        pattern: None,
+        boost: None,
+    }
+}
+
+#[test]
+fn test_evaluate_filter_terms_are_neutral() {
+    let term_indices = create_term_indices(&["foo", "bar"]);
+
+    // "foo AND path:src" behaves like "foo"
+    let expr = Expr::And(
+        Box::new(create_term("foo")),
+        Box::new(create_field_term("path", "src", false)),
+    );
+    assert!(expr.evaluate(&create_matched_terms(&[0]), &term_indices, false));
+    assert!(!expr.evaluate(&create_matched_terms(&[1]), &term_indices, false));
+
+    // "lang:rust OR bar" behaves like "bar", so the filter can't satisfy the OR on its own
+    let expr = Expr::Or(
+        Box::new(create_field_term("lang", "rust", false)),
+        Box::new(create_term("bar")),
+    );
+    assert!(expr.evaluate(&create_matched_terms(&[1]), &term_indices, false));
+    assert!(!expr.evaluate(&create_matched_terms(&[0]), &term_indices, false));
+
+    // Excluded filters don't exclude anything during evaluation either
+    let expr = Expr::And(
+        Box::new(create_term("foo")),
+        Box::new(create_field_term("type", "comment", true)),
+    );
+    assert!(expr.evaluate(&create_matched_terms(&[0]), &term_indices, false));
+
+    // "bar OR (lang:rust OR lang:go)" behaves like "bar": a group of filters is neutral too
+    let filters = Expr::Or(
+        Box::new(create_field_term("lang", "rust", false)),
+        Box::new(create_field_term("lang", "go", false)),
+    );
+    assert!(filters.is_filter_only());
+    let expr = Expr::Or(Box::new(create_term("bar")), Box::new(filters));
+    assert!(expr.evaluate(&create_matched_terms(&[1]), &term_indices, false));
+    assert!(!expr.evaluate(&create_matched_terms(&[0]), &term_indices, false));
+}
+
+#[test]
+fn test_evaluate_scoped_terms_use_field_keys() {
+    // "comment:foo" has its own index, separate from a plain "foo"
+    let term_indices = create_term_indices(&["foo", "comment:foo"]);
+    let expr = create_field_term("comment", "foo", false);
+
+    assert!(!expr.evaluate(&create_matched_terms(&[0]), &term_indices, false));
+    assert!(expr.evaluate(&create_matched_terms(&[1]), &term_indices, false));
+
+    let expr = Expr::And(
+        Box::new(create_term("foo")),
+        Box::new(create_field_term("code", "foo", true)),
+    );
+    let term_indices = create_term_indices(&["foo", "code:foo"]);
+    assert!(expr.evaluate(&create_matched_terms(&[0]), &term_indices, false));
+    assert!(!expr.evaluate(&create_matched_terms(&[0, 1]), &term_indices, false));
+}
@@ -91,6 +91,9 @@ fn term(keyword: &str) -> Expr {
         required: false,
         excluded: false,
         exact: false,
+        proximity: None,
+        pattern: None,
+        boost: None,
     }
 }
 
@@ -101,6 +104,9 @@ fn required_term(keyword: &str) -> Expr {
         required: true,
         excluded: false,
         exact: false,
+        proximity: None,
+        pattern: None,
+        boost: None,
     }
 }
 
@@ -111,6 +117,9 @@ fn excluded_term(keyword: &str) -> Expr {
         required: false,
         excluded: true,
         exact: false,
+        proximity: None,
+        pattern: None,
+        boost: None,
     }
 }
 
@@ -122,6 +131,9 @@ fn exact_term(keyword: &str) -> Expr {
         required: false,
         excluded: false,
         exact: true,
+        proximity: None,
+        pattern: None,
+        boost: None,
     }
 }
 
@@ -533,3 +545,331 @@ fn test_invalid_queries() {
     assert_parse_fails("++foo"); // Multiple prefixes
     assert_parse_fails("AND OR"); // Only operators
 }
+
+#[test]
+fn test_field_qualifiers() {
+    // Filter values are kept verbatim, including path separators and globs
+    assert_parse_eq(
+        "path:src/search/*.rs",
+        Expr::Term {
+            keywords: vec!["src/search/*.rs".to_string()],
+            field: Some("path".to_string()),
+            required: false,
+            excluded: false,
+            exact: false,
+            proximity: None,
+            pattern: None,
+            boost: None,
+        }
+    );
+
+    // Aliases are stored under the canonical field name
+    assert_parse_eq(
+        "file:main.rs",
+        Expr::Term {
+            keywords: vec!["main.rs".to_string()],
+            field: Some("path".to_string()),
+            required: false,
+            excluded: false,
+            exact: false,
+            proximity: None,
+            pattern: None,
+            boost: None,
+        }
+    );
+    assert_parse_eq(
+        "-language:Go",
+        Expr::Term {
+            keywords: vec!["Go".to_string()],
+            field: Some("lang".to_string()),
+            required: false,
+            excluded: true,
+            exact: false,
+            proximity: None,
+            pattern: None,
+            boost: None,
+        }
+    );
+
+    // comment:/code: terms are tokenized like any other term
+    assert_parse_eq(
+        "comment:parsing",
+        Expr::Term {
+            keywords: vec!["pars".to_string()],
+            field: Some("comment".to_string()),
+            required: false,
+            excluded: false,
+            exact: false,
+            proximity: None,
+            pattern: None,
+            boost: None,
+        }
+    );
+
+    // Unknown fields are ignored
+    assert_parse_eq("title:foo", term("foo"));
+
+    // Filters combine with other terms as usual
+    assert_parse_eq(
+        "foo AND type:function_item",
+        Expr::And(
+            Box::new(term("foo")),
+            Box::new(Expr::Term {
+                keywords: vec!["function_item".to_string()],
+                field: Some("type".to_string()),
+                required: false,
+                excluded: false,
+                exact: false,
+                proximity: None,
+                pattern: None,
+                boost: None,
+            })
+        )
+    );
+}
+
+#[test]
+fn test_proximity_operators() {
+    let near = |keywords: &[&str], distance| Expr::Term {
+        keywords: keywords.iter().map(|k| k.to_string()).collect(),
+        field: None,
+        required: false,
+        excluded: false,
+        exact: false,
+        proximity: Some(distance),
+        pattern: None,
+        boost: None,
+    };
+
+    // A quoted phrase with slop is split into words like an unquoted term
+    assert_parse_eq("\"token refresh\"~5", near(&["token", "refresh"], 5));
+    assert_parse_eq("token NEAR/3 refresh", near(&["token", "refresh"], 3));
+
+    // NEAR binds tighter than implicit OR, and chains use the largest distance
+    assert_parse_eq(
+        "token NEAR/2 refresh NEAR/4 expiri session",
+        Expr::Or(
+            Box::new(near(&["token", "refresh", "expiri"], 4)),
+            Box::new(term("session")),
+        ),
+    );
+
+    // Prefixes carry over to the joined term
+    assert_parse_eq(
+        "+\"token refresh\"~1",
+        Expr::Term {
+            keywords: vec!["token".to_string(), "refresh".to_string()],
+            field: None,
+            required: true,
+            excluded: false,
+            exact: false,
+            proximity: Some(1),
+            pattern: None,
+            boost: None,
+        },
+    );
+
+    // Without a number, `~` is ignored and NEAR is an ordinary word
+    assert!(parse_query_test("\"token refresh\"~").is_ok());
+    assert_parse_eq("near", term("near"));
+    assert_eq!(
+        near(&["token", "refresh"], 5).to_string(),
+        "\"token refresh\"~5"
+    );
+}
+
+#[test]
+fn test_proximity_span() {
+    let positions = crate::search::tokenization::token_positions(
+        "retry the request, then wait with backoff before the next retry attempt",
+    );
+    let keywords = |words: &[&str]| words.iter().map(|w| w.to_string()).collect::<Vec<_>>();
+
+    // Stop words do not count: "retry request wait backoff next retry attempt"
+    assert_eq!(proximity_span(&positions, &keywords(&["request", "wait"])), Some(0));
+    assert_eq!(proximity_span(&positions, &keywords(&["request", "backoff"])), Some(1));
+    // The closest pair wins: "backoff next retry" rather than "retry request wait backoff"
+    assert_eq!(proximity_span(&positions, &keywords(&["retri", "backoff"])), Some(1));
+    assert_eq!(
+        proximity_span(&positions, &keywords(&["retri", "wait", "attempt"])),
+        Some(2)
+    );
+    assert_eq!(proximity_span(&positions, &keywords(&["retri", "missing"])), None);
+}
+
+#[test]
+fn test_pattern_terms() {
+    let pattern_term = |source: &str, pattern| Expr::Term {
+        keywords: vec![source.to_string()],
+        field: None,
+        required: false,
+        excluded: false,
+        exact: false,
+        proximity: None,
+        pattern: Some(pattern),
+        boost: None,
+    };
+
+    // Wildcards and regexes are kept as written, without tokenization or stemming
+    assert_parse_eq("auth*", pattern_term("auth*", TermPattern::Wildcard));
+    assert_parse_eq("*Handler", pattern_term("*Handler", TermPattern::Wildcard));
+    assert_parse_eq(
+        "/re(try|tries)/",
+        pattern_term("re(try|tries)", TermPattern::Regex),
+    );
+    assert_parse_eq(r"/a\/b/", pattern_term("a/b", TermPattern::Regex));
+    assert_parse_eq(
+        "-/todo/",
+        Expr::Term {
+            keywords: vec!["todo".to_string()],
+            field: None,
+            required: false,
+            excluded: true,
+            exact: false,
+            proximity: None,
+            pattern: Some(TermPattern::Regex),
+            boost: None,
+        },
+    );
+
+    // A slash inside a word or without a partner is not a regex, and a bare `*` is dropped
+    assert_parse_eq(
+        "src/search",
+        Expr::Or(Box::new(term("src")), Box::new(term("search"))),
+    );
+    assert_parse_eq("* config", term("config"));
+    assert_eq!(
+        pattern_term("re(try|tries)", TermPattern::Regex).to_string(),
+        "/re(try|tries)/"
+    );
+
+    // Invalid regexes and NEAR on patterns don't parse as such
+    assert!(!parse_query_test("config /(unclosed/")
+        .unwrap()
+        .has_pattern());
+    assert!(!parse_query_test("auth* NEAR/2 token")
+        .unwrap()
+        .has_proximity());
+}
+
+#[test]
+fn test_term_pattern_matching() {
+    let wildcard = TermPattern::Wildcard;
+    assert_eq!(wildcard.line_pattern("auth*"), r"auth\w*");
+    assert!(wildcard.compile("auth*").matches("let authToken = get();"));
+    assert!(wildcard.compile("*handler").matches("struct RequestHandler;"));
+    // A wildcard matches whole word parts, not parts of words
+    assert!(!wildcard.compile("auth*").matches("let oauth = 1;"));
+    assert_eq!(
+        wildcard.compile("auth*").matched_tokens("fn authenticate(authToken: &str)"),
+        vec!["authent", "auth"]
+    );
+
+    let regex = TermPattern::Regex;
+    assert!(regex.compile("re(try|tries)").matches("// Retries the request"));
+    assert!(!regex.compile("^fn").matches("let x = 1;"));
+    // Matches are widened to whole words before they are tokenized
+    assert_eq!(regex.compile("tries").matched_tokens("// Retries"), vec!["retri"]);
+}
+
+#[test]
+fn test_term_boost() {
+    let boosted = |keyword: &str, boost| Expr::Term {
+        keywords: vec![keyword.to_string()],
+        field: None,
+        required: false,
+        excluded: false,
+        exact: false,
+        proximity: None,
+        pattern: None,
+        boost: Some(boost),
+    };
+
+    assert_parse_eq(
+        "oauth^3 token",
+        Expr::Or(Box::new(boosted("oauth", 3.0)), Box::new(term("token"))),
+    );
+    assert_parse_eq("oauth^0.5", boosted("oauth", 0.5));
+
+    // A group boost multiplies the boosts of its terms
+    assert_parse_eq(
+        "(oauth^2 OR token)^3",
+        Expr::Or(
+            Box::new(boosted("oauth", 6.0)),
+            Box::new(boosted("token", 3.0)),
+        ),
+    );
+    assert_eq!(boosted("oauth", 3.0).to_string(), "oauth^3");
+
+    // Boosts carry over to NEAR terms and combine with other operators
+    let near = parse_query_test("oauth^2 NEAR/3 token").unwrap();
+    assert!(matches!(near, Expr::Term { boost: Some(b), .. } if b == 2.0));
+    assert!(matches!(
+        parse_query_test("+\"token refresh\"^4").unwrap(),
+        Expr::Term { required: true, exact: true, boost: Some(b), .. } if b == 4.0
+    ));
+
+    // Without a positive number, `^` is an error and the query falls back to plain words
+    assert!(matches!(
+        parse_query_test("oauth^0").unwrap(),
+        Expr::Term { boost: None, .. }
+    ));
+}
+
+#[test]
+fn test_fuzzy_terms() {
+    let fuzzy = |keyword: &str, distance| Expr::Term {
+        keywords: vec![keyword.to_string()],
+        field: None,
+        required: false,
+        excluded: false,
+        exact: false,
+        proximity: None,
+        pattern: Some(TermPattern::Fuzzy(distance)),
+        boost: None,
+    };
+
+    assert_parse_eq("recieve~1", fuzzy("recieve", 1));
+    assert_parse_eq(
+        "recieve~1 mesage~2",
+        Expr::Or(
+            Box::new(fuzzy("recieve", 1)),
+            Box::new(fuzzy("mesage", 2)),
+        ),
+    );
+    assert_eq!(fuzzy("recieve", 1).to_string(), "recieve~1");
+    // Distances are capped, and short words stay exact
+    assert_parse_eq(
+        "recieve~5",
+        fuzzy("recieve", crate::search::fuzzy::MAX_FUZZY_DISTANCE),
+    );
+    assert!(!parse_query_test("log~1").unwrap().has_pattern());
+    assert!(matches!(
+        parse_query_test("-recieve~1^2").unwrap(),
+        Expr::Term { excluded: true, pattern: Some(TermPattern::Fuzzy(1)), boost: Some(b), .. }
+            if b == 2.0
+    ));
+
+    let pattern = TermPattern::Fuzzy(1);
+    assert!(pattern.compile("recieve").matches("fn onReceive(msg: Message)"));
+    assert!(!pattern.compile("recieve").matches("fn deceive()"));
+    assert_eq!(
+        pattern.compile("recieve").matched_tokens("fn receive(received: bool)"),
+        vec!["receiv"]
+    );
+
+    // `--fuzzy` rewrites the plain words of a query
+    assert_eq!(with_fuzzy("recieve mesage", 1), "recieve~1 mesage~1");
+    assert_eq!(
+        with_fuzzy("+handler -(legacy OR \"old code\") fn", 2),
+        "+handler~2 -(legacy~2 OR \"old code\") fn"
+    );
+    assert_eq!(
+        with_fuzzy("path:src auth* oauth^3 comment:retry", 1),
+        "path:src auth* oauth~1^3 comment:retry~1"
+    );
+    assert_eq!(
+        with_fuzzy("retry NEAR/3 backoff recieve~2", 1),
+        "retry NEAR/3 backoff recieve~2"
+    );
+}
@@ -5,7 +5,7 @@ use crate::search::tokenization::tokenize_and_stem;
 /// to the terms unless they are marked as exact.
 fn process_ast_terms(expr: Expr) -> Expr {
     match expr {
-        Expr::Term { keywords, field, required, excluded, exact } => {
+        Expr::Term { keywords, field, required, excluded, exact, proximity, pattern, boost } => {
             // If exact or excluded => skip tokenization
             let processed_keywords = if exact || excluded {
                 keywords
@@ -25,6 +25,9 @@ fn process_ast_terms(expr: Expr) -> Expr {
                 required,
                 excluded,
                 exact,
+                proximity,
+                pattern,
+                boost,
             }
         },
         Expr::And(left, right) => {
@@ -80,11 +83,14 @@ fn test_process_ast_terms() {
         required: false,
         excluded: false,
         exact: false,
+        proximity: None,
+        pattern: None,
+        boost: None,
     };
     
     let processed = process_ast_terms(expr);
     
-    if let Expr::Term { keywords, field, required, excluded, exact } = processed {
+    if let Expr::Term { keywords, field, required, excluded, exact, .. } = processed {
         assert_eq!(keywords, vec!["run"]);
         assert_eq!(field, None);
         assert!(!required);
@@ -101,11 +107,14 @@ fn test_process_ast_terms() {
         required: true,
         excluded: false,
         exact: false,
+        proximity: None,
+        pattern: None,
+        boost: None,
     };
     
     let processed = process_ast_terms(expr);
     
-    if let Expr::Term { keywords, field, required, excluded, exact } = processed {
+    if let Expr::Term { keywords, field, required, excluded, exact, .. } = processed {
         assert!(keywords.contains(&"enabl".to_string()));
         assert!(keywords.contains(&"ip".to_string()));
         assert!(keywords.contains(&"white".to_string()));
@@ -126,6 +135,9 @@ fn test_process_ast_terms() {
             required: false,
             excluded: false,
             exact: false,
+            proximity: None,
+            pattern: None,
+            boost: None,
         }),
         Box::new(Expr::Term {
             keywords: vec!["whitelist".to_string()],
@@ -133,6 +145,9 @@ fn test_process_ast_terms() {
             required: false,
             excluded: false,
             exact: false,
+            proximity: None,
+            pattern: None,
+            boost: None,
         })
     );
     
@@ -325,11 +340,14 @@ fn test_process_ast_terms_with_exact_flag() {
         required: false,
         excluded: false,
         exact: true,
+        proximity: None,
+        pattern: None,
+        boost: None,
     };
     
     let processed = process_ast_terms(expr);
     
-    if let Expr::Term { keywords, field, required, excluded, exact } = processed {
+    if let Expr::Term { keywords, field, required, excluded, exact, .. } = processed {
         // Keywords should not be stemmed for exact terms
         assert_eq!(keywords, vec!["running"]);
         assert_eq!(field, None);
@@ -348,6 +366,9 @@ fn test_process_ast_terms_with_exact_flag() {
             required: false,
             excluded: false,
             exact: false,
+            proximity: None,
+            pattern: None,
+            boost: None,
         }),
         Box::new(Expr::Term {
             keywords: vec!["whitelist".to_string()],
@@ -355,6 +376,9 @@ fn test_process_ast_terms_with_exact_flag() {
             required: false,
             excluded: false,
             exact: true,
+            proximity: None,
+            pattern: None,
+            boost: None,
         })
     );
     
@@ -397,6 +421,9 @@ fn test_evaluate_exact_terms_tokenization() {
         required: false,
         excluded: false,
         exact: true,
+        proximity: None,
+        pattern: None,
+        boost: None,
     };
     
     // Match when the exact term is present
@@ -414,6 +441,9 @@ fn test_evaluate_exact_terms_tokenization() {
         required: false,
         excluded: false,
         exact: false,
+        proximity: None,
+        pattern: None,
+        boost: None,
     };
     
     // The term is "run", so it won't match "running" directly
@@ -442,6 +472,9 @@ fn test_evaluate_exact_terms_tokenization() {
             required: false,
             excluded: false,
             exact: false,
+            proximity: None,
+            pattern: None,
+            boost: None,
         }),
         Box::new(Expr::Term {
             keywords: vec!["whitelist".to_string()],
@@ -449,6 +482,9 @@ fn test_evaluate_exact_terms_tokenization() {
             required: false,
             excluded: false,
             exact: true,
+            proximity: None,
+            pattern: None,
+            boost: None,
         })
     );
     
@@ -0,0 +1,438 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fs::{self, create_dir_all, File};
+use std::hash::{Hash, Hasher};
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::language::language_trait::Import;
+use crate::language::parser::{self, LineMap};
+use crate::search::cache::{
+    file_mtime_nanos, hash_query, persistent_cache_enabled, probe_cache_dir,
+};
+use crate::search::tokenization;
+
+/// Version of the on-disk entry format. Bump it whenever parsing or tokenization
+/// changes in a way that makes stored line maps or tokens stale.
+const FILE_CACHE_VERSION: u32 = 4;
+
+/// Entries not used for this long are removed when the cache is pruned
+const MAX_ENTRY_AGE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// Size the entries may take up on disk together; the least recently used ones are
+/// removed beyond it
+const MAX_CACHE_BYTES: u64 = 512 * 1024 * 1024;
+
+/// How often the cache directory is scanned for entries to remove
+const PRUNE_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Entries used within this long are not touched again, to spare a write per file read
+const TOUCH_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// What is remembered about one file between runs
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct FileCacheEntry {
+    version: u32,
+    /// Version of probe that wrote the entry
+    probe_version: String,
+    /// Modification time in nanoseconds since the Unix epoch
+    mtime: u64,
+    size: u64,
+    content_hash: u64,
+    /// Line-to-node maps from the parser, keyed by whether test code was allowed
+    line_maps: HashMap<String, LineMap>,
+    /// Path the block tokens were computed with (tokens include the file name)
+    token_path: String,
+    /// `tokenization::vocabulary_fingerprint` of the word lists and stem language the block
+    /// tokens were computed with
+    vocabulary: u64,
+    /// Tokens of each block, keyed by `start-end` line range
+    block_tokens: HashMap<String, Vec<String>>,
+    /// Name each block declares, if any, keyed by `start-end` line range
+    block_names: HashMap<String, Option<String>>,
+    /// Imports found in the file, once the import graph has been built
+    imports: Option<Vec<Import>>,
+}
+
+/// Persistent per-file cache of parse and tokenization results.
+///
+/// Entries live in the probe cache directory and record the file's mtime, size and
+/// content hash. A file whose content changed gets a fresh, empty entry, so only
+/// changed files are parsed and tokenized again on the next run.
+pub struct FileCache {
+    cache_path: Option<PathBuf>,
+    entry: FileCacheEntry,
+    dirty: bool,
+}
+
+/// Compute a hash of the content for cache validation
+fn compute_content_hash(content: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn line_map_key(allow_tests: bool) -> String {
+    if allow_tests {
+        "with_tests".to_string()
+    } else {
+        "no_tests".to_string()
+    }
+}
+
+fn block_key(start_line: usize, end_line: usize) -> String {
+    format!("{}-{}", start_line, end_line)
+}
+
+/// Get the path of the cache entry for a file
+fn entry_path(file_path: &Path) -> Option<PathBuf> {
+    let resolved_path = fs::canonicalize(file_path).ok()?;
+    Some(probe_cache_dir().join("files").join(format!(
+        "{}.json",
+        hash_query(&resolved_path.to_string_lossy())
+    )))
+}
+
+fn read_entry(cache_path: &Path) -> Option<FileCacheEntry> {
+    let reader = BufReader::new(File::open(cache_path).ok()?);
+    serde_json::from_reader(reader).ok()
+}
+
+/// Mark a reused entry as used, so pruning keeps the entries of files still searched
+fn touch_entry(cache_path: &Path) {
+    let Ok(file) = File::options().append(true).open(cache_path) else {
+        return;
+    };
+    let now = SystemTime::now();
+    let recently_used = file
+        .metadata()
+        .and_then(|metadata| metadata.modified())
+        .is_ok_and(|modified| now.duration_since(modified).unwrap_or_default() < TOUCH_INTERVAL);
+    if !recently_used {
+        let _ = file.set_modified(now);
+    }
+}
+
+/// Remove stale entries from the on-disk cache and keep it within its size cap, at most
+/// once per `PRUNE_INTERVAL`
+pub fn prune_if_due() {
+    if !persistent_cache_enabled() {
+        return;
+    }
+    let dir = probe_cache_dir().join("files");
+    let marker = dir.join(".last-prune");
+    let now = SystemTime::now();
+    let due = fs::metadata(&marker)
+        .and_then(|metadata| metadata.modified())
+        .map_or(true, |pruned| {
+            now.duration_since(pruned).unwrap_or_default() >= PRUNE_INTERVAL
+        });
+    if !due || File::create(&marker).is_err() {
+        return;
+    }
+    let removed = prune_dir(&dir, now, MAX_ENTRY_AGE, MAX_CACHE_BYTES);
+    if std::env::var("DEBUG").unwrap_or_default() == "1" {
+        println!("DEBUG: Pruned {} file cache entries", removed);
+    }
+}
+
+/// Remove the entries in `dir` unused for longer than `max_age` and leftover temporary
+/// files, then the least recently used entries until the rest take up at most
+/// `max_bytes`. Returns how many files were removed.
+fn prune_dir(dir: &Path, now: SystemTime, max_age: Duration, max_bytes: u64) -> usize {
+    let Ok(read_dir) = fs::read_dir(dir) else {
+        return 0;
+    };
+    let mut entries: Vec<(SystemTime, u64, PathBuf)> = read_dir
+        .flatten()
+        .filter_map(|dir_entry| {
+            let path = dir_entry.path();
+            let name = path.file_name()?.to_str()?;
+            // Temporary files are named `<entry>.json.<pid>.tmp`
+            if !name.ends_with(".json") && !name.ends_with(".tmp") {
+                return None;
+            }
+            let metadata = dir_entry.metadata().ok()?;
+            Some((metadata.modified().ok()?, metadata.len(), path))
+        })
+        .collect();
+
+    let mut removed = 0;
+    entries.retain(|(modified, _, path)| {
+        let age = now.duration_since(*modified).unwrap_or_default();
+        let is_tmp = path.extension().is_some_and(|ext| ext == "tmp");
+        // Another process may still be writing a recent temporary file
+        let stale = age > max_age || (is_tmp && age > Duration::from_secs(60 * 60));
+        if stale && fs::remove_file(path).is_ok() {
+            removed += 1;
+            return false;
+        }
+        !is_tmp
+    });
+
+    // Least recently used first
+    entries.sort_by_key(|(modified, _, _)| *modified);
+    let mut total: u64 = entries.iter().map(|(_, size, _)| size).sum();
+    for (_, size, path) in &entries {
+        if total <= max_bytes {
+            break;
+        }
+        if fs::remove_file(path).is_ok() {
+            total -= size;
+            removed += 1;
+        }
+    }
+    removed
+}
+
+impl FileCache {
+    /// Load the cache entry for a file whose current content is `content`.
+    ///
+    /// The stored entry is reused when the file's mtime and size are unchanged, or when
+    /// the content hash still matches (e.g. the file was only touched).
+    pub fn load(file_path: &Path, content: &str) -> Self {
+        let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
+
+        let cache_path = if persistent_cache_enabled() {
+            entry_path(file_path)
+        } else {
+            None
+        };
+        let metadata = fs::metadata(file_path).ok();
+        let mtime = metadata.as_ref().map(file_mtime_nanos).unwrap_or(0);
+        let size = content.len() as u64;
+
+        let stored = cache_path.as_deref().and_then(read_entry).filter(|entry| {
+            entry.version == FILE_CACHE_VERSION && entry.probe_version == env!("CARGO_PKG_VERSION")
+        });
+
+        if let Some(mut entry) = stored {
+            let unchanged = entry.mtime == mtime && entry.size == size;
+            if unchanged || entry.content_hash == compute_content_hash(content) {
+                if debug_mode {
+                    println!("DEBUG: File cache hit for {:?}", file_path);
+                }
+                let dirty = !unchanged;
+                if !dirty {
+                    if let Some(cache_path) = &cache_path {
+                        touch_entry(cache_path);
+                    }
+                }
+                entry.mtime = mtime;
+                entry.size = size;
+                return Self {
+                    cache_path,
+                    entry,
+                    dirty,
+                };
+            }
+            if debug_mode {
+                println!(
+                    "DEBUG: File {:?} changed, discarding cached entry",
+                    file_path
+                );
+            }
+        }
+
+        Self {
+            cache_path,
+            entry: FileCacheEntry {
+                version: FILE_CACHE_VERSION,
+                probe_version: env!("CARGO_PKG_VERSION").to_string(),
+                mtime,
+                size,
+                content_hash: compute_content_hash(content),
+                ..Default::default()
+            },
+            dirty: true,
+        }
+    }
+
+    /// Make a stored line map available to the parser, so unchanged files are not re-parsed
+    pub fn seed_line_map(&self, content: &str, extension: &str, allow_tests: bool) {
+        if let Some(line_map) = self.entry.line_maps.get(&line_map_key(allow_tests)) {
+            if parser::cached_line_map(content, extension, allow_tests).is_none() {
+                parser::seed_line_map(content, extension, allow_tests, line_map.clone());
+            }
+        }
+    }
+
+    /// Remember the line map the parser computed for this file
+    pub fn store_line_map(&mut self, content: &str, extension: &str, allow_tests: bool) {
+        let key = line_map_key(allow_tests);
+        if self.entry.line_maps.contains_key(&key) {
+            return;
+        }
+        if let Some(line_map) = parser::cached_line_map(content, extension, allow_tests) {
+            self.entry.line_maps.insert(key, line_map);
+            self.dirty = true;
+        }
+    }
+
+    /// The stored tokens of a block, if they were computed for `token_path` with the
+    /// vocabulary in effect
+    pub fn cached_block_tokens(
+        &mut self,
+        token_path: &str,
+        start_line: usize,
+        end_line: usize,
+    ) -> Option<Vec<String>> {
+        let vocabulary = tokenization::vocabulary_fingerprint();
+        if self.entry.token_path != token_path || self.entry.vocabulary != vocabulary {
+            self.entry.token_path = token_path.to_string();
+            self.entry.vocabulary = vocabulary;
+            self.entry.block_tokens.clear();
+        }
+        self.entry
+            .block_tokens
+            .get(&block_key(start_line, end_line))
+            .cloned()
+    }
+
+    /// Remember the tokens of a block, computed for the `token_path` last passed to
+    /// `cached_block_tokens`
+    pub fn store_block_tokens(&mut self, start_line: usize, end_line: usize, tokens: Vec<String>) {
+        self.entry
+            .block_tokens
+            .insert(block_key(start_line, end_line), tokens);
+        self.dirty = true;
+    }
+
+    /// Get the tokens of a block, computing and remembering them if needed
+    pub fn block_tokens(
+        &mut self,
+        token_path: &str,
+        start_line: usize,
+        end_line: usize,
+        tokenize: impl FnOnce() -> Vec<String>,
+    ) -> Vec<String> {
+        if let Some(tokens) = self.cached_block_tokens(token_path, start_line, end_line) {
+            return tokens;
+        }
+        let tokens = tokenize();
+        self.store_block_tokens(start_line, end_line, tokens.clone());
+        tokens
+    }
+
+    /// Get the name a block declares, finding and remembering it if needed
+    pub fn block_name(
+        &mut self,
+        start_line: usize,
+        end_line: usize,
+        find: impl FnOnce() -> Option<String>,
+    ) -> Option<String> {
+        let key = block_key(start_line, end_line);
+        if let Some(name) = self.entry.block_names.get(&key) {
+            return name.clone();
+        }
+
+        let name = find();
+        self.entry.block_names.insert(key, name.clone());
+        self.dirty = true;
+        name
+    }
+
+    /// Get the imports of the file, finding and remembering them if needed
+    pub fn imports(&mut self, find: impl FnOnce() -> Vec<Import>) -> Vec<Import> {
+        if let Some(imports) = &self.entry.imports {
+            return imports.clone();
+        }
+
+        let imports = find();
+        self.entry.imports = Some(imports.clone());
+        self.dirty = true;
+        imports
+    }
+
+    /// Write the entry back to disk if anything changed
+    pub fn save(&self) -> Result<()> {
+        let Some(cache_path) = &self.cache_path else {
+            return Ok(());
+        };
+        if !self.dirty {
+            return Ok(());
+        }
+
+        if let Some(parent) = cache_path.parent() {
+            create_dir_all(parent)?;
+        }
+
+        // Write to a temporary file first so concurrent readers never see a partial entry
+        let tmp_path = cache_path.with_extension(format!("{}.tmp", std::process::id()));
+        {
+            let mut writer = BufWriter::new(File::create(&tmp_path)?);
+            serde_json::to_writer(&mut writer, &self.entry)?;
+            writer.flush()?;
+        }
+        fs::rename(&tmp_path, cache_path)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write_entry(dir: &Path, name: &str, size: usize, age: Duration, now: SystemTime) {
+        let path = dir.join(name);
+        fs::write(&path, vec![b'x'; size]).unwrap();
+        File::options()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(now - age)
+            .unwrap();
+    }
+
+    fn remaining(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(dir)
+            .unwrap()
+            .flatten()
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_prune_removes_stale_entries() {
+        let dir = TempDir::new().unwrap();
+        let now = SystemTime::now();
+        let hour = Duration::from_secs(60 * 60);
+        write_entry(dir.path(), "fresh.json", 10, hour, now);
+        write_entry(dir.path(), "old.json", 10, MAX_ENTRY_AGE + hour, now);
+        write_entry(dir.path(), "fresh.json.1.tmp", 10, Duration::ZERO, now);
+        write_entry(dir.path(), "old.json.1.tmp", 10, 2 * hour, now);
+        write_entry(dir.path(), ".last-prune", 0, MAX_ENTRY_AGE + hour, now);
+
+        assert_eq!(
+            prune_dir(dir.path(), now, MAX_ENTRY_AGE, MAX_CACHE_BYTES),
+            2
+        );
+        assert_eq!(
+            remaining(dir.path()),
+            vec![".last-prune", "fresh.json", "fresh.json.1.tmp"]
+        );
+    }
+
+    #[test]
+    fn test_prune_keeps_the_cache_within_its_size() {
+        let dir = TempDir::new().unwrap();
+        let now = SystemTime::now();
+        for (name, days) in [("a.json", 3), ("b.json", 1), ("c.json", 2)] {
+            let age = Duration::from_secs(days * 24 * 60 * 60);
+            write_entry(dir.path(), name, 100, age, now);
+        }
+
+        // The least recently used entries go first
+        assert_eq!(prune_dir(dir.path(), now, MAX_ENTRY_AGE, 250), 1);
+        assert_eq!(remaining(dir.path()), vec!["b.json", "c.json"]);
+        assert_eq!(prune_dir(dir.path(), now, MAX_ENTRY_AGE, 100), 1);
+        assert_eq!(remaining(dir.path()), vec!["b.json"]);
+    }
+}
@@ -1,12 +1,63 @@
+use crate::search::cache::{
+    file_mtime_nanos, hash_query, persistent_cache_enabled, probe_cache_dir,
+};
+use crate::search::elastic_query::QueryField;
+use crate::search::timeout::CancellationToken;
 use crate::search::tokenization;
 use anyhow::Result;
 use ignore::WalkBuilder;
 use lazy_static::lazy_static;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
+use std::fs::{self, create_dir_all, File};
+use std::io::{BufReader, BufWriter, Write};
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, RwLock};
 use std::time::Instant;
 
+/// Version of the on-disk file list format. Lists written with another version are ignored.
+const FILE_LIST_VERSION: u32 = 1;
+
+/// Ignore files whose contents decide which files of a directory are listed
+const IGNORE_FILE_NAMES: [&str; 2] = [".gitignore", ".ignore"];
+
+/// Modification times of a walked directory and its ignore files.
+///
+/// Adding, removing or renaming an entry updates the directory's mtime, so a file list
+/// is still valid as long as none of the stamps of the directories it walked changed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DirStamp {
+    pub path: PathBuf,
+    pub mtime: u64,
+    /// Latest mtime of the directory's `.gitignore`/`.ignore` files (0 if there are none)
+    pub ignore_mtime: u64,
+}
+
+impl DirStamp {
+    /// Stamp a directory with its current modification times
+    fn new(path: &Path) -> Option<Self> {
+        let metadata = fs::metadata(path).ok()?;
+        let ignore_mtime = IGNORE_FILE_NAMES
+            .iter()
+            .filter_map(|name| fs::metadata(path.join(name)).ok())
+            .map(|metadata| file_mtime_nanos(&metadata))
+            .max()
+            .unwrap_or(0);
+
+        Some(Self {
+            path: path.to_path_buf(),
+            mtime: file_mtime_nanos(&metadata),
+            ignore_mtime,
+        })
+    }
+
+    /// Check whether the directory is unchanged since it was stamped
+    fn is_fresh(&self) -> bool {
+        Self::new(&self.path).as_ref() == Some(self)
+    }
+}
+
 /// A struct to hold the cached file list for a specific directory
 #[derive(Debug, Clone)]
 pub struct FileList {
@@ -15,11 +66,40 @@ pub struct FileList {
     /// When this cache was created
     #[allow(dead_code)]
     pub created_at: Instant,
+    /// Stamps of the directories walked to build the list (empty for derived lists)
+    pub dirs: Vec<DirStamp>,
+}
+
+impl FileList {
+    /// Check whether no walked directory changed since the list was built
+    pub fn is_fresh(&self) -> bool {
+        self.dirs.par_iter().all(DirStamp::is_fresh)
+    }
 }
 
-// Global in-memory cache for file lists
+/// A file list as persisted in the probe cache directory between runs
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredFileList {
+    version: u32,
+    /// Version of probe that wrote the list (ignore rules may differ between versions)
+    probe_version: String,
+    files: Vec<PathBuf>,
+    dirs: Vec<DirStamp>,
+}
+
+/// Cache key for a file list. Each search root gets its own entry, so searching
+/// several roots in one run never mixes their file lists.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct FileListCacheKey {
+    /// The search root exactly as it was walked
+    root: PathBuf,
+    allow_tests: bool,
+    custom_ignores: Vec<String>,
+}
+
+// Global in-memory cache for file lists, keyed per search root
 lazy_static! {
-    static ref FILE_LIST_CACHE: RwLock<HashMap<String, Arc<FileList>>> =
+    static ref FILE_LIST_CACHE: RwLock<HashMap<FileListCacheKey, Arc<FileList>>> =
         RwLock::new(HashMap::new());
 }
 
@@ -32,39 +112,103 @@ fn format_duration(duration: std::time::Duration) -> String {
     }
 }
 
-/// Generate a cache key for a specific directory and options
-fn generate_cache_key(path: &Path, allow_tests: bool, custom_ignores: &[String]) -> String {
-    // Create a unique identifier for this cache based on the path and options
-    let path_str = path.to_string_lossy();
-    let allow_tests_str = if allow_tests {
-        "with_tests"
-    } else {
-        "no_tests"
-    };
+/// Generate a cache key for a specific root directory and options
+fn generate_cache_key(
+    path: &Path,
+    allow_tests: bool,
+    custom_ignores: &[String],
+) -> FileListCacheKey {
+    FileListCacheKey {
+        root: path.to_path_buf(),
+        allow_tests,
+        custom_ignores: custom_ignores.to_vec(),
+    }
+}
 
-    // Create a hash of the custom ignores to include in the cache key
-    let ignores_hash = if custom_ignores.is_empty() {
-        "no_ignores".to_string()
-    } else {
-        // Simple hash function for the custom ignores
-        let mut hash = 0u64;
-        for ignore in custom_ignores {
-            for byte in ignore.bytes() {
-                hash = hash.wrapping_mul(31).wrapping_add(byte as u64);
-            }
+/// Get the path of the persisted file list for a cache key.
+///
+/// Listed paths start with the root as it was given, so the key combines that root with
+/// its canonical form (a relative root means something else in another directory).
+fn stored_list_path(key: &FileListCacheKey) -> Option<PathBuf> {
+    let canonical_root = fs::canonicalize(&key.root).ok()?;
+    let key_string = format!(
+        "{}\0{}\0{}\0{}",
+        canonical_root.to_string_lossy(),
+        key.root.to_string_lossy(),
+        key.allow_tests,
+        key.custom_ignores.join("\0")
+    );
+    Some(
+        probe_cache_dir()
+            .join("file_lists")
+            .join(format!("{}.json", hash_query(&key_string))),
+    )
+}
+
+/// Load a file list persisted by an earlier run, if it is still fresh
+fn load_stored_list(key: &FileListCacheKey) -> Option<FileList> {
+    let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
+    let path = stored_list_path(key)?;
+
+    let reader = BufReader::new(File::open(&path).ok()?);
+    let stored: StoredFileList = serde_json::from_reader(reader).ok()?;
+    if stored.version != FILE_LIST_VERSION || stored.probe_version != env!("CARGO_PKG_VERSION") {
+        return None;
+    }
+
+    let file_list = FileList {
+        files: stored.files,
+        created_at: Instant::now(),
+        dirs: stored.dirs,
+    };
+    if !file_list.is_fresh() {
+        if debug_mode {
+            println!("DEBUG: Stored file list {:?} is stale", path);
         }
-        format!("ignores_{:x}", hash)
+        return None;
+    }
+
+    Some(file_list)
+}
+
+/// Persist a file list so later runs can skip the directory walk
+fn save_stored_list(key: &FileListCacheKey, file_list: &FileList) -> Result<()> {
+    let Some(path) = stored_list_path(key) else {
+        return Ok(());
+    };
+    if let Some(parent) = path.parent() {
+        create_dir_all(parent)?;
+    }
+
+    let stored = StoredFileList {
+        version: FILE_LIST_VERSION,
+        probe_version: env!("CARGO_PKG_VERSION").to_string(),
+        files: file_list.files.clone(),
+        dirs: file_list.dirs.clone(),
     };
 
-    format!("{}_{}_{}", path_str, allow_tests_str, ignores_hash)
+    // Write to a temporary file first so concurrent readers never see a partial list
+    let tmp_path = path.with_extension(format!("{}.tmp", std::process::id()));
+    {
+        let mut writer = BufWriter::new(File::create(&tmp_path)?);
+        serde_json::to_writer(&mut writer, &stored)?;
+        writer.flush()?;
+    }
+    fs::rename(&tmp_path, &path)?;
+
+    Ok(())
 }
 
 /// Get a list of files in a directory, respecting ignore patterns and test file exclusions.
 /// This function will use a cached list if available, or build and cache a new list if not.
+/// Cached lists, in memory or persisted by an earlier run, are only used while none of
+/// the walked directories changed. A walk stopped by `cancel` returns the files found so
+/// far and is not cached.
 pub fn get_file_list(
     path: &Path,
     allow_tests: bool,
     custom_ignores: &[String],
+    cancel: &CancellationToken,
 ) -> Result<Arc<FileList>> {
     let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
     let start_time = Instant::now();
@@ -82,24 +226,65 @@ pub fn get_file_list(
     {
         let cache = FILE_LIST_CACHE.read().unwrap();
         if let Some(file_list) = cache.get(&cache_key) {
-            let elapsed = start_time.elapsed();
+            if file_list.is_fresh() {
+                let elapsed = start_time.elapsed();
+                if debug_mode {
+                    println!(
+                        "DEBUG: Found file list in cache with {} files (retrieved in {})",
+                        file_list.files.len(),
+                        format_duration(elapsed)
+                    );
+                }
+                return Ok(Arc::clone(file_list));
+            }
             if debug_mode {
-                println!(
-                    "DEBUG: Found file list in cache with {} files (retrieved in {})",
-                    file_list.files.len(),
-                    format_duration(elapsed)
-                );
+                println!("DEBUG: Cached file list is stale, building new list");
             }
-            return Ok(Arc::clone(file_list));
         }
     }
 
-    // If not in cache, build the file list
-    if debug_mode {
-        println!("DEBUG: File list not found in cache, building new list");
-    }
+    let stored_list = if persistent_cache_enabled() {
+        load_stored_list(&cache_key)
+    } else {
+        None
+    };
+
+    let file_list = match stored_list {
+        Some(file_list) => {
+            if debug_mode {
+                println!(
+                    "DEBUG: Loaded file list with {} files from the persistent cache",
+                    file_list.files.len()
+                );
+            }
+            file_list
+        }
+        None => {
+            // If not in cache, build the file list
+            if debug_mode {
+                println!("DEBUG: File list not found in cache, building new list");
+            }
 
-    let file_list = build_file_list(path, allow_tests, custom_ignores)?;
+            let file_list = build_file_list(path, allow_tests, custom_ignores, cancel)?;
+            if cancel.is_cancelled() {
+                if debug_mode {
+                    println!(
+                        "DEBUG: File list walk was cancelled after {} files, not caching it",
+                        file_list.files.len()
+                    );
+                }
+                return Ok(Arc::new(file_list));
+            }
+            if persistent_cache_enabled() {
+                if let Err(e) = save_stored_list(&cache_key, &file_list) {
+                    if debug_mode {
+                        println!("DEBUG: Failed to persist file list: {}", e);
+                    }
+                }
+            }
+            file_list
+        }
+    };
     let file_count = file_list.files.len();
 
     // Cache the file list
@@ -122,7 +307,12 @@ pub fn get_file_list(
 }
 
 /// Build a list of files in a directory, respecting ignore patterns and test file exclusions.
-fn build_file_list(path: &Path, allow_tests: bool, custom_ignores: &[String]) -> Result<FileList> {
+fn build_file_list(
+    path: &Path,
+    allow_tests: bool,
+    custom_ignores: &[String],
+    cancel: &CancellationToken,
+) -> Result<FileList> {
     let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
     let start_time = Instant::now();
 
@@ -272,9 +462,13 @@ fn build_file_list(path: &Path, allow_tests: bool, custom_ignores: &[String]) ->
     // Collect files
     let walk_start = Instant::now();
     let mut files = Vec::new();
+    let mut dirs = Vec::new();
     let mut total_files = 0;
 
     for result in builder.build() {
+        if cancel.is_cancelled() {
+            break;
+        }
         total_files += 1;
         let entry = match result {
             Ok(entry) => entry,
@@ -284,7 +478,11 @@ fn build_file_list(path: &Path, allow_tests: bool, custom_ignores: &[String]) ->
             }
         };
 
-        // Skip directories
+        // Skip directories, remembering their modification times for cache validation
+        if entry.file_type().is_some_and(|ft| ft.is_dir()) {
+            dirs.extend(DirStamp::new(entry.path()));
+            continue;
+        }
         if !entry.file_type().is_some_and(|ft| ft.is_file()) {
             continue;
         }
@@ -315,11 +513,14 @@ fn build_file_list(path: &Path, allow_tests: bool, custom_ignores: &[String]) ->
     Ok(FileList {
         files,
         created_at: Instant::now(),
+        dirs,
     })
 }
 
-/// Find files whose names match query words
+/// Find files whose names match words of `queries`, the queries as the user wrote them
+/// (terms they were expanded with, such as synonyms, are not matched against names)
 /// Returns a map of file paths to the term indices that matched the filename
+#[allow(clippy::too_many_arguments)]
 pub fn find_matching_filenames(
     path: &Path,
     queries: &[String],
@@ -328,6 +529,7 @@ pub fn find_matching_filenames(
     allow_tests: bool,
     term_indices: &HashMap<String, usize>,
     language: Option<&str>,
+    cancel: &CancellationToken,
 ) -> Result<HashMap<PathBuf, HashSet<usize>>> {
     let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
     let start_time = Instant::now();
@@ -343,7 +545,7 @@ pub fn find_matching_filenames(
     }
 
     // Get the cached file list, with language filtering if specified
-    let file_list = get_file_list_by_language(path, allow_tests, custom_ignores, language)?;
+    let file_list = get_file_list_by_language(path, allow_tests, custom_ignores, language, cancel)?;
 
     if debug_mode {
         println!(
@@ -369,13 +571,22 @@ pub fn find_matching_filenames(
     let mut matching_files = HashMap::new();
 
     for file_path in &file_list.files {
+        if cancel.is_cancelled() {
+            break;
+        }
+
         // Skip if this file is already in the results
         if already_found_files.contains(file_path) {
             continue;
         }
 
-        // Get the full relative path including directory structure
-        let relative_path = file_path.to_string_lossy().to_string();
+        // Get the path relative to the search root, so the directories above it
+        // (a temporary or home directory) don't match query words
+        let relative_path = file_path
+            .strip_prefix(path)
+            .unwrap_or(file_path)
+            .to_string_lossy()
+            .to_string();
 
         // Tokenize the full relative path using the standard tokenizer
         let filename_tokens = tokenization::tokenize(&relative_path);
@@ -390,7 +601,15 @@ pub fn find_matching_filenames(
         let mut matched_terms = HashSet::new();
 
         for (term, &idx) in term_indices {
+            // comment:/code: terms are about file content, never the file name
+            if QueryField::split_key(term).0.is_some() {
+                continue;
+            }
             let term_tokens = tokenization::tokenize(term);
+            // Terms the query was expanded with (synonyms) are about file content too
+            if !term_tokens.iter().any(|token| query_tokens.contains(token)) {
+                continue;
+            }
 
             // Check if any term token matches any filename token
             let matched = term_tokens.iter().any(|term_token| {
@@ -455,16 +674,33 @@ fn get_language_extensions(language: &str) -> Vec<String> {
     }
 }
 
+/// Check whether a file belongs to a language. Aliases such as `rs` or `ts` are accepted;
+/// languages without a known extension list are compared against the file extension directly.
+pub fn file_matches_language(path: &Path, language: &str) -> bool {
+    let Some(ext) = path.extension().map(|e| e.to_string_lossy().to_lowercase()) else {
+        return false;
+    };
+
+    let canonical = crate::search::search_runner::normalize_language_alias(language);
+    let extensions = get_language_extensions(canonical);
+    if extensions.is_empty() {
+        ext == language.to_lowercase()
+    } else {
+        extensions.iter().any(|e| e.trim_start_matches('.') == ext)
+    }
+}
+
 /// Get a list of files in a directory, filtered by language if specified
 pub fn get_file_list_by_language(
     path: &Path,
     allow_tests: bool,
     custom_ignores: &[String],
     language: Option<&str>,
+    cancel: &CancellationToken,
 ) -> Result<Arc<FileList>> {
     // If no language is specified, use the regular get_file_list function
     if language.is_none() {
-        return get_file_list(path, allow_tests, custom_ignores);
+        return get_file_list(path, allow_tests, custom_ignores, cancel);
     }
 
     let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
@@ -478,7 +714,7 @@ pub fn get_file_list_by_language(
     }
 
     // Get the full file list first
-    let full_file_list = get_file_list(path, allow_tests, custom_ignores)?;
+    let full_file_list = get_file_list(path, allow_tests, custom_ignores, cancel)?;
 
     // Get the extensions for the specified language
     let extensions = get_language_extensions(language.unwrap());
@@ -521,5 +757,6 @@ pub fn get_file_list_by_language(
     Ok(Arc::new(FileList {
         files: filtered_files,
         created_at: Instant::now(),
+        dirs: Vec::new(),
     }))
 }
@@ -8,8 +8,11 @@ use std::time::{Duration, Instant};
 use tree_sitter;
 
 use crate::language::{is_test_file, parse_file_for_code_blocks};
-use crate::models::SearchResult;
+use crate::models::{CodeBlock, SearchResult};
 use crate::ranking;
+use crate::search::elastic_query::QueryField;
+use crate::search::file_cache::FileCache;
+use crate::search::timeout::CancellationToken;
 use crate::search::tokenization;
 
 /// Structure to hold timing information for file processing stages
@@ -54,6 +57,9 @@ pub struct FileProcessingParams<'a> {
 
     #[allow(dead_code)]
     pub no_merge: bool,
+
+    /// Stops processing early; blocks not processed yet are left out
+    pub cancel: &'a CancellationToken,
 }
 
 /// Evaluate whether a block of lines satisfies a complex AST query
@@ -161,14 +167,36 @@ pub fn filter_code_block_with_ast(
 
 /// Evaluate whether a tokenized block satisfies a complex AST query
 /// using the 'evaluate' method in `elastic_query::Expr`.
+#[allow(dead_code)]
 pub fn filter_tokenized_block(
     tokenized_content: &[String],
     term_indices: &HashMap<String, usize>,
     plan: &crate::search::query::QueryPlan,
     debug_mode: bool,
+) -> bool {
+    filter_tokenized_block_with_scoped_terms(
+        tokenized_content,
+        &HashSet::new(),
+        None,
+        term_indices,
+        plan,
+        debug_mode,
+    )
+}
+
+/// Same as `filter_tokenized_block`, but also counts the `comment:`/`code:` terms
+/// already found in the matching part of the block (see `match_scoped_terms`), and checks
+/// proximity, wildcard and regex terms against `code`, the block's code.
+pub fn filter_tokenized_block_with_scoped_terms(
+    tokenized_content: &[String],
+    scoped_matches: &HashSet<usize>,
+    code: Option<&str>,
+    term_indices: &HashMap<String, usize>,
+    plan: &crate::search::query::QueryPlan,
+    debug_mode: bool,
 ) -> bool {
     // Create a set of matched term indices based on tokenized content
-    let mut matched_terms = HashSet::new();
+    let mut matched_terms = scoped_matches.clone();
 
     // For each token in the tokenized content, check if it's in the term_indices
     for token in tokenized_content {
@@ -177,6 +205,11 @@ pub fn filter_tokenized_block(
         }
     }
 
+    // Wildcard and regex terms are matched against the code itself
+    if let Some(code) = code {
+        matched_terms.extend(plan.match_pattern_terms(code));
+    }
+
     // Special handling for compound words like "whitelist"
     // Check if any term in the plan is a compound of tokens in the content
     for (term, &idx) in &plan.term_indices {
@@ -241,7 +274,17 @@ pub fn filter_tokenized_block(
     }
 
     // Use the evaluate function from the elastic query module
-    let result = plan.ast.evaluate(&matched_terms, &plan.term_indices, false);
+    // Proximity terms need to know where in the code each token is
+    let code_positions = code
+        .filter(|_| plan.ast.has_proximity())
+        .map(tokenization::token_positions);
+    let result = match code_positions {
+        Some(tokens) => {
+            plan.ast
+                .evaluate_with_tokens(&matched_terms, &plan.term_indices, false, &tokens)
+        }
+        None => plan.ast.evaluate(&matched_terms, &plan.term_indices, false),
+    };
 
     if debug_mode {
         println!("DEBUG: ===== EVALUATION RESULT =====");
@@ -267,6 +310,101 @@ pub fn filter_tokenized_block(
     decision
 }
 
+/// Byte ranges of all comment nodes under `node`, in document order.
+fn collect_comment_spans(node: tree_sitter::Node, spans: &mut Vec<(usize, usize)>) {
+    if node.kind().contains("comment") {
+        spans.push((node.start_byte(), node.end_byte()));
+        return;
+    }
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        collect_comment_spans(child, spans);
+    }
+}
+
+/// Split lines `start_line..=end_line` (1-based) into comment text and code text.
+/// Comment spans come from the syntax tree when one is available; otherwise each line
+/// is classified with the same heuristic used for fallback context.
+fn split_comments_and_code(
+    content: &str,
+    line_offsets: &[usize],
+    (start_line, end_line): (usize, usize),
+    comment_spans: Option<&[(usize, usize)]>,
+    extension: &str,
+) -> (String, String) {
+    let mut comment_text = String::new();
+    let mut code_text = String::new();
+
+    let Some(&start) = line_offsets.get(start_line.saturating_sub(1)) else {
+        return (comment_text, code_text);
+    };
+    let end = line_offsets
+        .get(end_line)
+        .copied()
+        .unwrap_or(content.len())
+        .max(start);
+
+    match comment_spans {
+        Some(spans) => {
+            let mut pos = start;
+            for &(span_start, span_end) in spans {
+                if span_end <= start || span_start >= end {
+                    continue;
+                }
+                let span_start = span_start.max(pos);
+                let span_end = span_end.min(end);
+                code_text.push_str(&content[pos..span_start]);
+                comment_text.push_str(&content[span_start..span_end]);
+                comment_text.push('\n');
+                pos = span_end;
+            }
+            code_text.push_str(&content[pos..end]);
+        }
+        None => {
+            for line in content[start..end].lines() {
+                let target = if determine_fallback_node_type(line, Some(extension)) == "comment" {
+                    &mut comment_text
+                } else {
+                    &mut code_text
+                };
+                target.push_str(line);
+                target.push('\n');
+            }
+        }
+    }
+
+    (comment_text, code_text)
+}
+
+/// Term indices of `comment:`/`code:` terms whose keyword occurs in the comment or
+/// code text of a block respectively.
+fn match_scoped_terms(
+    plan: &crate::search::query::QueryPlan,
+    comment_text: &str,
+    code_text: &str,
+) -> HashSet<usize> {
+    let comment_tokens: HashSet<String> = ranking::tokenize(comment_text).into_iter().collect();
+    let code_tokens: HashSet<String> = ranking::tokenize(code_text).into_iter().collect();
+    let term_patterns = plan.term_patterns();
+
+    plan.term_indices
+        .iter()
+        .filter_map(|(key, &idx)| {
+            let (field, keyword) = QueryField::split_key(key);
+            let (text, tokens) = match field {
+                Some(QueryField::Comment) => (comment_text, &comment_tokens),
+                Some(QueryField::Code) => (code_text, &code_tokens),
+                _ => return None,
+            };
+            let matched = match term_patterns.get(key) {
+                Some(pattern) => pattern.matches(text),
+                None => tokens.contains(keyword),
+            };
+            matched.then_some(idx)
+        })
+        .collect()
+}
+
 /// Determines a better node type for fallback context by analyzing the line content
 fn determine_fallback_node_type(line: &str, extension: Option<&str>) -> String {
     let trimmed = line.trim();
@@ -333,6 +471,40 @@ fn determine_fallback_node_type(line: &str, extension: Option<&str>) -> String {
 
     "code".to_string()
 }
+/// Get the name declared by the node a code block was built from (function, struct,
+/// class, ...), if it has one
+fn declared_name(tree: &tree_sitter::Tree, block: &CodeBlock, content: &str) -> Option<String> {
+    let node = find_block_node(tree.root_node(), block)?;
+    let name_node = crate::extract::symbol_finder::declaration_name_node(node)?;
+    name_node
+        .utf8_text(content.as_bytes())
+        .ok()
+        .map(str::to_string)
+}
+
+/// Find the outermost node of the block's type inside its byte range. The range can be
+/// wider than the node itself, e.g. when leading comments were attached to the block.
+fn find_block_node<'t>(
+    node: tree_sitter::Node<'t>,
+    block: &CodeBlock,
+) -> Option<tree_sitter::Node<'t>> {
+    if node.kind() == block.node_type
+        && node.start_byte() >= block.start_byte
+        && node.end_byte() <= block.end_byte
+    {
+        return Some(node);
+    }
+
+    let mut cursor = node.walk();
+    let children: Vec<_> = node
+        .children(&mut cursor)
+        .filter(|child| child.start_byte() < block.end_byte && child.end_byte() > block.start_byte)
+        .collect();
+    children
+        .into_iter()
+        .find_map(|child| find_block_node(child, block))
+}
+
 /// Main function for processing a file with matched lines
 pub fn process_file_with_results(
     params: &FileProcessingParams,
@@ -442,7 +614,10 @@ pub fn process_file_with_results(
     let file_path = params.path.to_string_lossy().to_string();
     let cache_key = format!("{}_{}", file_path, extension);
 
-    let _ = if language_impl.is_some() {
+    // comment:/code: terms need to know which parts of a block are comments, which is the
+    // only use of the tree here, so other searches can be answered from cached line maps
+    let has_scoped_terms = params.query_plan.has_scoped_terms();
+    let tree = if language_impl.is_some() && has_scoped_terms {
         crate::language::tree_cache::get_or_parse_tree(&cache_key, &content, &mut parser).ok()
     } else {
         None
@@ -453,6 +628,10 @@ pub fn process_file_with_results(
     // Measure line map building time (this is an approximation since we can't directly measure it)
     let line_map_building_start = Instant::now();
 
+    // Reuse the line map and block tokens from an earlier run if the file is unchanged
+    let mut file_cache = FileCache::load(params.path, &content);
+    file_cache.seed_line_map(&content, extension, params.allow_tests);
+
     // Call the original parse_file_for_code_blocks function
     let code_blocks_result = parse_file_for_code_blocks(
         &content,
@@ -461,6 +640,7 @@ pub fn process_file_with_results(
         params.allow_tests,
         Some(params.term_matches),
     );
+    file_cache.store_line_map(&content, extension, params.allow_tests);
 
     let line_map_building_duration = line_map_building_start.elapsed();
     timings.ast_parsing_line_map_building = Some(line_map_building_duration);
@@ -480,6 +660,49 @@ pub fn process_file_with_results(
         );
     }
 
+    let comment_spans = if has_scoped_terms {
+        tree.as_ref().map(|tree| {
+            let mut spans = Vec::new();
+            collect_comment_spans(tree.root_node(), &mut spans);
+            spans
+        })
+    } else {
+        None
+    };
+    // Block names only need the tree when they are not in the file cache yet, so the
+    // tree is parsed on first use unless scoped terms already needed it
+    let has_language = language_impl.is_some();
+    let name_source = Mutex::new((tree, parser));
+    let block_symbol_name = |block: &CodeBlock| -> Option<String> {
+        let mut source = name_source.lock().unwrap();
+        let (tree, parser) = &mut *source;
+        if tree.is_none() {
+            *tree =
+                crate::language::tree_cache::get_or_parse_tree(&cache_key, &content, parser).ok();
+        }
+        declared_name(tree.as_ref()?, block, &content)
+    };
+    let line_offsets: Vec<usize> = if has_scoped_terms {
+        std::iter::once(0)
+            .chain(content.match_indices('\n').map(|(i, _)| i + 1))
+            .collect()
+    } else {
+        Vec::new()
+    };
+    let scoped_matches_for = |start_line: usize, end_line: usize| -> HashSet<usize> {
+        if !has_scoped_terms {
+            return HashSet::new();
+        }
+        let (comment_text, code_text) = split_comments_and_code(
+            &content,
+            &line_offsets,
+            (start_line, end_line),
+            comment_spans.as_deref(),
+            extension,
+        );
+        match_scoped_terms(params.query_plan, &comment_text, &code_text)
+    };
+
     if let Ok(code_blocks) = code_blocks_result {
         if debug_mode {
             println!("DEBUG: AST parsing successful");
@@ -517,12 +740,18 @@ pub fn process_file_with_results(
         // Prepare shared resources for parallel processing
         let shared_results = Arc::new(Mutex::new(Vec::new()));
         let shared_covered_lines = Arc::new(Mutex::new(HashSet::new()));
+        let shared_file_cache = Mutex::new(&mut file_cache);
+        let token_path = params.path.to_string_lossy();
 
         // Process blocks in parallel
         code_blocks
             .par_iter()
             .enumerate()
             .for_each(|(block_idx, block)| {
+                if params.cancel.is_cancelled() {
+                    return;
+                }
+
                 // Start measuring code structure finding time for this block
                 let block_start = Instant::now();
 
@@ -549,6 +778,15 @@ pub fn process_file_with_results(
                     (start_line, end_line, false)
                 };
 
+                let node_type = if is_nested_struct {
+                    block
+                        .parent_node_type
+                        .clone()
+                        .unwrap_or_else(|| block.node_type.clone())
+                } else {
+                    block.node_type.clone()
+                };
+
                 let full_code = if final_start_line > 0 && final_end_line <= lines.len() {
                     // Skip empty lines (which were originally too long)
                     lines[final_start_line - 1..final_end_line]
@@ -568,11 +806,26 @@ pub fn process_file_with_results(
                 // Start measuring term matching time
                 let term_matching_start = Instant::now();
 
-                // Early tokenization with full path prepended
-                let block_terms = ranking::preprocess_text_with_filename(
-                    &full_code,
-                    &params.path.to_string_lossy(),
+                // Early tokenization with full path prepended, outside the lock so blocks
+                // are tokenized in parallel
+                let cached_terms = shared_file_cache.lock().unwrap().cached_block_tokens(
+                    &token_path,
+                    final_start_line,
+                    final_end_line,
                 );
+                let block_terms = match cached_terms {
+                    Some(terms) => terms,
+                    None => {
+                        let terms =
+                            ranking::preprocess_text_with_filename(&full_code, &token_path);
+                        shared_file_cache.lock().unwrap().store_block_tokens(
+                            final_start_line,
+                            final_end_line,
+                            terms.clone(),
+                        );
+                        terms
+                    }
+                };
 
                 // End term matching time measurement
                 let term_matching_block_duration = term_matching_start.elapsed();
@@ -592,8 +845,17 @@ pub fn process_file_with_results(
                         );
                     }
 
+                    // type: filters apply regardless of the search mode
+                    if !params.query_plan.matches_node_type(&node_type) {
+                        if debug_mode {
+                            println!(
+                                "DEBUG: Block {}-{} of type '{}' rejected by type filter",
+                                final_start_line, final_end_line, node_type
+                            );
+                        }
+                        false
                     // Skip tokenization and evaluation when exact flag is enabled
-                    if params.query_plan.exact {
+                    } else if params.query_plan.exact {
                         // In exact mode, we already matched the lines in the file
                         // so we should include this block without re-evaluating
                         if debug_mode {
@@ -605,8 +867,10 @@ pub fn process_file_with_results(
                         true
                     } else {
                         // Use the AST evaluation directly to ensure correct handling of complex queries
-                        let result = filter_tokenized_block(
+                        let result = filter_tokenized_block_with_scoped_terms(
                             &block_terms,
+                            &scoped_matches_for(final_start_line, final_end_line),
+                            Some(&full_code),
                             &params.query_plan.term_indices,
                             params.query_plan,
                             debug_mode,
@@ -674,7 +938,7 @@ pub fn process_file_with_results(
                         if block_terms.iter().any(|bt| bt == qterm) {
                             continue;
                         }
-                        let parts = tokenization::split_compound_word(qterm, vocabulary);
+                        let parts = tokenization::split_compound_word(qterm, &vocabulary);
                         if parts.len() > 1 && parts.iter().all(|part| block_terms.contains(part)) {
                             compound_matches.insert(qterm);
                         }
@@ -723,7 +987,7 @@ pub fn process_file_with_results(
                         if matched_term_indices.contains(&idx)
                             && !params.query_plan.excluded_terms.contains(term)
                         {
-                            matched_keywords.push(term.clone());
+                            matched_keywords.push(QueryField::split_key(term).1.to_string());
                         }
                     }
 
@@ -731,20 +995,23 @@ pub fn process_file_with_results(
                     matched_keywords.sort();
                     matched_keywords.dedup();
 
+                    // Remember the name the block declares for the definition boost
+                    let symbol_name = if has_language {
+                        shared_file_cache
+                            .lock()
+                            .unwrap()
+                            .block_name(start_line, end_line, || block_symbol_name(block))
+                    } else {
+                        None
+                    };
+
                     // Start measuring result creation time
                     let result_creation_start = Instant::now();
 
                     let result = SearchResult {
                         file: params.path.to_string_lossy().to_string(),
                         lines: (final_start_line, final_end_line),
-                        node_type: if is_nested_struct {
-                            block
-                                .parent_node_type
-                                .clone()
-                                .unwrap_or_else(|| block.node_type.clone())
-                        } else {
-                            block.node_type.clone()
-                        },
+                        node_type,
                         code: full_code,
                         matched_by_filename: None,
                         rank: None,
@@ -769,6 +1036,12 @@ pub fn process_file_with_results(
                             Some(matched_keywords)
                         },
                         tokenized_content: Some(block_terms),
+                        search_root: None,
+                        symbol_name,
+                        definition_boost: None,
+                        recency_boost: None,
+                        centrality: None,
+                        explanation: None,
                     };
 
                     let result_creation_duration_value = result_creation_start.elapsed();
@@ -907,6 +1180,10 @@ pub fn process_file_with_results(
 
     // Process uncovered lines only after all AST blocks have been processed
     for line_num in uncovered_lines {
+        if params.cancel.is_cancelled() {
+            break;
+        }
+
         // Skip fallback context for test files if allow_tests is false
         if !params.allow_tests && is_test_file(params.path) {
             if debug_mode {
@@ -993,8 +1270,11 @@ pub fn process_file_with_results(
         let term_matching_start = Instant::now();
 
         // Early tokenization for fallback context
+        let token_path = params.path.to_string_lossy();
         let context_terms =
-            ranking::preprocess_text_with_filename(&context_code, &params.path.to_string_lossy());
+            file_cache.block_tokens(&token_path, context_start, context_end, || {
+                ranking::preprocess_text_with_filename(&context_code, &token_path)
+            });
 
         // Add to term matching time
         let term_matching_duration_value = term_matching_start.elapsed();
@@ -1016,8 +1296,17 @@ pub fn process_file_with_results(
                 );
             }
 
+            // type: filters apply regardless of the search mode
+            if !params.query_plan.matches_node_type(&node_type) {
+                if debug_mode {
+                    println!(
+                        "DEBUG: Fallback context {}-{} of type '{}' rejected by type filter",
+                        context_start, context_end, node_type
+                    );
+                }
+                false
             // Skip tokenization and evaluation when exact flag is enabled
-            if params.query_plan.exact {
+            } else if params.query_plan.exact {
                 // In exact mode, we already matched the lines in the file
                 // so we should include this block without re-evaluating
                 if debug_mode {
@@ -1028,8 +1317,10 @@ pub fn process_file_with_results(
                 }
                 true
             } else {
-                filter_tokenized_block(
+                filter_tokenized_block_with_scoped_terms(
                     &context_terms,
+                    &scoped_matches_for(context_start, context_end),
+                    Some(&context_code),
                     &params.query_plan.term_indices,
                     params.query_plan,
                     debug_mode,
@@ -1073,7 +1364,7 @@ pub fn process_file_with_results(
                 if context_terms.iter().any(|bt| bt == qterm) {
                     continue;
                 }
-                let parts = tokenization::split_compound_word(qterm, vocabulary);
+                let parts = tokenization::split_compound_word(qterm, &vocabulary);
                 if parts.len() > 1 && parts.iter().all(|part| context_terms.contains(part)) {
                     compound_matches.insert(qterm);
                 }
@@ -1126,7 +1417,7 @@ pub fn process_file_with_results(
                 if matched_term_indices.contains(&idx)
                     && !params.query_plan.excluded_terms.contains(term)
                 {
-                    matched_keywords.push(term.clone());
+                    matched_keywords.push(QueryField::split_key(term).1.to_string());
                 }
             }
 
@@ -1165,6 +1456,12 @@ pub fn process_file_with_results(
                     Some(matched_keywords)
                 },
                 tokenized_content: Some(context_terms),
+                search_root: None,
+                symbol_name: None,
+                definition_boost: None,
+                recency_boost: None,
+                centrality: None,
+                explanation: None,
             };
 
             // Add to result creation time
@@ -1250,5 +1547,14 @@ pub fn process_file_with_results(
         }
     }
 
+    if let Err(e) = file_cache.save() {
+        if debug_mode {
+            println!(
+                "DEBUG: Failed to save file cache for {:?}: {}",
+                params.path, e
+            );
+        }
+    }
+
     Ok((results, timings))
 }
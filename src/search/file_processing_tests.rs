@@ -6,6 +6,7 @@ use tempfile::TempDir;
 use crate::search::elastic_query;
 use crate::search::file_processing::process_file_with_results;
 use crate::search::query::QueryPlan;
+use crate::search::timeout::CancellationToken;
 
 // Helper function to create a test file
 pub fn create_test_file(dir: &TempDir, filename: &str, content: &str) -> std::path::PathBuf {
@@ -30,14 +31,12 @@ pub fn create_test_query_plan(terms: &[&str]) -> QueryPlan {
         required: false,
         excluded: false,
         exact: false,
+        proximity: None,
+        pattern: None,
+        boost: None,
     };
 
-    QueryPlan {
-        ast,
-        term_indices,
-        excluded_terms: HashSet::new(),
-        exact: false,
-    }
+    QueryPlan::new(ast, term_indices, HashSet::new(), false)
 }
 
 // Helper function to preprocess query for testing (replacement for removed function)
@@ -83,6 +82,7 @@ mod tests {
             preprocessed_queries: None,
             query_plan: &query_plan,
             no_merge: false,
+            cancel: &CancellationToken::new(),
         };
 
         let (results, _) =
@@ -134,6 +134,7 @@ mod tests {
             preprocessed_queries: None,
             query_plan: &query_plan,
             no_merge: false,
+            cancel: &CancellationToken::new(),
         };
 
         // Capture the results to check them
@@ -184,6 +185,7 @@ mod tests {
             preprocessed_queries: None,
             query_plan: &query_plan,
             no_merge: false,
+            cancel: &CancellationToken::new(),
         };
 
         let (results, _) =
@@ -261,6 +263,7 @@ function test3() {
             preprocessed_queries: None, // No preprocessed queries
             query_plan: &query_plan,
             no_merge: false,
+            cancel: &CancellationToken::new(),
         };
 
         let (results, _) =
@@ -356,10 +359,11 @@ function processResults(results) {
             term_matches: &term_matches,
             num_queries: 2, // "process" and "data"
             filename_matched_queries: HashSet::new(),
-            queries_terms: &[term_pairs.clone()],
+            queries_terms: std::slice::from_ref(&term_pairs),
             preprocessed_queries: Some(&preprocessed_queries),
             query_plan: &query_plan,
             no_merge: false,
+            cancel: &CancellationToken::new(),
         };
 
         let (results, _) =
@@ -425,6 +429,7 @@ fn test_long_lines_are_ignored() {
         preprocessed_queries: None,
         query_plan: &query_plan,
         no_merge: false,
+        cancel: &CancellationToken::new(),
     };
 
     let (results, _) =
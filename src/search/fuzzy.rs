@@ -0,0 +1,290 @@
+//! Typo-tolerant matching for fuzzy terms (`recieve~1`, `--fuzzy`): edit distances and the
+//! vocabulary of a search root that fuzzy terms are expanded against.
+
+use anyhow::Result;
+use lazy_static::lazy_static;
+use rayon::prelude::*;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::search::cache::file_mtime_nanos;
+use crate::search::file_list_cache::{self, FileList};
+use crate::search::index::SearchIndex;
+use crate::search::timeout::CancellationToken;
+
+/// Words shorter than this are never matched fuzzily, whether written as `word~n` or made
+/// fuzzy by `--fuzzy`: a single edit turns most short words into other common words
+pub const MIN_FUZZY_LENGTH: usize = 4;
+
+/// Largest edit distance of a fuzzy term. Further away, most words of a codebase match.
+pub const MAX_FUZZY_DISTANCE: usize = 2;
+
+/// Most vocabulary words a fuzzy term is expanded into for the file search, closest first
+const MAX_CANDIDATES: usize = 100;
+
+/// Files larger than this are not read for their vocabulary (same limit as the file scan)
+const MAX_FILE_SIZE: u64 = 1024 * 1024;
+
+/// Edit distance between `a` and `b`, counting insertions, deletions, substitutions and
+/// transpositions of adjacent characters (`recieve` is one edit from `receive`), or `None`
+/// if it is more than `max`.
+pub fn edit_distance(a: &str, b: &str, max: usize) -> Option<usize> {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    if a.len().abs_diff(b.len()) > max {
+        return None;
+    }
+
+    // Rows i-2, i-1 and i of the optimal string alignment table
+    let mut before: Vec<usize> = vec![0; b.len() + 1];
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current: Vec<usize> = vec![0; b.len() + 1];
+    for i in 1..=a.len() {
+        current[0] = i;
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            current[j] = (previous[j] + 1)
+                .min(current[j - 1] + 1)
+                .min(previous[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                current[j] = current[j].min(before[j - 2] + 1);
+            }
+        }
+        // No alignment through this row can get back under the limit
+        if current.iter().min().is_some_and(|&d| d > max) {
+            return None;
+        }
+        std::mem::swap(&mut before, &mut previous);
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    Some(previous[b.len()]).filter(|&d| d <= max)
+}
+
+/// Words of `vocabulary` within `distance` edits of `word`, closest first
+pub fn candidates(word: &str, distance: usize, vocabulary: &HashSet<String>) -> Vec<String> {
+    let word = word.to_lowercase();
+    let mut matches: Vec<(usize, &String)> = vocabulary
+        .iter()
+        .filter_map(|candidate| Some((edit_distance(&word, candidate, distance)?, candidate)))
+        .collect();
+    matches.sort();
+    matches
+        .into_iter()
+        .take(MAX_CANDIDATES)
+        .map(|(_, candidate)| candidate.clone())
+        .collect()
+}
+
+/// Lowercase words of `text` plus the parts of its snake_case and camelCase identifiers
+fn add_words(text: &str, words: &mut HashSet<String>) {
+    for word in text.split(|c: char| !c.is_alphanumeric() && c != '_') {
+        if word.is_empty() {
+            continue;
+        }
+        if word.contains('_') || word.chars().any(|c| c.is_uppercase()) {
+            for part in word.split('_') {
+                for part in crate::search::tokenization::split_camel_case(part) {
+                    words.insert(part.to_lowercase());
+                }
+            }
+        }
+        words.insert(word.to_lowercase());
+    }
+}
+
+/// What a cached vocabulary was built from
+enum VocabularySource {
+    /// The words of a loaded search index
+    Index(Arc<SearchIndex>),
+    /// Listed files, compared by their `files_stamp`
+    Files(u64, Arc<FileList>),
+}
+
+impl VocabularySource {
+    fn is_same(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Index(a), Self::Index(b)) => Arc::ptr_eq(a, b),
+            (Self::Files(a, _), Self::Files(b, _)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+/// Cache key for a root vocabulary: the search root and the options that list its files
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct VocabularyCacheKey {
+    root: PathBuf,
+    allow_tests: bool,
+    custom_ignores: Vec<String>,
+    language: Option<String>,
+}
+
+/// A built vocabulary with what it was built from
+type CachedVocabulary = (VocabularySource, Arc<HashSet<String>>);
+
+// Vocabularies already built by this process, reused while their source is unchanged
+lazy_static! {
+    static ref VOCABULARY_CACHE: Mutex<HashMap<VocabularyCacheKey, CachedVocabulary>> =
+        Mutex::new(HashMap::new());
+}
+
+/// Hash of the walked directories and the modification time and size of every listed
+/// file, which changes whenever the words of the listed files may have changed
+fn files_stamp(file_list: &FileList) -> u64 {
+    let file_stamps: Vec<(u64, u64)> = file_list
+        .files
+        .par_iter()
+        .map(|file| {
+            fs::metadata(file)
+                .map(|metadata| (file_mtime_nanos(&metadata), metadata.len()))
+                .unwrap_or_default()
+        })
+        .collect();
+
+    let mut hasher = DefaultHasher::new();
+    for dir in &file_list.dirs {
+        (&dir.path, dir.mtime, dir.ignore_mtime).hash(&mut hasher);
+    }
+    file_list.files.hash(&mut hasher);
+    file_stamps.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// The words of the files under `root` that fuzzy terms are expanded against. The words of
+/// the search index are used when one covers `root` (see `probe index`); otherwise the files
+/// the search would scan are read. Vocabularies are kept in memory until the index or any
+/// listed file changes.
+pub fn root_vocabulary(
+    root: &Path,
+    allow_tests: bool,
+    custom_ignores: &[String],
+    language: Option<&str>,
+    cancel: &CancellationToken,
+) -> Result<Arc<HashSet<String>>> {
+    let key = VocabularyCacheKey {
+        root: root.to_path_buf(),
+        allow_tests,
+        custom_ignores: custom_ignores.to_vec(),
+        language: language.map(str::to_string),
+    };
+
+    let source = match SearchIndex::load_for(root) {
+        Some(index) => VocabularySource::Index(index),
+        None => {
+            let file_list = file_list_cache::get_file_list_by_language(
+                root,
+                allow_tests,
+                custom_ignores,
+                language,
+                cancel,
+            )?;
+            VocabularySource::Files(files_stamp(&file_list), file_list)
+        }
+    };
+
+    if let Some((cached_source, vocabulary)) = VOCABULARY_CACHE.lock().unwrap().get(&key) {
+        if cached_source.is_same(&source) {
+            return Ok(Arc::clone(vocabulary));
+        }
+    }
+
+    let vocabulary = Arc::new(match &source {
+        VocabularySource::Index(index) => index_vocabulary(index),
+        VocabularySource::Files(_, file_list) => files_vocabulary(file_list, cancel),
+    });
+    // A vocabulary cut short by cancellation is missing words, so it is not kept
+    if !cancel.is_cancelled() {
+        VOCABULARY_CACHE
+            .lock()
+            .unwrap()
+            .insert(key, (source, Arc::clone(&vocabulary)));
+    }
+    Ok(vocabulary)
+}
+
+/// The words of a search index
+fn index_vocabulary(index: &SearchIndex) -> HashSet<String> {
+    let mut vocabulary = HashSet::new();
+    for word in index.postings.keys() {
+        add_words(word, &mut vocabulary);
+    }
+    vocabulary
+}
+
+/// The words of the listed files
+fn files_vocabulary(file_list: &FileList, cancel: &CancellationToken) -> HashSet<String> {
+    let file_words: Vec<HashSet<String>> = file_list
+        .files
+        .par_iter()
+        .filter(|_| !cancel.is_cancelled())
+        .filter_map(|path| {
+            if fs::metadata(path).ok()?.len() > MAX_FILE_SIZE {
+                return None;
+            }
+            let content = fs::read_to_string(path).ok()?;
+            let mut words = HashSet::new();
+            add_words(&content, &mut words);
+            Some(words)
+        })
+        .collect();
+    let mut vocabulary = HashSet::new();
+    for words in file_words {
+        vocabulary.extend(words);
+    }
+    vocabulary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("receive", "receive", 1), Some(0));
+        // Transposition
+        assert_eq!(edit_distance("recieve", "receive", 1), Some(1));
+        // Deletion, insertion and substitution
+        assert_eq!(edit_distance("initalize", "initialize", 1), Some(1));
+        assert_eq!(edit_distance("initialize", "initalize", 1), Some(1));
+        assert_eq!(edit_distance("handler", "handlar", 1), Some(1));
+        assert_eq!(edit_distance("handler", "hondlar", 1), None);
+        assert_eq!(edit_distance("handler", "hondlar", 2), Some(2));
+        assert_eq!(edit_distance("auth", "authentication", 3), None);
+    }
+
+    #[test]
+    fn test_candidates() {
+        let vocabulary: HashSet<String> = ["receive", "received", "recipe", "deceive"]
+            .iter()
+            .map(|w| w.to_string())
+            .collect();
+        assert_eq!(candidates("Recieve", 1, &vocabulary), vec!["receive"]);
+        assert_eq!(
+            candidates("recieve", 2, &vocabulary),
+            vec!["receive", "deceive", "received", "recipe"]
+        );
+    }
+
+    #[test]
+    fn test_root_vocabulary_is_reused_until_files_change() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let file = temp_dir.path().join("inbox.rs");
+        fs::write(&file, "fn receive_message() {}\n").unwrap();
+        let cancel = CancellationToken::new();
+
+        let vocabulary = |cancel| root_vocabulary(temp_dir.path(), false, &[], None, cancel);
+        let first = vocabulary(&cancel).unwrap();
+        assert!(first.contains("receive"));
+        assert!(Arc::ptr_eq(&first, &vocabulary(&cancel).unwrap()));
+
+        fs::write(&file, "fn receive_message() {}\nfn deliver_parcel() {}\n").unwrap();
+        let changed = vocabulary(&cancel).unwrap();
+        assert!(!Arc::ptr_eq(&first, &changed));
+        assert!(changed.contains("parcel"));
+    }
+}
@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Commits older than this many half-lives add less than 0.1% and are not read
+const MAX_HALF_LIVES: f64 = 10.0;
+
+/// How actively each file of a git repository has been changing, read from the local
+/// history with `git log` (no network access).
+///
+/// Every commit that touched a file adds `0.5^(age / half_life)` to the file's activity,
+/// so a file changed once today scores 1.0, one changed four times a half-life ago
+/// scores 2.0, and files untouched for a long time score close to 0.
+pub struct GitHistory {
+    activity: HashMap<PathBuf, f64>,
+}
+
+impl GitHistory {
+    /// Read the history of the repository at `repo_root` (the directory holding `.git`).
+    /// Returns `None` if git is not available or the history cannot be read.
+    pub fn load(repo_root: &Path, half_life_days: f64) -> Option<Self> {
+        let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
+
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs() as i64;
+        let half_life_secs = half_life_days * 24.0 * 60.0 * 60.0;
+        let since = now - (half_life_secs * MAX_HALF_LIVES) as i64;
+
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(repo_root)
+            .args(["-c", "core.quotePath=false", "log", "--no-renames"])
+            .arg(format!("--since=@{}", since))
+            .args(["--format=%x00%ct", "--name-only"])
+            .output();
+        let output = match output {
+            Ok(output) if output.status.success() => output,
+            Ok(output) => {
+                if debug_mode {
+                    println!(
+                        "DEBUG: git log failed in {:?}: {}",
+                        repo_root,
+                        String::from_utf8_lossy(&output.stderr).trim()
+                    );
+                }
+                return None;
+            }
+            Err(e) => {
+                if debug_mode {
+                    println!("DEBUG: Could not run git: {}", e);
+                }
+                return None;
+            }
+        };
+
+        // Each commit is a "\0<timestamp>" line followed by the files it changed
+        let mut activity: HashMap<PathBuf, f64> = HashMap::new();
+        let mut weight = 0.0;
+        for line in String::from_utf8_lossy(&output.stdout).lines() {
+            if let Some(timestamp) = line.strip_prefix('\0') {
+                let age_secs = (now - timestamp.trim().parse::<i64>().unwrap_or(0)).max(0);
+                weight = 0.5f64.powf(age_secs as f64 / half_life_secs);
+            } else if !line.is_empty() {
+                *activity.entry(repo_root.join(line)).or_insert(0.0) += weight;
+            }
+        }
+
+        if debug_mode {
+            println!(
+                "DEBUG: Read git history of {:?}: {} files changed in the last {} days",
+                repo_root,
+                activity.len(),
+                half_life_days * MAX_HALF_LIVES
+            );
+        }
+
+        Some(Self { activity })
+    }
+
+    /// Decayed number of commits that touched `file` (an absolute, canonical path)
+    pub fn activity(&self, file: &Path) -> f64 {
+        self.activity.get(file).copied().unwrap_or(0.0)
+    }
+}
+
+/// Find the root of the git repository containing `path` by looking for `.git`
+pub fn find_repo_root(path: &Path) -> Option<PathBuf> {
+    path.ancestors()
+        .find(|dir| dir.join(".git").exists())
+        .map(Path::to_path_buf)
+}
+
+/// Git histories of the repositories results come from, each read once
+pub struct GitHistories {
+    half_life_days: f64,
+    repos: HashMap<PathBuf, Option<GitHistory>>,
+}
+
+impl GitHistories {
+    pub fn new(half_life_days: f64) -> Self {
+        Self {
+            half_life_days,
+            repos: HashMap::new(),
+        }
+    }
+
+    /// Decayed number of commits that touched `file`, or `None` if it is not in a
+    /// git repository whose history can be read
+    pub fn activity(&mut self, file: &Path) -> Option<f64> {
+        let file = file.canonicalize().ok()?;
+        let repo_root = find_repo_root(file.parent()?)?;
+        let half_life_days = self.half_life_days;
+        let history = self
+            .repos
+            .entry(repo_root)
+            .or_insert_with_key(|root| GitHistory::load(root, half_life_days));
+        history.as_ref().map(|history| history.activity(&file))
+    }
+}
@@ -0,0 +1,587 @@
+use anyhow::{bail, Context, Result};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write as _;
+use std::fs::{self, create_dir_all, File};
+use std::hash::{Hash, Hasher};
+use std::io::{BufReader, BufWriter, Write as _};
+use std::path::{Component, Path, PathBuf};
+use std::time::Instant;
+
+use crate::language::factory::get_language_impl;
+use crate::language::language_trait::{Import, LanguageImpl};
+use crate::search::cache::{
+    file_mtime_nanos, hash_query, persistent_cache_enabled, probe_cache_dir,
+};
+use crate::search::file_cache::FileCache;
+use crate::search::file_list_cache::{self, FileList};
+use crate::search::timeout::CancellationToken;
+
+/// Version of the on-disk graph format. Graphs written with another version are ignored.
+const GRAPH_VERSION: u32 = 1;
+
+/// Probability of following an import rather than jumping to a random file
+const DAMPING: f64 = 0.85;
+const MAX_ITERATIONS: usize = 100;
+const TOLERANCE: f64 = 1e-9;
+
+/// File stems that stand for the directory they are in
+const DIRECTORY_MODULES: &[&str] = &["mod", "__init__", "index"];
+
+/// Graph of which files import which, built from tree-sitter import nodes (`use`,
+/// `import`, `require`, `#include`, ...), with a PageRank centrality per file.
+///
+/// Imports are resolved to files under the root: relative paths against the importing
+/// file's directory, module paths (`crate::search::cache`, `com.example.Foo`) by
+/// matching the end of file paths. Imports of code outside the root are dropped.
+pub struct ImportGraph {
+    /// Canonical root the graph was built for
+    pub root: PathBuf,
+    /// Files relative to the root
+    pub files: Vec<PathBuf>,
+    /// (importing file, imported file) pairs, as indices into `files`
+    pub edges: Vec<(usize, usize)>,
+    /// PageRank of each file; the values sum to 1
+    pub centrality: Vec<f64>,
+    file_ids: HashMap<PathBuf, usize>,
+    max_centrality: f64,
+}
+
+/// A graph as persisted in the probe cache directory between runs
+#[derive(Serialize, Deserialize)]
+struct StoredGraph {
+    version: u32,
+    /// Version of probe that wrote the graph (import resolution may differ between versions)
+    probe_version: String,
+    /// `graph_stamp` of the files the graph was built from
+    stamp: u64,
+    files: Vec<PathBuf>,
+    edges: Vec<(usize, usize)>,
+    centrality: Vec<f64>,
+}
+
+#[derive(Serialize)]
+struct JsonNode {
+    file: String,
+    centrality: f64,
+    imports: usize,
+    imported_by: usize,
+}
+
+#[derive(Serialize)]
+struct JsonEdge<'a> {
+    from: &'a str,
+    to: &'a str,
+}
+
+impl ImportGraph {
+    /// Build the graph of all supported source files under `root`.
+    ///
+    /// The graph is persisted and reused by later runs until a file is added, removed or
+    /// modified. Fails if `cancel` is cancelled before the graph is complete.
+    pub fn build(
+        root: &Path,
+        allow_tests: bool,
+        custom_ignores: &[String],
+        cancel: &CancellationToken,
+    ) -> Result<Self> {
+        let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
+        let start_time = Instant::now();
+
+        let canonical_root = fs::canonicalize(root)
+            .with_context(|| format!("Failed to resolve graph root {:?}", root))?;
+        let file_list = file_list_cache::get_file_list(root, allow_tests, custom_ignores, cancel)?;
+        if cancel.is_cancelled() {
+            bail!("Building the import graph for {:?} was cancelled", root);
+        }
+
+        let mut files: Vec<PathBuf> = file_list
+            .files
+            .iter()
+            .filter(|file| {
+                file.extension()
+                    .and_then(|ext| ext.to_str())
+                    .and_then(get_language_impl)
+                    .is_some()
+            })
+            .filter_map(|file| {
+                let file = fs::canonicalize(file).ok()?;
+                file.strip_prefix(&canonical_root)
+                    .ok()
+                    .map(Path::to_path_buf)
+            })
+            .collect();
+        files.sort();
+        files.dedup();
+
+        let stamp = graph_stamp(&canonical_root, &file_list, &files);
+        let stored_path = persistent_cache_enabled()
+            .then(|| stored_graph_path(&canonical_root, allow_tests, custom_ignores));
+        if let Some(graph) = stored_path
+            .as_deref()
+            .and_then(|path| load_stored_graph(path, stamp, &canonical_root))
+        {
+            if debug_mode {
+                println!(
+                    "DEBUG: Reused stored import graph for {:?} in {:?}",
+                    canonical_root,
+                    start_time.elapsed()
+                );
+            }
+            return Ok(graph);
+        }
+
+        let imports: Vec<Vec<Import>> = files
+            .par_iter()
+            .map(|file| {
+                if cancel.is_cancelled() {
+                    return Vec::new();
+                }
+                file_imports(&canonical_root.join(file))
+            })
+            .collect();
+        if cancel.is_cancelled() {
+            bail!("Building the import graph for {:?} was cancelled", root);
+        }
+
+        let resolver = Resolver::new(&files);
+        let mut edges = HashSet::new();
+        for (from, file_imports) in imports.iter().enumerate() {
+            for import in file_imports {
+                for to in resolver.resolve(&files[from], import) {
+                    if to != from {
+                        edges.insert((from, to));
+                    }
+                }
+            }
+        }
+        let mut edges: Vec<(usize, usize)> = edges.into_iter().collect();
+        edges.sort();
+
+        let centrality = pagerank(files.len(), &edges);
+        let graph = Self::from_parts(canonical_root, files, edges, centrality);
+
+        if debug_mode {
+            println!(
+                "DEBUG: Built import graph for {:?}: {} files, {} imports in {:?}",
+                graph.root,
+                graph.files.len(),
+                graph.edges.len(),
+                start_time.elapsed()
+            );
+        }
+        if let Some(path) = &stored_path {
+            if let Err(e) = save_stored_graph(path, stamp, &graph) {
+                if debug_mode {
+                    println!("DEBUG: Failed to save import graph {:?}: {}", path, e);
+                }
+            }
+        }
+
+        Ok(graph)
+    }
+
+    fn from_parts(
+        root: PathBuf,
+        files: Vec<PathBuf>,
+        edges: Vec<(usize, usize)>,
+        centrality: Vec<f64>,
+    ) -> Self {
+        let max_centrality = centrality.iter().cloned().fold(0.0, f64::max);
+        let file_ids = files
+            .iter()
+            .enumerate()
+            .map(|(id, file)| (file.clone(), id))
+            .collect();
+        Self {
+            root,
+            files,
+            edges,
+            centrality,
+            file_ids,
+            max_centrality,
+        }
+    }
+
+    /// Centrality of a file relative to the most central file (1.0), or `None` if the
+    /// file is not part of the graph
+    pub fn relative_centrality(&self, file: &Path) -> Option<f64> {
+        let file = fs::canonicalize(file).ok()?;
+        let id = self.file_ids.get(file.strip_prefix(&self.root).ok()?)?;
+        Some(self.centrality[*id] / self.max_centrality)
+    }
+
+    /// The graph as JSON: the files, most central first, with their centrality and
+    /// number of imports in each direction, and the import edges between them
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut imports = vec![0; self.files.len()];
+        let mut imported_by = vec![0; self.files.len()];
+        for &(from, to) in &self.edges {
+            imports[from] += 1;
+            imported_by[to] += 1;
+        }
+
+        let mut nodes: Vec<JsonNode> = (0..self.files.len())
+            .map(|id| JsonNode {
+                file: self.files[id].to_string_lossy().to_string(),
+                centrality: self.centrality[id],
+                imports: imports[id],
+                imported_by: imported_by[id],
+            })
+            .collect();
+        nodes.sort_by(|a, b| b.centrality.total_cmp(&a.centrality));
+
+        let edges: Vec<JsonEdge> = self
+            .edges
+            .iter()
+            .map(|&(from, to)| JsonEdge {
+                from: self.files[from].to_str().unwrap_or_default(),
+                to: self.files[to].to_str().unwrap_or_default(),
+            })
+            .collect();
+
+        serde_json::json!({
+            "root": self.root,
+            "files": nodes,
+            "edges": edges,
+        })
+    }
+
+    /// The graph in Graphviz DOT format, with the centrality as a node attribute
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph imports {\n    rankdir=LR;\n");
+        for (id, file) in self.files.iter().enumerate() {
+            let _ = writeln!(
+                dot,
+                "    \"{}\" [centrality={:.6}];",
+                escape_dot(&file.to_string_lossy()),
+                self.centrality[id]
+            );
+        }
+        for &(from, to) in &self.edges {
+            let _ = writeln!(
+                dot,
+                "    \"{}\" -> \"{}\";",
+                escape_dot(&self.files[from].to_string_lossy()),
+                escape_dot(&self.files[to].to_string_lossy())
+            );
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// Identifies the files a graph is built from: the stamps of the directories walked to
+/// list them, which change when files are added or removed, and the modification time and
+/// size of each source file, which change when its imports may have
+fn graph_stamp(root: &Path, file_list: &FileList, files: &[PathBuf]) -> u64 {
+    let file_stamps: Vec<(u64, u64)> = files
+        .par_iter()
+        .map(|file| {
+            fs::metadata(root.join(file))
+                .map(|metadata| (file_mtime_nanos(&metadata), metadata.len()))
+                .unwrap_or_default()
+        })
+        .collect();
+
+    let mut hasher = DefaultHasher::new();
+    for dir in &file_list.dirs {
+        (&dir.path, dir.mtime, dir.ignore_mtime).hash(&mut hasher);
+    }
+    files.hash(&mut hasher);
+    file_stamps.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Get the path of the persisted graph for a root and listing options
+fn stored_graph_path(root: &Path, allow_tests: bool, custom_ignores: &[String]) -> PathBuf {
+    let key_string = format!(
+        "{}\0{}\0{}",
+        root.to_string_lossy(),
+        allow_tests,
+        custom_ignores.join("\0")
+    );
+    probe_cache_dir()
+        .join("import_graphs")
+        .join(format!("{}.json", hash_query(&key_string)))
+}
+
+/// Load a graph persisted by an earlier run, if it was built from the same files
+fn load_stored_graph(path: &Path, stamp: u64, root: &Path) -> Option<ImportGraph> {
+    let reader = BufReader::new(File::open(path).ok()?);
+    let stored: StoredGraph = serde_json::from_reader(reader).ok()?;
+    if stored.version != GRAPH_VERSION
+        || stored.probe_version != env!("CARGO_PKG_VERSION")
+        || stored.stamp != stamp
+        || stored.centrality.len() != stored.files.len()
+    {
+        return None;
+    }
+    Some(ImportGraph::from_parts(
+        root.to_path_buf(),
+        stored.files,
+        stored.edges,
+        stored.centrality,
+    ))
+}
+
+/// Persist a graph so later runs over unchanged files can skip building it
+fn save_stored_graph(path: &Path, stamp: u64, graph: &ImportGraph) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        create_dir_all(parent)?;
+    }
+
+    let stored = StoredGraph {
+        version: GRAPH_VERSION,
+        probe_version: env!("CARGO_PKG_VERSION").to_string(),
+        stamp,
+        files: graph.files.clone(),
+        edges: graph.edges.clone(),
+        centrality: graph.centrality.clone(),
+    };
+
+    // Write to a temporary file first so concurrent readers never see a partial graph
+    let tmp_path = path.with_extension(format!("{}.tmp", std::process::id()));
+    {
+        let mut writer = BufWriter::new(File::create(&tmp_path)?);
+        serde_json::to_writer(&mut writer, &stored)?;
+        writer.flush()?;
+    }
+    fs::rename(&tmp_path, path)?;
+
+    Ok(())
+}
+
+fn escape_dot(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Find the imports of a file, reusing the ones stored in the file cache if the file
+/// is unchanged
+fn file_imports(path: &Path) -> Vec<Import> {
+    let Some(language_impl) = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .and_then(get_language_impl)
+    else {
+        return Vec::new();
+    };
+    let Ok(content) = fs::read_to_string(path) else {
+        return Vec::new();
+    };
+
+    let mut file_cache = FileCache::load(path, &content);
+    let imports = file_cache.imports(|| parse_imports(language_impl.as_ref(), &content));
+    let _ = file_cache.save();
+    imports
+}
+
+fn parse_imports(language_impl: &dyn LanguageImpl, content: &str) -> Vec<Import> {
+    let mut parser = tree_sitter::Parser::new();
+    if parser
+        .set_language(&language_impl.get_tree_sitter_language())
+        .is_err()
+    {
+        return Vec::new();
+    }
+    let Some(tree) = parser.parse(content, None) else {
+        return Vec::new();
+    };
+
+    let mut imports = Vec::new();
+    let mut stack = vec![tree.root_node()];
+    while let Some(node) = stack.pop() {
+        let found = language_impl.find_imports(&node, content.as_bytes());
+        if found.is_empty() {
+            let mut cursor = node.walk();
+            stack.extend(node.children(&mut cursor));
+        } else {
+            imports.extend(found);
+        }
+    }
+    imports
+}
+
+/// A file or directory that module paths can refer to
+struct ModuleKey {
+    /// Path components, without the extension for files
+    segments: Vec<String>,
+    files: Vec<usize>,
+    is_file: bool,
+}
+
+/// Maps imports to the files they refer to
+struct Resolver<'a> {
+    files: &'a [PathBuf],
+    /// File ids by path without extension
+    by_stem: HashMap<PathBuf, Vec<usize>>,
+    /// File ids by exact path
+    by_path: HashMap<&'a Path, usize>,
+    /// Module keys (path components without extension) by their last component;
+    /// directory keys point at their module file or at all files in the directory
+    modules: HashMap<String, Vec<ModuleKey>>,
+}
+
+impl<'a> Resolver<'a> {
+    fn new(files: &'a [PathBuf]) -> Self {
+        let mut by_stem: HashMap<PathBuf, Vec<usize>> = HashMap::new();
+        let mut by_path = HashMap::new();
+        let mut dir_files: HashMap<Vec<String>, Vec<usize>> = HashMap::new();
+        let mut dir_modules: HashMap<Vec<String>, Vec<usize>> = HashMap::new();
+        let mut modules: HashMap<String, Vec<ModuleKey>> = HashMap::new();
+
+        for (id, file) in files.iter().enumerate() {
+            by_path.insert(file.as_path(), id);
+            by_stem.entry(file.with_extension("")).or_default().push(id);
+
+            let key = components(&file.with_extension(""));
+            let dir_key = key[..key.len() - 1].to_vec();
+            if DIRECTORY_MODULES.contains(&key[key.len() - 1].as_str()) {
+                dir_modules.entry(dir_key.clone()).or_default().push(id);
+            }
+            dir_files.entry(dir_key).or_default().push(id);
+            modules
+                .entry(key[key.len() - 1].clone())
+                .or_default()
+                .push(ModuleKey {
+                    segments: key,
+                    files: vec![id],
+                    is_file: true,
+                });
+        }
+
+        for (dir_key, ids) in dir_files {
+            let Some(last) = dir_key.last().cloned() else {
+                continue;
+            };
+            let ids = dir_modules.remove(&dir_key).unwrap_or(ids);
+            modules.entry(last).or_default().push(ModuleKey {
+                segments: dir_key,
+                files: ids,
+                is_file: false,
+            });
+        }
+
+        Self {
+            files,
+            by_stem,
+            by_path,
+            modules,
+        }
+    }
+
+    fn resolve(&self, importer: &Path, import: &Import) -> Vec<usize> {
+        match import {
+            Import::Path(path) => {
+                let base = importer.parent().unwrap_or(Path::new(""));
+                let relative = self.resolve_path(&normalize(&base.join(path)));
+                if !relative.is_empty() {
+                    return relative;
+                }
+                // Include paths: match the end of the path anywhere in the tree
+                let segments = components(&normalize(&Path::new(path).with_extension("")));
+                let ids = self.resolve_module(&segments, segments.len());
+                let extension = Path::new(path).extension();
+                ids.into_iter()
+                    .filter(|&id| extension.is_none() || self.files[id].extension() == extension)
+                    .collect()
+            }
+            Import::Module(segments) => self.resolve_module(segments, segments.len().min(2)),
+            Import::LocalModule(segments) => self.resolve_module(segments, 1),
+        }
+    }
+
+    /// A path relative to the root, with or without extension, or a directory module
+    fn resolve_path(&self, path: &Path) -> Vec<usize> {
+        if let Some(&id) = self.by_path.get(path) {
+            return vec![id];
+        }
+        if let Some(ids) = self.by_stem.get(path) {
+            return ids.clone();
+        }
+        DIRECTORY_MODULES
+            .iter()
+            .find_map(|module| self.by_stem.get(&path.join(module)))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// The longest leading part of the module path (at least `min_len` segments) that
+    /// matches the end of a file or directory path. Trailing segments that name items
+    /// inside the module are dropped this way.
+    fn resolve_module(&self, segments: &[String], min_len: usize) -> Vec<usize> {
+        for len in (min_len.max(1)..=segments.len()).rev() {
+            let prefix = &segments[..len];
+            let Some(candidates) = self.modules.get(&prefix[len - 1]) else {
+                continue;
+            };
+            for want_file in [true, false] {
+                let ids: Vec<usize> = candidates
+                    .iter()
+                    .filter(|key| key.is_file == want_file && key.segments.ends_with(prefix))
+                    .flat_map(|key| key.files.iter().copied())
+                    .collect();
+                if !ids.is_empty() {
+                    return ids;
+                }
+            }
+        }
+        Vec::new()
+    }
+}
+
+fn components(path: &Path) -> Vec<String> {
+    path.components()
+        .map(|component| component.as_os_str().to_string_lossy().to_string())
+        .collect()
+}
+
+/// Resolve `.` and `..` without touching the file system
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+/// PageRank over the import edges: files imported by many (central) files score high.
+/// Files without imports spread their rank evenly over all files.
+pub fn pagerank(n: usize, edges: &[(usize, usize)]) -> Vec<f64> {
+    if n == 0 {
+        return Vec::new();
+    }
+
+    let mut out_degree = vec![0usize; n];
+    for &(from, _) in edges {
+        out_degree[from] += 1;
+    }
+
+    let mut rank = vec![1.0 / n as f64; n];
+    for _ in 0..MAX_ITERATIONS {
+        let dangling: f64 = (0..n)
+            .filter(|&i| out_degree[i] == 0)
+            .map(|i| rank[i])
+            .sum();
+        let base = (1.0 - DAMPING) / n as f64 + DAMPING * dangling / n as f64;
+        let mut next = vec![base; n];
+        for &(from, to) in edges {
+            next[to] += DAMPING * rank[from] / out_degree[from] as f64;
+        }
+
+        let delta: f64 = next.iter().zip(&rank).map(|(a, b)| (a - b).abs()).sum();
+        rank = next;
+        if delta < TOLERANCE {
+            break;
+        }
+    }
+    rank
+}
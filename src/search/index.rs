@@ -0,0 +1,358 @@
+use anyhow::{Context, Result};
+use lazy_static::lazy_static;
+use rayon::prelude::*;
+use regex::{Regex, RegexSet};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, create_dir_all, File};
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::{Instant, SystemTime};
+
+use crate::search::cache::{file_mtime_nanos, hash_query, probe_cache_dir};
+use crate::search::file_list_cache;
+use crate::search::timeout::CancellationToken;
+use crate::search::tokenization;
+
+/// Version of the on-disk index format. Indexes written with another version are ignored.
+pub const INDEX_VERSION: u32 = 3;
+
+/// Files larger than this are not indexed (same limit as the regular file scan)
+const MAX_FILE_SIZE: u64 = 1024 * 1024;
+
+/// Lines longer than this are not indexed (same limit as the regular file scan)
+const MAX_LINE_LENGTH: usize = 2000;
+
+/// A single file recorded in the index
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexedFile {
+    /// Path relative to the index root
+    pub path: PathBuf,
+    /// Modification time in nanoseconds since the Unix epoch when the file was indexed
+    pub mtime: u64,
+    /// File size in bytes when the file was indexed
+    pub size: u64,
+    /// Number of lines in the file
+    pub lines: usize,
+    /// Number of tokens in the file (BM25 document length)
+    pub doc_length: usize,
+    /// Frequency of each stemmed token in the file
+    pub term_frequencies: HashMap<String, usize>,
+}
+
+/// The lines of one indexed file that contain a word
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Posting {
+    /// Position of the file in `SearchIndex::files`
+    pub file: usize,
+    /// 1-based line numbers
+    pub lines: Vec<usize>,
+}
+
+/// Persistent inverted index for a directory tree.
+///
+/// Postings are keyed by lowercased word (a run of alphanumeric characters and
+/// underscores), so any search pattern that can only match inside a word can be
+/// answered by matching it against the vocabulary instead of every line of every file.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchIndex {
+    pub version: u32,
+    /// Canonical root directory the index was built from
+    pub root: PathBuf,
+    pub files: Vec<IndexedFile>,
+    /// Fingerprint of the vocabulary the files were tokenized with (see
+    /// `tokenization::vocabulary_fingerprint`)
+    pub vocabulary: u64,
+    /// Word -> files and lines it appears on
+    pub postings: HashMap<String, Vec<Posting>>,
+    /// Relative path -> position in `files`
+    #[serde(skip)]
+    file_ids: HashMap<PathBuf, usize>,
+}
+
+// Indexes already loaded by this process, keyed by index file path
+lazy_static! {
+    static ref INDEX_CACHE: RwLock<HashMap<PathBuf, (SystemTime, Arc<SearchIndex>)>> =
+        RwLock::new(HashMap::new());
+}
+
+/// Get the path of the index file for a canonical root directory
+pub fn index_path(root: &Path) -> PathBuf {
+    probe_cache_dir()
+        .join("index")
+        .join(format!("{}.json", hash_query(&root.to_string_lossy())))
+}
+
+/// Check whether a character can be part of an indexed word
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Check whether every pattern can be answered from the index.
+///
+/// This holds when a pattern only contains word characters and grouping, so every
+/// match lies inside a single word, and it cannot match the empty string. A leading
+/// case-insensitivity flag is allowed since patterns are always matched that way.
+pub fn supports_patterns(patterns: &[(String, HashSet<usize>)]) -> bool {
+    patterns.iter().all(|(pattern, _)| {
+        let pattern = pattern.strip_prefix("(?i)").unwrap_or(pattern);
+        pattern
+            .chars()
+            .all(|c| is_word_char(c) || matches!(c, '(' | ')' | '|'))
+            && Regex::new(pattern).is_ok_and(|re| !re.is_match(""))
+    })
+}
+
+/// Read and index a single file. Returns `None` for files the regular scan would skip.
+fn index_file(root: &Path, file_path: &Path) -> Option<(IndexedFile, HashMap<String, Vec<usize>>)> {
+    let resolved_path = fs::canonicalize(file_path).ok()?;
+    let relative_path = resolved_path.strip_prefix(root).ok()?.to_path_buf();
+
+    let metadata = fs::metadata(&resolved_path).ok()?;
+    if metadata.len() > MAX_FILE_SIZE {
+        return None;
+    }
+    let content = fs::read_to_string(&resolved_path).ok()?;
+
+    let mut words: HashMap<String, Vec<usize>> = HashMap::new();
+    let mut lines = 0;
+    for (line_number, line) in content.lines().enumerate() {
+        lines += 1;
+        if line.len() > MAX_LINE_LENGTH {
+            continue;
+        }
+
+        let mut seen = HashSet::new();
+        for word in line.split(|c: char| !is_word_char(c)) {
+            if word.is_empty() {
+                continue;
+            }
+            let word = word.to_lowercase();
+            if seen.insert(word.clone()) {
+                words.entry(word).or_default().push(line_number + 1);
+            }
+        }
+    }
+
+    let tokens = tokenization::tokenize(&content);
+    let mut term_frequencies = HashMap::new();
+    for token in &tokens {
+        *term_frequencies.entry(token.clone()).or_insert(0) += 1;
+    }
+
+    Some((
+        IndexedFile {
+            path: relative_path,
+            mtime: file_mtime_nanos(&metadata),
+            size: metadata.len(),
+            lines,
+            doc_length: tokens.len(),
+            term_frequencies,
+        },
+        words,
+    ))
+}
+
+impl SearchIndex {
+    /// Build an index for every file under `root`, including test files.
+    /// Test and language filters are applied at search time.
+    pub fn build(root: &Path) -> Result<Self> {
+        let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
+        let start_time = Instant::now();
+
+        let canonical_root = fs::canonicalize(root)
+            .with_context(|| format!("Failed to resolve index root {:?}", root))?;
+        let file_list = file_list_cache::get_file_list(root, true, &[], &CancellationToken::new())?;
+
+        if debug_mode {
+            println!(
+                "DEBUG: Building search index for {:?} ({} files)",
+                canonical_root,
+                file_list.files.len()
+            );
+        }
+
+        let entries: Vec<_> = file_list
+            .files
+            .par_iter()
+            .filter_map(|file_path| index_file(&canonical_root, file_path))
+            .collect();
+
+        let mut files = Vec::with_capacity(entries.len());
+        let mut postings: HashMap<String, Vec<Posting>> = HashMap::new();
+        for (file, words) in entries {
+            let file_id = files.len();
+            for (word, lines) in words {
+                postings.entry(word).or_default().push(Posting {
+                    file: file_id,
+                    lines,
+                });
+            }
+            files.push(file);
+        }
+
+        let mut index = Self {
+            version: INDEX_VERSION,
+            root: canonical_root,
+            files,
+            vocabulary: tokenization::vocabulary_fingerprint(),
+            postings,
+            file_ids: HashMap::new(),
+        };
+        index.rebuild_file_ids();
+
+        if debug_mode {
+            println!(
+                "DEBUG: Built search index with {} files and {} words in {:?}",
+                index.files.len(),
+                index.postings.len(),
+                start_time.elapsed()
+            );
+        }
+
+        Ok(index)
+    }
+
+    /// Write the index to the probe cache directory and return the file it was written to
+    pub fn save(&self) -> Result<PathBuf> {
+        let path = index_path(&self.root);
+        if let Some(parent) = path.parent() {
+            create_dir_all(parent)?;
+        }
+
+        // Write to a temporary file first so readers never see a partial index
+        let tmp_path = path.with_extension("json.tmp");
+        {
+            let mut writer = BufWriter::new(File::create(&tmp_path)?);
+            serde_json::to_writer(&mut writer, self)?;
+            writer.flush()?;
+        }
+        fs::rename(&tmp_path, &path)?;
+
+        Ok(path)
+    }
+
+    /// Find the index covering `path`: the index of `path` itself or of its closest
+    /// indexed ancestor. Loaded indexes are kept in memory until their file changes.
+    pub fn load_for(path: &Path) -> Option<Arc<Self>> {
+        let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
+        let canonical_path = fs::canonicalize(path).ok()?;
+
+        let index_file = canonical_path
+            .ancestors()
+            .map(index_path)
+            .find(|candidate| candidate.is_file())?;
+        let modified = fs::metadata(&index_file).and_then(|m| m.modified()).ok()?;
+
+        {
+            let cache = INDEX_CACHE.read().unwrap();
+            if let Some((cached_modified, index)) = cache.get(&index_file) {
+                if *cached_modified == modified {
+                    return Some(Arc::clone(index));
+                }
+            }
+        }
+
+        let reader = BufReader::new(File::open(&index_file).ok()?);
+        let mut index: Self = match serde_json::from_reader(reader) {
+            Ok(index) => index,
+            Err(e) => {
+                if debug_mode {
+                    println!("DEBUG: Error parsing search index {:?}: {}", index_file, e);
+                }
+                return None;
+            }
+        };
+        if index.version != INDEX_VERSION {
+            if debug_mode {
+                println!(
+                    "DEBUG: Ignoring search index {:?} with version {} (expected {})",
+                    index_file, index.version, INDEX_VERSION
+                );
+            }
+            return None;
+        }
+        index.rebuild_file_ids();
+
+        if debug_mode {
+            println!(
+                "DEBUG: Loaded search index {:?} with {} files",
+                index_file,
+                index.files.len()
+            );
+        }
+
+        let index = Arc::new(index);
+        INDEX_CACHE
+            .write()
+            .unwrap()
+            .insert(index_file, (modified, Arc::clone(&index)));
+        Some(index)
+    }
+
+    fn rebuild_file_ids(&mut self) {
+        self.file_ids = self
+            .files
+            .iter()
+            .enumerate()
+            .map(|(id, file)| (file.path.clone(), id))
+            .collect();
+    }
+
+    /// Get the id of a file if it is indexed and has not changed since
+    pub fn fresh_file_id(&self, file_path: &Path) -> Option<usize> {
+        let resolved_path = fs::canonicalize(file_path).ok()?;
+        let relative_path = resolved_path.strip_prefix(&self.root).ok()?;
+        let id = *self.file_ids.get(relative_path)?;
+
+        let metadata = fs::metadata(&resolved_path).ok()?;
+        let file = &self.files[id];
+        (file.size == metadata.len() && file.mtime == file_mtime_nanos(&metadata)).then_some(id)
+    }
+
+    /// Match the search patterns against the vocabulary and collect, per file id,
+    /// the lines on which each term index matched
+    pub fn match_patterns(
+        &self,
+        regex_set: &RegexSet,
+        pattern_to_terms: &[HashSet<usize>],
+    ) -> HashMap<usize, HashMap<usize, HashSet<usize>>> {
+        let mut matches: HashMap<usize, HashMap<usize, HashSet<usize>>> = HashMap::new();
+
+        for (word, postings) in &self.postings {
+            let matched = regex_set.matches(word);
+            for pattern_idx in matched.iter() {
+                for &term_idx in &pattern_to_terms[pattern_idx] {
+                    for posting in postings {
+                        matches
+                            .entry(posting.file)
+                            .or_default()
+                            .entry(term_idx)
+                            .or_default()
+                            .extend(posting.lines.iter().copied());
+                    }
+                }
+            }
+        }
+
+        matches
+    }
+
+    /// Average number of tokens per indexed file
+    pub fn average_doc_length(&self) -> f64 {
+        if self.files.is_empty() {
+            return 0.0;
+        }
+        let total: usize = self.files.iter().map(|file| file.doc_length).sum();
+        total as f64 / self.files.len() as f64
+    }
+
+    /// Number of indexed files that contain `token`
+    pub fn document_frequency(&self, token: &str) -> usize {
+        self.files
+            .iter()
+            .filter(|file| file.term_frequencies.contains_key(token))
+            .count()
+    }
+}
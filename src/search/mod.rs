@@ -4,13 +4,22 @@ mod result_ranking;
 // Replace the old search_execution with new modules
 pub mod block_merging;
 pub mod cache; // New module for caching search results
+pub mod command_reranker; // External reranker programs (`--reranker cmd:<program>`)
+pub mod diversification; // Maximal marginal relevance re-ordering of ranked results
 pub mod elastic_query;
+mod file_cache; // Persistent per-file parse and token cache
 pub mod file_list_cache; // New module for caching file lists
+pub mod fuzzy; // Typo-tolerant matching of `term~N` terms
+pub mod git_history; // Recency and churn of files from the local git history
+pub mod import_graph; // File-level import graph and its PageRank centrality
+pub mod index; // Persistent on-disk search index
+mod search_events; // Progress events for streaming searches
 mod search_limiter;
 mod search_options;
 mod search_output;
 pub mod search_runner;
 pub mod search_tokens;
+pub mod synonyms; // Synonym and abbreviation expansion of query words
 pub mod term_exceptions; // New module for term exceptions
 pub mod timeout; // New module for timeout functionality
 pub mod tokenization; // New elastic search query parser
@@ -21,6 +30,14 @@ pub mod tokenization; // New elastic search query parser
 mod file_processing_tests;
 
 // Public exports
-pub use search_options::SearchOptions;
-pub use search_output::format_and_print_search_results;
-pub use search_runner::perform_probe;
+#[allow(unused_imports)]
+pub use result_ranking::{rank_search_results, rank_search_results_with};
+#[allow(unused_imports)]
+pub use search_events::{SearchEvent, SearchProgress};
+#[allow(unused_imports)]
+pub use search_options::{AsSearchOptions, RankingOptions, SearchOptions, SearchRequest};
+#[allow(unused_imports)]
+pub use search_output::{
+    format_and_print_search_results, format_search_event, format_search_results,
+};
+pub use search_runner::{perform_probe, perform_probe_with_events};
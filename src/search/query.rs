@@ -1,6 +1,7 @@
-use crate::search::elastic_query;
+use crate::search::elastic_query::{self, CompiledPattern, QueryField, TermPattern};
 // No term_exceptions import needed
 use std::collections::{HashMap, HashSet};
+use std::path::Path;
 use std::time::Instant;
 
 /// Escapes special regex characters in a string
@@ -32,6 +33,199 @@ pub struct QueryPlan {
     pub term_indices: HashMap<String, usize>,
     pub excluded_terms: HashSet<String>,
     pub exact: bool,
+    /// The wildcard, regex and fuzzy terms, compiled once for every file and block
+    term_patterns: HashMap<String, CompiledPattern>,
+    /// The `path:`, `lang:` and `type:` qualifiers, collected once for every file and block
+    field_filters: Vec<FieldFilter>,
+}
+
+/// A `path:`, `lang:` or `type:` qualifier collected from the query AST.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldFilter {
+    pub field: QueryField,
+    pub value: String,
+    pub excluded: bool,
+}
+
+impl QueryPlan {
+    /// Create a plan for a parsed query, compiling its pattern terms and collecting its
+    /// filters
+    pub fn new(
+        ast: elastic_query::Expr,
+        term_indices: HashMap<String, usize>,
+        excluded_terms: HashSet<String>,
+        exact: bool,
+    ) -> Self {
+        fn collect(expr: &elastic_query::Expr, out: &mut HashMap<String, CompiledPattern>) {
+            match expr {
+                elastic_query::Expr::Term {
+                    keywords,
+                    field,
+                    pattern: Some(pattern),
+                    ..
+                } => {
+                    for keyword in keywords {
+                        out.insert(
+                            QueryField::term_key(field.as_deref(), keyword),
+                            pattern.compile(keyword),
+                        );
+                    }
+                }
+                elastic_query::Expr::Term { .. } => {}
+                elastic_query::Expr::And(left, right) | elastic_query::Expr::Or(left, right) => {
+                    collect(left, out);
+                    collect(right, out);
+                }
+            }
+        }
+
+        let mut term_patterns = HashMap::new();
+        collect(&ast, &mut term_patterns);
+        let field_filters = collect_field_filters(&ast);
+        Self {
+            ast,
+            term_indices,
+            excluded_terms,
+            exact,
+            term_patterns,
+            field_filters,
+        }
+    }
+
+    /// All filter qualifiers in the query, wherever they appear in the AST, including groups
+    /// made only of filters (`(lang:rust OR lang:go)`).
+    ///
+    /// Filters on different fields must all hold, even when joined with OR; several values
+    /// for the same field are alternatives (`lang:rust lang:go` matches either), and excluded
+    /// values (`-path:vendor`) reject a match outright.
+    pub fn field_filters(&self) -> &[FieldFilter] {
+        &self.field_filters
+    }
+
+    /// Returns `true` if the query contains `comment:` or `code:` terms.
+    pub fn has_scoped_terms(&self) -> bool {
+        self.term_indices
+            .keys()
+            .any(|key| QueryField::split_key(key).0.is_some())
+    }
+
+    /// The wildcard, regex and fuzzy terms in the query, by term key.
+    pub fn term_patterns(&self) -> &HashMap<String, CompiledPattern> {
+        &self.term_patterns
+    }
+
+    /// Returns `true` if the query contains fuzzy (`term~N`) terms.
+    pub fn has_fuzzy_terms(&self) -> bool {
+        self.term_patterns
+            .values()
+            .any(|compiled| matches!(compiled.pattern, TermPattern::Fuzzy(_)))
+    }
+
+    /// Indices of the unscoped wildcard and regex terms that match somewhere in `code`.
+    /// Pattern terms can't be looked up among a block's tokens, so they are checked
+    /// against its text instead.
+    pub fn match_pattern_terms(&self, code: &str) -> HashSet<usize> {
+        self.term_patterns
+            .iter()
+            .filter(|(key, _)| QueryField::split_key(key).0.is_none())
+            .filter(|(_, pattern)| pattern.matches(code))
+            .filter_map(|(key, _)| self.term_indices.get(key).copied())
+            .collect()
+    }
+
+    /// Check a file against the query's `path:` and `lang:` filters.
+    pub fn matches_file(&self, path: &Path) -> bool {
+        let filters = self.field_filters();
+        filters_allow(filters, QueryField::Path, |value| {
+            path_matches_filter(path, value)
+        }) && filters_allow(filters, QueryField::Lang, |value| {
+            crate::search::file_list_cache::file_matches_language(path, value)
+        })
+    }
+
+    /// Check a block's node type against the query's `type:` filters.
+    /// A value matches any node type containing it, so `type:function` covers
+    /// `function_item`, `function_declaration`, and so on.
+    pub fn matches_node_type(&self, node_type: &str) -> bool {
+        let node_type = node_type.to_lowercase();
+        filters_allow(self.field_filters(), QueryField::Type, |value| {
+            node_type.contains(&value.to_lowercase())
+        })
+    }
+}
+
+/// The `path:`, `lang:` and `type:` qualifiers in `ast`
+fn collect_field_filters(ast: &elastic_query::Expr) -> Vec<FieldFilter> {
+    fn collect(expr: &elastic_query::Expr, out: &mut Vec<FieldFilter>) {
+        match expr {
+            elastic_query::Expr::Term {
+                keywords, excluded, ..
+            } => {
+                if let Some(field) = expr.query_field().filter(|f| f.is_filter()) {
+                    out.extend(keywords.iter().map(|value| FieldFilter {
+                        field,
+                        value: value.clone(),
+                        excluded: *excluded,
+                    }));
+                }
+            }
+            elastic_query::Expr::And(left, right) | elastic_query::Expr::Or(left, right) => {
+                collect(left, out);
+                collect(right, out);
+            }
+        }
+    }
+
+    let mut filters = Vec::new();
+    collect(ast, &mut filters);
+    filters
+}
+
+/// Apply the filters for one field: at least one included value must match (if any are
+/// given) and no excluded value may match.
+fn filters_allow(
+    filters: &[FieldFilter],
+    field: QueryField,
+    matches: impl Fn(&str) -> bool,
+) -> bool {
+    let mut has_includes = false;
+    let mut included = false;
+    for filter in filters.iter().filter(|f| f.field == field) {
+        let hit = matches(&filter.value);
+        if filter.excluded {
+            if hit {
+                return false;
+            }
+        } else {
+            has_includes = true;
+            included |= hit;
+        }
+    }
+    !has_includes || included
+}
+
+/// Match a `path:` value against a file path. Values containing glob characters are matched
+/// as a glob against the tail of the path; anything else is a case-insensitive substring.
+fn path_matches_filter(path: &Path, value: &str) -> bool {
+    let path_str = path.to_string_lossy().replace('\\', "/");
+
+    if value.contains(['*', '?', '[']) {
+        let options = glob::MatchOptions {
+            case_sensitive: false,
+            require_literal_separator: false,
+            require_literal_leading_dot: false,
+        };
+        let anchored = if value.starts_with('/') || value.starts_with('*') {
+            value.to_string()
+        } else {
+            format!("*/{}", value)
+        };
+        [value, anchored.as_str()].iter().any(|pattern| {
+            glob::Pattern::new(pattern).is_ok_and(|p| p.matches_with(&path_str, options))
+        })
+    } else {
+        path_str.to_lowercase().contains(&value.to_lowercase())
+    }
 }
 
 /// Helper function to format duration in a human-readable way
@@ -133,12 +327,7 @@ pub fn create_query_plan(query: &str, exact: bool) -> Result<QueryPlan, elastic_
         );
     }
 
-    Ok(QueryPlan {
-        ast,
-        term_indices,
-        excluded_terms,
-        exact,
-    })
+    Ok(QueryPlan::new(ast, term_indices, excluded_terms, exact))
 }
 
 /// Recursively update the AST to mark all terms as exact
@@ -182,13 +371,26 @@ fn collect_all_terms(
     }
 
     match expr {
+        // Filters don't match text, so they get no term index
+        elastic_query::Expr::Term { .. } if expr.is_filter_term() => {
+            if debug_mode {
+                println!("DEBUG: Skipping filter term '{}'", expr);
+            }
+        }
         elastic_query::Expr::Term {
             keywords,
-            field: _,
+            field,
             excluded: is_excluded,
             exact: _,
+            proximity,
             ..
         } => {
+            // Field-scoped keywords (comment:/code:) are stored under their own key
+            let keywords: Vec<String> = keywords
+                .iter()
+                .map(|kw| QueryField::term_key(field.as_deref(), kw))
+                .collect();
+
             // Add all keywords to all_terms
             all_terms.extend(keywords.clone());
 
@@ -199,8 +401,10 @@ fn collect_all_terms(
                 );
             }
 
-            if *is_excluded {
-                for keyword in keywords {
+            // An excluded proximity term only rules out its keywords occurring close
+            // together, so they may still be matched on their own
+            if *is_excluded && proximity.is_none() {
+                for keyword in &keywords {
                     if debug_mode {
                         println!("DEBUG: Adding '{}' to excluded terms set", keyword);
                     }
@@ -218,18 +422,22 @@ fn collect_all_terms(
             // Check if the right side is an excluded term
             if let elastic_query::Expr::Term {
                 keywords,
+                field,
                 excluded: true,
+                proximity: None,
                 ..
             } = &**right
             {
-                for keyword in keywords {
-                    if debug_mode {
-                        println!(
-                            "DEBUG: Adding excluded term '{}' from AND expression",
-                            keyword
-                        );
+                if !right.is_filter_term() {
+                    for keyword in keywords {
+                        if debug_mode {
+                            println!(
+                                "DEBUG: Adding excluded term '{}' from AND expression",
+                                keyword
+                            );
+                        }
+                        excluded.insert(QueryField::term_key(field.as_deref(), keyword));
                     }
-                    excluded.insert(keyword.clone());
                 }
             }
 
@@ -280,6 +488,40 @@ pub fn build_combined_pattern(terms: &[String]) -> String {
     pattern
 }
 
+/// Patterns for the fuzzy terms of the query: each matches the words of `vocabulary` within
+/// the term's edit distance, so the file search finds `receive` for `recieve~1`.
+pub fn create_fuzzy_patterns(
+    plan: &QueryPlan,
+    vocabulary: &HashSet<String>,
+) -> Vec<(String, HashSet<usize>)> {
+    let mut patterns = Vec::new();
+    for (key, compiled) in plan.term_patterns() {
+        let TermPattern::Fuzzy(distance) = compiled.pattern else {
+            continue;
+        };
+        if plan.excluded_terms.contains(key) {
+            continue;
+        }
+        let Some(&idx) = plan.term_indices.get(key) else {
+            continue;
+        };
+        let candidates =
+            crate::search::fuzzy::candidates(QueryField::split_key(key).1, distance, vocabulary);
+        if candidates.is_empty() {
+            continue;
+        }
+        let alternatives = candidates
+            .iter()
+            .map(|candidate| regex_escape(candidate))
+            .collect::<Vec<_>>();
+        patterns.push((
+            format!("(?i)({})", alternatives.join("|")),
+            HashSet::from([idx]),
+        ));
+    }
+    patterns
+}
+
 /// Generate regex patterns that respect the AST's logical structure.
 /// This creates a single combined pattern for all terms, regardless of whether they're
 /// required, optional, or negative.
@@ -300,6 +542,8 @@ pub fn create_structured_patterns(plan: &QueryPlan) -> Vec<(String, HashSet<usiz
         println!("DEBUG: Excluded terms: {:?}", plan.excluded_terms);
     }
 
+    let term_patterns = plan.term_patterns();
+
     // Extract all non-excluded terms from the query plan
     let terms: Vec<String> = plan
         .term_indices
@@ -309,7 +553,25 @@ pub fn create_structured_patterns(plan: &QueryPlan) -> Vec<(String, HashSet<usiz
         .collect();
 
     if !terms.is_empty() {
-        let combined_pattern = build_combined_pattern(&terms);
+        // Field-scoped keys (e.g. `comment:foo`) are searched for by their bare keyword
+        let keywords: Vec<String> = terms
+            .iter()
+            .map(|term| QueryField::split_key(term).1.to_string())
+            .collect();
+        let combined_pattern = if term_patterns.is_empty() {
+            build_combined_pattern(&keywords)
+        } else {
+            // Wildcard and regex terms are searched for by their own pattern
+            let alternatives = terms
+                .iter()
+                .zip(&keywords)
+                .map(|(term, keyword)| match term_patterns.get(term) {
+                    Some(compiled) => format!("(?:{})", compiled.pattern.line_pattern(keyword)),
+                    None => regex_escape(keyword),
+                })
+                .collect::<Vec<_>>();
+            format!("(?i)({})", alternatives.join("|"))
+        };
 
         // Create a HashSet with indices of non-excluded terms
         let all_indices: HashSet<usize> = terms
@@ -343,11 +605,17 @@ pub fn create_structured_patterns(plan: &QueryPlan) -> Vec<(String, HashSet<usiz
         match expr {
             elastic_query::Expr::Term {
                 keywords,
-                field: _,
+                field,
                 excluded,
                 exact,
+                pattern,
                 ..
             } => {
+                // Filters are applied to paths and blocks, not searched for in content
+                if expr.is_filter_term() {
+                    return;
+                }
+
                 // Skip pattern generation for excluded terms
                 if *excluded {
                     if debug_mode {
@@ -361,8 +629,9 @@ pub fn create_structured_patterns(plan: &QueryPlan) -> Vec<(String, HashSet<usiz
 
                 // Process each keyword
                 for keyword in keywords {
+                    let key = QueryField::term_key(field.as_deref(), keyword);
                     // ADDED: Check against the global exclusion list first
-                    if plan.excluded_terms.contains(keyword) {
+                    if plan.excluded_terms.contains(&key) {
                         if debug_mode {
                             println!(
                                     "DEBUG: Skipping pattern generation for globally excluded keyword: '{}'",
@@ -375,51 +644,66 @@ pub fn create_structured_patterns(plan: &QueryPlan) -> Vec<(String, HashSet<usiz
                     // No need for an additional check here for `*excluded` as the outer check handles it.
 
                     // Find the keyword's index in term_indices
-                    if let Some(&idx) = plan.term_indices.get(keyword) {
-                        let base_pattern = regex_escape(keyword);
-
-                        // For exact terms, use stricter matching
-                        let pattern = if *exact {
-                            base_pattern.to_string()
-                        } else {
-                            format!("({})", base_pattern)
-                        };
+                    let Some(&idx) = plan.term_indices.get(&key) else {
+                        continue;
+                    };
 
+                    // Wildcard and regex terms are matched as written, without tokenization
+                    if let Some(term_pattern) = pattern {
+                        let pattern = format!("({})", term_pattern.line_pattern(keyword));
                         if debug_mode {
                             println!(
-                                "DEBUG: Created pattern for keyword '{}': '{}'",
-                                keyword, pattern
+                                "DEBUG: Created pattern for {:?} term '{}': '{}'",
+                                term_pattern, keyword, pattern
                             );
                         }
-
                         results.push((pattern, HashSet::from([idx])));
+                        continue;
+                    }
 
-                        // Only tokenize if not exact
-                        if !*exact {
-                            // Generate patterns for each token of the term to match AST tokenization
-                            let tokens = crate::search::tokenization::tokenize_and_stem(keyword);
+                    let base_pattern = regex_escape(keyword);
 
-                            if debug_mode && tokens.len() > 1 {
-                                println!("DEBUG: Term '{}' tokenized into: {:?}", keyword, tokens);
-                            }
+                    // For exact terms, use stricter matching
+                    let pattern = if *exact {
+                        base_pattern.to_string()
+                    } else {
+                        format!("({})", base_pattern)
+                    };
+
+                    if debug_mode {
+                        println!(
+                            "DEBUG: Created pattern for keyword '{}': '{}'",
+                            keyword, pattern
+                        );
+                    }
+
+                    results.push((pattern, HashSet::from([idx])));
 
-                            // Generate a pattern for each token with the same term index
-                            for token in tokens {
-                                let token_pattern = regex_escape(&token);
-                                let pattern = format!("({})", token_pattern);
+                    // Only tokenize if not exact
+                    if !*exact {
+                        // Generate patterns for each token of the term to match AST tokenization
+                        let tokens = crate::search::tokenization::tokenize_and_stem(keyword);
 
-                                if debug_mode {
-                                    println!(
-                                            "DEBUG: Created pattern for token '{}' from term '{}': '{}'",
-                                            token, keyword, pattern
-                                        );
-                                }
+                        if debug_mode && tokens.len() > 1 {
+                            println!("DEBUG: Term '{}' tokenized into: {:?}", keyword, tokens);
+                        }
 
-                                results.push((pattern, HashSet::from([idx])));
+                        // Generate a pattern for each token with the same term index
+                        for token in tokens {
+                            let token_pattern = regex_escape(&token);
+                            let pattern = format!("({})", token_pattern);
+
+                            if debug_mode {
+                                println!(
+                                    "DEBUG: Created pattern for token '{}' from term '{}': '{}'",
+                                    token, keyword, pattern
+                                );
                             }
-                        } else if debug_mode {
-                            println!("DEBUG: Skipping tokenization for exact term '{}'", keyword);
+
+                            results.push((pattern, HashSet::from([idx])));
                         }
+                    } else if debug_mode {
+                        println!("DEBUG: Skipping tokenization for exact term '{}'", keyword);
                     }
                 }
             }
@@ -502,9 +786,10 @@ pub fn create_structured_patterns(plan: &QueryPlan) -> Vec<(String, HashSet<usiz
     let mut compound_patterns = Vec::new();
 
     // Process all terms from the term_indices map
-    for (keyword, &idx) in &plan.term_indices {
+    for (key, &idx) in &plan.term_indices {
+        let keyword = QueryField::split_key(key).1;
         // Check if the original keyword itself is excluded before processing for compound parts
-        if plan.excluded_terms.contains(keyword) {
+        if plan.excluded_terms.contains(key) {
             if debug_mode {
                 println!(
                     "DEBUG: Skipping compound processing for excluded keyword: '{}'",
@@ -513,6 +798,10 @@ pub fn create_structured_patterns(plan: &QueryPlan) -> Vec<(String, HashSet<usiz
             }
             continue; // Skip this keyword entirely
         }
+        // Wildcard and regex terms already have their own pattern
+        if term_patterns.contains_key(key) {
+            continue;
+        }
 
         // Process compound words - either camelCase or those in the vocabulary
         // Skip compound word processing if exact search is enabled
@@ -523,7 +812,7 @@ pub fn create_structured_patterns(plan: &QueryPlan) -> Vec<(String, HashSet<usiz
                 // Not a camelCase word, check if it's in vocabulary
                 crate::search::tokenization::split_compound_word(
                     keyword,
-                    crate::search::tokenization::load_vocabulary(),
+                    &crate::search::tokenization::load_vocabulary(),
                 )
             } else {
                 camel_parts
@@ -1,5 +1,11 @@
-use crate::models::SearchResult;
-use crate::ranking;
+use crate::models::{RankingExplanation, SearchResult};
+use crate::ranking::{self, Reranker};
+use crate::search::command_reranker::{CommandReranker, COMMAND_RERANKER_PREFIX};
+use crate::search::git_history::GitHistories;
+use crate::search::search_options::RankingOptions;
+use crate::search::timeout::CancellationToken;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
 use std::time::Instant;
 
 /// Helper function to format duration in a human-readable way
@@ -11,25 +17,99 @@ fn format_duration(duration: std::time::Duration) -> String {
     }
 }
 
-/// Function to rank search results based on query relevance using BM25 algorithm
-pub fn rank_search_results(results: &mut [SearchResult], queries: &[String], reranker: &str) {
+/// Function to rank search results based on query relevance using the named reranker
+/// (see `ranking::RERANKERS`) or external program (`cmd:<program>`), with the BM25
+/// parameters of `ranking.bm25`; unknown names fall back to BM25.
+/// `queries` are the queries as the user wrote them, which external programs get and
+/// explanations show; the results are scored with `weighted_queries`, the same queries with
+/// their synonyms and fuzzy terms expanded.
+/// If the search was already cancelled, the partial results are only ordered by how
+/// many query terms each block matched, skipping the scoring.
+pub fn rank_search_results(
+    results: &mut [SearchResult],
+    queries: &[String],
+    weighted_queries: &[String],
+    reranker: &str,
+    ranking: &RankingOptions,
+    cancel: &CancellationToken,
+) {
+    // An external program is killed when the search times out or is cancelled
+    let command_reranker = reranker
+        .strip_prefix(COMMAND_RERANKER_PREFIX)
+        .map(|command| {
+            Box::new(
+                CommandReranker::new(command)
+                    .bm25(ranking.bm25)
+                    .cancel(cancel.clone())
+                    .query(&queries.join(" ")),
+            ) as Box<dyn Reranker>
+        });
+    let reranker = command_reranker
+        .or_else(|| ranking::reranker_by_name_with(reranker, ranking.bm25))
+        .unwrap_or_else(|| {
+            if std::env::var("DEBUG").unwrap_or_default() == "1" {
+                println!("DEBUG: Unknown reranker '{}', using bm25", reranker);
+            }
+            Box::new(ranking::Bm25Reranker {
+                params: ranking.bm25,
+            })
+        });
+    rank_search_results_with(
+        results,
+        queries,
+        weighted_queries,
+        reranker.as_ref(),
+        ranking,
+        cancel,
+    );
+}
+
+/// Rank search results with a given reranker. Every ranked result gets `score`, `rank` and
+/// `combined_score_rank`, plus the score and rank fields the reranker records.
+/// Blocks whose declared name is made up of query terms have their score multiplied by
+/// `ranking.definition_boost`, so definitions outrank blocks that only mention them.
+/// With `ranking.recency_half_life_days` set, blocks in files that changed recently or
+/// often in git are boosted as well. With `ranking.explain` set, every ranked result gets
+/// an `explanation` of its score. `queries` and `weighted_queries` are as for
+/// `rank_search_results`.
+pub fn rank_search_results_with(
+    results: &mut [SearchResult],
+    queries: &[String],
+    weighted_queries: &[String],
+    reranker: &dyn Reranker,
+    ranking: &RankingOptions,
+    cancel: &CancellationToken,
+) {
     let start_time = Instant::now();
 
     // Check if debug mode is enabled
     let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
 
+    if cancel.is_cancelled() {
+        if debug_mode {
+            println!(
+                "DEBUG: Search was cancelled, ordering {} results by matched terms",
+                results.len()
+            );
+        }
+        rank_by_matched_terms(results);
+        return;
+    }
+
     if debug_mode {
         println!(
             "DEBUG: Starting result ranking with {} results",
             results.len()
         );
-        println!("DEBUG: Using reranker: {}", reranker);
+        println!("DEBUG: Using reranker: {}", reranker.name());
         println!("DEBUG: Queries: {:?}", queries);
+        println!("DEBUG: Weighted queries: {:?}", weighted_queries);
     }
 
     // Combine all queries into a single string for ranking
     let query_combine_start = Instant::now();
-    let combined_query = queries.join(" ");
+    let combined_query = weighted_queries.join(" ");
+    let written_query = queries.join(" ");
     let query_combine_duration = query_combine_start.elapsed();
 
     if debug_mode {
@@ -40,94 +120,45 @@ pub fn rank_search_results(results: &mut [SearchResult], queries: &[String], rer
         );
     }
 
-    // Extract document texts for ranking, including filename in each document
-    let document_extraction_start = Instant::now();
-    // This ensures filename terms are considered in the ranking algorithms
-    let documents: Vec<String> = results
-        .iter()
-        .map(|r| format!("// Filename: {}\n{}", r.file, r.code))
-        .collect();
-    let documents_refs: Vec<&str> = documents.iter().map(|s| s.as_str()).collect();
-    let document_extraction_duration = document_extraction_start.elapsed();
-
-    if debug_mode {
-        println!(
-            "DEBUG: Document extraction completed in {} - Extracted {} documents",
-            format_duration(document_extraction_duration),
-            documents.len()
-        );
-    }
-
-    // Rank the documents
-    let metrics_extraction_start = Instant::now();
-    // Get metrics from the first result (assuming they're the same for all results in this set)
-    let file_unique_terms = results.first().and_then(|r| r.file_unique_terms);
-    let file_total_matches = results.first().and_then(|r| r.file_total_matches);
-    let block_unique_terms = results.first().and_then(|r| r.block_unique_terms);
-    let block_total_matches = results.first().and_then(|r| r.block_total_matches);
-    let metrics_extraction_duration = metrics_extraction_start.elapsed();
-
-    if debug_mode {
-        println!(
-            "DEBUG: Metrics extraction completed in {}",
-            format_duration(metrics_extraction_duration)
-        );
-        println!(
-            "DEBUG: Extracted metrics - file_unique_terms: {:?}, file_total_matches: {:?}, block_unique_terms: {:?}, block_total_matches: {:?}",
-            file_unique_terms, file_total_matches, block_unique_terms, block_total_matches
-        );
-    }
-
-    // Extract pre-tokenized content if available
-    let tokenized_extraction_start = Instant::now();
-    let pre_tokenized: Vec<Vec<String>> = results
-        .iter()
-        .filter_map(|r| r.tokenized_content.clone())
-        .collect();
-
-    let has_tokenized = !pre_tokenized.is_empty() && pre_tokenized.len() == results.len();
-
-    if debug_mode {
-        if has_tokenized {
-            println!(
-                "DEBUG: Using pre-tokenized content from {} results",
-                pre_tokenized.len()
-            );
-        } else {
-            println!(
-                "DEBUG: Pre-tokenized content not available for all results (found {}/{}), falling back to tokenization",
-                pre_tokenized.len(),
-                results.len()
-            );
-        }
-    }
-
-    let tokenized_extraction_duration = tokenized_extraction_start.elapsed();
-
+    let document_ranking_start = Instant::now();
     if debug_mode {
         println!(
-            "DEBUG: Tokenized content extraction completed in {}",
-            format_duration(tokenized_extraction_duration)
+            "DEBUG: Starting document ranking with the {} reranker...",
+            reranker.name()
         );
     }
 
-    let ranking_params = ranking::RankingParams {
-        documents: &documents_refs,
-        query: &combined_query,
-        pre_tokenized: if has_tokenized {
-            Some(&pre_tokenized)
-        } else {
-            None
-        },
+    // Score the documents; results the query excludes get no score
+    let mut scored = reranker.score(&combined_query, results);
+    let reranker_scores: HashMap<usize, f64> = if ranking.explain {
+        scored.iter().copied().collect()
+    } else {
+        HashMap::new()
     };
-
-    let document_ranking_start = Instant::now();
-    if debug_mode {
-        println!("DEBUG: Starting document ranking...");
+    let boosts = definition_boosts(
+        results,
+        &combined_query,
+        ranking.definition_boost,
+        &mut scored,
+    );
+    let recency = ranking
+        .recency_half_life_days
+        .map(|half_life_days| recency_boosts(results, half_life_days, &mut scored))
+        .unwrap_or_default();
+    if ranking.centrality_weight > 0.0 {
+        for (index, score) in scored.iter_mut() {
+            if let Some(centrality) = results.get(*index).and_then(|r| r.centrality) {
+                *score *= 1.0 + ranking.centrality_weight * centrality;
+            }
+        }
     }
 
-    // Get ranked indices from the ranking module (BM25 scores)
-    let ranked_indices = ranking::rank_documents(&ranking_params);
+    // Sort in descending order by score, with a stable secondary sort by document index
+    scored.sort_by(|a, b| {
+        b.1.partial_cmp(&a.1)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then(a.0.cmp(&b.0))
+    });
 
     let document_ranking_duration = document_ranking_start.elapsed();
 
@@ -135,23 +166,54 @@ pub fn rank_search_results(results: &mut [SearchResult], queries: &[String], rer
         println!(
             "DEBUG: Document ranking completed in {} - Ranked {} documents",
             format_duration(document_ranking_duration),
-            ranked_indices.len()
+            scored.len()
         );
     }
 
-    // Update scores for all results returned by the ranking module
-    // We don't filter by BM25 score here because the ranking module already does some filtering
-    // based on the query, and we want to preserve OR query behavior
+    let bm25_explanations = if ranking.explain {
+        ranking::Bm25Reranker {
+            params: ranking.bm25,
+        }
+        .explain(&combined_query, results)
+    } else {
+        Vec::new()
+    };
+
+    // Update scores and ranks for all results returned by the reranker
+    // We don't filter by score here because the reranker already applies the query's
+    // required and excluded terms, and we want to preserve OR query behavior
     let filtering_start = Instant::now();
     let mut updated_results = Vec::new();
 
-    // Update scores for all results
-    for (rank_index, (original_index, bm25_score)) in ranked_indices.iter().enumerate() {
+    for (rank_index, (original_index, score)) in scored.iter().enumerate() {
         if let Some(result) = results.get(*original_index) {
             let mut result_clone = result.clone();
-            result_clone.rank = Some(rank_index + 1); // 1-based rank
-            result_clone.score = Some(*bm25_score);
-            result_clone.bm25_score = Some(*bm25_score);
+            let rank = rank_index + 1; // 1-based rank
+            reranker.record(&mut result_clone, *score, rank);
+            result_clone.rank = Some(rank);
+            result_clone.score = Some(*score);
+            result_clone.combined_score_rank = Some(rank);
+            result_clone.definition_boost = boosts
+                .contains(original_index)
+                .then_some(ranking.definition_boost);
+            result_clone.recency_boost = recency.get(original_index).copied();
+            if ranking.explain {
+                result_clone.explanation = Some(RankingExplanation {
+                    query: written_query.clone(),
+                    reranker: reranker.name().to_string(),
+                    reranker_score: reranker_scores[original_index],
+                    bm25: bm25_explanations.get(*original_index).cloned().flatten(),
+                    definition_boost: result_clone.definition_boost,
+                    recency_boost: result_clone.recency_boost,
+                    centrality_boost: result_clone
+                        .centrality
+                        .filter(|_| ranking.centrality_weight > 0.0)
+                        .map(|centrality| 1.0 + ranking.centrality_weight * centrality),
+                    score: *score,
+                    rank,
+                    merged_blocks: Vec::new(),
+                });
+            }
             updated_results.push(result_clone);
         }
     }
@@ -165,40 +227,6 @@ pub fn rank_search_results(results: &mut [SearchResult], queries: &[String], rer
         );
     }
 
-    // Sort updated results by BM25 score in descending order
-    let reranker_sort_start = Instant::now();
-
-    if debug_mode {
-        println!("DEBUG: Using BM25 ranking (Okapi BM25 algorithm)");
-    } else {
-        println!("Using BM25 ranking (Okapi BM25 algorithm)");
-    }
-
-    // Sort by BM25 score in descending order
-    updated_results.sort_by(|a, b| {
-        let score_a = a.bm25_score.unwrap_or(0.0);
-        let score_b = b.bm25_score.unwrap_or(0.0);
-        // Sort in descending order (higher score is better)
-        score_b
-            .partial_cmp(&score_a)
-            .unwrap_or(std::cmp::Ordering::Equal)
-    });
-
-    // Reassign ranks based on the sorted order
-    for (rank, result) in updated_results.iter_mut().enumerate() {
-        result.bm25_rank = Some(rank + 1); // 1-based rank
-        result.rank = Some(rank + 1);
-    }
-
-    let reranker_sort_duration = reranker_sort_start.elapsed();
-
-    if debug_mode {
-        println!(
-            "DEBUG: Reranker-specific sorting completed in {}",
-            format_duration(reranker_sort_duration)
-        );
-    }
-
     // Replace original results with updated results
     if updated_len < results.len() {
         // If we have fewer results than the original array, copy only what we have
@@ -241,3 +269,102 @@ pub fn rank_search_results(results: &mut [SearchResult], queries: &[String], rer
         );
     }
 }
+
+/// Multiply the score of every result whose block declares a name made up entirely of
+/// query terms (e.g. `struct SessionCache` for the query `SessionCache`) by `boost`.
+/// Returns the indices of the boosted results.
+fn definition_boosts(
+    results: &[SearchResult],
+    query: &str,
+    boost: f64,
+    scored: &mut [(usize, f64)],
+) -> HashSet<usize> {
+    let mut boosted = HashSet::new();
+    if boost == 1.0 {
+        return boosted;
+    }
+    let Ok(expr) = crate::search::elastic_query::parse_query(query, false) else {
+        return boosted;
+    };
+    let query_terms = ranking::extract_query_terms(&expr);
+
+    for (index, score) in scored.iter_mut() {
+        let Some(name) = results.get(*index).and_then(|r| r.symbol_name.as_deref()) else {
+            continue;
+        };
+        let name_tokens = ranking::tokenize(name);
+        if !name_tokens.is_empty() && name_tokens.iter().all(|t| query_terms.contains(t)) {
+            *score *= boost;
+            boosted.insert(*index);
+        }
+    }
+
+    if std::env::var("DEBUG").unwrap_or_default() == "1" && !boosted.is_empty() {
+        println!(
+            "DEBUG: Applied definition boost {} to {} results",
+            boost,
+            boosted.len()
+        );
+    }
+
+    boosted
+}
+
+/// Multiply the score of every result by `1 + ln(1 + activity)`, where activity is the
+/// number of commits that touched its file, each decayed by its age with the given
+/// half-life. Results outside a git repository keep their score.
+/// Returns the boost of each result that has git history.
+fn recency_boosts(
+    results: &[SearchResult],
+    half_life_days: f64,
+    scored: &mut [(usize, f64)],
+) -> HashMap<usize, f64> {
+    let mut histories = GitHistories::new(half_life_days);
+    let mut file_boosts: HashMap<&str, Option<f64>> = HashMap::new();
+    let mut boosts = HashMap::new();
+
+    for (index, score) in scored.iter_mut() {
+        let Some(result) = results.get(*index) else {
+            continue;
+        };
+        let boost = *file_boosts.entry(&result.file).or_insert_with(|| {
+            histories
+                .activity(Path::new(&result.file))
+                .map(|activity| 1.0 + activity.ln_1p())
+        });
+        if let Some(boost) = boost {
+            *score *= boost;
+            boosts.insert(*index, boost);
+        }
+    }
+
+    if std::env::var("DEBUG").unwrap_or_default() == "1" {
+        println!(
+            "DEBUG: Applied recency boost (half-life {} days) to {} results",
+            half_life_days,
+            boosts.len()
+        );
+    }
+
+    boosts
+}
+
+/// Cheap fallback ranking: blocks matching more unique query terms first, then blocks
+/// with more matches, keeping the original order for ties
+fn rank_by_matched_terms(results: &mut [SearchResult]) {
+    results.sort_by(|a, b| {
+        let key_a = (
+            a.block_unique_terms.unwrap_or(0),
+            a.block_total_matches.unwrap_or(0),
+        );
+        let key_b = (
+            b.block_unique_terms.unwrap_or(0),
+            b.block_total_matches.unwrap_or(0),
+        );
+        key_b.cmp(&key_a)
+    });
+
+    for (rank, result) in results.iter_mut().enumerate() {
+        result.rank = Some(rank + 1); // 1-based rank
+    }
+}
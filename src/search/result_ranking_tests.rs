@@ -12,57 +12,36 @@ mod tests {
                 lines: (1, 10),
                 node_type: "context".to_string(), // Changed to context for testing context boost
                 code: "fn test_function() { println!(\"This is a test function with search terms\"); }".to_string(),
-                matched_by_filename: None,
-                rank: None,
-                score: None,
-                tfidf_score: None,
-                bm25_score: None,
-                tfidf_rank: None,
-                bm25_rank: None,
-                new_score: None,
                 file_unique_terms: Some(2), // "search", "terms"
                 file_total_matches: Some(2),
                 file_match_rank: Some(2),
                 block_unique_terms: Some(2),
                 block_total_matches: Some(2),
+                ..Default::default()
             },
             SearchResult {
                 file: "file2.rs".to_string(),
                 lines: (1, 5),
                 node_type: "function".to_string(),
                 code: "fn another_function() { // This doesn't have the key term }".to_string(),
-                matched_by_filename: None,
-                rank: None,
-                score: None,
-                tfidf_score: None,
-                bm25_score: None,
-                tfidf_rank: None,
-                bm25_rank: None,
-                new_score: None,
                 file_unique_terms: Some(1), // No search terms
                 file_total_matches: Some(1),
                 file_match_rank: Some(3),
                 block_unique_terms: Some(0),
                 block_total_matches: Some(0),
+                ..Default::default()
             },
             SearchResult {
                 file: "file3.rs".to_string(),
                 lines: (1, 10),
                 node_type: "function".to_string(),
                 code: "fn search_function() { // This has search in the function name and multiple search terms search search }".to_string(),
-                matched_by_filename: None,
-                rank: None,
-                score: None,
-                tfidf_score: None,
-                bm25_score: None,
-                tfidf_rank: None,
-                bm25_rank: None,
-                new_score: None,
                 file_unique_terms: Some(3), // "search" appears multiple times
                 file_total_matches: Some(4),
                 file_match_rank: Some(1),
                 block_unique_terms: Some(1),
                 block_total_matches: Some(3),
+                ..Default::default()
             },
         ]
     }
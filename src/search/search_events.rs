@@ -0,0 +1,34 @@
+use crate::models::{LimitedSearchResults, SearchResult};
+
+/// Counters describing how far a search has progressed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SearchProgress {
+    /// Files whose content was searched (or looked up in the index)
+    pub files_scanned: usize,
+    /// Files that matched the query and will be processed into code blocks
+    pub files_matched: usize,
+    /// Matched files processed so far
+    pub files_processed: usize,
+    /// Code blocks extracted so far, before ranking and limits
+    pub blocks_extracted: usize,
+}
+
+/// Something that happened while a search ran, reported through the callback passed
+/// to `perform_probe_with_events`.
+///
+/// Events arrive in order: `Progress` after each root is scanned and once matching
+/// files are known, `FileResults` for every processed file, then a single `Ranked`.
+#[derive(Debug)]
+pub enum SearchEvent<'a> {
+    /// The search moved on to a new stage
+    Progress(SearchProgress),
+    /// Candidate results extracted from one file. They are not ranked yet and may be
+    /// cut by limits, merged or reordered in the final batch.
+    FileResults {
+        file: &'a str,
+        results: &'a [SearchResult],
+        progress: SearchProgress,
+    },
+    /// The final ranked, limited and merged results, as `perform_probe` returns them
+    Ranked(&'a LimitedSearchResults),
+}
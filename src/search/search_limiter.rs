@@ -14,6 +14,9 @@ pub fn apply_limits(
             skipped_files: Vec::new(),
             limits_applied: None,
             cached_blocks_skipped: None,
+            timed_out: false,
+            session_id: None,
+            session_generated: false,
         };
     }
 
@@ -41,7 +44,9 @@ pub fn apply_limits(
 
         if would_exceed_results || would_exceed_bytes || would_exceed_tokens {
             if r.rank.is_some()
-                && (r.tfidf_score.unwrap_or(0.0) > 0.0 || r.bm25_score.unwrap_or(0.0) > 0.0)
+                && (r.tfidf_score.unwrap_or(0.0) > 0.0
+                    || r.bm25_score.unwrap_or(0.0) > 0.0
+                    || r.new_score.unwrap_or(0.0) > 0.0)
             {
                 skipped.push(r);
             }
@@ -63,5 +68,8 @@ pub fn apply_limits(
             total_tokens,
         }),
         cached_blocks_skipped: None,
+        timed_out: false,
+        session_id: None,
+        session_generated: false,
     }
 }
@@ -1,8 +1,17 @@
-use std::path::Path;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::ranking::Bm25Params;
+use crate::search::elastic_query;
+use crate::search::query::{create_query_plan, QueryPlan};
+use crate::search::synonyms::SynonymDictionary;
+use crate::search::tokenization::{self, VocabularyOverrides};
 
 /// Options for performing a search
+#[derive(Debug, Clone, Copy)]
 pub struct SearchOptions<'a> {
-    pub path: &'a Path,
+    /// Root paths to search; results from all roots are merged and ranked together
+    pub paths: &'a [PathBuf],
     pub queries: &'a [String],
     pub files_only: bool,
     pub custom_ignores: &'a [String],
@@ -11,6 +20,14 @@ pub struct SearchOptions<'a> {
     #[allow(dead_code)]
     pub frequency_search: bool,
     pub exact: bool,
+    /// Make every plain query word of at least `fuzzy::MIN_FUZZY_LENGTH` characters a fuzzy
+    /// term matching words up to this many edits away (ignored with `exact`)
+    pub fuzzy: Option<usize>,
+    /// Expand query words into their synonyms and abbreviations (see `synonyms`)
+    pub synonyms: Option<&'a SynonymDictionary>,
+    /// Project-specific stop words, exception terms, compound words and stem language to
+    /// tokenize queries and code with during this search
+    pub vocabulary: Option<&'a VocabularyOverrides>,
     pub language: Option<&'a str>,
     pub max_results: Option<usize>,
     pub max_bytes: Option<usize>,
@@ -18,7 +35,395 @@ pub struct SearchOptions<'a> {
     pub allow_tests: bool,
     pub no_merge: bool,
     pub merge_threshold: Option<usize>,
+    #[allow(dead_code)]
     pub dry_run: bool,
     pub session: Option<&'a str>,
     pub timeout: u64,
+    pub ranking: RankingOptions,
+}
+
+/// Knobs that adjust how results are scored on top of the chosen reranker
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RankingOptions {
+    /// Score multiplier for blocks whose declared name (function, struct, class, ...)
+    /// is made up of query terms; 1.0 disables it
+    pub definition_boost: f64,
+    /// When set, blocks in files that changed recently or often in git are boosted.
+    /// Each commit's weight halves after this many days.
+    pub recency_half_life_days: Option<f64>,
+    /// Weight (0 to 1) of the penalty for blocks similar to better-ranked ones; 0 keeps
+    /// the ranked order
+    pub diversity: f64,
+    /// Keep at most this many results from any one file
+    pub max_per_file: Option<usize>,
+    /// Weight of the import-graph centrality prior: scores are multiplied by
+    /// `1 + weight * centrality`, so core modules outrank leaf files on ties; 0 disables it
+    pub centrality_weight: f64,
+    /// Attach an explanation of its score to every ranked result
+    pub explain: bool,
+    /// k1, b and filename weight of the rerankers that use BM25
+    pub bm25: Bm25Params,
+}
+
+impl RankingOptions {
+    /// Check that the options are in range
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(half_life_days) = self.recency_half_life_days {
+            if !(half_life_days > 0.0 && half_life_days.is_finite()) {
+                return Err(format!(
+                    "Recency half-life must be more than 0 days, got {}",
+                    half_life_days
+                ));
+            }
+        }
+        if !(0.0..=1.0).contains(&self.diversity) {
+            return Err(format!(
+                "Diversity must be between 0 and 1, got {}",
+                self.diversity
+            ));
+        }
+        if self.max_per_file == Some(0) {
+            return Err("Results per file must be 1 or more, got 0".to_string());
+        }
+        if !(self.centrality_weight >= 0.0 && self.centrality_weight.is_finite()) {
+            return Err(format!(
+                "Centrality weight must be 0 or more, got {}",
+                self.centrality_weight
+            ));
+        }
+        self.bm25.validate()
+    }
+}
+
+impl Default for RankingOptions {
+    fn default() -> Self {
+        Self {
+            definition_boost: 1.5,
+            recency_half_life_days: None,
+            diversity: 0.0,
+            max_per_file: None,
+            centrality_weight: 0.0,
+            explain: false,
+            bm25: Bm25Params::default(),
+        }
+    }
+}
+
+/// Anything `perform_probe` can run: the borrowed `SearchOptions` or an owned `SearchRequest`
+pub trait AsSearchOptions {
+    fn as_search_options(&self) -> SearchOptions<'_>;
+}
+
+impl SearchOptions<'_> {
+    /// The queries as searched for and ranked: with their words expanded into synonyms,
+    /// and then made fuzzy for `fuzzy` (unless the search is exact)
+    pub fn preprocessed_queries(&self) -> Vec<String> {
+        self.queries
+            .iter()
+            .map(|query| {
+                let query = match self.synonyms {
+                    Some(dictionary) if !self.exact => dictionary.expand_query(query),
+                    _ => query.clone(),
+                };
+                match self.fuzzy {
+                    Some(distance) if !self.exact => elastic_query::with_fuzzy(&query, distance),
+                    _ => query,
+                }
+            })
+            .collect()
+    }
+
+    /// The plan of all queries as searched for, combined with AND and parsed with this
+    /// search's vocabulary, for highlighting and the JSON and XML output
+    pub fn query_plan(&self) -> Option<QueryPlan> {
+        let queries = self.preprocessed_queries();
+        if queries.is_empty() {
+            return None;
+        }
+        let vocabulary = self.vocabulary.cloned().unwrap_or_default();
+        tokenization::with_vocabulary(&vocabulary, || {
+            create_query_plan(&queries.join(" AND "), false).ok()
+        })
+    }
+}
+
+impl AsSearchOptions for SearchOptions<'_> {
+    fn as_search_options(&self) -> SearchOptions<'_> {
+        *self
+    }
+}
+
+/// Owned counterpart of `SearchOptions` that can be stored, sent across threads and
+/// (de)serialized. Defaults match the CLI's flags; missing fields take their default when
+/// deserializing, except that searching needs at least one query in `queries`. It has no
+/// synonyms or project vocabulary until `config::configure_search` applies the project
+/// configuration, as the CLI and servers do.
+///
+/// ```no_run
+/// use probe::search::{perform_probe, SearchRequest};
+///
+/// let request = SearchRequest::new("rank results")
+///     .path("./src")
+///     .max_results(10)
+///     .allow_tests(true);
+/// let results = perform_probe(&request).unwrap();
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+#[allow(dead_code)]
+pub struct SearchRequest {
+    pub paths: Vec<PathBuf>,
+    pub queries: Vec<String>,
+    pub files_only: bool,
+    pub custom_ignores: Vec<String>,
+    pub exclude_filenames: bool,
+    pub reranker: String,
+    pub frequency_search: bool,
+    pub exact: bool,
+    pub fuzzy: Option<usize>,
+    pub synonyms: Option<SynonymDictionary>,
+    pub vocabulary: Option<VocabularyOverrides>,
+    pub language: Option<String>,
+    pub max_results: Option<usize>,
+    pub max_bytes: Option<usize>,
+    pub max_tokens: Option<usize>,
+    pub allow_tests: bool,
+    pub no_merge: bool,
+    pub merge_threshold: Option<usize>,
+    pub dry_run: bool,
+    pub session: Option<String>,
+    pub timeout: u64,
+    pub ranking: RankingOptions,
+}
+
+impl Default for SearchRequest {
+    fn default() -> Self {
+        Self {
+            paths: vec![PathBuf::from(".")],
+            queries: Vec::new(),
+            files_only: false,
+            custom_ignores: Vec::new(),
+            exclude_filenames: false,
+            reranker: "bm25".to_string(),
+            frequency_search: true,
+            exact: false,
+            fuzzy: None,
+            synonyms: None,
+            vocabulary: None,
+            language: None,
+            max_results: None,
+            max_bytes: None,
+            max_tokens: None,
+            allow_tests: false,
+            no_merge: false,
+            merge_threshold: None,
+            dry_run: false,
+            session: None,
+            timeout: 30,
+            ranking: RankingOptions::default(),
+        }
+    }
+}
+
+#[allow(dead_code)]
+impl SearchRequest {
+    /// Create a request for `query` in the current directory with the CLI defaults
+    pub fn new(query: impl Into<String>) -> Self {
+        Self {
+            queries: vec![query.into()],
+            ..Self::default()
+        }
+    }
+
+    /// Replace the searched roots with a single path
+    pub fn path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.paths = vec![path.into()];
+        self
+    }
+
+    /// Replace the searched roots; results from all roots are ranked together
+    pub fn paths<P: Into<PathBuf>>(mut self, paths: impl IntoIterator<Item = P>) -> Self {
+        self.paths = paths.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Add another query; all queries are searched together
+    pub fn query(mut self, query: impl Into<String>) -> Self {
+        self.queries.push(query.into());
+        self
+    }
+
+    pub fn files_only(mut self, files_only: bool) -> Self {
+        self.files_only = files_only;
+        self
+    }
+
+    /// Add a pattern to ignore in addition to .gitignore and common patterns
+    pub fn ignore(mut self, pattern: impl Into<String>) -> Self {
+        self.custom_ignores.push(pattern.into());
+        self
+    }
+
+    pub fn exclude_filenames(mut self, exclude_filenames: bool) -> Self {
+        self.exclude_filenames = exclude_filenames;
+        self
+    }
+
+    pub fn reranker(mut self, reranker: impl Into<String>) -> Self {
+        self.reranker = reranker.into();
+        self
+    }
+
+    pub fn frequency_search(mut self, frequency_search: bool) -> Self {
+        self.frequency_search = frequency_search;
+        self
+    }
+
+    pub fn exact(mut self, exact: bool) -> Self {
+        self.exact = exact;
+        self
+    }
+
+    /// Match every plain query word up to `distance` edits away (`term~N` for all words)
+    pub fn fuzzy(mut self, distance: usize) -> Self {
+        self.fuzzy = Some(distance);
+        self
+    }
+
+    /// Expand query words into their synonyms from `dictionary`, e.g.
+    /// `SynonymDictionary::builtin().clone()`
+    pub fn synonyms(mut self, dictionary: SynonymDictionary) -> Self {
+        self.synonyms = Some(dictionary);
+        self
+    }
+
+    /// Tokenize the queries and code of this search with project-specific word lists and
+    /// stem language on top of the built-in ones
+    pub fn vocabulary(mut self, vocabulary: VocabularyOverrides) -> Self {
+        self.vocabulary = Some(vocabulary);
+        self
+    }
+
+    pub fn language(mut self, language: impl Into<String>) -> Self {
+        self.language = Some(language.into());
+        self
+    }
+
+    pub fn max_results(mut self, max_results: usize) -> Self {
+        self.max_results = Some(max_results);
+        self
+    }
+
+    pub fn max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
+    pub fn max_tokens(mut self, max_tokens: usize) -> Self {
+        self.max_tokens = Some(max_tokens);
+        self
+    }
+
+    pub fn allow_tests(mut self, allow_tests: bool) -> Self {
+        self.allow_tests = allow_tests;
+        self
+    }
+
+    pub fn no_merge(mut self, no_merge: bool) -> Self {
+        self.no_merge = no_merge;
+        self
+    }
+
+    pub fn merge_threshold(mut self, merge_threshold: usize) -> Self {
+        self.merge_threshold = Some(merge_threshold);
+        self
+    }
+
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    pub fn session(mut self, session: impl Into<String>) -> Self {
+        self.session = Some(session.into());
+        self
+    }
+
+    /// Timeout in seconds; partial results are returned when it is reached
+    pub fn timeout(mut self, timeout: u64) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Score multiplier for blocks that define a searched symbol
+    pub fn definition_boost(mut self, definition_boost: f64) -> Self {
+        self.ranking.definition_boost = definition_boost;
+        self
+    }
+
+    /// Boost blocks in files that changed recently or often in git; a commit's weight
+    /// halves every `half_life_days`
+    pub fn boost_recent(mut self, half_life_days: f64) -> Self {
+        self.ranking.recency_half_life_days = Some(half_life_days);
+        self
+    }
+
+    /// Push down blocks that are similar to better-ranked ones (0 to 1)
+    pub fn diversity(mut self, diversity: f64) -> Self {
+        self.ranking.diversity = diversity;
+        self
+    }
+
+    pub fn max_per_file(mut self, max_per_file: usize) -> Self {
+        self.ranking.max_per_file = Some(max_per_file);
+        self
+    }
+
+    /// Boost blocks in files that many other files import, directly or indirectly
+    pub fn centrality_weight(mut self, weight: f64) -> Self {
+        self.ranking.centrality_weight = weight;
+        self
+    }
+
+    /// Attach an explanation of its score to every result
+    pub fn explain(mut self, explain: bool) -> Self {
+        self.ranking.explain = explain;
+        self
+    }
+
+    /// Set the BM25 parameters (k1, b and filename weight)
+    pub fn bm25(mut self, bm25: Bm25Params) -> Self {
+        self.ranking.bm25 = bm25;
+        self
+    }
+}
+
+impl AsSearchOptions for SearchRequest {
+    fn as_search_options(&self) -> SearchOptions<'_> {
+        SearchOptions {
+            paths: &self.paths,
+            queries: &self.queries,
+            files_only: self.files_only,
+            custom_ignores: &self.custom_ignores,
+            exclude_filenames: self.exclude_filenames,
+            reranker: &self.reranker,
+            frequency_search: self.frequency_search,
+            exact: self.exact,
+            fuzzy: self.fuzzy,
+            synonyms: self.synonyms.as_ref(),
+            vocabulary: self.vocabulary.as_ref(),
+            language: self.language.as_deref(),
+            max_results: self.max_results,
+            max_bytes: self.max_bytes,
+            max_tokens: self.max_tokens,
+            allow_tests: self.allow_tests,
+            no_merge: self.no_merge,
+            merge_threshold: self.merge_threshold,
+            dry_run: self.dry_run,
+            session: self.session.as_deref(),
+            timeout: self.timeout,
+            ranking: self.ranking,
+        }
+    }
 }
@@ -1,8 +1,11 @@
 use anyhow::Result;
+use std::fmt::Write;
 use std::path::Path;
 
-use crate::models::SearchResult;
+use crate::models::{RankingExplanation, SearchResult};
+use crate::search::elastic_query::Expr;
 use crate::search::query::QueryPlan;
+use crate::search::search_events::{SearchEvent, SearchProgress};
 use crate::search::search_tokens::count_tokens;
 
 /// Function to format and print search results according to the specified format
@@ -12,6 +15,34 @@ pub fn format_and_print_search_results(
     format: &str,
     query_plan: Option<&QueryPlan>,
 ) {
+    print!(
+        "{}",
+        format_search_results(results, dry_run, format, query_plan)
+    );
+}
+
+/// Function to format search results according to the specified format, returning the
+/// text `format_and_print_search_results` would print
+pub fn format_search_results(
+    results: &[SearchResult],
+    dry_run: bool,
+    format: &str,
+    query_plan: Option<&QueryPlan>,
+) -> String {
+    let mut out = String::new();
+    if let Err(e) = write_search_results(&mut out, results, dry_run, format, query_plan) {
+        eprintln!("Error formatting {}: {}", format.to_uppercase(), e);
+    }
+    out
+}
+
+fn write_search_results(
+    out: &mut String,
+    results: &[SearchResult],
+    dry_run: bool,
+    format: &str,
+    query_plan: Option<&QueryPlan>,
+) -> Result<()> {
     let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
 
     // Count valid results (with non-empty file names)
@@ -26,19 +57,13 @@ pub fn format_and_print_search_results(
     // Handle different output formats
     match format {
         "color" if use_color => {
-            format_and_print_color_results(&valid_results, dry_run, query_plan, debug_mode);
+            format_color_results(out, &valid_results, dry_run, query_plan, debug_mode)?;
         }
         "json" => {
-            if let Err(e) = format_and_print_json_results(&valid_results) {
-                eprintln!("Error formatting JSON: {}", e);
-            }
-            return; // Skip the summary output at the end
+            return format_json_results(out, &valid_results, query_plan); // Skip the summary output at the end
         }
         "xml" => {
-            if let Err(e) = format_and_print_xml_results(&valid_results) {
-                eprintln!("Error formatting XML: {}", e);
-            }
-            return; // Skip the summary output at the end
+            return format_xml_results(out, &valid_results, query_plan); // Skip the summary output at the end
         }
         _ => {
             // Default format (terminal)
@@ -53,134 +78,144 @@ pub fn format_and_print_search_results(
                 if dry_run {
                     // In dry-run mode, only print file names and line numbers
                     if is_full_file {
-                        println!("File: {}", result.file);
+                        writeln!(out, "File: {}", result.file)?;
                     } else {
-                        println!(
+                        writeln!(
+                            out,
                             "File: {}, Lines: {}-{}",
                             result.file, result.lines.0, result.lines.1
-                        );
+                        )?;
                     }
                 } else {
                     // Normal mode with full content
                     if is_full_file {
-                        println!("File: {}", result.file);
-                        println!("```{}", extension);
-                        println!("{}", result.code);
-                        println!("```");
+                        writeln!(out, "File: {}", result.file)?;
+                        if let Some(explanation) = &result.explanation {
+                            write_explanation(out, explanation, false)?;
+                        }
+                        writeln!(out, "```{}", extension)?;
+                        writeln!(out, "{}", result.code)?;
+                        writeln!(out, "```")?;
                     } else {
-                        println!("File: {}", result.file);
-                        println!("Lines: {}-{}", result.lines.0, result.lines.1);
-                        println!("```{}", extension);
-                        println!("{}", result.code);
-                        println!("```");
+                        writeln!(out, "File: {}", result.file)?;
+                        writeln!(out, "Lines: {}-{}", result.lines.0, result.lines.1)?;
+                        if let Some(explanation) = &result.explanation {
+                            write_explanation(out, explanation, false)?;
+                        }
+                        writeln!(out, "```{}", extension)?;
+                        writeln!(out, "{}", result.code)?;
+                        writeln!(out, "```")?;
                     }
                 }
                 if debug_mode {
                     if let Some(rank) = result.rank {
                         // Add a display order field to show the actual ordering of results
-                        println!(
+                        writeln!(
+                            out,
                             "Display Order: {}",
                             results
                                 .iter()
                                 .position(|r| r.file == result.file && r.lines == result.lines)
                                 .unwrap_or(0)
                                 + 1
-                        );
+                        )?;
 
-                        println!("Rank: {}", rank);
+                        writeln!(out, "Rank: {}", rank)?;
 
                         if let Some(score) = result.score {
-                            println!("Combined Score: {:.4}", score);
+                            writeln!(out, "Combined Score: {:.4}", score)?;
                         }
 
                         // Display the combined score rank if available, otherwise calculate it
                         if let Some(combined_rank) = result.combined_score_rank {
-                            println!("Combined Score Rank: {}", combined_rank);
+                            writeln!(out, "Combined Score Rank: {}", combined_rank)?;
                         } else {
                             // Fall back to the old behavior if the field isn't set
-                            println!("Combined Score Rank: {}", rank);
+                            writeln!(out, "Combined Score Rank: {}", rank)?;
                         }
 
                         if let Some(tfidf_score) = result.tfidf_score {
-                            println!("TF-IDF Score: {:.4}", tfidf_score);
+                            writeln!(out, "TF-IDF Score: {:.4}", tfidf_score)?;
                         }
 
                         if let Some(tfidf_rank) = result.tfidf_rank {
-                            println!("TF-IDF Rank: {}", tfidf_rank);
+                            writeln!(out, "TF-IDF Rank: {}", tfidf_rank)?;
                         }
 
                         if let Some(bm25_score) = result.bm25_score {
-                            println!("BM25 Score: {:.4}", bm25_score);
+                            writeln!(out, "BM25 Score: {:.4}", bm25_score)?;
                         }
 
                         if let Some(bm25_rank) = result.bm25_rank {
-                            println!("BM25 Rank: {}", bm25_rank);
+                            writeln!(out, "BM25 Rank: {}", bm25_rank)?;
                         }
 
                         // Display Hybrid 2 score and rank with more prominence
                         if let Some(new_score) = result.new_score {
-                            println!("Hybrid 2 Score: {:.4}", new_score);
+                            writeln!(out, "Hybrid 2 Score: {:.4}", new_score)?;
                         }
 
                         if let Some(hybrid2_rank) = result.hybrid2_rank {
-                            println!("Hybrid 2 Rank: {}", hybrid2_rank);
+                            writeln!(out, "Hybrid 2 Rank: {}", hybrid2_rank)?;
                         } else if result.new_score.is_some() {
-                            println!("Hybrid 2 Rank: N/A");
+                            writeln!(out, "Hybrid 2 Rank: N/A")?;
                         }
 
                         if let Some(file_unique_terms) = result.file_unique_terms {
-                            println!("File Unique Terms: {}", file_unique_terms);
+                            writeln!(out, "File Unique Terms: {}", file_unique_terms)?;
                         }
 
                         if let Some(file_total_matches) = result.file_total_matches {
-                            println!("File Total Matches: {}", file_total_matches);
+                            writeln!(out, "File Total Matches: {}", file_total_matches)?;
                         }
 
                         if let Some(file_match_rank) = result.file_match_rank {
-                            println!("File Match Rank: {}", file_match_rank);
+                            writeln!(out, "File Match Rank: {}", file_match_rank)?;
                         }
 
                         if let Some(block_unique_terms) = result.block_unique_terms {
-                            println!("Block Unique Terms: {}", block_unique_terms);
+                            writeln!(out, "Block Unique Terms: {}", block_unique_terms)?;
                         }
 
                         if let Some(block_total_matches) = result.block_total_matches {
-                            println!("Block Total Matches: {}", block_total_matches);
+                            writeln!(out, "Block Total Matches: {}", block_total_matches)?;
                         }
 
-                        println!("Type: {}", result.node_type);
+                        writeln!(out, "Type: {}", result.node_type)?;
                     }
                 }
             }
         }
     }
 
-    println!("Found {} search results", valid_results.len());
+    writeln!(out, "Found {} search results", valid_results.len())?;
 
     let total_bytes: usize = valid_results.iter().map(|r| r.code.len()).sum();
     let total_tokens: usize = valid_results.iter().map(|r| count_tokens(&r.code)).sum();
-    println!("Total bytes returned: {}", total_bytes);
-    println!("Total tokens returned: {}", total_tokens);
+    writeln!(out, "Total bytes returned: {}", total_bytes)?;
+    writeln!(out, "Total tokens returned: {}", total_tokens)?;
+    Ok(())
 }
 
-/// Format and print search results with color highlighting for matching words
-fn format_and_print_color_results(
+/// Format search results with color highlighting for matching words
+fn format_color_results(
+    out: &mut String,
     results: &[&SearchResult],
     dry_run: bool,
     query_plan: Option<&QueryPlan>,
     debug_mode: bool,
-) {
+) -> Result<()> {
     use colored::*;
     use regex::Regex;
 
     if results.is_empty() {
-        println!("No results found.");
-        return;
+        writeln!(out, "No results found.")?;
+        return Ok(());
     }
 
     // Print a header with the number of results
-    println!("{}", format!("Found {} results", results.len()).bold());
-    println!();
+    writeln!(out, "{}", format!("Found {} results", results.len()).bold())?;
+    writeln!(out)?;
 
     // Print the results
     for (index, result) in results.iter().enumerate() {
@@ -195,41 +230,48 @@ fn format_and_print_color_results(
         let is_full_file = result.node_type == "file";
 
         // Print result number
-        println!(
+        writeln!(
+            out,
             "{} {}",
             "Result".bold().blue(),
             format!("#{}", index + 1).bold().blue()
-        );
+        )?;
 
         // Print the file path and node info with color
         if is_full_file {
-            println!("{} {}", "File:".bold().green(), result.file.yellow());
+            writeln!(out, "{} {}", "File:".bold().green(), result.file.yellow())?;
         } else {
-            println!(
+            writeln!(
+                out,
                 "{} {} ({})",
                 "File:".bold().green(),
                 result.file.yellow(),
                 result.node_type.cyan()
-            );
-            println!(
+            )?;
+            writeln!(
+                out,
                 "{} {}-{}",
                 "Lines:".bold().green(),
                 result.lines.0,
                 result.lines.1
-            );
+            )?;
+        }
+
+        if let Some(explanation) = &result.explanation {
+            write_explanation(out, explanation, true)?;
         }
 
         // Print additional debug information if in debug mode
         if debug_mode {
             // Print the same debug info that would be shown in standard mode
             if let Some(keywords) = &result.matched_keywords {
-                println!("{} {:?}", "Matched Keywords:".bold().green(), keywords);
+                writeln!(out, "{} {:?}", "Matched Keywords:".bold().green(), keywords)?;
             }
             if let Some(score) = result.score {
-                println!("{} {:.4}", "Score:".bold().green(), score);
+                writeln!(out, "{} {:.4}", "Score:".bold().green(), score)?;
             }
             if let Some(query_plan) = query_plan {
-                println!("{} {:?}", "Query Plan:".bold().green(), query_plan);
+                writeln!(out, "{} {:?}", "Query Plan:".bold().green(), query_plan)?;
             }
         }
 
@@ -271,13 +313,13 @@ fn format_and_print_color_results(
             _ => "",
         };
 
-        println!("{}", "Code:".bold().magenta());
+        writeln!(out, "{}", "Code:".bold().magenta())?;
 
         // Print the code with syntax highlighting
         if !language.is_empty() {
-            println!("{}", format!("```{}", language).cyan());
+            writeln!(out, "{}", format!("```{}", language).cyan())?;
         } else {
-            println!("{}", "```".cyan());
+            writeln!(out, "{}", "```".cyan())?;
         }
 
         // Generate patterns from the matched keywords in the search result
@@ -363,98 +405,172 @@ fn format_and_print_color_results(
 
             // Print the line (highlighted or original if no matches)
             if !merged_matches.is_empty() {
-                println!("{}", output_line);
+                writeln!(out, "{}", output_line)?;
             } else {
-                println!("{}", line);
+                writeln!(out, "{}", line)?;
             }
         }
 
-        println!();
+        writeln!(out)?;
 
         // Print a separator between results
         if index < results.len() - 1 {
-            println!();
-            println!("{}", "─".repeat(50).cyan());
-            println!();
+            writeln!(out)?;
+            writeln!(out, "{}", "─".repeat(50).cyan())?;
+            writeln!(out)?;
         }
 
         if debug_mode {
             if let Some(rank) = result.rank {
                 // Add a display order field to show the actual ordering of results
-                println!(
+                writeln!(
+                    out,
                     "Display Order: {}",
                     results
                         .iter()
                         .position(|r| r.file == result.file && r.lines == result.lines)
                         .unwrap_or(0)
                         + 1
-                );
+                )?;
 
-                println!("Rank: {}", rank);
+                writeln!(out, "Rank: {}", rank)?;
 
                 if let Some(score) = result.score {
-                    println!("Combined Score: {:.4}", score);
+                    writeln!(out, "Combined Score: {:.4}", score)?;
                 }
 
                 // Display the combined score rank if available, otherwise calculate it
                 if let Some(combined_rank) = result.combined_score_rank {
-                    println!("Combined Score Rank: {}", combined_rank);
+                    writeln!(out, "Combined Score Rank: {}", combined_rank)?;
                 } else {
                     // Fall back to the old behavior if the field isn't set
-                    println!("Combined Score Rank: {}", rank);
+                    writeln!(out, "Combined Score Rank: {}", rank)?;
                 }
 
                 if let Some(tfidf_score) = result.tfidf_score {
-                    println!("TF-IDF Score: {:.4}", tfidf_score);
+                    writeln!(out, "TF-IDF Score: {:.4}", tfidf_score)?;
                 }
 
                 if let Some(tfidf_rank) = result.tfidf_rank {
-                    println!("TF-IDF Rank: {}", tfidf_rank);
+                    writeln!(out, "TF-IDF Rank: {}", tfidf_rank)?;
                 }
 
                 if let Some(bm25_score) = result.bm25_score {
-                    println!("BM25 Score: {:.4}", bm25_score);
+                    writeln!(out, "BM25 Score: {:.4}", bm25_score)?;
                 }
 
                 if let Some(bm25_rank) = result.bm25_rank {
-                    println!("BM25 Rank: {}", bm25_rank);
+                    writeln!(out, "BM25 Rank: {}", bm25_rank)?;
                 }
 
                 // Display Hybrid 2 score and rank with more prominence
                 if let Some(new_score) = result.new_score {
-                    println!("Hybrid 2 Score: {:.4}", new_score);
+                    writeln!(out, "Hybrid 2 Score: {:.4}", new_score)?;
                 }
 
                 if let Some(hybrid2_rank) = result.hybrid2_rank {
-                    println!("Hybrid 2 Rank: {}", hybrid2_rank);
+                    writeln!(out, "Hybrid 2 Rank: {}", hybrid2_rank)?;
                 } else if result.new_score.is_some() {
-                    println!("Hybrid 2 Rank: N/A");
+                    writeln!(out, "Hybrid 2 Rank: N/A")?;
                 }
 
                 if let Some(file_unique_terms) = result.file_unique_terms {
-                    println!("File Unique Terms: {}", file_unique_terms);
+                    writeln!(out, "File Unique Terms: {}", file_unique_terms)?;
                 }
 
                 if let Some(file_total_matches) = result.file_total_matches {
-                    println!("File Total Matches: {}", file_total_matches);
+                    writeln!(out, "File Total Matches: {}", file_total_matches)?;
                 }
 
                 if let Some(file_match_rank) = result.file_match_rank {
-                    println!("File Match Rank: {}", file_match_rank);
+                    writeln!(out, "File Match Rank: {}", file_match_rank)?;
                 }
 
                 if let Some(block_unique_terms) = result.block_unique_terms {
-                    println!("Block Unique Terms: {}", block_unique_terms);
+                    writeln!(out, "Block Unique Terms: {}", block_unique_terms)?;
                 }
 
                 if let Some(block_total_matches) = result.block_total_matches {
-                    println!("Block Total Matches: {}", block_total_matches);
+                    writeln!(out, "Block Total Matches: {}", block_total_matches)?;
                 }
 
-                println!("Type: {}", result.node_type);
+                writeln!(out, "Type: {}", result.node_type)?;
             }
         }
     }
+
+    Ok(())
+}
+
+/// Write the ranking explanation of a result (`--explain`) as indented lines
+fn write_explanation(
+    out: &mut String,
+    explanation: &RankingExplanation,
+    use_color: bool,
+) -> Result<()> {
+    use colored::*;
+
+    let header = if use_color {
+        "Explanation:".bold().green().to_string()
+    } else {
+        "Explanation:".to_string()
+    };
+    writeln!(
+        out,
+        "{} rank {}, score {:.4}",
+        header, explanation.rank, explanation.score
+    )?;
+    writeln!(out, "  Query: {}", explanation.query)?;
+    writeln!(
+        out,
+        "  Reranker {}: {:.4}",
+        explanation.reranker, explanation.reranker_score
+    )?;
+    if let Some(bm25) = &explanation.bm25 {
+        write!(
+            out,
+            "  BM25: {:.4} (doc length {}, avgdl {:.2}, k1 {}, b {}",
+            bm25.score, bm25.doc_length, bm25.avgdl, bm25.k1, bm25.b
+        )?;
+        if bm25.filename_weight != 1.0 {
+            write!(out, ", filename weight {}", bm25.filename_weight)?;
+        }
+        writeln!(out, ")")?;
+        for term in &bm25.terms {
+            writeln!(
+                out,
+                "    {}: tf {}, idf {:.4}, score {:.4}",
+                term.term, term.tf, term.idf, term.score
+            )?;
+        }
+    }
+    for (name, boost) in [
+        ("Definition boost", explanation.definition_boost),
+        ("Recency boost", explanation.recency_boost),
+        ("Centrality boost", explanation.centrality_boost),
+    ] {
+        if let Some(boost) = boost {
+            writeln!(out, "  {}: x{:.4}", name, boost)?;
+        }
+    }
+    if !explanation.merged_blocks.is_empty() {
+        let blocks: Vec<String> = explanation
+            .merged_blocks
+            .iter()
+            .map(|block| {
+                let rank = block.rank.map_or("-".to_string(), |rank| rank.to_string());
+                let score = block
+                    .score
+                    .map_or("-".to_string(), |score| format!("{:.4}", score));
+                format!(
+                    "{}-{} (rank {}, score {})",
+                    block.lines.0, block.lines.1, rank, score
+                )
+            })
+            .collect();
+        writeln!(out, "  Merged blocks: {}", blocks.join(", "))?;
+    }
+    Ok(())
 }
 
 /// Helper function to escape XML special characters
@@ -466,12 +582,72 @@ fn escape_xml(s: &str) -> String {
         .replace("'", "&apos;")
 }
 
-/// Format and print search results in JSON format
-fn format_and_print_json_results(results: &[&SearchResult]) -> Result<()> {
+/// Format search results in JSON format
+fn format_json_results(
+    out: &mut String,
+    results: &[&SearchResult],
+    query_plan: Option<&QueryPlan>,
+) -> Result<()> {
+    let mut document = json_results_document(results);
+    if let Some(plan) = query_plan {
+        document["query_plan"] = json_query_plan(plan);
+    }
+    writeln!(out, "{}", serde_json::to_string_pretty(&document)?)?;
+    Ok(())
+}
+
+/// The search terms of a query plan, in query order, leaving out `path:`/`lang:`/`type:`
+/// filters
+fn plan_terms(expr: &Expr) -> Vec<&Expr> {
+    match expr {
+        Expr::Term { .. } if expr.is_filter_term() => Vec::new(),
+        Expr::Term { .. } => vec![expr],
+        Expr::And(left, right) | Expr::Or(left, right) => {
+            let mut terms = plan_terms(left);
+            terms.extend(plan_terms(right));
+            terms
+        }
+    }
+}
+
+/// Build the `query_plan` object of the JSON format: the parsed query and its terms
+fn json_query_plan(plan: &QueryPlan) -> serde_json::Value {
+    let terms: Vec<serde_json::Value> = plan_terms(&plan.ast)
+        .into_iter()
+        .filter_map(|term| match term {
+            Expr::Term {
+                keywords,
+                field,
+                required,
+                excluded,
+                boost,
+                ..
+            } => Some(serde_json::json!({
+                "keywords": keywords,
+                "field": field,
+                "required": required,
+                "excluded": excluded,
+                "boost": boost.unwrap_or(1.0),
+            })),
+            _ => None,
+        })
+        .collect();
+
+    serde_json::json!({
+        "query": plan.ast.to_string(),
+        "terms": terms,
+    })
+}
+
+/// Build the `{"results": [...], "summary": {...}}` document of the JSON format
+fn json_results_document(results: &[&SearchResult]) -> serde_json::Value {
     // Create a simplified version of the results for JSON output
     #[derive(serde::Serialize)]
     struct JsonResult<'a> {
         file: &'a str,
+        // Search root the file was found under
+        #[serde(skip_serializing_if = "Option::is_none")]
+        search_root: Option<&'a str>,
         lines: [usize; 2],
         node_type: &'a str,
         code: &'a str,
@@ -484,12 +660,22 @@ fn format_and_print_json_results(results: &[&SearchResult]) -> Result<()> {
         file_total_matches: Option<usize>,
         block_unique_terms: Option<usize>,
         block_total_matches: Option<usize>,
+        // Name the block declares, and the boost it earned for defining a query term
+        symbol_name: Option<&'a str>,
+        definition_boost: Option<f64>,
+        // Boost earned by the file's recent git activity (--boost-recent)
+        recency_boost: Option<f64>,
+        centrality: Option<f64>,
+        // Why the result ranked where it did (--explain)
+        #[serde(skip_serializing_if = "Option::is_none")]
+        explanation: Option<&'a RankingExplanation>,
     }
 
     let json_results: Vec<JsonResult> = results
         .iter()
         .map(|r| JsonResult {
             file: &r.file,
+            search_root: r.search_root.as_deref(),
             lines: [r.lines.0, r.lines.1],
             node_type: &r.node_type,
             code: &r.code,
@@ -501,102 +687,283 @@ fn format_and_print_json_results(results: &[&SearchResult]) -> Result<()> {
             file_total_matches: r.file_total_matches,
             block_unique_terms: r.block_unique_terms,
             block_total_matches: r.block_total_matches,
+            symbol_name: r.symbol_name.as_deref(),
+            definition_boost: r.definition_boost,
+            recency_boost: r.recency_boost,
+            centrality: r.centrality,
+            explanation: r.explanation.as_ref(),
         })
         .collect();
 
     // Create a wrapper object with results and summary
-    let wrapper = serde_json::json!({
+    serde_json::json!({
         "results": json_results,
         "summary": {
             "count": results.len(),
             "total_bytes": results.iter().map(|r| r.code.len()).sum::<usize>(),
             "total_tokens": results.iter().map(|r| count_tokens(&r.code)).sum::<usize>(),
         }
-    });
+    })
+}
 
-    println!("{}", serde_json::to_string_pretty(&wrapper)?);
-    Ok(())
+/// Format one search event as a single line of JSON, for `--stream` output.
+///
+/// Results use the same fields as the JSON format; the final `ranked` event also carries
+/// the summary, whether the search timed out, and the session ID.
+pub fn format_search_event(event: &SearchEvent) -> String {
+    let progress_json = |progress: &SearchProgress| {
+        serde_json::json!({
+            "files_scanned": progress.files_scanned,
+            "files_matched": progress.files_matched,
+            "files_processed": progress.files_processed,
+            "blocks_extracted": progress.blocks_extracted,
+        })
+    };
+
+    let line = match event {
+        SearchEvent::Progress(progress) => serde_json::json!({
+            "event": "progress",
+            "progress": progress_json(progress),
+        }),
+        SearchEvent::FileResults {
+            file,
+            results,
+            progress,
+        } => {
+            let results: Vec<&SearchResult> = results.iter().collect();
+            serde_json::json!({
+                "event": "file_results",
+                "file": file,
+                "results": json_results_document(&results)["results"],
+                "progress": progress_json(progress),
+            })
+        }
+        SearchEvent::Ranked(limited) => {
+            let results: Vec<&SearchResult> = limited
+                .results
+                .iter()
+                .filter(|r| !r.file.is_empty())
+                .collect();
+            let mut document = json_results_document(&results);
+            document["event"] = "ranked".into();
+            document["timed_out"] = limited.timed_out.into();
+            document["session"] = limited.session_id.clone().into();
+            document
+        }
+    };
+
+    line.to_string()
 }
 
-/// Format and print search results in XML format
-fn format_and_print_xml_results(results: &[&SearchResult]) -> Result<()> {
-    println!("<?xml version=\"1.0\" encoding=\"UTF-8\"?>");
-    println!("<probe_results>");
+/// Format search results in XML format
+fn format_xml_results(
+    out: &mut String,
+    results: &[&SearchResult],
+    query_plan: Option<&QueryPlan>,
+) -> Result<()> {
+    writeln!(out, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+    writeln!(out, "<probe_results>")?;
 
     for result in results {
-        println!("  <result>");
-        println!("    <file>{}</file>", escape_xml(&result.file));
-        println!("    <lines>{}-{}</lines>", result.lines.0, result.lines.1);
-        println!(
+        writeln!(out, "  <result>")?;
+        writeln!(out, "    <file>{}</file>", escape_xml(&result.file))?;
+        writeln!(
+            out,
+            "    <lines>{}-{}</lines>",
+            result.lines.0, result.lines.1
+        )?;
+        writeln!(
+            out,
             "    <node_type>{}</node_type>",
             escape_xml(&result.node_type)
-        );
+        )?;
 
         if let Some(keywords) = &result.matched_keywords {
-            println!("    <matched_keywords>");
+            writeln!(out, "    <matched_keywords>")?;
             for keyword in keywords {
-                println!("      <keyword>{}</keyword>", escape_xml(keyword));
+                writeln!(out, "      <keyword>{}</keyword>", escape_xml(keyword))?;
             }
-            println!("    </matched_keywords>");
+            writeln!(out, "    </matched_keywords>")?;
         }
 
         if let Some(score) = result.score {
-            println!("    <score>{:.4}</score>", score);
+            writeln!(out, "    <score>{:.4}</score>", score)?;
         }
 
         if let Some(tfidf_score) = result.tfidf_score {
-            println!("    <tfidf_score>{:.4}</tfidf_score>", tfidf_score);
+            writeln!(out, "    <tfidf_score>{:.4}</tfidf_score>", tfidf_score)?;
         }
 
         if let Some(bm25_score) = result.bm25_score {
-            println!("    <bm25_score>{:.4}</bm25_score>", bm25_score);
+            writeln!(out, "    <bm25_score>{:.4}</bm25_score>", bm25_score)?;
         }
 
         if let Some(file_unique_terms) = result.file_unique_terms {
-            println!(
+            writeln!(
+                out,
                 "    <file_unique_terms>{}</file_unique_terms>",
                 file_unique_terms
-            );
+            )?;
         }
 
         if let Some(file_total_matches) = result.file_total_matches {
-            println!(
+            writeln!(
+                out,
                 "    <file_total_matches>{}</file_total_matches>",
                 file_total_matches
-            );
+            )?;
         }
 
         if let Some(block_unique_terms) = result.block_unique_terms {
-            println!(
+            writeln!(
+                out,
                 "    <block_unique_terms>{}</block_unique_terms>",
                 block_unique_terms
-            );
+            )?;
         }
 
         if let Some(block_total_matches) = result.block_total_matches {
-            println!(
+            writeln!(
+                out,
                 "    <block_total_matches>{}</block_total_matches>",
                 block_total_matches
-            );
+            )?;
+        }
+
+        if let Some(explanation) = &result.explanation {
+            write_xml_explanation(out, explanation)?;
         }
 
-        println!("    <code><![CDATA[{}]]></code>", result.code);
-        println!("  </result>");
+        writeln!(out, "    <code><![CDATA[{}]]></code>", result.code)?;
+        writeln!(out, "  </result>")?;
     }
 
     // Add summary section
-    println!("  <summary>");
-    println!("    <count>{}</count>", results.len());
-    println!(
+    writeln!(out, "  <summary>")?;
+    writeln!(out, "    <count>{}</count>", results.len())?;
+    writeln!(
+        out,
         "    <total_bytes>{}</total_bytes>",
         results.iter().map(|r| r.code.len()).sum::<usize>()
-    );
-    println!(
+    )?;
+    writeln!(
+        out,
         "    <total_tokens>{}</total_tokens>",
         results.iter().map(|r| count_tokens(&r.code)).sum::<usize>()
-    );
-    println!("  </summary>");
+    )?;
+    writeln!(out, "  </summary>")?;
 
-    println!("</probe_results>");
+    if let Some(plan) = query_plan {
+        write_xml_query_plan(out, plan)?;
+    }
+
+    writeln!(out, "</probe_results>")?;
+    Ok(())
+}
+
+/// Write the parsed query and its terms as a `<query_plan>` element
+fn write_xml_query_plan(out: &mut String, plan: &QueryPlan) -> Result<()> {
+    writeln!(out, "  <query_plan>")?;
+    writeln!(
+        out,
+        "    <query>{}</query>",
+        escape_xml(&plan.ast.to_string())
+    )?;
+    for term in plan_terms(&plan.ast) {
+        if let Expr::Term {
+            keywords,
+            field,
+            required,
+            excluded,
+            boost,
+            ..
+        } = term
+        {
+            write!(out, "    <term")?;
+            if let Some(field) = field {
+                write!(out, " field=\"{}\"", escape_xml(field))?;
+            }
+            writeln!(
+                out,
+                " required=\"{}\" excluded=\"{}\" boost=\"{}\">{}</term>",
+                required,
+                excluded,
+                boost.unwrap_or(1.0),
+                escape_xml(&keywords.join(" "))
+            )?;
+        }
+    }
+    writeln!(out, "  </query_plan>")?;
+    Ok(())
+}
+
+/// Write the ranking explanation of a result (`--explain`) as an `<explanation>` element
+fn write_xml_explanation(out: &mut String, explanation: &RankingExplanation) -> Result<()> {
+    writeln!(out, "    <explanation>")?;
+    writeln!(
+        out,
+        "      <query>{}</query>",
+        escape_xml(&explanation.query)
+    )?;
+    writeln!(
+        out,
+        "      <reranker>{}</reranker>",
+        escape_xml(&explanation.reranker)
+    )?;
+    writeln!(
+        out,
+        "      <reranker_score>{:.4}</reranker_score>",
+        explanation.reranker_score
+    )?;
+    if let Some(bm25) = &explanation.bm25 {
+        writeln!(out, "      <bm25>")?;
+        writeln!(out, "        <score>{:.4}</score>", bm25.score)?;
+        writeln!(out, "        <doc_length>{}</doc_length>", bm25.doc_length)?;
+        writeln!(out, "        <avgdl>{:.4}</avgdl>", bm25.avgdl)?;
+        writeln!(out, "        <k1>{}</k1>", bm25.k1)?;
+        writeln!(out, "        <b>{}</b>", bm25.b)?;
+        writeln!(
+            out,
+            "        <filename_weight>{}</filename_weight>",
+            bm25.filename_weight
+        )?;
+        for term in &bm25.terms {
+            writeln!(
+                out,
+                "        <term name=\"{}\" tf=\"{}\" idf=\"{:.4}\" score=\"{:.4}\"/>",
+                escape_xml(&term.term),
+                term.tf,
+                term.idf,
+                term.score
+            )?;
+        }
+        writeln!(out, "      </bm25>")?;
+    }
+    for (name, boost) in [
+        ("definition_boost", explanation.definition_boost),
+        ("recency_boost", explanation.recency_boost),
+        ("centrality_boost", explanation.centrality_boost),
+    ] {
+        if let Some(boost) = boost {
+            writeln!(out, "      <{}>{:.4}</{}>", name, boost, name)?;
+        }
+    }
+    writeln!(out, "      <score>{:.4}</score>", explanation.score)?;
+    writeln!(out, "      <rank>{}</rank>", explanation.rank)?;
+    for block in &explanation.merged_blocks {
+        write!(
+            out,
+            "      <merged_block lines=\"{}-{}\"",
+            block.lines.0, block.lines.1
+        )?;
+        if let Some(rank) = block.rank {
+            write!(out, " rank=\"{}\"", rank)?;
+        }
+        if let Some(score) = block.score {
+            write!(out, " score=\"{:.4}\"", score)?;
+        }
+        writeln!(out, "/>")?;
+    }
+    writeln!(out, "    </explanation>")?;
     Ok(())
 }
@@ -2,19 +2,26 @@ use crate::search::file_list_cache;
 use anyhow::Result;
 use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time::{Duration, Instant};
 // No need for term_exceptions import
 
 use crate::models::{LimitedSearchResults, SearchResult};
 use crate::search::{
     cache,
+    diversification::diversify_results,
+    elastic_query::QueryField,
+    file_cache,
     // file_list_cache, // Add the new file_list_cache module (unused)
     file_processing::{process_file_with_results, FileProcessingParams},
-    query::{create_query_plan, create_structured_patterns, QueryPlan},
+    import_graph::ImportGraph,
+    query::{create_fuzzy_patterns, create_query_plan, create_structured_patterns, QueryPlan},
     result_ranking::rank_search_results,
+    search_events::{SearchEvent, SearchProgress},
     search_limiter::apply_limits,
-    search_options::SearchOptions,
-    timeout,
+    search_options::{AsSearchOptions, SearchOptions},
+    timeout::{self, CancellationToken},
+    tokenization,
 };
 use probe::path_resolver::resolve_path;
 
@@ -201,19 +208,52 @@ pub fn print_timings(timings: &SearchTimings) {
 /// incorporate "search_with_structured_patterns" to handle the AST logic in a specialized path.
 /// For simplicity, we won't fully replace the existing logic. Instead, we'll demonstrate
 /// how you'd do it if you wanted to leverage the new approach.
-pub fn perform_probe(options: &SearchOptions) -> Result<LimitedSearchResults> {
+///
+/// Accepts either borrowed `&SearchOptions` or an owned `&SearchRequest`.
+pub fn perform_probe(options: &(impl AsSearchOptions + ?Sized)) -> Result<LimitedSearchResults> {
+    perform_probe_with_events(options, &mut |_| {})
+}
+
+/// Like `perform_probe`, but reports progress and per-file candidate results through
+/// `on_event` while the search runs, followed by the final ranked batch.
+pub fn perform_probe_with_events(
+    options: &(impl AsSearchOptions + ?Sized),
+    on_event: &mut (dyn FnMut(&SearchEvent) + Send),
+) -> Result<LimitedSearchResults> {
+    let options = options.as_search_options();
+    let vocabulary = options.vocabulary.cloned().unwrap_or_default();
+    let results =
+        tokenization::with_vocabulary(&vocabulary, || search_and_rank(&options, on_event))?;
+    on_event(&SearchEvent::Ranked(&results));
+    file_cache::prune_if_due();
+    Ok(results)
+}
+
+fn search_and_rank(
+    options: &SearchOptions,
+    on_event: &mut dyn FnMut(&SearchEvent),
+) -> Result<LimitedSearchResults> {
+    // Requests built without `SearchRequest::new` or deserialized without `queries` have none
+    if options.queries.is_empty() {
+        return Err(anyhow::anyhow!("At least one query is required"));
+    }
+    options.ranking.validate().map_err(|e| anyhow::anyhow!(e))?;
+
     // Start timing the entire search process
     let total_start = Instant::now();
 
     let SearchOptions {
-        path,
-        queries,
+        paths,
+        queries: _, // Preprocessed below
         files_only,
         custom_ignores,
         exclude_filenames,
         reranker,
         frequency_search: _,
         exact,
+        fuzzy: _,      // Applied by `preprocessed_queries`
+        synonyms: _,   // Applied by `preprocessed_queries`
+        vocabulary: _, // Applied by `perform_probe_with_events`
         language,
         max_results,
         max_bytes,
@@ -224,9 +264,17 @@ pub fn perform_probe(options: &SearchOptions) -> Result<LimitedSearchResults> {
         dry_run: _, // We don't need this in perform_probe, but need to include it in the pattern
         session,
         timeout,
+        ranking,
     } = options;
-    // Start the timeout thread
-    let timeout_handle = timeout::start_timeout_thread(*timeout);
+    // Synonym expansion and `--fuzzy` rewrite the queries for term matching and weighting;
+    // file names are matched and external rerankers are given the queries as written
+    let preprocessed_queries = options.preprocessed_queries();
+    let queries = &preprocessed_queries;
+    let written_queries = options.queries;
+    // Start the timeout thread. When it fires, every stage stops early and the
+    // results found so far are returned with `timed_out` set.
+    let cancel = CancellationToken::new();
+    let timeout_handle = timeout::start_timeout_thread(*timeout, cancel.clone());
 
     let include_filenames = !exclude_filenames;
     let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
@@ -367,12 +415,16 @@ pub fn perform_probe(options: &SearchOptions) -> Result<LimitedSearchResults> {
 
     // If the query fails to parse, return empty results
     if parse_res.is_err() {
-        println!("Failed to parse query as AST expression");
+        eprintln!("Failed to parse query as AST expression");
+        timeout_handle.store(true, std::sync::atomic::Ordering::SeqCst);
         return Ok(LimitedSearchResults {
             results: Vec::new(),
             skipped_files: Vec::new(),
             limits_applied: None,
             cached_blocks_skipped: None,
+            timed_out: false,
+            session_id: None,
+            session_generated: false,
         });
     }
 
@@ -424,14 +476,46 @@ pub fn perform_probe(options: &SearchOptions) -> Result<LimitedSearchResults> {
     // Normalize language parameter to handle aliases
     let lang_param = language.as_ref().map(|lang| normalize_language_alias(lang));
 
-    let mut file_term_map = search_with_structured_patterns(
-        path,
-        &plan,
-        &structured_patterns,
-        custom_ignores,
-        *allow_tests,
-        lang_param,
-    )?;
+    // Search every root and merge the per-file term maps. A file reachable from
+    // several (overlapping) roots is attributed to the first root that found it.
+    let mut file_term_map: HashMap<PathBuf, HashMap<usize, HashSet<usize>>> = HashMap::new();
+    let mut file_roots: HashMap<PathBuf, String> = HashMap::new();
+    let mut seen_files: HashSet<PathBuf> = HashSet::new();
+    let mut progress = SearchProgress::default();
+    for root in paths.iter() {
+        let files_scanned = AtomicUsize::new(0);
+        let root_term_map = search_root_with_patterns(
+            root,
+            &plan,
+            &structured_patterns,
+            custom_ignores,
+            *allow_tests,
+            lang_param,
+            &cancel,
+            &files_scanned,
+        )?;
+
+        if debug_mode {
+            println!(
+                "DEBUG: Root {:?} produced matches in {} files",
+                root,
+                root_term_map.len()
+            );
+        }
+
+        for (file, term_map) in root_term_map {
+            let canonical = std::fs::canonicalize(&file).unwrap_or_else(|_| file.clone());
+            if !seen_files.insert(canonical) {
+                continue;
+            }
+            file_roots.insert(file.clone(), root.to_string_lossy().to_string());
+            file_term_map.insert(file, term_map);
+        }
+
+        progress.files_scanned += files_scanned.into_inner();
+        progress.files_matched = file_term_map.len();
+        on_event(&SearchEvent::Progress(progress));
+    }
 
     let fs_duration = fs_start.elapsed();
     timings.file_searching = Some(fs_duration);
@@ -465,43 +549,55 @@ pub fn perform_probe(options: &SearchOptions) -> Result<LimitedSearchResults> {
             println!("DEBUG: Starting filename matching...");
         }
         // Find all files that match our patterns by filename, along with the terms that matched
-        // Resolve the path if it's a special format (e.g., "go:github.com/user/repo")
-        let resolved_path = if let Some(path_str) = path.to_str() {
-            match resolve_path(path_str) {
-                Ok(resolved_path) => {
-                    if debug_mode {
-                        println!(
-                            "DEBUG: Resolved path '{}' to '{}'",
-                            path_str,
-                            resolved_path.display()
-                        );
+        let mut filename_matches: HashMap<PathBuf, HashSet<usize>> = HashMap::new();
+        for root in paths.iter() {
+            // Resolve the path if it's a special format (e.g., "go:github.com/user/repo")
+            let resolved_path = if let Some(path_str) = root.to_str() {
+                match resolve_path(path_str) {
+                    Ok(resolved_path) => {
+                        if debug_mode {
+                            println!(
+                                "DEBUG: Resolved path '{}' to '{}'",
+                                path_str,
+                                resolved_path.display()
+                            );
+                        }
+                        resolved_path
                     }
-                    resolved_path
-                }
-                Err(err) => {
-                    if debug_mode {
-                        println!("DEBUG: Failed to resolve path '{}': {}", path_str, err);
+                    Err(err) => {
+                        if debug_mode {
+                            println!("DEBUG: Failed to resolve path '{}': {}", path_str, err);
+                        }
+                        // Fall back to the original path
+                        root.to_path_buf()
                     }
-                    // Fall back to the original path
-                    path.to_path_buf()
                 }
-            }
-        } else {
-            // If we can't convert the path to a string, use it as is
-            path.to_path_buf()
-        };
+            } else {
+                // If we can't convert the path to a string, use it as is
+                root.to_path_buf()
+            };
 
-        let filename_matches: HashMap<PathBuf, HashSet<usize>> =
-            file_list_cache::find_matching_filenames(
+            let root_matches = file_list_cache::find_matching_filenames(
                 &resolved_path,
-                queries,
+                written_queries,
                 &all_files,
                 custom_ignores,
                 *allow_tests,
                 &plan.term_indices,
                 lang_param,
+                &cancel,
             )?;
 
+            for (file, matched_terms) in root_matches {
+                let canonical = std::fs::canonicalize(&file).unwrap_or_else(|_| file.clone());
+                if !seen_files.insert(canonical) {
+                    continue;
+                }
+                file_roots.insert(file.clone(), root.to_string_lossy().to_string());
+                filename_matches.insert(file, matched_terms);
+            }
+        }
+
         if debug_mode {
             println!(
                 "DEBUG: Found {} files matching by filename",
@@ -511,6 +607,13 @@ pub fn perform_probe(options: &SearchOptions) -> Result<LimitedSearchResults> {
 
         // Process files that matched by filename
         for (pathbuf, matched_terms) in &filename_matches {
+            if !plan.matches_file(pathbuf) {
+                if debug_mode {
+                    println!("DEBUG: File {:?} rejected by field filters", pathbuf);
+                }
+                continue;
+            }
+
             // Define a reasonable maximum file size (e.g., 10MB)
             const MAX_FILE_SIZE: u64 = 1024 * 1024;
 
@@ -694,6 +797,9 @@ pub fn perform_probe(options: &SearchOptions) -> Result<LimitedSearchResults> {
 
     // Handle files-only mode
     if *files_only {
+        progress.files_matched = all_files.len();
+        on_event(&SearchEvent::Progress(progress));
+
         let mut res = Vec::new();
         for f in all_files {
             res.push(SearchResult {
@@ -720,6 +826,12 @@ pub fn perform_probe(options: &SearchOptions) -> Result<LimitedSearchResults> {
                 block_id: None,
                 matched_keywords: None,
                 tokenized_content: None,
+                search_root: file_roots.get(&f).cloned(),
+                symbol_name: None,
+                definition_boost: None,
+                recency_boost: None,
+                centrality: None,
+                explanation: None,
             });
         }
         let mut limited = apply_limits(res, *max_results, *max_bytes, *max_tokens);
@@ -727,6 +839,10 @@ pub fn perform_probe(options: &SearchOptions) -> Result<LimitedSearchResults> {
         // No caching for files-only mode
         limited.cached_blocks_skipped = None;
 
+        // Stop the timeout thread
+        timeout_handle.store(true, std::sync::atomic::Ordering::SeqCst);
+        limited.timed_out = cancel.is_cancelled();
+
         // Set total search time
         timings.total_search_time = Some(total_start.elapsed());
 
@@ -792,6 +908,9 @@ pub fn perform_probe(options: &SearchOptions) -> Result<LimitedSearchResults> {
         );
     }
 
+    progress.files_matched = all_files.len();
+    on_event(&SearchEvent::Progress(progress));
+
     // Process the files for detailed results
     let rp_start = Instant::now();
     if debug_mode {
@@ -829,6 +948,13 @@ pub fn perform_probe(options: &SearchOptions) -> Result<LimitedSearchResults> {
     let mut total_synchronization_time = Duration::new(0, 0);
     let mut total_uncovered_lines_time = Duration::new(0, 0);
     for pathbuf in &all_files {
+        if cancel.is_cancelled() {
+            if debug_mode {
+                println!("DEBUG: Search timed out, skipping the remaining files");
+            }
+            break;
+        }
+
         if debug_mode {
             println!("DEBUG: Processing file: {:?}", pathbuf);
         }
@@ -863,7 +989,7 @@ pub fn perform_probe(options: &SearchOptions) -> Result<LimitedSearchResults> {
             let term_pairs: Vec<(String, String)> = plan
                 .term_indices
                 .keys()
-                .map(|term| (term.clone(), term.clone()))
+                .map(|term| (term.clone(), QueryField::split_key(term).1.to_string()))
                 .collect();
 
             let pparams = FileProcessingParams {
@@ -877,6 +1003,7 @@ pub fn perform_probe(options: &SearchOptions) -> Result<LimitedSearchResults> {
                 preprocessed_queries: None,
                 no_merge: *no_merge,
                 query_plan: &plan,
+                cancel: &cancel,
             };
 
             if debug_mode {
@@ -1023,6 +1150,20 @@ pub fn perform_probe(options: &SearchOptions) -> Result<LimitedSearchResults> {
                             );
                         }
                     }
+                    // Remember which search root each result came from
+                    let search_root = file_roots.get(pathbuf).cloned();
+                    for result in &mut file_res {
+                        result.search_root = search_root.clone();
+                    }
+
+                    progress.files_processed += 1;
+                    progress.blocks_extracted += file_res.len();
+                    on_event(&SearchEvent::FileResults {
+                        file: &pathbuf.to_string_lossy(),
+                        results: &file_res,
+                        progress,
+                    });
+
                     final_results.append(&mut file_res);
                 }
                 Err(e) => {
@@ -1161,11 +1302,33 @@ pub fn perform_probe(options: &SearchOptions) -> Result<LimitedSearchResults> {
         }
     }
 
+    if !*exact && ranking.centrality_weight > 0.0 {
+        set_centrality(
+            &mut final_results,
+            paths,
+            *allow_tests,
+            custom_ignores,
+            &cancel,
+        );
+    }
+
     if !*exact {
         // Only perform ranking if exact flag is not set
-        rank_search_results(&mut final_results, queries, reranker);
+        rank_search_results(
+            &mut final_results,
+            written_queries,
+            queries,
+            reranker,
+            ranking,
+            &cancel,
+        );
     }
 
+    // Nothing after ranking can be cancelled, so stop the timeout thread here and
+    // remember whether the results are partial
+    timeout_handle.store(true, std::sync::atomic::Ordering::SeqCst);
+    let timed_out = cancel.is_cancelled();
+
     let rr_duration = rr_start.elapsed();
     timings.result_ranking = Some(rr_duration);
 
@@ -1185,7 +1348,11 @@ pub fn perform_probe(options: &SearchOptions) -> Result<LimitedSearchResults> {
 
     // We'll move the caching step AFTER limiting results
     let mut skipped_count = early_skipped_count;
-    let filtered_results = final_results;
+    let filtered_results = if ranking.diversity > 0.0 || ranking.max_per_file.is_some() {
+        diversify_results(final_results, ranking.diversity, ranking.max_per_file)
+    } else {
+        final_results
+    };
 
     // Apply limits
     let la_start = Instant::now();
@@ -1195,6 +1362,7 @@ pub fn perform_probe(options: &SearchOptions) -> Result<LimitedSearchResults> {
 
     // First apply limits to the results
     let mut limited = apply_limits(filtered_results, *max_results, *max_bytes, *max_tokens);
+    limited.timed_out = timed_out;
 
     // Then apply caching AFTER limiting results
     let fc_start = Instant::now();
@@ -1313,6 +1481,9 @@ pub fn perform_probe(options: &SearchOptions) -> Result<LimitedSearchResults> {
             skipped_files: limited.skipped_files,
             limits_applied: limited.limits_applied,
             cached_blocks_skipped: limited.cached_blocks_skipped,
+            timed_out: limited.timed_out,
+            session_id: None,
+            session_generated: false,
         };
 
         // Update the cache with the merged results (after merging)
@@ -1352,17 +1523,10 @@ pub fn perform_probe(options: &SearchOptions) -> Result<LimitedSearchResults> {
         limited
     };
 
-    // Print the session ID to the console if it was generated or provided
-    if let Some(session_id) = effective_session {
-        if session_was_generated {
-            println!(
-                "Session ID: {} (generated - ALWAYS USE IT in future sessions for caching)",
-                session_id
-            );
-        } else {
-            println!("Session ID: {}", session_id);
-        }
-    }
+    // Report the session ID so callers can show it or reuse it
+    let mut final_results = final_results;
+    final_results.session_id = effective_session.map(str::to_string);
+    final_results.session_generated = session_was_generated;
 
     // Set total search time
     timings.total_search_time = Some(total_start.elapsed());
@@ -1370,9 +1534,6 @@ pub fn perform_probe(options: &SearchOptions) -> Result<LimitedSearchResults> {
     // Print timing information
     print_timings(&timings);
 
-    // Stop the timeout thread
-    timeout_handle.store(true, std::sync::atomic::Ordering::SeqCst);
-
     Ok(final_results)
 }
 
@@ -1387,13 +1548,43 @@ pub fn perform_probe(options: &SearchOptions) -> Result<LimitedSearchResults> {
 /// * `patterns` - The generated regex patterns with their term indices
 /// * `custom_ignores` - Custom ignore patterns
 /// * `allow_tests` - Whether to include test files
+/// * `language` - Only search files of this language
+/// * `cancel` - Stops the search early; files not searched yet are left out
+///
+/// Files rejected by the plan's `path:`/`lang:` filters are not searched.
+#[allow(dead_code)]
 pub fn search_with_structured_patterns(
     root_path_str: &Path,
-    _plan: &QueryPlan,
+    plan: &QueryPlan,
     patterns: &[(String, HashSet<usize>)],
     custom_ignores: &[String],
     allow_tests: bool,
     language: Option<&str>,
+    cancel: &CancellationToken,
+) -> Result<HashMap<PathBuf, HashMap<usize, HashSet<usize>>>> {
+    search_root_with_patterns(
+        root_path_str,
+        plan,
+        patterns,
+        custom_ignores,
+        allow_tests,
+        language,
+        cancel,
+        &AtomicUsize::new(0),
+    )
+}
+
+/// `search_with_structured_patterns`, also counting the files searched in `files_scanned`
+#[allow(clippy::too_many_arguments)]
+fn search_root_with_patterns(
+    root_path_str: &Path,
+    plan: &QueryPlan,
+    patterns: &[(String, HashSet<usize>)],
+    custom_ignores: &[String],
+    allow_tests: bool,
+    language: Option<&str>,
+    cancel: &CancellationToken,
+    files_scanned: &AtomicUsize,
 ) -> Result<HashMap<PathBuf, HashMap<usize, HashSet<usize>>>> {
     // Resolve the path if it's a special format (e.g., "go:github.com/user/repo")
     let root_path = if let Some(path_str) = root_path_str.to_str() {
@@ -1427,6 +1618,29 @@ pub fn search_with_structured_patterns(
     let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
     let search_start = Instant::now();
 
+    // Fuzzy terms are searched for by the words of this root within their edit distance
+    let fuzzy_patterns;
+    let patterns = if plan.has_fuzzy_terms() {
+        let vocabulary = crate::search::fuzzy::root_vocabulary(
+            &root_path,
+            allow_tests,
+            custom_ignores,
+            language,
+            cancel,
+        )?;
+        fuzzy_patterns = [patterns, &create_fuzzy_patterns(plan, &vocabulary)].concat();
+        if debug_mode {
+            println!(
+                "DEBUG: Added {} fuzzy term patterns from a vocabulary of {} words",
+                fuzzy_patterns.len() - patterns.len(),
+                vocabulary.len()
+            );
+        }
+        &fuzzy_patterns
+    } else {
+        patterns
+    };
+
     // Step 1: Create RegexSet for deterministic pattern matching
     if debug_mode {
         println!("DEBUG: Starting parallel structured pattern search with RegexSet...");
@@ -1459,10 +1673,31 @@ pub fn search_with_structured_patterns(
         allow_tests,
         custom_ignores,
         language,
+        cancel,
     )?;
 
     if debug_mode {
         println!("DEBUG: Got {} files from cache", file_list.files.len());
+    }
+
+    // Use the persistent index (see `probe index`) when one covers this root and every
+    // pattern can be answered from it. Files changed since indexing are still scanned.
+    let search_index = if crate::search::index::supports_patterns(patterns) {
+        crate::search::index::SearchIndex::load_for(&root_path)
+    } else {
+        if debug_mode {
+            println!("DEBUG: Patterns cannot be answered from the search index, scanning files");
+        }
+        None
+    };
+    let indexed_matches = search_index
+        .as_ref()
+        .map(|index| index.match_patterns(&regex_set, &pattern_to_terms));
+
+    if debug_mode {
+        if search_index.is_some() {
+            println!("DEBUG: Using search index for unchanged files");
+        }
         println!("DEBUG: Starting parallel file processing with RegexSet");
     }
 
@@ -1480,6 +1715,22 @@ pub fn search_with_structured_patterns(
     let individual_regexes = Arc::new(individual_regexes);
 
     file_list.files.par_iter().for_each(|file_path| {
+        if cancel.is_cancelled() || !plan.matches_file(file_path) {
+            return;
+        }
+        files_scanned.fetch_add(1, Ordering::Relaxed);
+
+        // Take matches for unchanged files straight from the index
+        if let (Some(index), Some(indexed_matches)) = (&search_index, &indexed_matches) {
+            if let Some(file_id) = index.fresh_file_id(file_path) {
+                if let Some(term_map) = indexed_matches.get(&file_id) {
+                    let mut maps = file_term_maps.lock().unwrap();
+                    maps.insert(file_path.clone(), term_map.clone());
+                }
+                return;
+            }
+        }
+
         let regex_set = Arc::clone(&regex_set);
         let pattern_to_terms = Arc::clone(&pattern_to_terms);
         let individual_regexes = Arc::clone(&individual_regexes);
@@ -1648,7 +1899,7 @@ fn search_file_with_regex_set(
 
 /// Normalize language aliases to their canonical names
 /// This function maps language aliases like "ts" to their canonical names like "typescript"
-fn normalize_language_alias(lang: &str) -> &str {
+pub(crate) fn normalize_language_alias(lang: &str) -> &str {
     match lang.to_lowercase().as_str() {
         "rs" => "rust",
         "js" | "jsx" => "javascript",
@@ -1661,3 +1912,41 @@ fn normalize_language_alias(lang: &str) -> &str {
         _ => lang, // Return the original language if no alias is found
     }
 }
+
+/// Look up the import-graph centrality of each result's file, building (or reusing) one
+/// graph per search path. Results keep no centrality if the search is cancelled first.
+fn set_centrality(
+    results: &mut [SearchResult],
+    paths: &[PathBuf],
+    allow_tests: bool,
+    custom_ignores: &[String],
+    cancel: &CancellationToken,
+) {
+    let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
+    let graphs: Vec<ImportGraph> = paths
+        .iter()
+        .filter(|path| path.is_dir())
+        .filter_map(
+            |path| match ImportGraph::build(path, allow_tests, custom_ignores, cancel) {
+                Ok(graph) => Some(graph),
+                Err(e) => {
+                    if debug_mode {
+                        println!("DEBUG: Could not build import graph for {:?}: {}", path, e);
+                    }
+                    None
+                }
+            },
+        )
+        .collect();
+
+    let mut file_centrality: HashMap<String, Option<f64>> = HashMap::new();
+    for result in results.iter_mut() {
+        result.centrality = *file_centrality
+            .entry(result.file.clone())
+            .or_insert_with(|| {
+                graphs
+                    .iter()
+                    .find_map(|graph| graph.relative_centrality(Path::new(&result.file)))
+            });
+    }
+}
@@ -0,0 +1,234 @@
+//! Synonym and abbreviation expansion of query words: `cfg` also finds `config`, `auth`
+//! finds `authentication` and `db` finds `database`, and the other way around.
+
+use anyhow::{anyhow, Context, Result};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+
+use crate::search::{elastic_query, tokenization};
+
+/// Boost of the words a query word is expanded into, so that blocks with the word as
+/// written rank above blocks that only contain a synonym
+pub const SYNONYM_BOOST: f64 = 0.5;
+
+/// Common abbreviations in code and the words they stand for
+const BUILTIN_SYNONYMS: &[(&str, &[&str])] = &[
+    ("addr", &["address"]),
+    ("alloc", &["allocate", "allocation"]),
+    ("arg", &["argument"]),
+    ("args", &["arguments"]),
+    ("auth", &["authentication", "authenticate"]),
+    ("btn", &["button"]),
+    ("buf", &["buffer"]),
+    ("calc", &["calculate"]),
+    ("cfg", &["config", "configuration"]),
+    ("cmd", &["command"]),
+    ("conf", &["config", "configuration"]),
+    ("config", &["configuration"]),
+    ("conn", &["connection"]),
+    ("ctx", &["context"]),
+    ("db", &["database"]),
+    ("dir", &["directory"]),
+    ("doc", &["document"]),
+    ("elem", &["element"]),
+    ("env", &["environment"]),
+    ("err", &["error"]),
+    ("exec", &["execute"]),
+    ("fmt", &["format"]),
+    ("func", &["function"]),
+    ("idx", &["index"]),
+    ("img", &["image"]),
+    ("impl", &["implementation"]),
+    ("info", &["information"]),
+    ("init", &["initialize", "initialization"]),
+    ("k8s", &["kubernetes"]),
+    ("len", &["length"]),
+    ("lib", &["library"]),
+    ("mgr", &["manager"]),
+    ("msg", &["message"]),
+    ("num", &["number"]),
+    ("obj", &["object"]),
+    ("param", &["parameter"]),
+    ("params", &["parameters"]),
+    ("pkg", &["package"]),
+    ("prev", &["previous"]),
+    ("ptr", &["pointer"]),
+    ("pwd", &["password"]),
+    ("ref", &["reference"]),
+    ("repo", &["repository"]),
+    ("req", &["request"]),
+    ("res", &["response", "result"]),
+    ("resp", &["response"]),
+    ("spec", &["specification"]),
+    ("src", &["source"]),
+    ("srv", &["server"]),
+    ("str", &["string"]),
+    ("svc", &["service"]),
+    ("sync", &["synchronize"]),
+    ("tmp", &["temporary"]),
+    ("tx", &["transaction"]),
+    ("util", &["utility", "utilities"]),
+    ("val", &["value"]),
+    ("var", &["variable"]),
+    ("ver", &["version"]),
+];
+
+static BUILTIN: Lazy<SynonymDictionary> = Lazy::new(|| {
+    let mut dictionary = SynonymDictionary::default();
+    for (word, synonyms) in BUILTIN_SYNONYMS {
+        for synonym in *synonyms {
+            dictionary.add(word, synonym);
+        }
+    }
+    dictionary
+});
+
+/// Words that a query word is expanded into, by lowercase word
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SynonymDictionary {
+    expansions: BTreeMap<String, Vec<String>>,
+}
+
+impl SynonymDictionary {
+    /// The built-in dictionary of code abbreviations
+    pub fn builtin() -> &'static Self {
+        &BUILTIN
+    }
+
+    /// Make `word` and `synonym` expand into each other
+    pub fn add(&mut self, word: &str, synonym: &str) {
+        let (word, synonym) = (word.trim().to_lowercase(), synonym.trim().to_lowercase());
+        if word.is_empty() || synonym.is_empty() || word == synonym {
+            return;
+        }
+        for (from, to) in [(&word, &synonym), (&synonym, &word)] {
+            let expansions = self.expansions.entry(from.clone()).or_default();
+            if !expansions.contains(to) {
+                expansions.push(to.clone());
+            }
+        }
+    }
+
+    /// Add the entries of a synonyms file: one `word = synonym, synonym, ...` entry per
+    /// line, with `#` starting a comment
+    pub fn add_file(&mut self, path: &Path) -> Result<()> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        for (number, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (word, synonyms) = line.split_once('=').ok_or_else(|| {
+                anyhow!(
+                    "{}:{}: expected `word = synonym, ...`",
+                    path.display(),
+                    number + 1
+                )
+            })?;
+            for synonym in synonyms.split(',') {
+                self.add(word, synonym);
+            }
+        }
+        Ok(())
+    }
+
+    /// The words `word` is expanded into
+    pub fn expansions(&self, word: &str) -> &[String] {
+        self.expansions
+            .get(&word.to_lowercase())
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// Rewrite `query` so that each optional, unscoped plain word with synonyms becomes a
+    /// group of the word and its synonyms, the synonyms boosted by [`SYNONYM_BOOST`]:
+    /// `cfg load` => `(cfg OR config^0.5) load`. `+` and `-` words are kept as written,
+    /// since a group can't be required or excluded as a whole.
+    pub fn expand_query(&self, query: &str) -> String {
+        elastic_query::rewrite_words(query, |query_word| {
+            if query_word.field.is_some() || query_word.required || query_word.excluded {
+                return None;
+            }
+            // Synonyms that share a token with the word or an earlier synonym would be
+            // scored twice (`configuration` is tokenized to `config` and `urat`)
+            let mut seen: HashSet<String> = tokenization::tokenize(query_word.word)
+                .into_iter()
+                .collect();
+            let mut alternatives = Vec::new();
+            for synonym in self.expansions(query_word.word) {
+                let tokens = tokenization::tokenize(synonym);
+                if tokens.is_empty() || tokens.iter().any(|token| seen.contains(token)) {
+                    continue;
+                }
+                seen.extend(tokens);
+                alternatives.push(
+                    if synonym.chars().all(|c| c.is_alphanumeric() || c == '_') {
+                        format!("{}^{}", synonym, SYNONYM_BOOST)
+                    } else {
+                        format!("\"{}\"^{}", synonym.replace('"', ""), SYNONYM_BOOST)
+                    },
+                );
+            }
+            if alternatives.is_empty() {
+                return None;
+            }
+            Some(format!(
+                "({} OR {})",
+                query_word.word,
+                alternatives.join(" OR ")
+            ))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_expansions() {
+        let builtin = SynonymDictionary::builtin();
+        assert_eq!(builtin.expansions("cfg"), ["config", "configuration"]);
+        assert_eq!(builtin.expansions("DB"), ["database"]);
+        // Expansions go both ways
+        assert!(builtin.expansions("config").contains(&"cfg".to_string()));
+        assert!(builtin.expansions("widget").is_empty());
+    }
+
+    #[test]
+    fn test_expand_query() {
+        let builtin = SynonymDictionary::builtin();
+        assert_eq!(
+            builtin.expand_query("db connect"),
+            "(db OR database^0.5) connect"
+        );
+        assert_eq!(
+            builtin.expand_query("auth^2 AND token"),
+            "(auth OR authentication^0.5)^2 AND token"
+        );
+        // Required, excluded, scoped and quoted words are kept as written
+        assert_eq!(
+            builtin.expand_query("+db -cfg comment:db \"db\""),
+            "+db -cfg comment:db \"db\""
+        );
+    }
+
+    #[test]
+    fn test_add_file() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("synonyms.txt");
+        std::fs::write(&path, "# Domain words\nledger = journal, book\n\n").unwrap();
+
+        let mut dictionary = SynonymDictionary::default();
+        dictionary.add_file(&path).unwrap();
+        assert_eq!(dictionary.expansions("ledger"), ["journal", "book"]);
+        assert_eq!(dictionary.expansions("book"), ["ledger"]);
+
+        std::fs::write(&path, "ledger journal\n").unwrap();
+        let error = dictionary.add_file(&path).unwrap_err().to_string();
+        assert!(error.ends_with(":1: expected `word = synonym, ...`"));
+    }
+}
@@ -59,7 +59,10 @@ pub static EXCEPTION_TERMS: Lazy<HashSet<String>> = Lazy::new(|| {
     .collect()
 });
 
-/// Checks if a term is in the exception list
+/// Checks if a term is in the exception list, including the terms added by the
+/// vocabulary in effect (see `tokenization::with_vocabulary`)
 pub fn is_exception_term(term: &str) -> bool {
-    EXCEPTION_TERMS.contains(&term.to_lowercase())
+    crate::search::tokenization::active_vocabulary()
+        .exception_terms
+        .contains(&term.to_lowercase())
 }
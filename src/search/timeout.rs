@@ -3,9 +3,35 @@ use std::sync::Arc;
 use std::thread;
 use std::time::Duration;
 
-/// Starts a timeout thread that will terminate the process if the timeout is reached.
+/// A flag shared by all stages of a search that tells them to stop early.
+///
+/// Cancellation is cooperative: file listing, file searching, file processing and
+/// ranking check the token between units of work and return what they have so far.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    /// Create a token that has not been cancelled
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ask every holder of this token to stop
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    /// Check whether the token has been cancelled
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+/// Starts a timeout thread that will cancel `token` if the timeout is reached.
 /// Returns a handle to the timeout thread that can be used to stop it.
-pub fn start_timeout_thread(timeout_seconds: u64) -> Arc<AtomicBool> {
+pub fn start_timeout_thread(timeout_seconds: u64, token: CancellationToken) -> Arc<AtomicBool> {
     let should_stop = Arc::new(AtomicBool::new(false));
     let should_stop_clone = should_stop.clone();
 
@@ -34,12 +60,14 @@ pub fn start_timeout_thread(timeout_seconds: u64) -> Arc<AtomicBool> {
             elapsed_time += sleep_interval;
         }
 
-        // Timeout reached, print a message and terminate the process
-        eprintln!(
-            "Search operation timed out after {} seconds",
-            timeout_seconds
-        );
-        std::process::exit(1);
+        // Timeout reached, let the search wind down and return partial results
+        if std::env::var("DEBUG").unwrap_or_default() == "1" {
+            println!(
+                "DEBUG: Search operation timed out after {} seconds, cancelling",
+                timeout_seconds
+            );
+        }
+        token.cancel();
     });
 
     should_stop
@@ -1,9 +1,13 @@
-use crate::ranking::get_stemmer;
+use crate::ranking::{get_stemmer, StemLanguage};
 use crate::search::term_exceptions::{is_exception_term, EXCEPTION_TERMS};
 use decompound::{decompound, DecompositionOptions};
 use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::hash_map::DefaultHasher;
 use std::collections::HashSet;
-use std::sync::Mutex;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
 
 // Dynamic set of special terms that should not be tokenized
 // This includes terms from queries with exact=true or excluded=true flags
@@ -21,6 +25,162 @@ pub fn add_special_term(term: &str) {
     }
 }
 
+/// Project-specific changes to the built-in word lists, applied on top of the defaults for
+/// one search (`SearchRequest::vocabulary`) or with [`with_vocabulary`]. Read from the
+/// `vocabulary` section of `.probe.json`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct VocabularyOverrides {
+    /// Words to leave out of queries and code, in addition to the built-in stop words
+    pub stop_words: Vec<String>,
+    /// Built-in stop words to search for after all
+    pub not_stop_words: Vec<String>,
+    /// Words that are never split into parts and also match unstemmed, in addition to
+    /// `term_exceptions::EXCEPTION_TERMS`
+    pub exception_terms: Vec<String>,
+    /// Words that compound words are split into, in addition to those of
+    /// [`load_vocabulary`]
+    pub compound_words: Vec<String>,
+    /// Language to stem words in instead of English
+    pub stem_language: Option<StemLanguage>,
+}
+
+impl VocabularyOverrides {
+    /// Add the words of `other` to these; its stem language, if any, replaces this one
+    pub fn extend(&mut self, other: VocabularyOverrides) {
+        self.stop_words.extend(other.stop_words);
+        self.not_stop_words.extend(other.not_stop_words);
+        self.exception_terms.extend(other.exception_terms);
+        self.compound_words.extend(other.compound_words);
+        if other.stem_language.is_some() {
+            self.stem_language = other.stem_language;
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    fn fingerprint(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+/// The word lists in effect: the built-in defaults with the overrides applied
+pub(crate) struct ActiveVocabulary {
+    stop_words: HashSet<String>,
+    not_stop_words: HashSet<String>,
+    pub(crate) exception_terms: HashSet<String>,
+    compound_words: Arc<HashSet<String>>,
+    pub(crate) stem_language: StemLanguage,
+    fingerprint: u64,
+}
+
+impl ActiveVocabulary {
+    fn new(overrides: &VocabularyOverrides) -> Self {
+        let lowercase =
+            |words: &[String]| -> Vec<String> { words.iter().map(|w| w.to_lowercase()).collect() };
+
+        let mut exception_terms = EXCEPTION_TERMS.clone();
+        exception_terms.extend(lowercase(&overrides.exception_terms));
+        let mut compound_words = builtin_vocabulary().clone();
+        compound_words.extend(lowercase(&overrides.compound_words));
+
+        Self {
+            stop_words: lowercase(&overrides.stop_words).into_iter().collect(),
+            not_stop_words: lowercase(&overrides.not_stop_words).into_iter().collect(),
+            exception_terms,
+            compound_words: Arc::new(compound_words),
+            stem_language: overrides.stem_language.unwrap_or_default(),
+            fingerprint: overrides.fingerprint(),
+        }
+    }
+
+    fn is_stop_word(&self, word: &str) -> bool {
+        if self.not_stop_words.contains(word) {
+            return false;
+        }
+        self.stop_words.contains(word)
+            || is_english_stop_word(word)
+            || is_programming_stop_word(word)
+    }
+}
+
+static DEFAULT_VOCABULARY: Lazy<Arc<ActiveVocabulary>> =
+    Lazy::new(|| Arc::new(ActiveVocabulary::new(&VocabularyOverrides::default())));
+
+thread_local! {
+    // Set on the threads of the pools `with_vocabulary` runs searches on
+    static THREAD_VOCABULARY: RefCell<Option<Arc<ActiveVocabulary>>> = const { RefCell::new(None) };
+}
+
+/// Most vocabularies that keep a thread pool at a time; a server searching projects with
+/// many different vocabularies starts pools again as needed
+const MAX_VOCABULARY_POOLS: usize = 4;
+
+/// A rayon pool whose threads tokenize with the vocabulary of the given fingerprint
+type VocabularyPool = (u64, Arc<rayon::ThreadPool>);
+
+/// Pools for vocabularies other than the default, least recently used first
+static VOCABULARY_POOLS: Lazy<Mutex<Vec<VocabularyPool>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+/// The word lists tokenization on this thread uses
+pub(crate) fn active_vocabulary() -> Arc<ActiveVocabulary> {
+    THREAD_VOCABULARY
+        .with(|vocabulary| vocabulary.borrow().clone())
+        .unwrap_or_else(|| DEFAULT_VOCABULARY.clone())
+}
+
+/// Run `op` with project-specific stop words, exception terms, compound words and stem
+/// language applied to all tokenization it does, of queries and code alike, including in
+/// the rayon tasks it starts. The built-in lists stay in effect underneath. Other threads
+/// are not affected, so concurrent searches can use different vocabularies.
+pub fn with_vocabulary<R: Send>(
+    overrides: &VocabularyOverrides,
+    op: impl FnOnce() -> R + Send,
+) -> R {
+    let fingerprint = overrides.fingerprint();
+    if fingerprint == active_vocabulary().fingerprint {
+        return op();
+    }
+    // Tokenization looks the vocabulary up per thread, so the work runs on a pool whose
+    // threads all have it
+    vocabulary_pool(fingerprint, overrides).install(op)
+}
+
+fn vocabulary_pool(fingerprint: u64, overrides: &VocabularyOverrides) -> Arc<rayon::ThreadPool> {
+    let mut pools = VOCABULARY_POOLS.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(position) = pools.iter().position(|(f, _)| *f == fingerprint) {
+        let entry = pools.remove(position);
+        let pool = entry.1.clone();
+        pools.push(entry);
+        return pool;
+    }
+
+    let vocabulary = Arc::new(ActiveVocabulary::new(overrides));
+    let pool = rayon::ThreadPoolBuilder::new()
+        .thread_name(|index| format!("probe-vocabulary-{}", index))
+        .start_handler(move |_| {
+            THREAD_VOCABULARY.with(|current| *current.borrow_mut() = Some(vocabulary.clone()));
+        })
+        .build()
+        .map(Arc::new)
+        .expect("Failed to start tokenization threads");
+    if pools.len() >= MAX_VOCABULARY_POOLS {
+        pools.remove(0);
+    }
+    pools.push((fingerprint, pool.clone()));
+    pool
+}
+
+/// Identifies the word lists and stem language in effect, so that cached tokens computed
+/// with others are not reused
+pub fn vocabulary_fingerprint() -> u64 {
+    active_vocabulary().fingerprint
+}
+
 /// Static set of common English stop words
 static ENGLISH_STOP_WORDS: Lazy<HashSet<String>> = Lazy::new(|| {
     vec![
@@ -417,7 +577,9 @@ pub fn split_camel_case(input: &str) -> Vec<String> {
         let _potential_splits: Vec<String> = Vec::new();
 
         // Use the exception terms from our centralized list
-        let common_terms = EXCEPTION_TERMS
+        let vocabulary = active_vocabulary();
+        let common_terms = vocabulary
+            .exception_terms
             .iter()
             .map(|s| s.as_str())
             .collect::<Vec<_>>();
@@ -514,9 +676,10 @@ pub fn is_programming_stop_word(word: &str) -> bool {
     PROGRAMMING_STOP_WORDS.contains(word)
 }
 
-/// Checks if a word is either an English or programming stop word
+/// Checks if a word is either an English or programming stop word, or one added by the
+/// vocabulary in effect (see [`with_vocabulary`])
 pub fn is_stop_word(word: &str) -> bool {
-    is_english_stop_word(word) || is_programming_stop_word(word)
+    active_vocabulary().is_stop_word(word)
 }
 
 /// Attempts to split a compound word into its constituent parts using a vocabulary
@@ -527,14 +690,8 @@ pub fn split_compound_word(word: &str, vocab: &HashSet<String>) -> Vec<String> {
         return vec![word.to_lowercase()];
     }
 
-    // Use the exception terms from our centralized list
-    let common_terms = EXCEPTION_TERMS
-        .iter()
-        .map(|s| s.as_str())
-        .collect::<Vec<_>>();
-
-    // If the word is in common_terms, don't split it
-    if common_terms.contains(&word.to_lowercase().as_str()) {
+    // If the word is one of the exception terms, don't split it
+    if is_exception_term(word) {
         return vec![word.to_string()];
     }
 
@@ -553,9 +710,15 @@ pub fn split_compound_word(word: &str, vocab: &HashSet<String>) -> Vec<String> {
     }
 }
 
-/// Loads a vocabulary for compound word splitting
+/// Loads the vocabulary for compound word splitting: the built-in words plus those added
+/// by the vocabulary in effect (see [`with_vocabulary`])
+pub fn load_vocabulary() -> Arc<HashSet<String>> {
+    active_vocabulary().compound_words.clone()
+}
+
+/// The built-in vocabulary for compound word splitting
 /// This is a simplified version that could be expanded with a real dictionary
-pub fn load_vocabulary() -> &'static HashSet<String> {
+fn builtin_vocabulary() -> &'static HashSet<String> {
     static VOCABULARY: Lazy<HashSet<String>> = Lazy::new(|| {
         // This is a simplified vocabulary for demonstration
         // In a real application, this would be loaded from a file or database
@@ -1070,7 +1233,7 @@ pub fn tokenize_and_stem(keyword: &str) -> Vec<String> {
             .collect()
     } else {
         // Try compound word splitting
-        let compound_parts = split_compound_word(keyword, vocabulary);
+        let compound_parts = split_compound_word(keyword, &vocabulary);
 
         if compound_parts.len() > 1 {
             // Return stemmed compound parts, filtering out stop words
@@ -1100,8 +1263,33 @@ pub fn tokenize_and_stem(keyword: &str) -> Vec<String> {
 /// 7. Collect unique tokens
 /// 8. Exclude terms that were negated with a "-" prefix
 pub fn tokenize(text: &str) -> Vec<String> {
+    // Create a set to track unique tokens after processing
+    let mut processed_tokens = HashSet::new();
+    let mut result = Vec::new();
+
+    for_each_token(text, |_, token| {
+        if processed_tokens.insert(token.clone()) {
+            result.push(token);
+        }
+    });
+
+    result
+}
+
+/// Tokenizes text like [`tokenize`], but keeps every occurrence of a token together with
+/// its position in the text. Positions count the words left after stop word removal; the
+/// original and stemmed form of an exception term share a position.
+pub fn token_positions(text: &str) -> Vec<(usize, String)> {
+    let mut result = Vec::new();
+    for_each_token(text, |position, token| result.push((position, token)));
+    result
+}
+
+/// Run the tokenization steps described on [`tokenize`], passing each token and the
+/// position of the word it came from to `emit`
+fn for_each_token(text: &str, mut emit: impl FnMut(usize, String)) {
     let stemmer = get_stemmer();
-    let vocabulary = load_vocabulary();
+    let vocabulary = active_vocabulary();
 
     // Track negated terms to exclude them from the final result
     let mut negated_terms = HashSet::new();
@@ -1148,11 +1336,9 @@ pub fn tokenize(text: &str) -> Vec<String> {
         }
     }
 
-    // Create a set to track unique tokens after processing
-    let mut processed_tokens = HashSet::new();
-    let mut result = Vec::new();
+    let mut position = 0;
 
-    // Process each token: filter stop words, apply stemming, and add to result if unique
+    // Process each token: filter stop words and apply stemming
     for token in tokens {
         // Always try to split using camel case rules, even for lowercase tokens
         // This allows us to handle tokens that were already lowercased
@@ -1163,7 +1349,7 @@ pub fn tokenize(text: &str) -> Vec<String> {
             let lowercase_part = part.to_lowercase();
 
             // Skip both English and programming stop words
-            if is_stop_word(&lowercase_part) {
+            if vocabulary.is_stop_word(&lowercase_part) {
                 continue;
             }
 
@@ -1173,11 +1359,11 @@ pub fn tokenize(text: &str) -> Vec<String> {
             }
 
             // Try to split compound words
-            let compound_parts = split_compound_word(&lowercase_part, vocabulary);
+            let compound_parts = split_compound_word(&lowercase_part, &vocabulary.compound_words);
 
             for compound_part in compound_parts {
                 // Skip stop words in compound parts
-                if is_stop_word(&compound_part) {
+                if vocabulary.is_stop_word(&compound_part) {
                     continue;
                 }
 
@@ -1186,28 +1372,25 @@ pub fn tokenize(text: &str) -> Vec<String> {
                     continue;
                 }
 
+                let word_position = position;
+                position += 1;
+
                 // Preserve the original form for all exception terms
-                if is_exception_term(&compound_part)
-                    && processed_tokens.insert(compound_part.clone())
-                {
-                    result.push(compound_part.clone());
+                if vocabulary.exception_terms.contains(&compound_part) {
+                    emit(word_position, compound_part.clone());
                 }
 
-                // Also add the stemmed part if it's unique
+                // Also add the stemmed part
                 let stemmed_part = stemmer.stem(&compound_part).to_string();
                 // Skip if the stemmed version is a negated term
                 if negated_terms.contains(&stemmed_part) {
                     continue;
                 }
 
-                if processed_tokens.insert(stemmed_part.clone()) {
-                    result.push(stemmed_part);
-                }
+                emit(word_position, stemmed_part);
             }
         }
     }
-
-    result
 }
 
 #[cfg(test)]
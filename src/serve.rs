@@ -0,0 +1,298 @@
+//! Local HTTP JSON API.
+//!
+//! `probe serve` exposes search, query and extract as JSON endpoints. The server is a
+//! long-lived process, so the tree cache, the file-list cache and the tokenizer stay
+//! resident between requests. Responses use the same JSON as `--format json` on the CLI.
+//!
+//! `/extract` reads any file the user can, so the server only answers requests addressed
+//! to this machine (`Host`) that don't come from a web page on another site (`Origin`),
+//! and sends no CORS headers.
+
+use anyhow::Result;
+use colored::*;
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{IpAddr, SocketAddr, TcpListener, TcpStream};
+use std::thread;
+use std::time::Duration;
+
+use crate::config::{configure_search, ConfigOverrides};
+use crate::extract::format_extraction_results;
+use crate::mcp::{bool_arg, path_arg, run_extract, str_arg, strings_arg, usize_arg};
+use crate::query::{format_query_results, perform_query, QueryOptions};
+use crate::search::command_reranker::COMMAND_RERANKER_PREFIX;
+use crate::search::{format_search_results, perform_probe, AsSearchOptions, SearchRequest};
+
+/// Largest request body the server accepts
+const MAX_BODY_BYTES: usize = 10 * 1024 * 1024;
+
+/// How long a connection may stay idle while the request is read
+const READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// An HTTP error status with a message for the `error` field of the response
+type HttpError = (u16, String);
+
+/// Bind to `host:port` and serve requests until the process is stopped
+pub fn handle_serve(host: &str, port: u16) -> Result<()> {
+    let listener = TcpListener::bind((host, port))?;
+    println!(
+        "{} http://{}",
+        "Probe API listening on".bold().green(),
+        listener.local_addr()?
+    );
+    println!("Endpoints: GET /health, POST /search, POST /query, POST /extract");
+
+    serve(listener)
+}
+
+/// Serve requests from an already bound listener, one thread per connection
+pub fn serve(listener: TcpListener) -> Result<()> {
+    // Load the tokenizer vocabulary and BPE once, up front, instead of on the first request
+    crate::search::tokenization::tokenize("warmUp");
+    crate::search::search_tokens::count_tokens("warm up");
+
+    let local_addr = listener.local_addr()?;
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                thread::spawn(move || {
+                    if let Err(e) = handle_connection(stream, &local_addr) {
+                        eprintln!("Error handling request: {}", e);
+                    }
+                });
+            }
+            Err(e) => eprintln!("Error accepting connection: {}", e),
+        }
+    }
+
+    Ok(())
+}
+
+fn handle_connection(stream: TcpStream, local_addr: &SocketAddr) -> Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or("").to_string();
+    let target = parts.next().unwrap_or("").to_string();
+
+    let mut content_length = 0;
+    let mut host = None;
+    let mut origin = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            break;
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            let (name, value) = (name.trim(), value.trim().to_string());
+            if name.eq_ignore_ascii_case("Content-Length") {
+                content_length = value.parse().unwrap_or(0);
+            } else if name.eq_ignore_ascii_case("Host") {
+                host = Some(value);
+            } else if name.eq_ignore_ascii_case("Origin") {
+                origin = Some(value);
+            }
+        }
+    }
+
+    let local_request = host
+        .as_deref()
+        .is_some_and(|host| is_local_host(host, local_addr))
+        && origin.as_deref().is_none_or(|origin| {
+            origin
+                .split_once("://")
+                .is_some_and(|(_, authority)| is_local_host(authority, local_addr))
+        });
+
+    let (status, body) = if !local_request {
+        error_body((403, "Requests must come from this machine".to_string()))
+    } else if content_length > MAX_BODY_BYTES {
+        error_body((413, "Request body too large".to_string()))
+    } else {
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body)?;
+        handle_request(&method, &target, &body)
+    };
+
+    write_response(stream, status, &body)
+}
+
+fn write_response(mut stream: TcpStream, status: u16, body: &str) -> Result<()> {
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Payload Too Large",
+        _ => "Internal Server Error",
+    };
+
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\n\
+         Content-Type: application/json\r\n\
+         Content-Length: {}\r\n\
+         Connection: close\r\n\r\n{}",
+        status,
+        reason,
+        body.len(),
+        body
+    )?;
+    stream.flush()?;
+    Ok(())
+}
+
+/// Whether the host of a `Host` header or `Origin` authority (`localhost:8080`, `[::1]`) is
+/// a loopback name or address, or the address the server is bound to
+fn is_local_host(authority: &str, local_addr: &SocketAddr) -> bool {
+    let host = match authority.strip_prefix('[') {
+        Some(rest) => rest.split(']').next().unwrap_or_default(),
+        None => authority.split(':').next().unwrap_or_default(),
+    };
+    host.eq_ignore_ascii_case("localhost")
+        || host
+            .parse::<IpAddr>()
+            .is_ok_and(|ip| ip.is_loopback() || ip == local_addr.ip())
+}
+
+fn error_body((status, message): HttpError) -> (u16, String) {
+    (status, json!({ "error": message }).to_string())
+}
+
+/// Route one request and return the status code and JSON body of the response
+pub fn handle_request(method: &str, target: &str, body: &[u8]) -> (u16, String) {
+    let path = target.split('?').next().unwrap_or(target);
+
+    let result = match (method, path) {
+        ("GET", "/health") => Ok(json!({
+            "status": "ok",
+            "version": env!("CARGO_PKG_VERSION")
+        })
+        .to_string()),
+        ("POST", "/search") => parse_body(body).and_then(|args| search(&args)),
+        ("POST", "/query") => parse_body(body).and_then(|args| query(&args)),
+        ("POST", "/extract") => parse_body(body).and_then(|args| extract(&args)),
+        (_, "/health" | "/search" | "/query" | "/extract") => {
+            Err((405, format!("Method {} not allowed for {}", method, path)))
+        }
+        _ => Err((404, format!("Not found: {}", path))),
+    };
+
+    match result {
+        Ok(body) => (200, body),
+        Err(error) => error_body(error),
+    }
+}
+
+fn parse_body(body: &[u8]) -> Result<Value, HttpError> {
+    if body.is_empty() {
+        return Ok(json!({}));
+    }
+    serde_json::from_slice(body).map_err(|e| (400, format!("Invalid JSON body: {}", e)))
+}
+
+fn internal_error(e: anyhow::Error) -> HttpError {
+    (500, e.to_string())
+}
+
+fn search(arguments: &Value) -> Result<String, HttpError> {
+    let query = str_arg(arguments, "query").ok_or((400, "Query is required".to_string()))?;
+    let mut paths: Vec<_> = strings_arg(arguments, "paths")
+        .into_iter()
+        .map(Into::into)
+        .collect();
+    if paths.is_empty() {
+        paths.push(path_arg(arguments));
+    }
+    let reranker = str_arg(arguments, "reranker").unwrap_or("hybrid");
+    // Requests must not be able to start programs on the host
+    if reranker.starts_with(COMMAND_RERANKER_PREFIX) {
+        return Err((400, "External rerankers are not allowed".to_string()));
+    }
+
+    let request = SearchRequest {
+        paths,
+        queries: vec![query.to_string()],
+        files_only: bool_arg(arguments, "filesOnly"),
+        custom_ignores: strings_arg(arguments, "ignore"),
+        exclude_filenames: bool_arg(arguments, "excludeFilenames"),
+        reranker: reranker.to_string(),
+        frequency_search: arguments
+            .get("frequencySearch")
+            .and_then(Value::as_bool)
+            .unwrap_or(true),
+        exact: bool_arg(arguments, "exact"),
+        fuzzy: usize_arg(arguments, "fuzzy"),
+        language: str_arg(arguments, "language").map(str::to_string),
+        max_results: usize_arg(arguments, "maxResults"),
+        max_bytes: usize_arg(arguments, "maxBytes"),
+        max_tokens: usize_arg(arguments, "maxTokens"),
+        allow_tests: bool_arg(arguments, "allowTests"),
+        no_merge: bool_arg(arguments, "noMerge"),
+        merge_threshold: usize_arg(arguments, "mergeThreshold"),
+        session: str_arg(arguments, "session").map(str::to_string),
+        timeout: usize_arg(arguments, "timeout").unwrap_or(30) as u64,
+        ..SearchRequest::default()
+    };
+    let request = configure_search(request, &ConfigOverrides::default()).map_err(internal_error)?;
+
+    let limited_results = perform_probe(&request).map_err(internal_error)?;
+    let query_plan = request.as_search_options().query_plan();
+    let output =
+        format_search_results(&limited_results.results, false, "json", query_plan.as_ref());
+
+    // Add what the CLI reports outside of the JSON document
+    let mut response: Value =
+        serde_json::from_str(&output).map_err(|e| internal_error(e.into()))?;
+    response["session"] = json!(limited_results.session_id);
+    response["timed_out"] = json!(limited_results.timed_out);
+    response["skipped_files"] = json!(limited_results.skipped_files.len());
+
+    Ok(response.to_string())
+}
+
+fn query(arguments: &Value) -> Result<String, HttpError> {
+    let pattern = str_arg(arguments, "pattern").ok_or((400, "Pattern is required".to_string()))?;
+    let path = path_arg(arguments);
+    let ignore = strings_arg(arguments, "ignore");
+
+    let options = QueryOptions {
+        path: &path,
+        pattern,
+        language: str_arg(arguments, "language"),
+        ignore: &ignore,
+        allow_tests: bool_arg(arguments, "allowTests"),
+        max_results: usize_arg(arguments, "maxResults"),
+        format: "json",
+    };
+
+    let matches = perform_query(&options).map_err(internal_error)?;
+    format_query_results(&matches, "json").map_err(internal_error)
+}
+
+fn extract(arguments: &Value) -> Result<String, HttpError> {
+    if strings_arg(arguments, "files").is_empty() {
+        return Err((
+            400,
+            "Files array is required and must not be empty".to_string(),
+        ));
+    }
+
+    let (results, errors) = run_extract(arguments, "json").map_err(internal_error)?;
+    let output =
+        format_extraction_results(&results, "json", None, None, None).map_err(internal_error)?;
+
+    let mut response: Value =
+        serde_json::from_str(&output).map_err(|e| internal_error(e.into()))?;
+    response["errors"] = json!(errors);
+
+    Ok(response.to_string())
+}
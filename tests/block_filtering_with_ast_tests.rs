@@ -402,6 +402,9 @@ fn test_required_terms_query() {
                 required: true,
                 excluded: false,
                 exact: false,
+                proximity: None,
+                pattern: None,
+                boost: None,
             }),
             Box::new(elastic_query::Expr::Term {
                 keywords: vec!["white".to_string(), "list".to_string()],
@@ -409,6 +412,9 @@ fn test_required_terms_query() {
                 required: true,
                 excluded: false,
                 exact: false,
+                proximity: None,
+                pattern: None,
+                boost: None,
             }),
         )),
         Box::new(elastic_query::Expr::Term {
@@ -417,6 +423,9 @@ fn test_required_terms_query() {
             required: true,
             excluded: false,
             exact: false,
+            proximity: None,
+            pattern: None,
+            boost: None,
         }),
     );
     println!("Parsed AST: {:?}", ast);
@@ -428,12 +437,8 @@ fn test_required_terms_query() {
     indices.insert("secur".to_string(), 2);
     indices.insert("white".to_string(), 3);
 
-    let plan = probe::search::query::QueryPlan {
-        ast: ast.clone(),
-        term_indices: indices.clone(),
-        excluded_terms: HashSet::new(),
-        exact: false,
-    };
+    let plan =
+        probe::search::query::QueryPlan::new(ast.clone(), indices.clone(), HashSet::new(), false);
 
     // Use the term indices directly
     let term_indices = &indices;
@@ -4,7 +4,7 @@ use tempfile::TempDir;
 
 use probe::models::SearchResult;
 use probe::search::block_merging::merge_ranked_blocks;
-use probe::search::{perform_probe, SearchOptions};
+use probe::search::{perform_probe, SearchRequest};
 
 #[test]
 fn test_merge_ranked_blocks() {
@@ -16,7 +16,6 @@ fn test_merge_ranked_blocks() {
         code:
             "fn test_function() {\n    let x = 1;\n    let y = 2;\n    println!(\"{}\", x + y);\n}"
                 .to_string(),
-        matched_by_filename: None,
         rank: Some(1),
         score: Some(0.9),
         tfidf_score: Some(0.8),
@@ -31,17 +30,13 @@ fn test_merge_ranked_blocks() {
         file_match_rank: Some(1),
         block_unique_terms: Some(2),
         block_total_matches: Some(3),
-        parent_file_id: None,
-        block_id: None,
-        matched_keywords: None,
-        tokenized_content: None,
+        ..Default::default()
     };
     let block2 = SearchResult {
     file: "test_file.rs".to_string(),
     lines: (6, 10),
     node_type: "function".to_string(),
     code: "fn another_function() {\n    let z = 3;\n    let result = z * 2;\n    println!(\"{}\", result);\n}".to_string(),
-    matched_by_filename: None,
     rank: Some(2),
     score: Some(0.8),
     tfidf_score: Some(0.7),
@@ -56,10 +51,7 @@ fn test_merge_ranked_blocks() {
     file_match_rank: Some(1),
     block_unique_terms: Some(2),
     block_total_matches: Some(2),
-    parent_file_id: None,
-    block_id: None,
-    matched_keywords: None,
-    tokenized_content: None,
+    ..Default::default()
 };
 
     // Create block from a different file that should not be merged
@@ -68,7 +60,6 @@ fn test_merge_ranked_blocks() {
         lines: (1, 5),
         node_type: "function".to_string(),
         code: "fn other_function() {\n    let a = 10;\n    let b = 20;\n    println!(\"{}\", a + b);\n}".to_string(),
-        matched_by_filename: None,
         rank: Some(3),
         score: Some(0.7),
         tfidf_score: Some(0.6),
@@ -83,10 +74,7 @@ fn test_merge_ranked_blocks() {
         file_match_rank: Some(2),
         block_unique_terms: Some(1),
         block_total_matches: Some(3),
-        parent_file_id: None,
-        block_id: None,
-        matched_keywords: None,
-        tokenized_content: None,
+        ..Default::default()
     };
 
     // Create a vector with all blocks
@@ -157,6 +145,10 @@ fn test_merge_ranked_blocks() {
         (1, 5),
         "Unmerged block should preserve its line range"
     );
+
+    // Merged results keep the ranked order across files
+    let ranks: Vec<_> = merged_blocks.iter().map(|b| b.rank).collect();
+    assert_eq!(ranks, vec![Some(1), Some(3)]);
 }
 
 #[test]
@@ -169,30 +161,14 @@ fn test_integration_with_search_flow() {
     create_test_files(temp_path);
 
     // Create search query that matches the actual function names in test files
-    let queries = vec!["test_function".to_string()];
-    let custom_ignores: Vec<String> = vec![];
-
-    // Create SearchOptions
-    let options = SearchOptions {
-        path: temp_path,
-        queries: &queries,
-        files_only: false,
-        custom_ignores: &custom_ignores,
-        exclude_filenames: false,
-        language: None,
-        reranker: "combined",
-        frequency_search: false,
-        max_results: None,
-        max_bytes: None,
-        max_tokens: None,
-        allow_tests: true,
-        no_merge: false,
-        merge_threshold: Some(20), // Increase threshold to ensure non-adjacent blocks are merged
-        dry_run: false,
-        session: None,
-        timeout: 30,
-        exact: false,
-    };
+
+    // Create the search request
+    let options = SearchRequest::new("test_function")
+        .path(temp_path)
+        .reranker("combined")
+        .allow_tests(true)
+        // Increase threshold to ensure non-adjacent blocks are merged
+        .merge_threshold(20);
 
     // Run a search that should produce multiple overlapping blocks
     let search_results = perform_probe(&options).unwrap();
@@ -282,55 +258,26 @@ fn test_no_merge_flag() {
     create_test_files(temp_path);
 
     // Create search query that matches the actual function names in test files
-    let queries = vec!["test_function".to_string()];
-    let custom_ignores: Vec<String> = vec![];
 
     // First test with merging enabled (no_merge = false)
-    let options_with_merge = SearchOptions {
-        path: temp_path,
-        queries: &queries,
-        files_only: false,
-        custom_ignores: &custom_ignores,
-        exclude_filenames: false,
-        language: None,
-        reranker: "combined",
-        frequency_search: false,
-        max_results: None,
-        max_bytes: None,
-        max_tokens: None,
-        allow_tests: true,
-        no_merge: false,
-        merge_threshold: Some(20), // Increase threshold to ensure non-adjacent blocks are merged
-        dry_run: false,
-        session: None,
-        timeout: 30,
-        exact: false,
-    };
+    let options_with_merge = SearchRequest::new("test_function")
+        .path(temp_path)
+        .reranker("combined")
+        .allow_tests(true)
+        // Increase threshold to ensure non-adjacent blocks are merged
+        .merge_threshold(20);
 
     // Run a search that should produce merged blocks
     let merged_results = perform_probe(&options_with_merge).unwrap();
 
     // Now test with merging disabled (no_merge = true)
-    let options_without_merge = SearchOptions {
-        path: temp_path,
-        queries: &queries,
-        files_only: false,
-        custom_ignores: &custom_ignores,
-        exclude_filenames: false,
-        language: None,
-        reranker: "combined",
-        frequency_search: false,
-        max_results: None,
-        max_bytes: None,
-        max_tokens: None,
-        allow_tests: true,
-        no_merge: true,
-        merge_threshold: Some(20), // Increase threshold to ensure non-adjacent blocks are merged
-        dry_run: false,
-        session: None,
-        timeout: 30,
-        exact: false,
-    };
+    let options_without_merge = SearchRequest::new("test_function")
+        .path(temp_path)
+        .reranker("combined")
+        .allow_tests(true)
+        .no_merge(true)
+        // Increase threshold to ensure non-adjacent blocks are merged
+        .merge_threshold(20);
 
     // Run a search that should not merge blocks
     let unmerged_results = perform_probe(&options_without_merge).unwrap();
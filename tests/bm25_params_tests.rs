@@ -0,0 +1,125 @@
+use std::fs;
+use tempfile::TempDir;
+
+use probe::config::ProjectConfig;
+use probe::ranking::Bm25Params;
+use probe::{perform_probe, SearchRequest};
+
+#[test]
+fn test_config_is_found_in_parent_directories() {
+    let temp_dir = TempDir::new().unwrap();
+    let nested = temp_dir.path().join("src").join("deep");
+    fs::create_dir_all(&nested).unwrap();
+
+    fs::write(
+        temp_dir.path().join(".probe.json"),
+        r#"{"ranking": {"bm25_k1": 2.0, "filename_weight": 0.5}}"#,
+    )
+    .unwrap();
+    let found = ProjectConfig::find(&nested).expect("Config should be found");
+    assert_eq!(
+        found,
+        temp_dir.path().canonicalize().unwrap().join(".probe.json")
+    );
+
+    let config = ProjectConfig::for_path(&nested).unwrap();
+    assert_eq!(config.ranking.bm25_k1, Some(2.0));
+    assert_eq!(config.ranking.bm25_b, None);
+}
+
+#[test]
+fn test_command_line_overrides_config() {
+    let config: ProjectConfig =
+        serde_json::from_str(r#"{"ranking": {"bm25_k1": 2.0, "bm25_b": 0.5}}"#).unwrap();
+
+    let params = config.bm25_params(None, None, None).unwrap();
+    assert_eq!(
+        params,
+        Bm25Params {
+            k1: 2.0,
+            b: 0.5,
+            filename_weight: 1.0
+        }
+    );
+
+    let params = config.bm25_params(Some(0.8), None, Some(3.0)).unwrap();
+    assert_eq!(
+        (params.k1, params.b, params.filename_weight),
+        (0.8, 0.5, 3.0)
+    );
+
+    assert_eq!(
+        ProjectConfig::default()
+            .bm25_params(None, None, None)
+            .unwrap(),
+        Bm25Params::default()
+    );
+}
+
+#[test]
+fn test_invalid_config_is_rejected() {
+    assert!(ProjectConfig::default()
+        .bm25_params(None, Some(1.5), None)
+        .is_err());
+    assert!(ProjectConfig::default()
+        .bm25_params(Some(-1.0), None, None)
+        .is_err());
+
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join(".probe.json");
+    fs::write(&path, r#"{"ranking": {"bm25_k": 2.0}}"#).unwrap();
+    assert!(ProjectConfig::load(&path).is_err());
+}
+
+#[test]
+fn test_search_uses_bm25_params() {
+    let temp_dir = TempDir::new().unwrap();
+    // Same code, so only the file name tells the blocks apart
+    fs::write(
+        temp_dir.path().join("session.rs"),
+        "fn load() {\n    let session = open();\n}\n",
+    )
+    .unwrap();
+    fs::write(
+        temp_dir.path().join("other.rs"),
+        "fn load() {\n    let session = open();\n}\n",
+    )
+    .unwrap();
+
+    let search = |bm25: Bm25Params| {
+        let request = SearchRequest::new("session")
+            .path(temp_dir.path())
+            .no_merge(true)
+            .explain(true)
+            .bm25(bm25);
+        perform_probe(&request).unwrap().results
+    };
+
+    let bm25 = Bm25Params {
+        k1: 2.0,
+        b: 0.3,
+        filename_weight: 4.0,
+    };
+    let results = search(bm25);
+    assert_eq!(results.len(), 2);
+    assert!(results[0].file.ends_with("session.rs"));
+    let explanation = results[0]
+        .explanation
+        .as_ref()
+        .unwrap()
+        .bm25
+        .as_ref()
+        .unwrap();
+    assert_eq!((explanation.k1, explanation.b), (2.0, 0.3));
+    assert_eq!(explanation.filename_weight, 4.0);
+    // One match in the code plus the file name counted four times
+    assert_eq!(explanation.terms[0].tf, 5.0);
+
+    // Weighting the file name down removes its advantage
+    let results = search(Bm25Params {
+        filename_weight: 0.0,
+        ..bm25
+    });
+    let scores: Vec<f64> = results.iter().map(|r| r.score.unwrap()).collect();
+    assert!((scores[0] - scores[1]).abs() < 1e-9);
+}
@@ -343,7 +343,7 @@ fn test_cli_custom_ignores() {
 
     // Find where "search.js" appears in the debug output
     if let Some(pos) = stdout.find("search.js") {
-        let start = if pos > 50 { pos - 50 } else { 0 };
+        let start = pos.saturating_sub(50);
         let end = if pos + 50 < stdout.len() {
             pos + 50
         } else {
@@ -421,3 +421,92 @@ fn test_cli_max_results() {
         "Should find only 1 result"
     );
 }
+
+#[test]
+fn test_cli_rejects_large_fuzzy_distance() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    create_test_directory_structure(&temp_dir);
+
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "search",
+            "search",
+            temp_dir.path().to_str().unwrap(),
+            "--fuzzy=3",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("expected at most 2"),
+        "Should explain the limit: {}",
+        stderr
+    );
+}
+
+#[test]
+fn test_cli_rejects_invalid_boost_recent_half_life() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    create_test_directory_structure(&temp_dir);
+
+    for half_life in ["0", "-7", "NaN", "inf"] {
+        let output = Command::new("cargo")
+            .args([
+                "run",
+                "--",
+                "search",
+                "search",
+                temp_dir.path().to_str().unwrap(),
+                &format!("--boost-recent={}", half_life),
+            ])
+            .output()
+            .expect("Failed to execute command");
+
+        assert!(!output.status.success(), "{} should be rejected", half_life);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(
+            stderr.contains("expected a number of days above 0"),
+            "Should explain the limit: {}",
+            stderr
+        );
+    }
+}
+
+#[test]
+fn test_cli_rejects_invalid_ranking_options() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    create_test_directory_structure(&temp_dir);
+
+    for (option, expected) in [
+        ("--diversity=1.5", "expected a number from 0 to 1"),
+        ("--diversity=NaN", "expected a number from 0 to 1"),
+        ("--max-per-file=0", "expected at least 1"),
+        ("--centrality-weight=-1", "expected a number of 0 or more"),
+        ("--centrality-weight=inf", "expected a number of 0 or more"),
+    ] {
+        let output = Command::new("cargo")
+            .args([
+                "run",
+                "--",
+                "search",
+                "search",
+                temp_dir.path().to_str().unwrap(),
+                option,
+            ])
+            .output()
+            .expect("Failed to execute command");
+
+        assert!(!output.status.success(), "{} should be rejected", option);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(
+            stderr.contains(expected),
+            "Should explain the limit of {}: {}",
+            option,
+            stderr
+        );
+    }
+}
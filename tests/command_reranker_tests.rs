@@ -0,0 +1,202 @@
+#![cfg(unix)]
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+
+use probe::models::SearchResult;
+use probe::ranking::{Bm25Reranker, Reranker};
+use probe::search::command_reranker::CommandReranker;
+use probe::{perform_probe, SearchRequest};
+
+// Helper function to write an executable fake reranker that saves its input to
+// `request.json` and answers with `response`
+fn fake_reranker(dir: &Path, response: &str) -> String {
+    let script = dir.join("rerank.sh");
+    fs::write(
+        &script,
+        format!(
+            "#!/bin/sh\ncat > '{}'\necho '{}'\n",
+            dir.join("request.json").display(),
+            response
+        ),
+    )
+    .unwrap();
+    fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+    script.display().to_string()
+}
+
+// Helper function to search a small file with two matching functions
+fn search(reranker: &str) -> (TempDir, Vec<SearchResult>) {
+    search_request(SearchRequest::new("parse header").reranker(reranker))
+}
+
+// Helper function to run `request` on a small file with two matching functions
+fn search_request(request: SearchRequest) -> (TempDir, Vec<SearchResult>) {
+    let temp_dir = TempDir::new().unwrap();
+    let src = temp_dir.path().join("src");
+    fs::create_dir(&src).unwrap();
+    fs::write(
+        src.join("parser.rs"),
+        "fn parse_header(input: &str) -> usize {\n    input.len()\n}\n\nfn parse_body(input: &str) -> usize {\n    parse_header(input)\n}\n",
+    )
+    .unwrap();
+
+    let request = request.path(&src).exclude_filenames(true).no_merge(true);
+    let results = perform_probe(&request).unwrap().results;
+    (temp_dir, results)
+}
+
+fn first_line(result: &SearchResult) -> &str {
+    result.code.lines().next().unwrap()
+}
+
+#[test]
+fn test_command_reranker_receives_candidates() {
+    let temp_dir = TempDir::new().unwrap();
+    let script = fake_reranker(temp_dir.path(), r#"{"scores": [1.0, 2.0]}"#);
+    search(&format!("cmd:{}", script));
+
+    let request: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(temp_dir.path().join("request.json")).unwrap())
+            .unwrap();
+    assert_eq!(request["query"], "parse header");
+    let candidates = request["candidates"].as_array().unwrap();
+    assert_eq!(candidates.len(), 2);
+    for (id, candidate) in candidates.iter().enumerate() {
+        assert_eq!(candidate["id"], id);
+        assert!(candidate["file"].as_str().unwrap().ends_with("parser.rs"));
+        assert!(candidate["code"].as_str().unwrap().starts_with("fn parse_"));
+        assert_eq!(candidate["lines"].as_array().unwrap().len(), 2);
+        assert!(candidate["scores"]["bm25"].as_f64().unwrap() > 0.0);
+    }
+}
+
+#[test]
+fn test_command_reranker_receives_query_as_written() {
+    let temp_dir = TempDir::new().unwrap();
+    let script = fake_reranker(temp_dir.path(), r#"{"scores": [1.0, 2.0]}"#);
+    let results = search_request(
+        SearchRequest::new("parse headr")
+            .fuzzy(1)
+            .reranker(format!("cmd:{}", script)),
+    )
+    .1;
+    // BM25 still picks the candidates with the fuzzy terms
+    assert_eq!(results.len(), 2);
+
+    let request: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(temp_dir.path().join("request.json")).unwrap())
+            .unwrap();
+    assert_eq!(request["query"], "parse headr");
+}
+
+#[test]
+fn test_command_reranker_scores_reorder_results() {
+    let temp_dir = TempDir::new().unwrap();
+    let script = fake_reranker(temp_dir.path(), r#"{"scores": [1.0, 5.0]}"#);
+    let results = search(&format!("cmd:{}", script)).1;
+    let request: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(temp_dir.path().join("request.json")).unwrap())
+            .unwrap();
+
+    // The second candidate got the higher score, so it comes first even if the first one
+    // earns the definition boost
+    let preferred = request["candidates"][1]["code"].as_str().unwrap();
+    assert_eq!(results.len(), 2);
+    assert_eq!(first_line(&results[0]), preferred.lines().next().unwrap());
+    assert_eq!(results[0].rank, Some(1));
+}
+
+#[test]
+fn test_command_reranker_order_puts_listed_candidates_first() {
+    let temp_dir = TempDir::new().unwrap();
+    let script = fake_reranker(temp_dir.path(), r#"{"order": [1]}"#);
+    let results = search(&format!("cmd:{}", script)).1;
+    let request: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(temp_dir.path().join("request.json")).unwrap())
+            .unwrap();
+
+    // The candidate left out of the ordering is kept, after the listed one
+    let listed = request["candidates"][1]["code"].as_str().unwrap();
+    assert_eq!(results.len(), 2);
+    assert_eq!(first_line(&results[0]), listed.lines().next().unwrap());
+}
+
+#[test]
+fn test_command_reranker_failure_keeps_bm25_order() {
+    let bm25 = search("bm25").1;
+
+    let temp_dir = TempDir::new().unwrap();
+    let script = fake_reranker(temp_dir.path(), "not json");
+    let results = search(&format!("cmd:{}", script)).1;
+    assert_eq!(results.len(), bm25.len());
+    assert_eq!(first_line(&results[0]), first_line(&bm25[0]));
+
+    let results = search("cmd:/nonexistent/reranker").1;
+    assert_eq!(results.len(), bm25.len());
+}
+
+#[test]
+fn test_command_reranker_arguments_can_be_quoted() {
+    let temp_dir = TempDir::new().unwrap();
+    let script = temp_dir.path().join("rerank.sh");
+    fs::write(
+        &script,
+        format!(
+            "#!/bin/sh\nprintf '%s' \"$1\" > '{}'\necho '{{\"order\": [1]}}'\n",
+            temp_dir.path().join("argument.txt").display()
+        ),
+    )
+    .unwrap();
+    fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+
+    search(&format!("cmd:'{}' \"my model\"", script.display()));
+    assert_eq!(
+        fs::read_to_string(temp_dir.path().join("argument.txt")).unwrap(),
+        "my model"
+    );
+}
+
+#[test]
+fn test_command_reranker_is_killed_after_its_timeout() {
+    let results = vec![
+        SearchResult {
+            file: "a.rs".to_string(),
+            code: "fn parse_header() {}".to_string(),
+            ..Default::default()
+        },
+        SearchResult {
+            file: "b.rs".to_string(),
+            code: "fn parse_header() { parse_header() }".to_string(),
+            ..Default::default()
+        },
+    ];
+    let bm25 = Bm25Reranker::default().score("parse header", &results);
+
+    let start = Instant::now();
+    let reranker = CommandReranker::new("sleep 30").timeout(Duration::from_millis(200));
+    let scores = reranker.score("parse header", &results);
+    assert!(start.elapsed() < Duration::from_secs(10));
+    assert_eq!(scores, bm25);
+}
+
+#[test]
+fn test_command_reranker_is_killed_when_the_search_times_out() {
+    let start = Instant::now();
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("parser.rs"),
+        "fn parse_header(input: &str) -> usize {\n    input.len()\n}\n",
+    )
+    .unwrap();
+    let request = SearchRequest::new("parse header")
+        .path(temp_dir.path())
+        .reranker("cmd:sleep 30")
+        .timeout(1);
+    let results = perform_probe(&request).unwrap();
+    assert!(start.elapsed() < Duration::from_secs(10));
+    assert_eq!(results.results.len(), 1);
+}
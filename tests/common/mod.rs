@@ -0,0 +1,55 @@
+//! Helpers shared by the integration tests
+//!
+//! Each test binary compiles this module on its own and uses only part of it.
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+use probe::{perform_probe, SearchRequest, SearchResult};
+
+/// Write `files`, given as paths relative to `root` and their contents, creating
+/// missing directories
+pub fn write_files(root: &Path, files: &[(&str, &str)]) {
+    for (path, content) in files {
+        let path = root.join(path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).unwrap();
+        }
+        fs::write(path, content).unwrap();
+    }
+}
+
+/// A temporary project directory holding `files` (see [`write_files`])
+pub fn create_project(files: &[(&str, &str)]) -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    write_files(temp_dir.path(), files);
+    temp_dir
+}
+
+/// The blocks `query` matches in `temp_dir` (see [`search_request`])
+pub fn search(temp_dir: &TempDir, query: &str) -> Vec<SearchResult> {
+    search_request(SearchRequest::new(query).path(temp_dir.path()))
+}
+
+/// Run `request` without file name matches and without merging blocks, so results are
+/// the blocks whose contents matched
+pub fn search_request(request: SearchRequest) -> Vec<SearchResult> {
+    perform_probe(&request.exclude_filenames(true).no_merge(true))
+        .unwrap()
+        .results
+}
+
+/// The name of the file `result` is from, without its directories
+pub fn file_name(result: &SearchResult) -> &str {
+    result.file.rsplit('/').next().unwrap()
+}
+
+/// The names of the files in `results`, sorted and without duplicates
+pub fn files(results: &[SearchResult]) -> Vec<String> {
+    let mut files: Vec<String> = results.iter().map(|r| file_name(r).to_string()).collect();
+    files.sort();
+    files.dedup();
+    files
+}
@@ -6,7 +6,7 @@ use tempfile::TempDir;
 use probe::search::elastic_query::parse_query_test as parse_query;
 use probe::search::file_processing::filter_code_block_with_ast;
 use probe::search::query::create_query_plan;
-use probe::search::{perform_probe, SearchOptions};
+use probe::search::{perform_probe, SearchRequest};
 
 /// Test complex boolean expressions for block filtering
 #[test]
@@ -287,30 +287,13 @@ func (i *IPWhiteListMiddleware) Process() {
 
     // Test query with stemming: "ips AND whitelisting"
     let query = "ips AND whitelisting";
-    let queries = vec![query.to_string()];
-    let custom_ignores: Vec<String> = vec![];
-
-    // Create SearchOptions with stemming enabled (exact=false)
-    let options = SearchOptions {
-        path: temp_path,
-        queries: &queries,
-        files_only: false,
-        custom_ignores: &custom_ignores,
-        exclude_filenames: false,
-        language: None,
-        reranker: "hybrid",
-        frequency_search: true,
-        max_results: None,
-        max_bytes: None,
-        max_tokens: None,
-        allow_tests: true,
-        no_merge: true,
-        merge_threshold: None,
-        dry_run: false,
-        session: None,
-        timeout: 30,
-        exact: false,
-    };
+
+    // Create the search request with stemming enabled (exact=false)
+    let options = SearchRequest::new(query)
+        .path(temp_path)
+        .reranker("hybrid")
+        .allow_tests(true)
+        .no_merge(true);
 
     // Run the search
     let search_results = perform_probe(&options).unwrap();
@@ -366,30 +349,13 @@ func Process() {
     // Test query that should match the filename and content
     // Use the exact terms that appear in the content
     let query = "ip AND whitelist"; // Changed to use OR semantics (space instead of AND)
-    let queries = vec![query.to_string()];
-    let custom_ignores: Vec<String> = vec![];
-
-    // Create SearchOptions with filename matching enabled
-    let options = SearchOptions {
-        path: temp_path,
-        queries: &queries,
-        files_only: false,
-        custom_ignores: &custom_ignores,
-        exclude_filenames: false, // Include filenames in search
-        language: None,
-        reranker: "hybrid",
-        frequency_search: true,
-        max_results: None,
-        max_bytes: None,
-        max_tokens: None,
-        allow_tests: true,
-        no_merge: true,
-        merge_threshold: None,
-        dry_run: false,
-        session: None,
-        timeout: 30,
-        exact: false,
-    };
+
+    // Create the search request with filename matching enabled
+    let options = SearchRequest::new(query)
+        .path(temp_path)
+        .reranker("hybrid")
+        .allow_tests(true)
+        .no_merge(true);
 
     // Run the search
     let search_results = perform_probe(&options).unwrap();
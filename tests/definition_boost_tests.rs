@@ -0,0 +1,105 @@
+mod common;
+
+use std::path::Path;
+use std::process::Command;
+use tempfile::TempDir;
+
+use common::{search_request, write_files};
+use probe::SearchRequest;
+
+/// One file defines `SessionCache`, another mentions it several times
+fn create_test_files(root: &Path) {
+    write_files(
+        root,
+        &[
+            (
+                "store.rs",
+                r#"
+pub struct SessionCache {
+    entries: Vec<String>,
+    capacity: usize,
+    hits: u64,
+    misses: u64,
+    evictions: u64,
+    created_at: std::time::Instant,
+    last_cleanup: std::time::Instant,
+}
+"#,
+            ),
+            (
+                "users.rs",
+                r#"
+pub fn load_users(cache: &SessionCache) -> usize {
+    // Read from the SessionCache, falling back to a fresh SessionCache
+    let sessions: &SessionCache = cache;
+    let fallback = SessionCache::default();
+    sessions.len() + fallback.len()
+}
+"#,
+            ),
+        ],
+    );
+}
+
+fn search(request: SearchRequest, root: &Path) -> Vec<probe::SearchResult> {
+    search_request(request.path(root))
+}
+
+#[test]
+fn test_definition_outranks_mentions() {
+    let temp_dir = TempDir::new().unwrap();
+    create_test_files(temp_dir.path());
+
+    let request = SearchRequest::new("SessionCache");
+    let results = search(request.clone().definition_boost(1.0), temp_dir.path());
+    assert!(results[0].file.ends_with("users.rs"));
+    assert!(results.iter().all(|r| r.definition_boost.is_none()));
+
+    let results = search(request, temp_dir.path());
+    assert!(results[0].file.ends_with("store.rs"));
+    assert_eq!(results[0].symbol_name.as_deref(), Some("SessionCache"));
+    assert_eq!(results[0].definition_boost, Some(1.5));
+
+    // Blocks that declare some other name are not boosted
+    let users = results
+        .iter()
+        .find(|r| r.file.ends_with("users.rs"))
+        .unwrap();
+    assert_eq!(users.symbol_name.as_deref(), Some("load_users"));
+    assert_eq!(users.definition_boost, None);
+}
+
+#[test]
+fn test_cli_json_shows_definition_boost() {
+    let temp_dir = TempDir::new().unwrap();
+    create_test_files(temp_dir.path());
+
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "search",
+            "SessionCache",
+            temp_dir.path().to_str().unwrap(),
+            "--format",
+            "json",
+            "--exclude-filenames",
+            "--definition-boost",
+            "3",
+        ])
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let json_start = stdout.find('{').expect("Output should contain JSON");
+    let json: serde_json::Value = serde_json::from_str(&stdout[json_start..]).unwrap();
+    let definition = json["results"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|r| r["file"].as_str().unwrap().ends_with("store.rs"))
+        .expect("The definition should be found");
+    assert_eq!(definition["symbol_name"], "SessionCache");
+    assert_eq!(definition["definition_boost"], 3.0);
+}
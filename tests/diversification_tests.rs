@@ -0,0 +1,98 @@
+mod common;
+
+use std::path::Path;
+use tempfile::TempDir;
+
+use common::{file_name, search_request, write_files};
+use probe::{perform_probe, SearchRequest};
+
+/// Three copy-pasted functions in one file and a different one in another file
+fn create_test_files(root: &Path) {
+    let copies: String = (0..3)
+        .map(|i| {
+            format!(
+                r#"
+pub fn parse_config_{i}(path: &str) -> Config {{
+    let text = std::fs::read_to_string(path).unwrap();
+    Config::parse(&text).unwrap()
+}}
+"#
+            )
+        })
+        .collect();
+    write_files(
+        root,
+        &[
+            ("copies.rs", &copies),
+            (
+                "loader.rs",
+                r#"
+pub fn load(settings: &Settings) -> Config {
+    // Parse the config from settings, falling back to defaults
+    settings.config().unwrap_or_default()
+}
+"#,
+            ),
+        ],
+    );
+}
+
+fn files(request: SearchRequest, root: &Path) -> Vec<String> {
+    search_request(request.path(root))
+        .iter()
+        .map(|r| file_name(r).to_string())
+        .collect()
+}
+
+#[test]
+fn test_diversity_pushes_down_near_duplicates() {
+    let temp_dir = TempDir::new().unwrap();
+    create_test_files(temp_dir.path());
+
+    let request = SearchRequest::new("parse config");
+    assert_eq!(
+        files(request.clone(), temp_dir.path()),
+        ["copies.rs", "copies.rs", "copies.rs", "loader.rs"]
+    );
+    assert_eq!(
+        files(request.clone().diversity(0.7), temp_dir.path()),
+        ["copies.rs", "loader.rs", "copies.rs", "copies.rs"]
+    );
+
+    // Diversification runs before limits, so the budget is spent on distinct blocks
+    assert_eq!(
+        files(request.diversity(0.7).max_results(2), temp_dir.path()),
+        ["copies.rs", "loader.rs"]
+    );
+}
+
+#[test]
+fn test_max_per_file_caps_results() {
+    let temp_dir = TempDir::new().unwrap();
+    create_test_files(temp_dir.path());
+
+    let request = SearchRequest::new("parse config").max_per_file(2);
+    assert_eq!(
+        files(request, temp_dir.path()),
+        ["copies.rs", "copies.rs", "loader.rs"]
+    );
+}
+
+#[test]
+fn test_invalid_diversity_and_max_per_file_are_rejected() {
+    let temp_dir = TempDir::new().unwrap();
+    create_test_files(temp_dir.path());
+
+    let request = SearchRequest::new("parse config").path(temp_dir.path());
+    for diversity in [-0.5, 1.5, f64::NAN] {
+        let error = perform_probe(&request.clone().diversity(diversity)).unwrap_err();
+        assert!(
+            error.to_string().contains("Diversity"),
+            "{}: {}",
+            diversity,
+            error
+        );
+    }
+    let error = perform_probe(&request.max_per_file(0)).unwrap_err();
+    assert!(error.to_string().contains("Results per file"), "{}", error);
+}
@@ -5,7 +5,7 @@ use tempfile::TempDir;
 
 use probe::search::elastic_query::Expr;
 use probe::search::query::QueryPlan;
-use probe::search::{perform_probe, SearchOptions};
+use probe::search::{perform_probe, SearchRequest};
 
 /// Create test files with different content for testing queries
 fn create_test_files(temp_dir: &Path) {
@@ -93,32 +93,16 @@ fn test_required_term_query() {
     // Since we've removed any_term parameter and changed the default to AND,
     // we need to use explicit OR syntax to maintain the original behavior
     let queries = vec!["keywordAlpha OR keywordBeta OR keywordGamma".to_string()];
-    let custom_ignores: Vec<String> = vec![];
 
     // Print the query for debugging
     println!("Testing query: {:?}", queries);
 
-    // Create SearchOptions
-    let options = SearchOptions {
-        path: temp_path,
-        queries: &queries,
-        files_only: false,
-        custom_ignores: &custom_ignores,
-        exclude_filenames: false,
-        language: None,
-        reranker: "hybrid",
-        frequency_search: false,
-        max_results: None,
-        max_bytes: None,
-        max_tokens: None,
-        allow_tests: true,
-        no_merge: false,
-        merge_threshold: Some(5),
-        dry_run: false,
-        session: None,
-        timeout: 30,
-        exact: false,
-    };
+    // Create the search request
+    let options = SearchRequest::new(queries[0].as_str())
+        .path(temp_path)
+        .reranker("hybrid")
+        .allow_tests(true)
+        .merge_threshold(5);
 
     // Print the temp_path for debugging
     println!("Temp path: {:?}", temp_path);
@@ -189,7 +173,6 @@ fn test_excluded_term_query() {
 
     // Create search query with an excluded term
     let queries = vec!["(key OR word OR keyword) -keywordGamma".to_string()];
-    let custom_ignores: Vec<String> = vec![];
 
     // Print the test files for debugging
     println!("Test files created in: {:?}", temp_path);
@@ -206,33 +189,18 @@ fn test_excluded_term_query() {
         );
     }
 
-    // Create SearchOptions
-    let options = SearchOptions {
-        path: temp_path,
-        queries: &queries,
-        files_only: false,
-        custom_ignores: &custom_ignores,
-        exclude_filenames: false,
-        language: None,
-        reranker: "hybrid",
-        frequency_search: false,
-        max_results: None,
-        max_bytes: None,
-        max_tokens: None,
-        allow_tests: true,
-        no_merge: false,
-        merge_threshold: Some(5),
-        dry_run: false,
-        session: None,
-        timeout: 30,
-        exact: false,
-    };
+    // Create the search request
+    let options = SearchRequest::new(queries[0].as_str())
+        .path(temp_path)
+        .reranker("hybrid")
+        .allow_tests(true)
+        .merge_threshold(5);
 
     // Print the query for debugging
     println!("Executing search with query: {:?}", queries);
     println!(
-        "Path: {:?}, frequency_search: {}",
-        options.path, options.frequency_search
+        "Paths: {:?}, frequency_search: {}",
+        options.paths, options.frequency_search
     );
 
     // Run the search
@@ -296,29 +264,15 @@ fn test_or_query() {
     // Create search query with explicit OR syntax
     // Make sure to use uppercase OR to ensure it's recognized as an operator
     let queries = vec!["keywordAlpha OR keywordBeta".to_string()];
-    let custom_ignores: Vec<String> = vec![];
-
-    // Create SearchOptions
-    let options = SearchOptions {
-        path: temp_path,
-        queries: &queries,
-        files_only: true, // Use files_only to ensure we find all matching files
-        custom_ignores: &custom_ignores,
-        exclude_filenames: false,
-        language: None,
-        reranker: "hybrid",
-        frequency_search: true, // Enable frequency search to improve matching
-        max_results: None,
-        max_bytes: None,
-        max_tokens: None,
-        allow_tests: true,
-        no_merge: false,
-        merge_threshold: Some(5),
-        dry_run: false,
-        session: None,
-        timeout: 30,
-        exact: false,
-    };
+
+    // Create the search request
+    let options = SearchRequest::new(queries[0].as_str())
+        .path(temp_path)
+        // Use files_only to ensure we find all matching files
+        .files_only(true)
+        .reranker("hybrid")
+        .allow_tests(true)
+        .merge_threshold(5);
 
     // Print the test files for debugging
     println!("Test files created in: {:?}", temp_path);
@@ -338,8 +292,8 @@ fn test_or_query() {
     // Print the query for debugging
     println!("Executing search with query: {:?}", queries);
     println!(
-        "Path: {:?}, frequency_search: {}",
-        options.path, options.frequency_search
+        "Paths: {:?}, frequency_search: {}",
+        options.paths, options.frequency_search
     );
 
     // Run the search
@@ -396,29 +350,13 @@ fn test_complex_query_or() {
     // Test with explicit OR syntax
     // "keywordAlpha OR keywordBeta" means files with keywordAlpha OR keywordBeta
     let queries = vec!["keywordAlpha OR keywordBeta".to_string()];
-    let custom_ignores: Vec<String> = vec![];
-
-    // Create SearchOptions
-    let options = SearchOptions {
-        path: temp_path,
-        queries: &queries,
-        files_only: false,
-        custom_ignores: &custom_ignores,
-        exclude_filenames: false,
-        language: None,
-        reranker: "hybrid",
-        frequency_search: true, // Enable frequency search to improve matching
-        max_results: None,
-        max_bytes: None,
-        max_tokens: None,
-        allow_tests: true,
-        no_merge: false,
-        merge_threshold: Some(5),
-        dry_run: false,
-        session: None,
-        timeout: 30,
-        exact: false,
-    };
+
+    // Create the search request
+    let options = SearchRequest::new(queries[0].as_str())
+        .path(temp_path)
+        .reranker("hybrid")
+        .allow_tests(true)
+        .merge_threshold(5);
 
     // Print the test files for debugging
     println!("Test files created in: {:?}", temp_path);
@@ -438,8 +376,8 @@ fn test_complex_query_or() {
     // Print the query for debugging
     println!("Executing search with query: {:?}", queries);
     println!(
-        "Path: {:?}, frequency_search: {}",
-        options.path, options.frequency_search
+        "Paths: {:?}, frequency_search: {}",
+        options.paths, options.frequency_search
     );
 
     // Run the search
@@ -494,35 +432,19 @@ fn test_complex_query_exclusion() {
 
     // Test with exclusion
     let queries = vec!["\"keywordAlpha\" -keywordGamma".to_string()];
-    let custom_ignores: Vec<String> = vec![];
-
-    // Create SearchOptions
-    let options = SearchOptions {
-        path: temp_path,
-        queries: &queries,
-        files_only: false,
-        custom_ignores: &custom_ignores,
-        exclude_filenames: false,
-        language: None,
-        reranker: "hybrid",
-        frequency_search: false,
-        max_results: None,
-        max_bytes: None,
-        max_tokens: None,
-        allow_tests: true,
-        no_merge: false,
-        merge_threshold: Some(5),
-        dry_run: false,
-        session: None,
-        timeout: 30,
-        exact: false,
-    };
+
+    // Create the search request
+    let options = SearchRequest::new(queries[0].as_str())
+        .path(temp_path)
+        .reranker("hybrid")
+        .allow_tests(true)
+        .merge_threshold(5);
 
     // Print the query for debugging
     println!("Executing search with query: {:?}", queries);
     println!(
-        "Path: {:?}, frequency_search: {}",
-        options.path, options.frequency_search
+        "Paths: {:?}, frequency_search: {}",
+        options.paths, options.frequency_search
     );
 
     // Run the search
@@ -592,30 +514,13 @@ fn test_function() {
     // Test query with underscore using explicit OR syntax
     // Since we've removed any_term parameter and changed the default to AND,
     // we need to use explicit OR syntax to maintain the original behavior
-    let queries = vec!["key OR word OR score".to_string()];
-    let custom_ignores: Vec<String> = vec![];
-
-    // Create SearchOptions
-    let options = SearchOptions {
-        path: temp_path,
-        queries: &queries,
-        files_only: false,
-        custom_ignores: &custom_ignores,
-        exclude_filenames: false,
-        language: None,
-        reranker: "hybrid",
-        frequency_search: false,
-        max_results: None,
-        max_bytes: None,
-        max_tokens: None,
-        allow_tests: true,
-        no_merge: false,
-        merge_threshold: Some(5),
-        dry_run: false,
-        session: None,
-        timeout: 30,
-        exact: false,
-    };
+
+    // Create the search request
+    let options = SearchRequest::new("key OR word OR score")
+        .path(temp_path)
+        .reranker("hybrid")
+        .allow_tests(true)
+        .merge_threshold(5);
 
     // Run the search
     let search_results = perform_probe(&options).unwrap();
@@ -671,6 +576,9 @@ fn test_filter_code_block_with_ast() {
             required: false,
             excluded: false,
             exact: false,
+            proximity: None,
+            pattern: None,
+            boost: None,
         }),
         Box::new(Expr::Term {
             keywords: vec!["keywordBeta".to_string()],
@@ -678,6 +586,9 @@ fn test_filter_code_block_with_ast() {
             required: false,
             excluded: true,
             exact: false,
+            proximity: None,
+            pattern: None,
+            boost: None,
         }),
     );
 
@@ -687,16 +598,16 @@ fn test_filter_code_block_with_ast() {
     term_indices.insert("keywordBeta".to_string(), 1);
 
     // Create a QueryPlan
-    let plan = QueryPlan {
+    let plan = QueryPlan::new(
         ast,
         term_indices,
-        excluded_terms: {
+        {
             let mut set = HashSet::new();
             set.insert("keywordBeta".to_string());
             set
         },
-        exact: false,
-    };
+        false,
+    );
 
     // Create term matches for a block
     let mut term_matches = HashMap::new();
@@ -747,6 +658,9 @@ fn test_filter_tokenized_block() {
             required: false,
             excluded: false,
             exact: false,
+            proximity: None,
+            pattern: None,
+            boost: None,
         }),
         Box::new(Expr::Term {
             keywords: vec!["keywordBeta".to_string()],
@@ -754,6 +668,9 @@ fn test_filter_tokenized_block() {
             required: false,
             excluded: true,
             exact: false,
+            proximity: None,
+            pattern: None,
+            boost: None,
         }),
     );
 
@@ -763,16 +680,16 @@ fn test_filter_tokenized_block() {
     term_indices.insert("keywordBeta".to_string(), 1);
 
     // Create a QueryPlan
-    let plan = QueryPlan {
+    let plan = QueryPlan::new(
         ast,
-        term_indices: term_indices.clone(),
-        excluded_terms: {
+        term_indices.clone(),
+        {
             let mut set = HashSet::new();
             set.insert("keywordBeta".to_string());
             set
         },
-        exact: false,
-    };
+        false,
+    );
 
     // Import the function from probe crate
     use probe::search::file_processing::filter_tokenized_block;
@@ -823,6 +740,9 @@ fn test_filter_tokenized_block() {
             required: false,
             excluded: false,
             exact: false,
+            proximity: None,
+            pattern: None,
+            boost: None,
         }),
         Box::new(Expr::Term {
             keywords: vec!["keywordGamma".to_string()],
@@ -830,6 +750,9 @@ fn test_filter_tokenized_block() {
             required: false,
             excluded: false,
             exact: false,
+            proximity: None,
+            pattern: None,
+            boost: None,
         }),
     );
 
@@ -839,12 +762,7 @@ fn test_filter_tokenized_block() {
     term_indices_or.insert("keywordGamma".to_string(), 2);
 
     // Create a QueryPlan
-    let plan_or = QueryPlan {
-        ast: ast_or,
-        term_indices: term_indices_or.clone(),
-        excluded_terms: HashSet::new(),
-        exact: false,
-    };
+    let plan_or = QueryPlan::new(ast_or, term_indices_or.clone(), HashSet::new(), false);
 
     // Test with only keywordGamma
     let tokenized_content = vec!["keywordGamma".to_string()];
@@ -864,3 +782,14 @@ fn test_filter_tokenized_block() {
         "Block should match because it has both keywords in OR expression"
     );
 }
+
+#[test]
+fn test_query_plan_compiles_pattern_terms() {
+    let plan = probe::search::query::create_query_plan("auth* OR /re(try|tries)/", false).unwrap();
+    let patterns = plan.term_patterns();
+    assert_eq!(patterns.len(), 2);
+    assert!(patterns["auth*"].matches("let authToken = get();"));
+
+    let matched = plan.match_pattern_terms("// Retries the request");
+    assert_eq!(matched, HashSet::from([plan.term_indices["re(try|tries)"]]));
+}
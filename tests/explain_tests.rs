@@ -0,0 +1,108 @@
+mod common;
+
+use tempfile::TempDir;
+
+use common::create_project;
+use probe::search::format_search_results;
+use probe::{perform_probe, SearchRequest, SearchResult};
+
+/// A function that defines the searched symbol next to one that only calls it
+fn create_test_project() -> TempDir {
+    create_project(&[
+        ("parser.rs", "fn parse_header(input: &str) -> usize {\n    input.len()\n}\n\nfn parse_body(input: &str) -> usize {\n    parse_header(input)\n}\n"),
+    ])
+}
+
+fn search(temp_dir: &TempDir, request: SearchRequest) -> Vec<SearchResult> {
+    let request = request.path(temp_dir.path()).exclude_filenames(true);
+    perform_probe(&request).unwrap().results
+}
+
+#[test]
+fn test_explain_breaks_down_bm25_and_boosts() {
+    let temp_dir = create_test_project();
+    let results = search(
+        &temp_dir,
+        SearchRequest::new("parse header")
+            .explain(true)
+            .no_merge(true),
+    );
+    assert_eq!(results.len(), 2);
+
+    for result in &results {
+        let explanation = result.explanation.as_ref().expect("Explanation missing");
+        assert_eq!(explanation.reranker, "bm25");
+        assert_eq!(Some(explanation.rank), result.rank);
+        assert_eq!(Some(explanation.score), result.score);
+        assert!(explanation.merged_blocks.is_empty());
+
+        // The per-term scores add up to the BM25 score, which is the reranker score here
+        let bm25 = explanation.bm25.as_ref().expect("BM25 breakdown missing");
+        assert_eq!(bm25.terms.len(), 2);
+        let sum: f64 = bm25.terms.iter().map(|t| t.score).sum();
+        assert!((sum - bm25.score).abs() < 1e-9);
+        assert!((bm25.score - explanation.reranker_score).abs() < 1e-9);
+        assert_eq!((bm25.k1, bm25.b), (1.2, 0.75));
+        assert!(bm25.doc_length > 0.0 && bm25.avgdl > 0.0);
+    }
+
+    // The definition is boosted on top of its BM25 score
+    let definition = &results[0];
+    assert!(definition.code.starts_with("fn parse_header"));
+    let explanation = definition.explanation.as_ref().unwrap();
+    assert_eq!(explanation.definition_boost, Some(1.5));
+    assert!((explanation.score - explanation.reranker_score * 1.5).abs() < 1e-9);
+}
+
+#[test]
+fn test_explain_lists_merged_blocks() {
+    let temp_dir = create_test_project();
+    let results = search(&temp_dir, SearchRequest::new("parse header").explain(true));
+    assert_eq!(results.len(), 1);
+
+    let explanation = results[0].explanation.as_ref().unwrap();
+    assert_eq!(explanation.rank, 1);
+    let lines: Vec<(usize, usize)> = explanation
+        .merged_blocks
+        .iter()
+        .map(|block| block.lines)
+        .collect();
+    assert_eq!(lines, vec![(1, 3), (5, 7)]);
+}
+
+#[test]
+fn test_explain_is_off_by_default() {
+    let temp_dir = create_test_project();
+    let results = search(&temp_dir, SearchRequest::new("parse header"));
+    assert!(!results.is_empty());
+    assert!(results.iter().all(|r| r.explanation.is_none()));
+
+    let json: serde_json::Value =
+        serde_json::from_str(&format_search_results(&results, false, "json", None)).unwrap();
+    assert!(json["results"][0].get("explanation").is_none());
+}
+
+#[test]
+fn test_explain_output_formats() {
+    let temp_dir = create_test_project();
+    let results = search(&temp_dir, SearchRequest::new("parse header").explain(true));
+
+    let json: serde_json::Value =
+        serde_json::from_str(&format_search_results(&results, false, "json", None)).unwrap();
+    let explanation = &json["results"][0]["explanation"];
+    assert_eq!(explanation["reranker"], "bm25");
+    assert_eq!(explanation["bm25"]["k1"], 1.2);
+    assert_eq!(explanation["bm25"]["terms"].as_array().unwrap().len(), 2);
+    assert_eq!(explanation["merged_blocks"].as_array().unwrap().len(), 2);
+
+    let xml = format_search_results(&results, false, "xml", None);
+    assert!(xml.contains("<explanation>"));
+    assert!(xml.contains("<term name=\"header\" tf=\"1\""));
+    assert!(xml.contains("<merged_block lines=\"1-3\" rank=\"1\""));
+
+    let markdown = format_search_results(&results, false, "markdown", None);
+    assert!(markdown.contains("Explanation: rank 1"));
+    assert!(markdown.contains("BM25: "));
+    assert!(markdown.contains("Definition boost: x1.5000"));
+    assert!(markdown.contains("Merged blocks: 1-3"));
+}
@@ -149,25 +149,7 @@ fn test_format_and_print_extraction_results() {
         lines: (1, 5),
         node_type: "function".to_string(),
         code: "fn test() {\n    println!(\"Hello\");\n}".to_string(),
-        matched_by_filename: None,
-        rank: None,
-        score: None,
-        tfidf_score: None,
-        bm25_score: None,
-        tfidf_rank: None,
-        bm25_rank: None,
-        new_score: None,
-        hybrid2_rank: None,
-        combined_score_rank: None,
-        file_unique_terms: None,
-        file_total_matches: None,
-        file_match_rank: None,
-        block_unique_terms: None,
-        block_total_matches: None,
-        parent_file_id: None,
-        block_id: None,
-        matched_keywords: None,
-        tokenized_content: None,
+        ..Default::default()
     };
 
     // Test different formats
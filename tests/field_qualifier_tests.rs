@@ -0,0 +1,112 @@
+mod common;
+
+use std::path::Path;
+use tempfile::TempDir;
+
+use common::{search_request, write_files};
+use probe::search::SearchRequest;
+
+/// Create a small project with the same terms spread over paths, languages,
+/// node types, comments and code
+fn create_test_files(root: &Path) {
+    write_files(
+        root,
+        &[
+            (
+                "src/auth/session.rs",
+                r#"
+// Tokens are refreshed before they expire
+pub fn refresh_session(token: &str) -> String {
+    token.to_uppercase()
+}
+
+pub struct SessionStore {
+    sessions: Vec<String>,
+}
+"#,
+            ),
+            (
+                "legacy/session.go",
+                r#"
+package legacy
+
+// RefreshSession refreshes the session token
+func RefreshSession(token string) string {
+	return token
+}
+"#,
+            ),
+        ],
+    );
+}
+
+fn search(root: &Path, query: &str) -> Vec<probe::SearchResult> {
+    search_request(SearchRequest::new(query).path(root).allow_tests(true))
+}
+
+#[test]
+fn test_path_and_lang_filters() {
+    let temp_dir = TempDir::new().unwrap();
+    create_test_files(temp_dir.path());
+
+    let all = search(temp_dir.path(), "refresh");
+    assert!(all.iter().any(|r| r.file.ends_with("session.rs")));
+    assert!(all.iter().any(|r| r.file.ends_with("session.go")));
+
+    let by_path = search(temp_dir.path(), "refresh path:src/auth");
+    assert!(!by_path.is_empty());
+    assert!(by_path.iter().all(|r| r.file.contains("src/auth")));
+
+    let by_glob = search(temp_dir.path(), "refresh file:*.go");
+    assert!(!by_glob.is_empty());
+    assert!(by_glob.iter().all(|r| r.file.ends_with(".go")));
+
+    let excluded = search(temp_dir.path(), "refresh -path:legacy");
+    assert!(!excluded.is_empty());
+    assert!(excluded.iter().all(|r| !r.file.contains("legacy")));
+
+    let by_lang = search(temp_dir.path(), "refresh lang:rs");
+    assert!(!by_lang.is_empty());
+    assert!(by_lang.iter().all(|r| r.file.ends_with(".rs")));
+
+    // A group of filters filters files; it doesn't match every block on its own
+    let grouped = search(temp_dir.path(), "SessionStore OR (path:src OR path:legacy)");
+    assert!(!grouped.is_empty());
+    assert!(grouped.iter().all(|r| r.code.contains("SessionStore")));
+}
+
+#[test]
+fn test_type_filter() {
+    let temp_dir = TempDir::new().unwrap();
+    create_test_files(temp_dir.path());
+
+    let results = search(temp_dir.path(), "session type:struct");
+    assert!(!results.is_empty());
+    assert!(results.iter().all(|r| r.node_type.contains("struct")));
+
+    let results = search(temp_dir.path(), "session -type:struct lang:rust");
+    assert!(!results.is_empty());
+    assert!(results.iter().all(|r| !r.node_type.contains("struct")));
+}
+
+#[test]
+fn test_comment_and_code_terms() {
+    let temp_dir = TempDir::new().unwrap();
+    create_test_files(temp_dir.path());
+
+    // "expire" only appears in a comment
+    let in_comments = search(temp_dir.path(), "comment:expire");
+    assert!(!in_comments.is_empty());
+    assert!(search(temp_dir.path(), "code:expire").is_empty());
+
+    // "uppercase" only appears in code
+    let in_code = search(temp_dir.path(), "code:uppercase");
+    assert!(!in_code.is_empty());
+    assert!(search(temp_dir.path(), "comment:uppercase").is_empty());
+
+    // Matched keywords are reported without the field prefix
+    assert!(in_code.iter().any(|r| r
+        .matched_keywords
+        .as_ref()
+        .is_some_and(|kw| kw.iter().any(|k| k == "uppercas" || k == "uppercase"))));
+}
@@ -0,0 +1,63 @@
+mod common;
+
+use tempfile::TempDir;
+
+use common::{create_project, files, search_request};
+use probe::SearchRequest;
+
+/// Files with words that are easy to misspell
+fn create_test_project() -> TempDir {
+    create_project(&[
+        (
+            "inbox.rs",
+            "fn receive_message(queue: &Queue) -> Message {\n    queue.pop()\n}\n",
+        ),
+        (
+            "setup.rs",
+            "fn initialize_logger() {\n    Logger::init();\n}\n",
+        ),
+        ("tricks.rs", "fn deceive() -> bool {\n    false\n}\n"),
+    ])
+}
+
+fn request(temp_dir: &TempDir, query: &str) -> SearchRequest {
+    SearchRequest::new(query).path(temp_dir.path())
+}
+
+#[test]
+fn test_fuzzy_term_finds_misspelled_word() {
+    let temp_dir = create_test_project();
+    assert!(search_request(request(&temp_dir, "recieve")).is_empty());
+    assert_eq!(
+        files(&search_request(request(&temp_dir, "recieve~1"))),
+        vec!["inbox.rs"]
+    );
+    // `deceive` is two edits from `recieve`
+    assert_eq!(
+        files(&search_request(request(&temp_dir, "recieve~2"))),
+        vec!["inbox.rs", "tricks.rs"]
+    );
+}
+
+#[test]
+fn test_fuzzy_flag_applies_to_every_word() {
+    let temp_dir = create_test_project();
+    let results = search_request(request(&temp_dir, "recieve OR initalize").fuzzy(1));
+    assert_eq!(files(&results), vec!["inbox.rs", "setup.rs"]);
+    assert!(results.iter().all(|r| r.score.unwrap_or(0.0) > 0.0));
+
+    // Exact searches are not made fuzzy
+    assert!(search_request(request(&temp_dir, "recieve").fuzzy(1).exact(true)).is_empty());
+}
+
+#[test]
+fn test_fuzzy_terms_can_be_excluded() {
+    let temp_dir = create_test_project();
+    assert_eq!(
+        files(&search_request(request(
+            &temp_dir,
+            "(message OR logger) -recieve~1"
+        ))),
+        vec!["setup.rs"]
+    );
+}
@@ -0,0 +1,203 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use tempfile::TempDir;
+
+use probe::search::import_graph::ImportGraph;
+use probe::search::timeout::CancellationToken;
+use probe::{perform_probe, SearchRequest};
+
+/// A small Rust crate where `util.rs` is imported by every other module, plus a
+/// Python package and a JavaScript app importing each other relatively
+fn create_project(root: &Path) {
+    fs::create_dir_all(root.join("src/net")).unwrap();
+    fs::write(root.join("src/lib.rs"), "mod net;\nmod util;\nmod cli;\n").unwrap();
+    fs::write(
+        root.join("src/util.rs"),
+        "pub fn retry_request() -> bool {\n    true\n}\n",
+    )
+    .unwrap();
+    fs::write(
+        root.join("src/net/mod.rs"),
+        "use crate::util::retry_request;\n\npub fn connect() -> bool {\n    retry_request()\n}\n",
+    )
+    .unwrap();
+    fs::write(
+        root.join("src/cli.rs"),
+        "use crate::{net, util};\n\npub fn main() {\n    net::connect();\n}\n",
+    )
+    .unwrap();
+
+    fs::create_dir_all(root.join("app/pkg")).unwrap();
+    fs::write(root.join("app/pkg/__init__.py"), "").unwrap();
+    fs::write(root.join("app/pkg/models.py"), "class User:\n    pass\n").unwrap();
+    fs::write(
+        root.join("app/pkg/views.py"),
+        "from .models import User\nimport os\n",
+    )
+    .unwrap();
+
+    fs::create_dir_all(root.join("web")).unwrap();
+    fs::write(
+        root.join("web/api.js"),
+        "export function fetchUser() {\n  return 1;\n}\n",
+    )
+    .unwrap();
+    fs::write(
+        root.join("web/main.js"),
+        "import { fetchUser } from './api';\nconst lodash = require('lodash');\n",
+    )
+    .unwrap();
+}
+
+fn edges(graph: &ImportGraph) -> Vec<(String, String)> {
+    graph
+        .edges
+        .iter()
+        .map(|&(from, to)| {
+            (
+                graph.files[from].to_string_lossy().replace('\\', "/"),
+                graph.files[to].to_string_lossy().replace('\\', "/"),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn test_import_graph_resolves_imports() {
+    let temp_dir = TempDir::new().unwrap();
+    create_project(temp_dir.path());
+
+    let graph = ImportGraph::build(temp_dir.path(), false, &[], &CancellationToken::new()).unwrap();
+    let edges = edges(&graph);
+    for (from, to) in [
+        ("src/lib.rs", "src/net/mod.rs"),
+        ("src/lib.rs", "src/util.rs"),
+        ("src/lib.rs", "src/cli.rs"),
+        ("src/net/mod.rs", "src/util.rs"),
+        ("src/cli.rs", "src/net/mod.rs"),
+        ("src/cli.rs", "src/util.rs"),
+        ("app/pkg/views.py", "app/pkg/models.py"),
+        ("web/main.js", "web/api.js"),
+    ] {
+        assert!(
+            edges.contains(&(from.to_string(), to.to_string())),
+            "missing edge {} -> {} in {:?}",
+            from,
+            to,
+            edges
+        );
+    }
+    // External packages (os, lodash) are not part of the graph
+    assert_eq!(edges.len(), 8, "unexpected edges: {:?}", edges);
+
+    let centrality = |file: &str| {
+        graph
+            .relative_centrality(&temp_dir.path().join(file))
+            .unwrap()
+    };
+    assert_eq!(centrality("src/util.rs"), 1.0);
+    assert!(centrality("src/net/mod.rs") > centrality("src/cli.rs"));
+    assert!(centrality("web/api.js") > centrality("web/main.js"));
+    let total: f64 = graph.centrality.iter().sum();
+    assert!((total - 1.0).abs() < 1e-6);
+}
+
+#[test]
+fn test_stored_graph_follows_changed_files() {
+    let temp_dir = TempDir::new().unwrap();
+    create_project(temp_dir.path());
+    let build = || ImportGraph::build(temp_dir.path(), false, &[], &CancellationToken::new());
+
+    let first = build().unwrap();
+    let reused = build().unwrap();
+    assert_eq!(edges(&reused), edges(&first));
+    for (reused, first) in reused.centrality.iter().zip(&first.centrality) {
+        assert!((reused - first).abs() < 1e-12);
+    }
+
+    // A changed import is picked up even though no file was added or removed
+    fs::write(
+        temp_dir.path().join("web/api.js"),
+        "import { helper } from './main.js';\n",
+    )
+    .unwrap();
+    let rebuilt = build().unwrap();
+    assert!(edges(&rebuilt).contains(&("web/api.js".to_string(), "web/main.js".to_string())));
+}
+
+#[test]
+fn test_cancelled_graph_build_fails() {
+    let temp_dir = TempDir::new().unwrap();
+    create_project(temp_dir.path());
+    let cancel = CancellationToken::new();
+    cancel.cancel();
+    assert!(ImportGraph::build(temp_dir.path(), false, &[], &cancel).is_err());
+}
+
+#[test]
+fn test_centrality_prior_breaks_ties() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    let code = "pub fn retry_request() -> bool {\n    true\n}\n";
+    fs::write(root.join("core.rs"), code).unwrap();
+    fs::write(root.join("leaf.rs"), code).unwrap();
+    fs::write(root.join("a.rs"), "use crate::core;\n").unwrap();
+    fs::write(root.join("b.rs"), "use crate::core;\n").unwrap();
+
+    let search = |request: SearchRequest| {
+        let request = request.path(root).exclude_filenames(true);
+        perform_probe(&request).unwrap().results
+    };
+
+    let results = search(SearchRequest::new("retry_request"));
+    assert!(results.iter().all(|r| r.centrality.is_none()));
+
+    let results = search(SearchRequest::new("retry_request").centrality_weight(1.0));
+    assert!(results[0].file.ends_with("core.rs"));
+    assert_eq!(results[0].centrality, Some(1.0));
+    assert!(results[1].file.ends_with("leaf.rs"));
+    assert!(results[1].centrality.unwrap() < 1.0);
+    assert!(results[0].score > results[1].score);
+
+    for weight in [-1.0, f64::NAN, f64::INFINITY] {
+        let request = SearchRequest::new("retry_request")
+            .path(root)
+            .centrality_weight(weight);
+        let error = perform_probe(&request).unwrap_err();
+        assert!(
+            error.to_string().contains("Centrality weight"),
+            "{}: {}",
+            weight,
+            error
+        );
+    }
+}
+
+#[test]
+fn test_graph_command_exports_json_and_dot() {
+    let temp_dir = TempDir::new().unwrap();
+    create_project(temp_dir.path());
+
+    let output = Command::new("cargo")
+        .args(["run", "--", "graph"])
+        .arg(temp_dir.path())
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let files = json["files"].as_array().unwrap();
+    assert_eq!(files[0]["file"], "src/util.rs");
+    assert_eq!(files[0]["imported_by"], 3);
+    assert_eq!(json["edges"].as_array().unwrap().len(), 8);
+
+    let output = Command::new("cargo")
+        .args(["run", "--", "graph", "--format", "dot"])
+        .arg(temp_dir.path())
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+    let dot = String::from_utf8_lossy(&output.stdout);
+    assert!(dot.starts_with("digraph imports {"));
+    assert!(dot.contains("\"web/main.js\" -> \"web/api.js\";"));
+}
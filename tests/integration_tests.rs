@@ -4,7 +4,7 @@ use std::path::PathBuf;
 use tempfile::TempDir;
 
 // The integration test needs access to the library crate
-use probe::search::{perform_probe, SearchOptions};
+use probe::search::{perform_probe, SearchRequest};
 
 // Helper function to create test files
 fn create_test_file(dir: &TempDir, filename: &str, content: &str) -> PathBuf {
@@ -129,30 +129,13 @@ fn test_search_single_term() {
     create_test_directory_structure(&temp_dir);
 
     // Create search query
-    let queries = vec!["search".to_string()];
-    let custom_ignores: Vec<String> = vec![];
-
-    // Create SearchOptions
-    let options = SearchOptions {
-        path: temp_dir.path(),
-        queries: &queries,
-        files_only: false,
-        custom_ignores: &custom_ignores,
-        exclude_filenames: true,
-        language: None,
-        reranker: "hybrid",
-        frequency_search: false,
-        max_results: None,
-        max_bytes: None,
-        max_tokens: None,
-        allow_tests: false,
-        no_merge: true,
-        merge_threshold: None,
-        dry_run: false,
-        session: None,
-        timeout: 30,
-        exact: false,
-    };
+
+    // Create the search request
+    let options = SearchRequest::new("search")
+        .path(temp_dir.path())
+        .exclude_filenames(true)
+        .reranker("hybrid")
+        .no_merge(true);
 
     // Search for a single term
     let search_results = perform_probe(&options).expect("Failed to perform search");
@@ -193,30 +176,14 @@ fn test_search_multiple_terms() {
     create_test_directory_structure(&temp_dir);
 
     // Create search query
-    let queries = vec!["search".to_string(), "function".to_string()];
-    let custom_ignores: Vec<String> = vec![];
-
-    // Create SearchOptions
-    let options = SearchOptions {
-        path: temp_dir.path(),
-        queries: &queries,
-        files_only: false,
-        custom_ignores: &custom_ignores,
-        exclude_filenames: true,
-        language: None,
-        reranker: "hybrid",
-        frequency_search: false,
-        max_results: None,
-        max_bytes: None,
-        max_tokens: None,
-        allow_tests: false,
-        no_merge: true,
-        merge_threshold: None,
-        dry_run: false,
-        session: None,
-        timeout: 30,
-        exact: false,
-    };
+
+    // Create the search request
+    let options = SearchRequest::new("search")
+        .query("function")
+        .path(temp_dir.path())
+        .exclude_filenames(true)
+        .reranker("hybrid")
+        .no_merge(true);
 
     // Search for multiple terms
     let search_results = perform_probe(&options).expect("Failed to perform search");
@@ -239,30 +206,14 @@ fn test_search_files_only() {
     create_test_directory_structure(&temp_dir);
 
     // Create search query
-    let queries = vec!["search".to_string()];
-    let custom_ignores: Vec<String> = vec![];
-
-    // Create SearchOptions
-    let options = SearchOptions {
-        path: temp_dir.path(),
-        queries: &queries,
-        files_only: true,
-        custom_ignores: &custom_ignores,
-        exclude_filenames: true,
-        language: None,
-        reranker: "hybrid",
-        frequency_search: false,
-        max_results: None,
-        max_bytes: None,
-        max_tokens: None,
-        allow_tests: false,
-        no_merge: true,
-        merge_threshold: None,
-        dry_run: false,
-        session: None,
-        timeout: 30,
-        exact: false,
-    };
+
+    // Create the search request
+    let options = SearchRequest::new("search")
+        .path(temp_dir.path())
+        .files_only(true)
+        .exclude_filenames(true)
+        .reranker("hybrid")
+        .no_merge(true);
 
     // Search for files only
     let search_results = perform_probe(&options).expect("Failed to perform search");
@@ -314,30 +265,12 @@ fn test_search_include_filenames() {
     println!("Created test file at: {:?}", search_file_path);
 
     // Create search query
-    let queries = vec!["search".to_string()];
-    let custom_ignores: Vec<String> = vec![];
-
-    // Create SearchOptions
-    let options = SearchOptions {
-        path: temp_dir.path(),
-        queries: &queries,
-        files_only: false,
-        custom_ignores: &custom_ignores,
-        exclude_filenames: false,
-        language: None,
-        reranker: "hybrid",
-        frequency_search: false,
-        max_results: None,
-        max_bytes: None,
-        max_tokens: None,
-        allow_tests: false,
-        no_merge: true,
-        merge_threshold: None,
-        dry_run: false,
-        session: None,
-        timeout: 30,
-        exact: false,
-    };
+
+    // Create the search request
+    let options = SearchRequest::new("search")
+        .path(temp_dir.path())
+        .reranker("hybrid")
+        .no_merge(true);
 
     // Search with filename matching enabled
     let search_results = perform_probe(&options).expect("Failed to perform search");
@@ -370,30 +303,15 @@ fn test_search_with_limits() {
     create_test_directory_structure(&temp_dir);
 
     // Create search query
-    let queries = vec!["search".to_string()];
-    let custom_ignores: Vec<String> = vec![];
-
-    // Create SearchOptions
-    let options = SearchOptions {
-        path: temp_dir.path(),
-        queries: &queries,
-        files_only: false,
-        custom_ignores: &custom_ignores,
-        exclude_filenames: true,
-        language: None,
-        reranker: "hybrid",
-        frequency_search: false,
-        max_results: Some(2), // limit to 2 results
-        max_bytes: None,
-        max_tokens: None,
-        allow_tests: false,
-        no_merge: true,
-        merge_threshold: None,
-        dry_run: false,
-        session: None,
-        timeout: 30,
-        exact: false,
-    };
+
+    // Create the search request
+    let options = SearchRequest::new("search")
+        .path(temp_dir.path())
+        .exclude_filenames(true)
+        .reranker("hybrid")
+        // limit to 2 results
+        .max_results(2)
+        .no_merge(true);
 
     // Search with limits
     let search_results = perform_probe(&options).expect("Failed to perform search");
@@ -420,30 +338,13 @@ fn test_frequency_search() {
     create_test_directory_structure(&temp_dir);
 
     // Create search query
-    let queries = vec!["search".to_string()];
-    let custom_ignores: Vec<String> = vec![];
-
-    // Create SearchOptions
-    let options = SearchOptions {
-        path: temp_dir.path(),
-        queries: &queries,
-        files_only: false,
-        custom_ignores: &custom_ignores,
-        exclude_filenames: true,
-        language: None,
-        reranker: "hybrid",
-        frequency_search: true,
-        max_results: None,
-        max_bytes: None,
-        max_tokens: None,
-        allow_tests: false,
-        no_merge: true,
-        merge_threshold: None,
-        dry_run: false,
-        session: None,
-        timeout: 30,
-        exact: false,
-    };
+
+    // Create the search request
+    let options = SearchRequest::new("search")
+        .path(temp_dir.path())
+        .exclude_filenames(true)
+        .reranker("hybrid")
+        .no_merge(true);
 
     // Search using frequency-based search
     let search_results = perform_probe(&options).expect("Failed to perform search");
@@ -477,31 +378,13 @@ func main() {
     create_test_file(&temp_dir, "ip_utils.go", content);
 
     // Create search query
-    let queries = vec!["ip".to_string(), "whitelist".to_string()];
-    let custom_ignores: Vec<String> = vec![];
-
-    // Create SearchOptions
-    let options = SearchOptions {
-        path: temp_dir.path(),
-        queries: &queries,
-        files_only: false,
-        custom_ignores: &custom_ignores,
-        exclude_filenames: false, // filename matching is enabled by default
-        language: None,
-        reranker: "hybrid",
-        frequency_search: false,
-        max_results: None,
-        max_bytes: None,
-        max_tokens: None,
-        allow_tests: false,
-        // using "all terms" mode
-        no_merge: true,
-        merge_threshold: None,
-        dry_run: false,
-        session: None,
-        timeout: 30,
-        exact: false,
-    };
+
+    // Create the search request
+    let options = SearchRequest::new("ip")
+        .query("whitelist")
+        .path(temp_dir.path())
+        .reranker("hybrid")
+        .no_merge(true);
 
     // Search for both terms in "all terms" mode
     let _ = perform_probe(&options).expect("Failed to perform search");
@@ -517,32 +400,16 @@ fn test_search_with_custom_ignores() {
     create_test_directory_structure(&temp_dir);
 
     // Create a custom ignore pattern for Python files
-    let custom_ignores = vec!["*.py".to_string()];
 
     // Create search query
-    let queries = vec!["search".to_string()];
-
-    // Create SearchOptions
-    let options = SearchOptions {
-        path: temp_dir.path(),
-        queries: &queries,
-        files_only: false,
-        custom_ignores: &custom_ignores,
-        exclude_filenames: true,
-        language: None,
-        reranker: "hybrid",
-        frequency_search: false,
-        max_results: None,
-        max_bytes: None,
-        max_tokens: None,
-        allow_tests: false,
-        no_merge: true,
-        merge_threshold: None,
-        dry_run: false,
-        session: None,
-        timeout: 30,
-        exact: false,
-    };
+
+    // Create the search request
+    let options = SearchRequest::new("search")
+        .path(temp_dir.path())
+        .ignore("*.py")
+        .exclude_filenames(true)
+        .reranker("hybrid")
+        .no_merge(true);
 
     // Search with custom ignore patterns
     let search_results = perform_probe(&options).expect("Failed to perform search");
@@ -636,30 +503,14 @@ fn calculate_product(a: i32, b: i32) -> i32 {
     let query = "calculate";
 
     // Create search query
-    let queries = vec![query.to_string()];
-    let custom_ignores: Vec<String> = vec![];
-
-    // Create SearchOptions
-    let options = SearchOptions {
-        path: temp_dir.path(),
-        queries: &queries,
-        files_only: false,
-        custom_ignores: &custom_ignores,
-        exclude_filenames: true,
-        language: None,
-        reranker: "combined",
-        frequency_search: false,
-        max_results: None,
-        max_bytes: None,
-        max_tokens: None,
-        allow_tests: true,
-        no_merge: false,
-        merge_threshold: Some(5),
-        dry_run: false,
-        session: None,
-        timeout: 30,
-        exact: false,
-    };
+
+    // Create the search request
+    let options = SearchRequest::new(query)
+        .path(temp_dir.path())
+        .exclude_filenames(true)
+        .reranker("combined")
+        .allow_tests(true)
+        .merge_threshold(5);
 
     // Perform search
     let search_result = perform_probe(&options).expect("Search should succeed");
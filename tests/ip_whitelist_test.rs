@@ -1,4 +1,4 @@
-use probe::search::{perform_probe, SearchOptions};
+use probe::search::{perform_probe, SearchRequest};
 use std::path::PathBuf;
 
 #[test]
@@ -7,30 +7,14 @@ fn test_ip_whitelist_stemming() {
     let file_path = PathBuf::from("tests/mocks/test_ip_whitelist.go");
 
     // Create search query
-    let queries = vec!["ip whitelisting".to_string()];
-    let custom_ignores: Vec<String> = vec![];
-
-    // Create SearchOptions
-    let options = SearchOptions {
-        path: file_path.parent().unwrap().parent().unwrap(), // Use the tests directory
-        queries: &queries,
-        files_only: false,
-        custom_ignores: &custom_ignores,
-        exclude_filenames: true,
-        language: None,
-        reranker: "hybrid",
-        frequency_search: true, // Use frequency search to get detailed term stats
-        exact: false,
-        max_results: None,
-        max_bytes: None,
-        max_tokens: None,
-        allow_tests: true,
-        no_merge: true,
-        merge_threshold: None,
-        dry_run: false,
-        session: None,
-        timeout: 30,
-    };
+
+    // Create the search request
+    let options = SearchRequest::new("ip whitelisting")
+        .path(file_path.parent().unwrap().parent().unwrap())
+        .exclude_filenames(true)
+        .reranker("hybrid")
+        .allow_tests(true)
+        .no_merge(true);
 
     // Enable debug mode to see the actual terms
     std::env::set_var("DEBUG", "1");
@@ -96,30 +80,14 @@ fn test_negative_terms_exclude_files() {
     let file_path = PathBuf::from("tests/mocks/test_ip_whitelist.go");
 
     // Create search query with a negative term
-    let queries = vec!["(+ip) -whitelist".to_string()];
-    let custom_ignores: Vec<String> = vec![];
-
-    // Create SearchOptions
-    let options = SearchOptions {
-        path: file_path.parent().unwrap().parent().unwrap(), // Use the tests directory
-        queries: &queries,
-        files_only: false,
-        custom_ignores: &custom_ignores,
-        exclude_filenames: true,
-        language: None,
-        reranker: "hybrid",
-        frequency_search: true,
-        exact: false,
-        max_results: None,
-        max_bytes: None,
-        max_tokens: None,
-        allow_tests: true,
-        no_merge: true,
-        merge_threshold: None,
-        dry_run: false,
-        session: None,
-        timeout: 30,
-    };
+
+    // Create the search request
+    let options = SearchRequest::new("(+ip) -whitelist")
+        .path(file_path.parent().unwrap().parent().unwrap())
+        .exclude_filenames(true)
+        .reranker("hybrid")
+        .allow_tests(true)
+        .no_merge(true);
 
     // Enable debug mode to see the actual terms
     std::env::set_var("DEBUG", "1");
@@ -0,0 +1,189 @@
+use probe::lsp::LspServer;
+use serde_json::{json, Value};
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use tempfile::TempDir;
+
+// Helper function to create a small Rust project
+fn create_test_project() -> TempDir {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    fs::write(
+        temp_dir.path().join("lib.rs"),
+        r#"pub struct SearchEngine {
+    name: String,
+}
+
+impl SearchEngine {
+    pub fn run_query(&self) -> usize {
+        helper_score()
+    }
+}
+
+fn helper_score() -> usize {
+    42
+}
+"#,
+    )
+    .expect("Failed to write test file");
+    temp_dir
+}
+
+fn file_uri(path: &Path) -> String {
+    format!("file://{}", path.display())
+}
+
+fn initialized_server(root: &Path) -> LspServer {
+    let mut server = LspServer::new(root.to_path_buf());
+    let response = server
+        .handle_message(&json!({
+            "jsonrpc": "2.0", "id": 1, "method": "initialize",
+            "params": {"rootUri": file_uri(root)}
+        }))
+        .unwrap();
+    assert_eq!(
+        response["result"]["capabilities"]["workspaceSymbolProvider"],
+        true
+    );
+    server
+}
+
+fn request(server: &mut LspServer, method: &str, params: Value) -> Value {
+    let response = server
+        .handle_message(&json!({"jsonrpc": "2.0", "id": 2, "method": method, "params": params}))
+        .expect("Requests should get a response");
+    assert!(
+        response.get("error").is_none(),
+        "Unexpected error: {}",
+        response
+    );
+    response["result"].clone()
+}
+
+#[test]
+fn test_workspace_symbol() {
+    let temp_dir = create_test_project();
+    let mut server = initialized_server(temp_dir.path());
+
+    let result = request(&mut server, "workspace/symbol", json!({"query": "query"}));
+    let symbols = result.as_array().unwrap();
+    assert_eq!(symbols.len(), 1);
+    assert_eq!(symbols[0]["name"], "run_query");
+    assert_eq!(symbols[0]["containerName"], "SearchEngine");
+    // Method
+    assert_eq!(symbols[0]["kind"], 6);
+    assert_eq!(symbols[0]["location"]["range"]["start"]["line"], 5);
+
+    let result = request(&mut server, "workspace/symbol", json!({"query": "SEARCH"}));
+    let names: Vec<&str> = result
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|s| s["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, vec!["SearchEngine"]);
+}
+
+#[test]
+fn test_definition_uses_open_documents() {
+    let temp_dir = create_test_project();
+    let mut server = initialized_server(temp_dir.path());
+    let uri = file_uri(&temp_dir.path().join("lib.rs"));
+
+    // Cursor on the call to helper_score inside run_query
+    let result = request(
+        &mut server,
+        "textDocument/definition",
+        json!({"textDocument": {"uri": uri}, "position": {"line": 6, "character": 12}}),
+    );
+    let locations = result.as_array().unwrap();
+    assert_eq!(locations.len(), 1);
+    assert_eq!(locations[0]["range"]["start"]["line"], 10);
+    assert_eq!(locations[0]["range"]["start"]["character"], 3);
+
+    // Unsaved edits are used instead of the file on disk
+    server.handle_message(&json!({
+        "jsonrpc": "2.0", "method": "textDocument/didOpen",
+        "params": {"textDocument": {"uri": uri, "languageId": "rust", "version": 1,
+            "text": "fn helper_score() {}\nfn caller() { helper_score(); }\n"}}
+    }));
+    let result = request(
+        &mut server,
+        "textDocument/definition",
+        json!({"textDocument": {"uri": uri}, "position": {"line": 1, "character": 16}}),
+    );
+    assert_eq!(result[0]["range"]["start"]["line"], 0);
+}
+
+#[test]
+fn test_probe_search_returns_locations() {
+    let temp_dir = create_test_project();
+    let mut server = initialized_server(temp_dir.path());
+
+    let result = request(
+        &mut server,
+        "probe/search",
+        json!({"query": "helper_score"}),
+    );
+    let locations = result.as_array().unwrap();
+    assert!(!locations.is_empty());
+    assert!(locations[0]["uri"].as_str().unwrap().ends_with("/lib.rs"));
+    assert!(locations[0]["range"]["start"]["line"].is_u64());
+
+    let response = server
+        .handle_message(&json!({"jsonrpc": "2.0", "id": 3, "method": "probe/search", "params": {}}))
+        .unwrap();
+    assert_eq!(response["error"]["code"], -32602);
+}
+
+#[test]
+fn test_lsp_server_over_stdio() {
+    let temp_dir = create_test_project();
+    let messages = [
+        json!({"jsonrpc": "2.0", "id": 1, "method": "initialize",
+            "params": {"rootUri": file_uri(temp_dir.path())}}),
+        json!({"jsonrpc": "2.0", "method": "initialized", "params": {}}),
+        json!({"jsonrpc": "2.0", "id": 2, "method": "workspace/symbol",
+            "params": {"query": "helper"}}),
+        json!({"jsonrpc": "2.0", "id": 3, "method": "shutdown"}),
+        json!({"jsonrpc": "2.0", "method": "exit"}),
+    ];
+
+    let mut child = Command::new("cargo")
+        .args(["run", "--", "lsp"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("Failed to start LSP server");
+
+    {
+        let mut stdin = child.stdin.take().unwrap();
+        for message in &messages {
+            let body = message.to_string();
+            write!(stdin, "Content-Length: {}\r\n\r\n{}", body.len(), body).unwrap();
+        }
+    }
+
+    let output = child.wait_with_output().expect("Failed to wait for server");
+    assert!(output.status.success(), "LSP server should exit cleanly");
+
+    // Split the framed responses
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let responses: Vec<Value> = stdout
+        .split("Content-Length: ")
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let (_, body) = part
+                .split_once("\r\n\r\n")
+                .expect("Missing header separator");
+            serde_json::from_str(body).expect("Response body should be JSON")
+        })
+        .collect();
+
+    assert_eq!(responses.len(), 3);
+    assert_eq!(responses[1]["result"][0]["name"], "helper_score");
+    assert_eq!(responses[2]["id"], 3);
+    assert!(responses[2]["result"].is_null());
+}
@@ -0,0 +1,140 @@
+use serde_json::{json, Value};
+use std::fs::File;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use tempfile::TempDir;
+
+// Helper function to create a small project to search
+fn create_test_project() -> TempDir {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let mut file = File::create(temp_dir.path().join("search.rs")).expect("Failed to create file");
+    file.write_all(
+        br#"
+fn search_function(query: &str) -> bool {
+    println!("Searching for: {}", query);
+    query.contains("search")
+}
+"#,
+    )
+    .expect("Failed to write test content");
+    temp_dir
+}
+
+// Helper function to get the text of a tool call result
+fn tool_text(response: &Value) -> &str {
+    response["result"]["content"][0]["text"]
+        .as_str()
+        .expect("Tool result should contain text")
+}
+
+#[test]
+fn test_mcp_server_over_stdio() {
+    let temp_dir = create_test_project();
+    let path = temp_dir.path().to_str().unwrap();
+
+    let requests = [
+        json!({"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {"protocolVersion": "2024-11-05"}}),
+        json!({"jsonrpc": "2.0", "method": "notifications/initialized"}),
+        json!({"jsonrpc": "2.0", "id": 2, "method": "tools/list"}),
+        json!({"jsonrpc": "2.0", "id": 3, "method": "tools/call", "params": {
+            "name": "search_code",
+            "arguments": {"path": path, "query": "search_function"}
+        }}),
+    ];
+
+    let mut child = Command::new("cargo")
+        .args(["run", "--", "mcp"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("Failed to start MCP server");
+
+    {
+        let mut stdin = child.stdin.take().unwrap();
+        for request in &requests {
+            writeln!(stdin, "{}", request).unwrap();
+        }
+        // Dropping stdin closes it and lets the server exit
+    }
+
+    let output = child.wait_with_output().expect("Failed to wait for server");
+    assert!(output.status.success(), "MCP server should exit cleanly");
+
+    let responses: Vec<Value> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("Every stdout line should be JSON"))
+        .collect();
+
+    // The notification gets no response
+    assert_eq!(responses.len(), 3);
+
+    assert_eq!(responses[0]["id"], 1);
+    assert_eq!(responses[0]["result"]["serverInfo"]["name"], "probe");
+
+    let tools: Vec<&str> = responses[1]["result"]["tools"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|tool| tool["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(tools, vec!["search_code", "query_code", "extract_code"]);
+
+    assert_eq!(responses[2]["id"], 3);
+    let text = tool_text(&responses[2]);
+    assert!(text.contains("Session ID:"));
+    assert!(text.contains("search.rs"));
+    assert!(text.contains("fn search_function"));
+}
+
+#[test]
+fn test_mcp_query_and_extract_tools() {
+    let temp_dir = create_test_project();
+    let path = temp_dir.path().to_str().unwrap();
+
+    let response = probe::mcp::handle_message(&json!({
+        "jsonrpc": "2.0", "id": 1, "method": "tools/call", "params": {
+            "name": "query_code",
+            "arguments": {"path": path, "pattern": "fn $NAME($$$PARAMS) $$$BODY", "language": "rust"}
+        }
+    }))
+    .unwrap();
+    assert!(tool_text(&response).contains("search_function"));
+
+    let response = probe::mcp::handle_message(&json!({
+        "jsonrpc": "2.0", "id": 2, "method": "tools/call", "params": {
+            "name": "extract_code",
+            "arguments": {"path": path, "files": ["search.rs:3"]}
+        }
+    }))
+    .unwrap();
+    let text = tool_text(&response);
+    assert!(text.contains("search.rs"));
+    assert!(text.contains("query.contains(\"search\")"));
+}
+
+#[test]
+fn test_mcp_errors() {
+    // Tool failures are reported to the model as error results
+    let response = probe::mcp::handle_message(&json!({
+        "jsonrpc": "2.0", "id": 1, "method": "tools/call", "params": {
+            "name": "search_code",
+            "arguments": {"path": "."}
+        }
+    }))
+    .unwrap();
+    assert_eq!(response["result"]["isError"], true);
+    assert!(tool_text(&response).contains("Query is required"));
+
+    // Unknown tools and methods are protocol errors
+    let response = probe::mcp::handle_message(&json!({
+        "jsonrpc": "2.0", "id": 2, "method": "tools/call", "params": {"name": "missing"}
+    }))
+    .unwrap();
+    assert_eq!(response["error"]["code"], -32602);
+
+    let response =
+        probe::mcp::handle_message(&json!({"jsonrpc": "2.0", "id": 3, "method": "missing"}))
+            .unwrap();
+    assert_eq!(response["error"]["code"], -32601);
+}
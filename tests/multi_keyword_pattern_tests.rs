@@ -12,6 +12,9 @@ fn test_multi_keyword_pattern_generation() {
         required: false,
         excluded: false,
         exact: false,
+        proximity: None,
+        pattern: None,
+        boost: None,
     };
 
     // Create a term indices map
@@ -20,12 +23,7 @@ fn test_multi_keyword_pattern_generation() {
     term_indices.insert("list".to_string(), 1);
 
     // Create a QueryPlan
-    let plan = QueryPlan {
-        ast,
-        term_indices,
-        excluded_terms: HashSet::new(),
-        exact: false,
-    };
+    let plan = QueryPlan::new(ast, term_indices, HashSet::new(), false);
 
     // Generate patterns
     let patterns = create_structured_patterns(&plan);
@@ -71,6 +69,9 @@ fn test_excluded_term_pattern_generation() {
         required: false,
         excluded: true,
         exact: false,
+        proximity: None,
+        pattern: None,
+        boost: None,
     };
 
     // Create a term indices map
@@ -81,12 +82,7 @@ fn test_excluded_term_pattern_generation() {
     let mut excluded_terms = HashSet::new();
     excluded_terms.insert("excluded".to_string());
 
-    let plan = QueryPlan {
-        ast,
-        term_indices,
-        excluded_terms,
-        exact: false,
-    };
+    let plan = QueryPlan::new(ast, term_indices, excluded_terms, false);
 
     // Generate patterns
     let patterns = create_structured_patterns(&plan);
@@ -109,6 +105,9 @@ fn test_and_expression_pattern_generation() {
             required: false,
             excluded: false,
             exact: false,
+            proximity: None,
+            pattern: None,
+            boost: None,
         }),
         Box::new(Expr::Term {
             keywords: vec!["term2".to_string()],
@@ -116,6 +115,9 @@ fn test_and_expression_pattern_generation() {
             required: false,
             excluded: false,
             exact: false,
+            proximity: None,
+            pattern: None,
+            boost: None,
         }),
     );
 
@@ -125,12 +127,7 @@ fn test_and_expression_pattern_generation() {
     term_indices.insert("term2".to_string(), 1);
 
     // Create a QueryPlan
-    let plan = QueryPlan {
-        ast,
-        term_indices,
-        excluded_terms: HashSet::new(),
-        exact: false,
-    };
+    let plan = QueryPlan::new(ast, term_indices, HashSet::new(), false);
 
     // Generate patterns
     let patterns = create_structured_patterns(&plan);
@@ -168,6 +165,9 @@ fn test_or_expression_pattern_generation() {
             required: false,
             excluded: false,
             exact: false,
+            proximity: None,
+            pattern: None,
+            boost: None,
         }),
         Box::new(Expr::Term {
             keywords: vec!["term2".to_string()],
@@ -175,6 +175,9 @@ fn test_or_expression_pattern_generation() {
             required: false,
             excluded: false,
             exact: false,
+            proximity: None,
+            pattern: None,
+            boost: None,
         }),
     );
 
@@ -184,12 +187,7 @@ fn test_or_expression_pattern_generation() {
     term_indices.insert("term2".to_string(), 1);
 
     // Create a QueryPlan
-    let plan = QueryPlan {
-        ast,
-        term_indices,
-        excluded_terms: HashSet::new(),
-        exact: false,
-    };
+    let plan = QueryPlan::new(ast, term_indices, HashSet::new(), false);
 
     // Generate patterns
     let patterns = create_structured_patterns(&plan);
@@ -237,6 +235,9 @@ fn test_complex_expression_pattern_generation() {
             required: false,
             excluded: false,
             exact: false,
+            proximity: None,
+            pattern: None,
+            boost: None,
         }),
         Box::new(Expr::Or(
             Box::new(Expr::Term {
@@ -245,6 +246,9 @@ fn test_complex_expression_pattern_generation() {
                 required: false,
                 excluded: false,
                 exact: false,
+                proximity: None,
+                pattern: None,
+                boost: None,
             }),
             Box::new(Expr::Term {
                 keywords: vec!["network".to_string()],
@@ -252,6 +256,9 @@ fn test_complex_expression_pattern_generation() {
                 required: false,
                 excluded: false,
                 exact: false,
+                proximity: None,
+                pattern: None,
+                boost: None,
             }),
         )),
     );
@@ -265,12 +272,7 @@ fn test_complex_expression_pattern_generation() {
     term_indices.insert("network".to_string(), 4);
 
     // Create a QueryPlan
-    let plan = QueryPlan {
-        ast,
-        term_indices,
-        excluded_terms: HashSet::new(),
-        exact: false,
-    };
+    let plan = QueryPlan::new(ast, term_indices, HashSet::new(), false);
 
     // Generate patterns
     let patterns = create_structured_patterns(&plan);
@@ -328,6 +330,9 @@ fn test_pattern_deduplication() {
             required: false,
             excluded: false,
             exact: false,
+            proximity: None,
+            pattern: None,
+            boost: None,
         }),
         Box::new(Expr::Term {
             keywords: vec!["term".to_string()],
@@ -335,6 +340,9 @@ fn test_pattern_deduplication() {
             required: false,
             excluded: false,
             exact: false,
+            proximity: None,
+            pattern: None,
+            boost: None,
         }),
     );
 
@@ -343,12 +351,7 @@ fn test_pattern_deduplication() {
     term_indices.insert("term".to_string(), 0);
 
     // Create a QueryPlan
-    let plan = QueryPlan {
-        ast,
-        term_indices,
-        excluded_terms: HashSet::new(),
-        exact: false,
-    };
+    let plan = QueryPlan::new(ast, term_indices, HashSet::new(), false);
 
     // Generate patterns
     let patterns = create_structured_patterns(&plan);
@@ -0,0 +1,84 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+use probe::search::{perform_probe, SearchRequest};
+
+/// Create three sibling directories that all contain a matching file
+fn create_test_files(root: &Path) {
+    for dir in ["src", "lib", "other"] {
+        fs::create_dir_all(root.join(dir)).unwrap();
+        fs::write(
+            root.join(dir).join("session.rs"),
+            format!(
+                r#"
+pub fn refresh_session_{dir}(token: &str) -> String {{
+    token.to_string()
+}}
+"#
+            ),
+        )
+        .unwrap();
+    }
+}
+
+fn search(paths: &[PathBuf], query: &str) -> Vec<probe::SearchResult> {
+    let options = SearchRequest::new(query)
+        .paths(paths)
+        .exclude_filenames(true)
+        .allow_tests(true)
+        .no_merge(true);
+
+    perform_probe(&options).unwrap().results
+}
+
+#[test]
+fn test_search_multiple_roots() {
+    let temp_dir = TempDir::new().unwrap();
+    create_test_files(temp_dir.path());
+
+    let src = temp_dir.path().join("src");
+    let lib = temp_dir.path().join("lib");
+    let results = search(&[src.clone(), lib.clone()], "refresh");
+
+    let files: HashSet<&str> = results.iter().map(|r| r.file.as_str()).collect();
+    assert_eq!(
+        files.len(),
+        2,
+        "Expected one file per root, got {:?}",
+        files
+    );
+    assert!(results.iter().all(|r| !r.file.contains("other")));
+
+    // Every result remembers the root it was found under
+    for result in &results {
+        let root = result.search_root.as_deref().expect("result without root");
+        assert!(
+            root == src.to_string_lossy() || root == lib.to_string_lossy(),
+            "Unexpected root {}",
+            root
+        );
+        assert!(result.file.starts_with(root));
+    }
+}
+
+#[test]
+fn test_overlapping_roots_are_deduplicated() {
+    let temp_dir = TempDir::new().unwrap();
+    create_test_files(temp_dir.path());
+
+    let root = temp_dir.path().to_path_buf();
+    let nested = temp_dir.path().join("src");
+    let results = search(&[root.clone(), nested], "refresh");
+
+    let files: Vec<&str> = results.iter().map(|r| r.file.as_str()).collect();
+    let unique: HashSet<&str> = files.iter().copied().collect();
+    assert_eq!(files.len(), unique.len(), "Duplicate results: {:?}", files);
+    assert_eq!(unique.len(), 3);
+
+    // Files reachable from both roots are attributed to the first one
+    assert!(results
+        .iter()
+        .all(|r| r.search_root.as_deref() == Some(&*root.to_string_lossy())));
+}
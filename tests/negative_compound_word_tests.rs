@@ -3,7 +3,7 @@ use std::path::Path;
 use tempfile::TempDir;
 
 use probe::search::query::create_query_plan;
-use probe::search::{perform_probe, SearchOptions};
+use probe::search::{perform_probe, SearchRequest};
 
 /// Test negative compound word handling
 #[test]
@@ -82,30 +82,13 @@ fn test_basic_negative_compound_word(temp_path: &Path) {
 
     // Create the query
     let query = "-networkfirewall";
-    let queries = vec![query.to_string()];
-    let custom_ignores: Vec<String> = vec![];
-
-    // Create SearchOptions
-    let options = SearchOptions {
-        path: temp_path,
-        queries: &queries,
-        files_only: false,
-        custom_ignores: &custom_ignores,
-        exclude_filenames: false,
-        language: None,
-        reranker: "hybrid",
-        frequency_search: true,
-        max_results: None,
-        max_bytes: None,
-        max_tokens: None,
-        allow_tests: true,
-        no_merge: true,
-        merge_threshold: None,
-        dry_run: false,
-        session: None,
-        timeout: 30,
-        exact: false,
-    };
+
+    // Create the search request
+    let options = SearchRequest::new(query)
+        .path(temp_path)
+        .reranker("hybrid")
+        .allow_tests(true)
+        .no_merge(true);
 
     // Run the search
     let search_results = perform_probe(&options).unwrap();
@@ -147,31 +130,13 @@ fn test_complex_negative_compound_word(temp_path: &Path) {
 
     // Create the query
     let query = "settings AND -network AND -firewall";
-    let queries = vec![query.to_string()];
-    let custom_ignores: Vec<String> = vec![];
-
-    // Create SearchOptions
-    let options = SearchOptions {
-        path: temp_path,
-        queries: &queries,
-        files_only: false,
-        custom_ignores: &custom_ignores,
-        exclude_filenames: false,
-        language: None,
-        reranker: "hybrid",
-        frequency_search: true,
-        max_results: None,
-        max_bytes: None,
-        max_tokens: None,
-        allow_tests: true,
-        // Use all terms mode
-        no_merge: true,
-        merge_threshold: None,
-        dry_run: false,
-        session: None,
-        timeout: 30,
-        exact: false,
-    };
+
+    // Create the search request
+    let options = SearchRequest::new(query)
+        .path(temp_path)
+        .reranker("hybrid")
+        .allow_tests(true)
+        .no_merge(true);
 
     // Run the search
     let search_results = perform_probe(&options).unwrap();
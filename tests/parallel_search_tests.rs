@@ -1,5 +1,6 @@
 use probe::search::search_runner::{perform_probe, search_with_structured_patterns};
-use probe::search::SearchOptions;
+use probe::search::timeout::CancellationToken;
+use probe::search::SearchRequest;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::time::Instant;
@@ -58,33 +59,16 @@ fn test_parallel_file_search() {
     let lines_per_file = 100;
     let _file_paths = create_test_files(base_path, file_count, lines_per_file);
 
-    // Create search options
-    let queries = vec![
-        "search_term_alpha".to_string(),
-        "search_term_beta".to_string(),
-    ];
-    let custom_ignores = Vec::new();
-
-    let options = SearchOptions {
-        path: base_path,
-        queries: &queries,
-        files_only: false,
-        custom_ignores: &custom_ignores,
-        exclude_filenames: false,
-        language: None,
-        reranker: "hybrid",
-        frequency_search: false,
-        max_results: Some(100),
-        max_bytes: Some(1_000_000),
-        max_tokens: Some(100_000),
-        allow_tests: true,
-        no_merge: false,
-        merge_threshold: Some(5),
-        dry_run: false,
-        session: None,
-        timeout: 30,
-        exact: false,
-    };
+    // Create the search request
+    let options = SearchRequest::new("search_term_alpha")
+        .query("search_term_beta")
+        .path(base_path)
+        .reranker("hybrid")
+        .max_results(100)
+        .max_bytes(1_000_000)
+        .max_tokens(100_000)
+        .allow_tests(true)
+        .merge_threshold(5);
 
     // Measure search time
     let start_time = Instant::now();
@@ -140,6 +124,7 @@ fn test_structured_patterns_search() {
         &custom_ignores,
         true,
         None,
+        &CancellationToken::new(),
     );
     let duration = start_time.elapsed();
 
@@ -190,33 +175,16 @@ fn function_{}() {{
 
     fs::write(&file_path, content).unwrap();
 
-    // Create search options targeting the large file
-    let queries = vec![
-        "search_term_alpha".to_string(),
-        "search_term_beta".to_string(),
-    ];
-    let custom_ignores = Vec::new();
-
-    let options = SearchOptions {
-        path: base_path,
-        queries: &queries,
-        files_only: false,
-        custom_ignores: &custom_ignores,
-        exclude_filenames: false,
-        language: None,
-        reranker: "hybrid",
-        frequency_search: false,
-        max_results: Some(100),
-        max_bytes: Some(1_000_000),
-        max_tokens: Some(100_000),
-        allow_tests: true,
-        no_merge: false,
-        merge_threshold: Some(5),
-        dry_run: false,
-        session: None,
-        timeout: 30,
-        exact: false,
-    };
+    // Create the search request targeting the large file
+    let options = SearchRequest::new("search_term_alpha")
+        .query("search_term_beta")
+        .path(base_path)
+        .reranker("hybrid")
+        .max_results(100)
+        .max_bytes(1_000_000)
+        .max_tokens(100_000)
+        .allow_tests(true)
+        .merge_threshold(5);
 
     // Measure search time
     let start_time = Instant::now();
@@ -293,33 +261,16 @@ fn function_with_blocks_{}() {{
 
     fs::write(&file_path, content).unwrap();
 
-    // Create search options targeting the multi-block file
-    let queries = vec![
-        "search_term_alpha".to_string(),
-        "search_term_beta".to_string(),
-    ];
-    let custom_ignores = Vec::new();
-
-    let options = SearchOptions {
-        path: base_path,
-        queries: &queries,
-        files_only: false,
-        custom_ignores: &custom_ignores,
-        exclude_filenames: false,
-        language: None,
-        reranker: "hybrid",
-        frequency_search: false,
-        max_results: Some(100),
-        max_bytes: Some(1_000_000),
-        max_tokens: Some(100_000),
-        allow_tests: true,
-        no_merge: false,
-        merge_threshold: Some(5),
-        dry_run: false,
-        session: None,
-        timeout: 30,
-        exact: false,
-    };
+    // Create the search request targeting the multi-block file
+    let options = SearchRequest::new("search_term_alpha")
+        .query("search_term_beta")
+        .path(base_path)
+        .reranker("hybrid")
+        .max_results(100)
+        .max_bytes(1_000_000)
+        .max_tokens(100_000)
+        .allow_tests(true)
+        .merge_threshold(5);
 
     // Measure search time
     let start_time = Instant::now();
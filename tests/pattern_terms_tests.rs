@@ -0,0 +1,66 @@
+mod common;
+
+use tempfile::TempDir;
+
+use common::{create_project, files, search};
+
+/// Files whose identifiers share prefixes and suffixes
+fn create_test_project() -> TempDir {
+    create_project(&[
+        (
+            "session.rs",
+            "fn authenticate_user(name: &str) -> bool {\n    check_password(name)\n}\n",
+        ),
+        (
+            "handlers.rs",
+            "struct RequestHandler;\n\nfn dispatch(handler: &RequestHandler) {\n    handler.run();\n}\n",
+        ),
+        (
+            "network.rs",
+            "fn connect() {\n    // retries the connection three times\n    open_socket();\n}\n",
+        ),
+    ])
+}
+
+#[test]
+fn test_prefix_wildcard() {
+    let temp_dir = create_test_project();
+    assert_eq!(files(&search(&temp_dir, "auth*")), vec!["session.rs"]);
+}
+
+#[test]
+fn test_suffix_wildcard_matches_camel_case_parts() {
+    let temp_dir = create_test_project();
+    assert_eq!(files(&search(&temp_dir, "*Handler")), vec!["handlers.rs"]);
+}
+
+#[test]
+fn test_regex_term() {
+    let temp_dir = create_test_project();
+    assert_eq!(
+        files(&search(&temp_dir, "/re(try|tries)/")),
+        vec!["network.rs"]
+    );
+    assert!(search(&temp_dir, "/^fn\\s+missing/").is_empty());
+}
+
+#[test]
+fn test_patterns_combine_with_operators() {
+    let temp_dir = create_test_project();
+    assert_eq!(
+        files(&search(&temp_dir, "auth* OR /sock(et)?/")),
+        vec!["network.rs", "session.rs"]
+    );
+    assert_eq!(
+        files(&search(&temp_dir, "(check* OR dispatch OR connect) -auth*")),
+        vec!["handlers.rs", "network.rs"]
+    );
+}
+
+#[test]
+fn test_pattern_results_are_scored() {
+    let temp_dir = create_test_project();
+    let results = search(&temp_dir, "auth*");
+    assert!(!results.is_empty());
+    assert!(results.iter().all(|r| r.score.unwrap_or(0.0) > 0.0));
+}
@@ -0,0 +1,80 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+use tempfile::TempDir;
+
+use probe::search::file_list_cache::get_file_list;
+use probe::search::timeout::CancellationToken;
+use probe::search::{perform_probe, SearchRequest};
+
+fn search(path: &Path, query: &str) -> Vec<probe::SearchResult> {
+    let paths = [path.to_path_buf()];
+
+    let options = SearchRequest::new(query)
+        .paths(&paths)
+        .exclude_filenames(true)
+        .allow_tests(true)
+        .no_merge(true);
+
+    perform_probe(&options).unwrap().results
+}
+
+#[test]
+fn test_file_list_notices_new_files() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::create_dir_all(temp_dir.path().join("src")).unwrap();
+    fs::write(temp_dir.path().join("src/a.rs"), "fn a() {}\n").unwrap();
+
+    let file_list = get_file_list(temp_dir.path(), true, &[], &CancellationToken::new()).unwrap();
+    assert_eq!(file_list.files.len(), 1);
+    assert!(file_list.is_fresh());
+
+    // Make sure the directory's new modification time differs from the stamped one
+    thread::sleep(Duration::from_millis(20));
+    fs::write(temp_dir.path().join("src/b.rs"), "fn b() {}\n").unwrap();
+    assert!(!file_list.is_fresh());
+
+    let file_list = get_file_list(temp_dir.path(), true, &[], &CancellationToken::new()).unwrap();
+    let mut files: Vec<PathBuf> = file_list.files.clone();
+    files.sort();
+    assert_eq!(
+        files,
+        vec![
+            temp_dir.path().join("src/a.rs"),
+            temp_dir.path().join("src/b.rs")
+        ]
+    );
+}
+
+#[test]
+fn test_changed_files_are_reparsed() {
+    let temp_dir = TempDir::new().unwrap();
+    let file = temp_dir.path().join("lib.rs");
+    fs::write(
+        &file,
+        "pub fn refresh_session() -> u32 {\n    42\n}\n\npub fn other() {}\n",
+    )
+    .unwrap();
+
+    // The second search is answered from the cached line map and tokens
+    let first = search(temp_dir.path(), "refresh");
+    let second = search(temp_dir.path(), "refresh");
+    assert_eq!(first.len(), 1);
+    assert_eq!(second.len(), 1);
+    assert_eq!(first[0].code, second[0].code);
+    assert_eq!(first[0].lines, second[0].lines);
+
+    // Make sure the new modification time differs from the cached one
+    thread::sleep(Duration::from_millis(20));
+    fs::write(
+        &file,
+        "pub fn other() {}\n\npub fn refresh_token() -> u32 {\n    let token = 7;\n    token\n}\n",
+    )
+    .unwrap();
+
+    let changed = search(temp_dir.path(), "refresh");
+    assert_eq!(changed.len(), 1);
+    assert_eq!(changed[0].lines, (3, 6));
+    assert!(changed[0].code.contains("refresh_token"));
+}
@@ -93,6 +93,9 @@ proptest! {
             documents: &docs_refs,
             query: &query,
             pre_tokenized: None,
+            filenames: None,
+            bm25: Default::default(),
+            index: None,
         };
 
         // This should never panic
@@ -0,0 +1,75 @@
+mod common;
+
+use tempfile::TempDir;
+
+use common::{create_project, files, search, write_files};
+
+/// One function that implements retry backoff, and one file that mentions each word once,
+/// far apart
+fn create_test_project() -> TempDir {
+    let mut scattered = String::from("fn schedule_job() {\n    let retry = true;\n");
+    for i in 0..40 {
+        scattered.push_str(&format!("    let value_{} = compute({});\n", i, i));
+    }
+    scattered.push_str("    let backoff = 3;\n}\n");
+    create_project(&[
+        (
+            "client.rs",
+            "fn send_request(req: &Request) -> Response {\n    loop {\n        // retry with exponential backoff\n        sleep(delay);\n    }\n}\n",
+        ),
+        ("jobs.rs", &scattered),
+    ])
+}
+
+#[test]
+fn test_plain_terms_match_anywhere() {
+    let temp_dir = create_test_project();
+    assert_eq!(
+        files(&search(&temp_dir, "retry backoff")),
+        vec!["client.rs", "jobs.rs"]
+    );
+}
+
+#[test]
+fn test_near_requires_terms_close_together() {
+    let temp_dir = create_test_project();
+    assert_eq!(
+        files(&search(&temp_dir, "retry NEAR/3 backoff")),
+        vec!["client.rs"]
+    );
+    assert_eq!(
+        files(&search(&temp_dir, "\"retry backoff\"~2")),
+        vec!["client.rs"]
+    );
+
+    // "retry with exponential backoff": `with` is a stop word, so one token in between
+    assert!(search(&temp_dir, "\"retry backoff\"~0").is_empty());
+    assert_eq!(
+        files(&search(&temp_dir, "\"retry backoff\"~1")),
+        vec!["client.rs"]
+    );
+}
+
+#[test]
+fn test_excluded_proximity_term() {
+    let temp_dir = create_test_project();
+    let results = search(&temp_dir, "+retry -\"retry backoff\"~3");
+    assert_eq!(files(&results), vec!["jobs.rs"]);
+}
+
+#[test]
+fn test_closer_terms_score_higher() {
+    let temp_dir = create_test_project();
+    write_files(
+        temp_dir.path(),
+        &[(
+            "worker.rs",
+            "fn run_worker() {\n    // retry failed tasks; the delay between attempts grows with backoff\n    run();\n}\n",
+        )],
+    );
+
+    let results = search(&temp_dir, "retry NEAR/10 backoff");
+    assert_eq!(files(&results), vec!["client.rs", "worker.rs"]);
+    assert!(results[0].file.ends_with("client.rs"));
+    assert!(results[0].score.unwrap() > results[1].score.unwrap());
+}
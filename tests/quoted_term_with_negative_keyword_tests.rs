@@ -2,7 +2,7 @@ use std::fs;
 use std::path::Path;
 use tempfile::TempDir;
 
-use probe::search::{perform_probe, SearchOptions};
+use probe::search::{perform_probe, SearchRequest};
 
 /// Create test files with different content for testing queries
 fn create_test_files(temp_dir: &Path) {
@@ -50,7 +50,6 @@ fn test_quoted_term_with_negative_keyword() {
 
     // Create search query with a quoted term and a negative keyword
     let queries = vec!["\"keywordAlpha\" -keywordGamma".to_string()];
-    let custom_ignores: Vec<String> = vec![];
 
     // Print the test files for debugging
     println!("Test files created in: {:?}", temp_path);
@@ -67,33 +66,18 @@ fn test_quoted_term_with_negative_keyword() {
         );
     }
 
-    // Create SearchOptions
-    let options = SearchOptions {
-        path: temp_path,
-        queries: &queries,
-        files_only: false,
-        custom_ignores: &custom_ignores,
-        exclude_filenames: false,
-        language: None,
-        reranker: "hybrid",
-        frequency_search: false,
-        max_results: None,
-        max_bytes: None,
-        max_tokens: None,
-        allow_tests: true,
-        no_merge: false,
-        merge_threshold: Some(5),
-        dry_run: false,
-        session: None,
-        timeout: 30,
-        exact: false,
-    };
+    // Create the search request
+    let options = SearchRequest::new(queries[0].as_str())
+        .path(temp_path)
+        .reranker("hybrid")
+        .allow_tests(true)
+        .merge_threshold(5);
 
     // Print the query for debugging
     println!("Executing search with query: {:?}", queries);
     println!(
-        "Path: {:?}, frequency_search: {}",
-        options.path, options.frequency_search
+        "Paths: {:?}, frequency_search: {}",
+        options.paths, options.frequency_search
     );
 
     // Run the search
@@ -146,29 +130,13 @@ fn test_negative_quoted_term() {
 
     // Create search query with a negative quoted term
     let queries = vec!["\"keywordalpha\" -\"keywordgamma\"".to_string()];
-    let custom_ignores: Vec<String> = vec![];
-
-    // Create SearchOptions
-    let options = SearchOptions {
-        path: temp_path,
-        queries: &queries,
-        files_only: false,
-        custom_ignores: &custom_ignores,
-        exclude_filenames: false,
-        language: None,
-        reranker: "hybrid",
-        frequency_search: false,
-        max_results: None,
-        max_bytes: None,
-        max_tokens: None,
-        allow_tests: true,
-        no_merge: false,
-        merge_threshold: Some(5),
-        dry_run: false,
-        session: None,
-        timeout: 30,
-        exact: false,
-    };
+
+    // Create the search request
+    let options = SearchRequest::new(queries[0].as_str())
+        .path(temp_path)
+        .reranker("hybrid")
+        .allow_tests(true)
+        .merge_threshold(5);
 
     // Print the query for debugging
     println!("Executing search with query: {:?}", queries);
@@ -0,0 +1,102 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use tempfile::TempDir;
+
+use probe::{perform_probe, SearchRequest};
+
+// Run git in `dir` with a fixed identity, optionally backdating the commit
+fn git(dir: &Path, args: &[&str], date: Option<&str>) {
+    let mut command = Command::new("git");
+    command
+        .current_dir(dir)
+        .args([
+            "-c",
+            "user.name=Probe",
+            "-c",
+            "user.email=probe@example.com",
+        ])
+        .args(args);
+    if let Some(date) = date {
+        command
+            .env("GIT_AUTHOR_DATE", date)
+            .env("GIT_COMMITTER_DATE", date);
+    }
+    let status = command.status().expect("Failed to run git");
+    assert!(status.success());
+}
+
+/// Two files with the same content: one last changed years ago, one changed just now
+fn create_repo(root: &Path) {
+    let code = "pub fn parse_config(path: &str) -> String {\n    path.to_string()\n}\n";
+    git(root, &["init", "-q"], None);
+    fs::write(root.join("old.rs"), code).unwrap();
+    git(root, &["add", "old.rs"], None);
+    git(
+        root,
+        &["commit", "-q", "-m", "old"],
+        Some("2020-01-01T00:00:00"),
+    );
+    fs::write(root.join("new.rs"), code).unwrap();
+    git(root, &["add", "new.rs"], None);
+    git(root, &["commit", "-q", "-m", "new"], None);
+}
+
+fn search(request: SearchRequest, root: &Path) -> Vec<probe::SearchResult> {
+    let request = request.path(root).exclude_filenames(true);
+    perform_probe(&request).unwrap().results
+}
+
+#[test]
+fn test_boost_recent_prefers_recently_changed_files() {
+    let temp_dir = TempDir::new().unwrap();
+    create_repo(temp_dir.path());
+
+    let results = search(SearchRequest::new("parse_config"), temp_dir.path());
+    assert_eq!(results.len(), 2);
+    assert!(results.iter().all(|r| r.recency_boost.is_none()));
+
+    let results = search(
+        SearchRequest::new("parse_config").boost_recent(30.0),
+        temp_dir.path(),
+    );
+    assert!(results[0].file.ends_with("new.rs"));
+    // One commit today: 1 + ln(2)
+    let new_boost = results[0].recency_boost.unwrap();
+    assert!((new_boost - (1.0 + 2f64.ln())).abs() < 0.01);
+    // Commits older than ten half-lives are not counted
+    assert_eq!(results[1].recency_boost, Some(1.0));
+    assert!(results[0].score > results[1].score);
+}
+
+#[test]
+fn test_boost_recent_outside_git_repository() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("lib.rs"), "pub fn parse_config() {}\n").unwrap();
+
+    let results = search(
+        SearchRequest::new("parse_config").boost_recent(30.0),
+        temp_dir.path(),
+    );
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].recency_boost, None);
+}
+
+#[test]
+fn test_boost_recent_rejects_invalid_half_life() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("lib.rs"), "pub fn parse_config() {}\n").unwrap();
+
+    for half_life in [0.0, -7.0, f64::NAN, f64::INFINITY] {
+        let request = SearchRequest::new("parse_config")
+            .path(temp_dir.path())
+            .boost_recent(half_life);
+        let error = perform_probe(&request).unwrap_err();
+        assert!(
+            error.to_string().contains("half-life"),
+            "{}: {}",
+            half_life,
+            error
+        );
+    }
+}
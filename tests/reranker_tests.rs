@@ -0,0 +1,131 @@
+use std::fs;
+use tempfile::TempDir;
+
+use probe::models::SearchResult;
+use probe::ranking::{reranker_by_name, Bm25fReranker, Reranker, RERANKERS};
+use probe::{perform_probe, SearchRequest};
+
+// Helper function to build a result the way file processing does
+fn result(
+    file: &str,
+    code: &str,
+    file_unique_terms: usize,
+    file_total_matches: usize,
+) -> SearchResult {
+    SearchResult {
+        file: file.to_string(),
+        lines: (1, code.lines().count()),
+        node_type: "function_item".to_string(),
+        code: code.to_string(),
+        file_unique_terms: Some(file_unique_terms),
+        file_total_matches: Some(file_total_matches),
+        ..Default::default()
+    }
+}
+
+fn best(reranker: &dyn Reranker, query: &str, results: &[SearchResult]) -> usize {
+    let mut scored = reranker.score(query, results);
+    scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+    scored[0].0
+}
+
+#[test]
+fn test_all_rerankers_are_selectable() {
+    for name in RERANKERS {
+        let reranker = reranker_by_name(name).expect("Built-in reranker should exist");
+        assert_eq!(reranker.name(), *name);
+    }
+    assert!(reranker_by_name("unknown").is_none());
+}
+
+#[test]
+fn test_rerankers_respect_required_terms() {
+    let results = vec![
+        result("a.rs", "fn parse() { config(); }", 2, 2),
+        result("b.rs", "fn parse() { legacy(); }", 1, 1),
+    ];
+    for name in RERANKERS {
+        let reranker = reranker_by_name(name).unwrap();
+        let scored = reranker.score("parse +config", &results);
+        assert_eq!(
+            scored.iter().map(|&(i, _)| i).collect::<Vec<_>>(),
+            vec![0],
+            "{} should drop the result missing a required term",
+            name
+        );
+    }
+}
+
+#[test]
+fn test_bm25f_prefers_symbol_name_matches() {
+    let results = vec![
+        // Mentions the term three times in the body
+        result(
+            "src/caller.rs",
+            "fn run() {\n    let a = tokenizer();\n    tokenizer();\n    tokenizer();\n}",
+            1,
+            3,
+        ),
+        // Declares it
+        result(
+            "src/lib.rs",
+            "/// Builds the thing\nfn tokenizer() {\n    build();\n}",
+            1,
+            1,
+        ),
+    ];
+    assert_eq!(best(&Bm25fReranker::default(), "tokenizer", &results), 1);
+
+    // With the symbol field switched off the body matches win again
+    let mut body_only = Bm25fReranker::default();
+    body_only.weights.symbol = 0.0;
+    body_only.weights.filename = 0.0;
+    assert_eq!(best(&body_only, "tokenizer", &results), 0);
+}
+
+#[test]
+fn test_hybrid_uses_file_statistics() {
+    // Identical blocks, but the second file matches every query term many times
+    let results = vec![
+        result("a.rs", "fn load_config() {}", 1, 1),
+        result("b.rs", "fn load_config() {}", 2, 20),
+    ];
+    let hybrid = reranker_by_name("hybrid").unwrap();
+    assert_eq!(best(hybrid.as_ref(), "load config", &results), 1);
+}
+
+#[test]
+fn test_search_fills_reranker_fields() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("parser.rs"),
+        "fn parse_header(input: &str) -> usize {\n    input.len()\n}\n\nfn parse_body(input: &str) -> usize {\n    parse_header(input)\n}\n",
+    )
+    .unwrap();
+
+    let search = |reranker: &str| {
+        let request = SearchRequest::new("parse header")
+            .path(temp_dir.path())
+            .exclude_filenames(true)
+            .no_merge(true)
+            .reranker(reranker);
+        perform_probe(&request).unwrap().results
+    };
+
+    let results = search("tfidf");
+    assert!(!results.is_empty());
+    for r in &results {
+        assert!(r.tfidf_score.is_some() && r.tfidf_rank.is_some());
+        assert_eq!(r.combined_score_rank, r.rank);
+    }
+
+    let results = search("hybrid");
+    assert!(results
+        .iter()
+        .all(|r| r.new_score.is_some() && r.hybrid2_rank.is_some()));
+    assert_eq!(results[0].hybrid2_rank, Some(1));
+
+    let results = search("bm25f");
+    assert!(results.iter().all(|r| r.bm25_score.is_some()));
+    assert!(results[0].code.starts_with("fn parse_header"));
+}
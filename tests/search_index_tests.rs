@@ -0,0 +1,218 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+use tempfile::TempDir;
+
+use probe::search::index::{index_path, supports_patterns, SearchIndex};
+use probe::search::query::{create_query_plan, create_structured_patterns};
+use probe::search::search_runner::search_with_structured_patterns;
+use probe::search::timeout::CancellationToken;
+use probe::search::tokenization;
+use probe::{perform_probe, SearchRequest};
+
+type FileTermMap = HashMap<PathBuf, HashMap<usize, HashSet<usize>>>;
+
+fn create_test_files(root: &Path) {
+    fs::create_dir_all(root.join("src")).unwrap();
+
+    fs::write(
+        root.join("src/session.rs"),
+        r#"
+/// Refreshes the session token
+pub fn refresh_session(token: &str) -> String {
+    let refreshed = token.to_uppercase();
+    refreshed
+}
+
+pub struct SessionStore {
+    sessions: Vec<String>,
+}
+"#,
+    )
+    .unwrap();
+
+    fs::write(
+        root.join("src/client.go"),
+        r#"
+package client
+
+// RefreshSession asks the server for a new session
+func RefreshSession(token string) string {
+	return token + "-refreshed"
+}
+"#,
+    )
+    .unwrap();
+}
+
+fn scan(root: &Path, query: &str) -> FileTermMap {
+    let plan = create_query_plan(query, false).unwrap();
+    let patterns = create_structured_patterns(&plan);
+    search_with_structured_patterns(
+        root,
+        &plan,
+        &patterns,
+        &[],
+        true,
+        None,
+        &CancellationToken::new(),
+    )
+    .unwrap()
+}
+
+/// Removes the index written for a test root from the probe cache directory
+struct IndexCleanup(PathBuf);
+
+impl Drop for IndexCleanup {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+#[test]
+fn test_index_matches_scan() {
+    let temp_dir = TempDir::new().unwrap();
+    create_test_files(temp_dir.path());
+
+    let queries = [
+        "refresh",
+        "session AND token",
+        "refresh OR store",
+        "sessionstore",
+    ];
+    let scanned: Vec<FileTermMap> = queries.iter().map(|q| scan(temp_dir.path(), q)).collect();
+
+    let index = SearchIndex::build(temp_dir.path()).unwrap();
+    let _cleanup = IndexCleanup(index.save().unwrap());
+    assert_eq!(index.files.len(), 2);
+    assert!(index.files.iter().all(|file| file.size > 0));
+    assert!(index.postings.contains_key("refresh_session"));
+
+    let loaded = SearchIndex::load_for(&temp_dir.path().join("src")).expect("index not found");
+    for file in ["src/session.rs", "src/client.go"] {
+        assert!(loaded.fresh_file_id(&temp_dir.path().join(file)).is_some());
+    }
+
+    // Searching with the index gives exactly the same matches as scanning
+    for (query, expected) in queries.iter().zip(scanned) {
+        let plan = create_query_plan(query, false).unwrap();
+        assert!(supports_patterns(&create_structured_patterns(&plan)));
+        assert_eq!(scan(temp_dir.path(), query), expected, "query: {}", query);
+    }
+}
+
+#[test]
+fn test_changed_files_are_rescanned() {
+    let temp_dir = TempDir::new().unwrap();
+    create_test_files(temp_dir.path());
+
+    let index = SearchIndex::build(temp_dir.path()).unwrap();
+    let index_file = index.save().unwrap();
+    let _cleanup = IndexCleanup(index_file.clone());
+    assert_eq!(
+        index_file,
+        index_path(&fs::canonicalize(temp_dir.path()).unwrap())
+    );
+
+    // Make sure the new modification time differs from the indexed one
+    thread::sleep(Duration::from_millis(20));
+    let changed = temp_dir.path().join("src/client.go");
+    fs::write(&changed, "package client\n\nfunc Invalidate() {}\n").unwrap();
+
+    let loaded = SearchIndex::load_for(temp_dir.path()).unwrap();
+    assert!(loaded.fresh_file_id(&changed).is_none());
+
+    let matches = scan(temp_dir.path(), "invalidate");
+    assert_eq!(matches.len(), 1);
+    assert!(matches.keys().all(|file| file.ends_with("client.go")));
+
+    let matches = scan(temp_dir.path(), "refresh");
+    assert!(matches.keys().all(|file| file.ends_with("session.rs")));
+}
+
+#[test]
+fn test_index_stores_token_statistics() {
+    let temp_dir = TempDir::new().unwrap();
+    create_test_files(temp_dir.path());
+
+    let index = SearchIndex::build(temp_dir.path()).unwrap();
+    let _cleanup = IndexCleanup(index.save().unwrap());
+
+    let loaded = SearchIndex::load_for(temp_dir.path()).expect("index not found");
+    assert_eq!(loaded.vocabulary, tokenization::vocabulary_fingerprint());
+    let session = temp_dir.path().join("src/session.rs");
+    let file = &loaded.files[loaded.fresh_file_id(&session).unwrap()];
+    let tokens = tokenization::tokenize(&fs::read_to_string(&session).unwrap());
+    assert_eq!(file.doc_length, tokens.len());
+    assert_eq!(file.lines, 10);
+    assert_eq!(file.term_frequencies.values().sum::<usize>(), tokens.len());
+    for token in &tokens {
+        let count = tokens.iter().filter(|t| *t == token).count();
+        assert_eq!(file.term_frequencies[token], count, "token: {}", token);
+    }
+
+    let total: usize = loaded.files.iter().map(|file| file.doc_length).sum();
+    assert_eq!(loaded.average_doc_length(), total as f64 / 2.0);
+    assert_eq!(loaded.document_frequency("refresh"), 2);
+    assert_eq!(loaded.document_frequency("store"), 1);
+}
+
+#[test]
+fn test_ranking_uses_index_statistics() {
+    let temp_dir = TempDir::new().unwrap();
+    create_test_files(temp_dir.path());
+
+    let index = SearchIndex::build(temp_dir.path()).unwrap();
+    let _cleanup = IndexCleanup(index.save().unwrap());
+
+    let request = SearchRequest::new("store")
+        .path(temp_dir.path())
+        .exclude_filenames(true)
+        .explain(true);
+    let results = perform_probe(&request).unwrap().results;
+    assert!(!results.is_empty());
+    for result in &results {
+        let bm25 = result.explanation.as_ref().unwrap().bm25.as_ref().unwrap();
+        assert_eq!(bm25.avgdl, index.average_doc_length());
+        // IDF over both indexed files, not over the one matching block
+        let store = bm25.terms.iter().find(|term| term.term == "store").unwrap();
+        assert!((store.idf - (1.0f64 + 1.5 / 1.5).ln()).abs() < 1e-9);
+    }
+}
+
+#[test]
+fn test_ranking_reads_whole_file_statistics_from_index() {
+    let temp_dir = TempDir::new().unwrap();
+    create_test_files(temp_dir.path());
+    fs::write(
+        temp_dir.path().join("notes.txt"),
+        "The session store keeps one session per user.\nExpired entries leave the store.\n",
+    )
+    .unwrap();
+
+    let index = SearchIndex::build(temp_dir.path()).unwrap();
+    let _cleanup = IndexCleanup(index.save().unwrap());
+
+    let request = SearchRequest::new("store")
+        .path(temp_dir.path())
+        .exclude_filenames(true)
+        .explain(true);
+    let results = perform_probe(&request).unwrap().results;
+    let notes = results
+        .iter()
+        .find(|result| result.file.ends_with("notes.txt"))
+        .expect("notes.txt not found");
+    assert_eq!(notes.lines, (1, 2));
+
+    let file = &index.files[index.fresh_file_id(Path::new(&notes.file)).unwrap()];
+    let bm25 = notes.explanation.as_ref().unwrap().bm25.as_ref().unwrap();
+    let filename_tokens = tokenization::tokenize(&notes.file);
+    assert_eq!(
+        bm25.doc_length,
+        (file.doc_length + filename_tokens.len()) as f64
+    );
+    let store = bm25.terms.iter().find(|term| term.term == "store").unwrap();
+    assert_eq!(store.tf, file.term_frequencies["store"] as f64);
+}
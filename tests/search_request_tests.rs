@@ -0,0 +1,87 @@
+mod common;
+
+use tempfile::TempDir;
+
+use common::create_project;
+use probe::extract::PromptTemplate;
+use probe::{perform_probe, perform_query, ExtractOptions, QueryRequest, SearchRequest};
+
+// Helper function to create a small project to search
+fn create_test_project() -> TempDir {
+    create_project(&[(
+        "search.rs",
+        r#"
+fn search_function(query: &str) -> bool {
+    query.contains("search")
+}
+"#,
+    )])
+}
+
+#[test]
+fn test_search_request_defaults_match_cli() {
+    let request = SearchRequest::new("search");
+    assert_eq!(request.queries, vec!["search".to_string()]);
+    assert_eq!(request.paths, vec![std::path::PathBuf::from(".")]);
+    assert_eq!(request.reranker, "bm25");
+    assert!(request.frequency_search);
+    assert_eq!(request.timeout, 30);
+
+    // Missing fields take their defaults when deserializing
+    let parsed: SearchRequest =
+        serde_json::from_str(r#"{"queries": ["search"], "max_results": 5}"#).unwrap();
+    assert_eq!(parsed, request.clone().max_results(5));
+
+    // And a serialized request round-trips
+    let json = serde_json::to_string(&request).unwrap();
+    assert_eq!(
+        serde_json::from_str::<SearchRequest>(&json).unwrap(),
+        request
+    );
+}
+
+#[test]
+fn test_perform_probe_accepts_owned_request() {
+    let temp_dir = create_test_project();
+    let request = SearchRequest::new("search_function")
+        .path(temp_dir.path())
+        .max_results(1);
+
+    // The request can be moved to another thread before running
+    let results = std::thread::spawn(move || perform_probe(&request))
+        .join()
+        .unwrap()
+        .unwrap();
+    assert_eq!(results.results.len(), 1);
+    assert!(results.results[0].file.ends_with("search.rs"));
+}
+
+#[test]
+fn test_request_without_queries_is_an_error() {
+    let temp_dir = create_test_project();
+    let request: SearchRequest =
+        serde_json::from_str(&format!(r#"{{"paths": [{:?}]}}"#, temp_dir.path())).unwrap();
+    let error = perform_probe(&request).unwrap_err();
+    assert_eq!(error.to_string(), "At least one query is required");
+}
+
+#[test]
+fn test_query_and_extract_requests() {
+    let temp_dir = create_test_project();
+    let request: QueryRequest =
+        serde_json::from_str(r#"{"pattern": "fn $NAME($$$PARAMS) $$$BODY", "language": "rust"}"#)
+            .unwrap();
+    assert_eq!(request.format, "color");
+
+    let matches = perform_query(&request.path(temp_dir.path())).unwrap();
+    assert_eq!(matches.len(), 1);
+
+    let options: ExtractOptions =
+        serde_json::from_str(r#"{"files": ["search.rs:2"], "prompt": "engineer"}"#).unwrap();
+    assert_eq!(
+        options,
+        ExtractOptions::new(["search.rs:2"]).prompt(PromptTemplate::Engineer)
+    );
+    assert_eq!(options.format, "color");
+    assert_eq!(options.context_lines, 0);
+}
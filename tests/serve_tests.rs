@@ -0,0 +1,195 @@
+use serde_json::{json, Value};
+use std::fs;
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::thread;
+use tempfile::TempDir;
+
+// Helper function to create a small project to search
+fn create_test_project() -> TempDir {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    fs::write(
+        temp_dir.path().join("search.rs"),
+        r#"
+fn search_function(query: &str) -> bool {
+    println!("Searching for: {}", query);
+    query.contains("search")
+}
+"#,
+    )
+    .expect("Failed to write test file");
+    temp_dir
+}
+
+// Start a server on a free port in the background
+fn start_server() -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind");
+    let addr = listener.local_addr().unwrap();
+    thread::spawn(move || probe::serve::serve(listener));
+    addr
+}
+
+// Send one request and return the status code and parsed JSON body
+fn send(addr: SocketAddr, method: &str, path: &str, body: Option<Value>) -> (u16, Value) {
+    let body = body.map(|b| b.to_string()).unwrap_or_default();
+    let mut stream = TcpStream::connect(addr).expect("Failed to connect");
+    write!(
+        stream,
+        "{} {} HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+        method,
+        path,
+        body.len(),
+        body
+    )
+    .unwrap();
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    let (head, body) = response.split_once("\r\n\r\n").expect("Malformed response");
+    let status = head.split_whitespace().nth(1).unwrap().parse().unwrap();
+    (
+        status,
+        serde_json::from_str(body).expect("Body should be JSON"),
+    )
+}
+
+#[test]
+fn test_serve_endpoints() {
+    let temp_dir = create_test_project();
+    let path = temp_dir.path().to_str().unwrap();
+    let addr = start_server();
+
+    let (status, body) = send(addr, "GET", "/health", None);
+    assert_eq!(status, 200);
+    assert_eq!(body["status"], "ok");
+
+    let (status, body) = send(
+        addr,
+        "POST",
+        "/search",
+        Some(json!({"path": path, "query": "search_function"})),
+    );
+    assert_eq!(status, 200);
+    assert_eq!(body["summary"]["count"], 1);
+    assert!(body["results"][0]["file"]
+        .as_str()
+        .unwrap()
+        .ends_with("search.rs"));
+    assert_eq!(body["timed_out"], false);
+
+    let (status, body) = send(
+        addr,
+        "POST",
+        "/query",
+        Some(json!({"path": path, "pattern": "fn $NAME($$$PARAMS) $$$BODY", "language": "rust"})),
+    );
+    assert_eq!(status, 200);
+    assert_eq!(body["results"].as_array().unwrap().len(), 1);
+
+    let (status, body) = send(
+        addr,
+        "POST",
+        "/extract",
+        Some(json!({"path": path, "files": ["search.rs:3"]})),
+    );
+    assert_eq!(status, 200);
+    assert_eq!(body["results"][0]["lines"], json!([2, 5]));
+    assert_eq!(body["errors"], json!([]));
+}
+
+#[test]
+fn test_serve_applies_project_config() {
+    let temp_dir = create_test_project();
+    let path = temp_dir.path().to_str().unwrap();
+    let addr = start_server();
+    let search = |query: &str| {
+        let (status, body) = send(
+            addr,
+            "POST",
+            "/search",
+            Some(json!({"path": path, "query": query, "excludeFilenames": true})),
+        );
+        assert_eq!(status, 200);
+        body["summary"]["count"].as_u64().unwrap()
+    };
+
+    // `fn` is a built-in stop word until the project's vocabulary says otherwise
+    assert_eq!(search("fn"), 0);
+    fs::write(
+        temp_dir.path().join(".probe.json"),
+        r#"{"vocabulary": {"not_stop_words": ["fn"]}}"#,
+    )
+    .unwrap();
+    assert_eq!(search("fn"), 1);
+}
+
+#[test]
+fn test_serve_errors() {
+    let addr = start_server();
+
+    let (status, body) = send(addr, "POST", "/search", Some(json!({})));
+    assert_eq!(status, 400);
+    assert_eq!(body["error"], "Query is required");
+
+    let (status, body) = send(
+        addr,
+        "POST",
+        "/search",
+        Some(json!({"query": "search", "reranker": "cmd:touch /tmp/probe-serve-test"})),
+    );
+    assert_eq!(status, 400);
+    assert_eq!(body["error"], "External rerankers are not allowed");
+
+    let (status, _) = send(addr, "GET", "/search", None);
+    assert_eq!(status, 405);
+
+    let (status, _) = send(addr, "GET", "/missing", None);
+    assert_eq!(status, 404);
+}
+
+// Send a raw request and return the status code and the whole response
+fn send_raw(addr: SocketAddr, request: &str) -> (u16, String) {
+    let mut stream = TcpStream::connect(addr).expect("Failed to connect");
+    stream.write_all(request.as_bytes()).unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    let status = response.split_whitespace().nth(1).unwrap().parse().unwrap();
+    (status, response)
+}
+
+#[test]
+fn test_serve_rejects_requests_from_other_sites() {
+    let addr = start_server();
+
+    let (status, response) = send_raw(addr, "GET /health HTTP/1.1\r\nHost: localhost\r\n\r\n");
+    assert_eq!(status, 200);
+    assert!(!response.contains("Access-Control-Allow-Origin"));
+
+    // A web page on another site, through the browser or DNS rebinding
+    let body = r#"{"files": ["/etc/passwd"]}"#;
+    let (status, _) = send_raw(
+        addr,
+        &format!(
+            "POST /extract HTTP/1.1\r\nHost: localhost\r\nOrigin: https://example.com\r\nContent-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        ),
+    );
+    assert_eq!(status, 403);
+    let (status, _) = send_raw(
+        addr,
+        "GET /health HTTP/1.1\r\nHost: attacker.example:8080\r\n\r\n",
+    );
+    assert_eq!(status, 403);
+    let (status, _) = send_raw(addr, "GET /health HTTP/1.1\r\n\r\n");
+    assert_eq!(status, 403);
+
+    // Local pages are allowed, but there is no CORS preflight
+    let (status, _) = send_raw(
+        addr,
+        "GET /health HTTP/1.1\r\nHost: 127.0.0.1\r\nOrigin: http://localhost:3000\r\n\r\n",
+    );
+    assert_eq!(status, 200);
+    let (status, _) = send_raw(addr, "OPTIONS /search HTTP/1.1\r\nHost: [::1]:8080\r\n\r\n");
+    assert_eq!(status, 405);
+}
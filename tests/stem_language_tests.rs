@@ -0,0 +1,83 @@
+mod common;
+
+use tempfile::TempDir;
+
+use common::{create_project, files, search_request};
+use probe::config::ProjectConfig;
+use probe::ranking::StemLanguage;
+use probe::search::tokenization::{tokenize, with_vocabulary, VocabularyOverrides};
+use probe::SearchRequest;
+
+fn create_test_project() -> TempDir {
+    create_project(&[
+        (
+            "zahlung.rs",
+            "fn neue_zahlung(betrag: u64) -> Zahlung {\n    Zahlung::offen(betrag)\n}\n",
+        ),
+        (
+            "runner.rs",
+            "fn start(jobs: &[Job]) {\n    // Keeps the jobs running\n    loop {}\n}\n",
+        ),
+    ])
+}
+
+fn search(temp_dir: &TempDir, query: &str, vocabulary: &VocabularyOverrides) -> Vec<String> {
+    files(&search_request(
+        SearchRequest::new(query)
+            .path(temp_dir.path())
+            .vocabulary(vocabulary.clone()),
+    ))
+}
+
+#[test]
+fn test_english_stemming_by_default() {
+    let temp_dir = create_test_project();
+    let defaults = VocabularyOverrides::default();
+    assert_eq!(tokenize("zahlungen running"), vec!["zahlungen", "run"]);
+    assert!(search(&temp_dir, "zahlungen", &defaults).is_empty());
+    assert_eq!(search(&temp_dir, "runs", &defaults), vec!["runner.rs"]);
+}
+
+#[test]
+fn test_configured_stem_language() {
+    // Queries and code are stemmed in the configured language
+    let temp_dir = create_test_project();
+    let config: ProjectConfig =
+        serde_json::from_str(r#"{"vocabulary": {"stem_language": "german"}}"#).unwrap();
+    with_vocabulary(&config.vocabulary, || {
+        assert_eq!(tokenize("zahlungen running"), vec!["zahlung", "running"]);
+    });
+    assert_eq!(
+        search(&temp_dir, "zahlungen", &config.vocabulary),
+        vec!["zahlung.rs"]
+    );
+}
+
+#[test]
+fn test_no_stemming() {
+    // Words are matched as written without stemming
+    let temp_dir = create_test_project();
+    let vocabulary = VocabularyOverrides {
+        stem_language: Some(StemLanguage::None),
+        ..Default::default()
+    };
+    with_vocabulary(&vocabulary, || {
+        assert_eq!(tokenize("zahlungen running"), vec!["zahlungen", "running"]);
+    });
+    assert!(search(&temp_dir, "runs", &vocabulary).is_empty());
+}
+
+#[test]
+fn test_stem_language_names() {
+    assert_eq!("German".parse(), Ok(StemLanguage::German));
+    assert_eq!("none".parse(), Ok(StemLanguage::None));
+    assert!("klingon"
+        .parse::<StemLanguage>()
+        .unwrap_err()
+        .starts_with("expected one of arabic, danish"));
+    assert_eq!(StemLanguage::ALL.len(), 19);
+    assert!(serde_json::from_str::<ProjectConfig>(
+        r#"{"vocabulary": {"stem_language": "klingon"}}"#
+    )
+    .is_err());
+}
@@ -6,7 +6,7 @@ use tempfile::TempDir;
 use probe::search::elastic_query::parse_query_test as parse_query;
 use probe::search::file_processing::filter_code_block_with_ast;
 use probe::search::query::create_query_plan;
-use probe::search::{perform_probe, SearchOptions};
+use probe::search::{perform_probe, SearchRequest};
 
 /// Test stemming and compound word handling in block filtering with complex queries
 #[test]
@@ -77,30 +77,13 @@ fn test_stemming_with_and_query(temp_path: &Path) {
 
     // Create the query
     let query = "ips AND whitelisting";
-    let queries = vec![query.to_string()];
-    let custom_ignores: Vec<String> = vec![];
-
-    // Create SearchOptions with stemming enabled (exact=false)
-    let options = SearchOptions {
-        path: temp_path,
-        queries: &queries,
-        files_only: false,
-        custom_ignores: &custom_ignores,
-        exclude_filenames: false,
-        language: None,
-        reranker: "hybrid",
-        frequency_search: true,
-        max_results: None,
-        max_bytes: None,
-        max_tokens: None,
-        allow_tests: true,
-        no_merge: true,
-        merge_threshold: None,
-        dry_run: false,
-        session: None,
-        timeout: 30,
-        exact: false,
-    };
+
+    // Create the search request with stemming enabled (exact=false)
+    let options = SearchRequest::new(query)
+        .path(temp_path)
+        .reranker("hybrid")
+        .allow_tests(true)
+        .no_merge(true);
 
     // Run the search
     let search_results = perform_probe(&options).unwrap();
@@ -156,31 +139,13 @@ fn test_stemming_with_or_query(temp_path: &Path) {
 
     // Create the query
     let query = "ips OR whitelisting";
-    let queries = vec![query.to_string()];
-    let custom_ignores: Vec<String> = vec![];
-
-    // Create SearchOptions with stemming enabled (exact=false)
-    let options = SearchOptions {
-        path: temp_path,
-        queries: &queries,
-        files_only: false,
-        custom_ignores: &custom_ignores,
-        exclude_filenames: false,
-        language: None,
-        reranker: "hybrid",
-        frequency_search: true,
-        max_results: None,
-        max_bytes: None,
-        max_tokens: None,
-        allow_tests: true,
-        // Use any term mode
-        no_merge: true,
-        merge_threshold: None,
-        dry_run: false,
-        session: None,
-        timeout: 30,
-        exact: false,
-    };
+
+    // Create the search request with stemming enabled (exact=false)
+    let options = SearchRequest::new(query)
+        .path(temp_path)
+        .reranker("hybrid")
+        .allow_tests(true)
+        .no_merge(true);
 
     // Run the search
     let search_results = perform_probe(&options).unwrap();
@@ -359,30 +324,13 @@ fn test_compound_word_splitting(temp_path: &Path) {
 
     // Create the query
     let query = "networkfirewall";
-    let queries = vec![query.to_string()];
-    let custom_ignores: Vec<String> = vec![];
-
-    // Create SearchOptions with stemming and compound word splitting enabled
-    let options = SearchOptions {
-        path: temp_path,
-        queries: &queries,
-        files_only: false,
-        custom_ignores: &custom_ignores,
-        exclude_filenames: false,
-        language: None,
-        reranker: "hybrid",
-        frequency_search: true,
-        max_results: None,
-        max_bytes: None,
-        max_tokens: None,
-        allow_tests: true,
-        no_merge: true,
-        merge_threshold: None,
-        dry_run: false,
-        session: None,
-        timeout: 30,
-        exact: false,
-    };
+
+    // Create the search request with stemming and compound word splitting enabled
+    let options = SearchRequest::new(query)
+        .path(temp_path)
+        .reranker("hybrid")
+        .allow_tests(true)
+        .no_merge(true);
 
     // Run the search
     let search_results = perform_probe(&options).unwrap();
@@ -405,29 +353,12 @@ fn test_compound_word_splitting(temp_path: &Path) {
 
     // Now test with a more complex query: "network AND firewall"
     let complex_query = "network AND firewall";
-    let complex_queries = vec![complex_query.to_string()];
-
-    let complex_options = SearchOptions {
-        path: temp_path,
-        queries: &complex_queries,
-        files_only: false,
-        custom_ignores: &custom_ignores,
-        exclude_filenames: false,
-        language: None,
-        reranker: "hybrid",
-        frequency_search: true,
-        max_results: None,
-        max_bytes: None,
-        max_tokens: None,
-        allow_tests: true,
-        // Use all terms mode
-        no_merge: true,
-        merge_threshold: None,
-        dry_run: false,
-        session: None,
-        timeout: 30,
-        exact: false,
-    };
+
+    let complex_options = SearchRequest::new(complex_query)
+        .path(temp_path)
+        .reranker("hybrid")
+        .allow_tests(true)
+        .no_merge(true);
 
     // Run the search
     let complex_results = perform_probe(&complex_options).unwrap();
@@ -461,30 +392,13 @@ fn test_negative_compound_word_in_existing_tests(temp_path: &Path) {
 
     // Create the query
     let query = "-networkfirewall";
-    let queries = vec![query.to_string()];
-    let custom_ignores: Vec<String> = vec![];
-
-    // Create SearchOptions
-    let options = SearchOptions {
-        path: temp_path,
-        queries: &queries,
-        files_only: false,
-        custom_ignores: &custom_ignores,
-        exclude_filenames: false,
-        language: None,
-        reranker: "hybrid",
-        frequency_search: true,
-        max_results: None,
-        max_bytes: None,
-        max_tokens: None,
-        allow_tests: true,
-        no_merge: true,
-        merge_threshold: None,
-        dry_run: false,
-        session: None,
-        timeout: 30,
-        exact: false,
-    };
+
+    // Create the search request
+    let options = SearchRequest::new(query)
+        .path(temp_path)
+        .reranker("hybrid")
+        .allow_tests(true)
+        .no_merge(true);
 
     // Run the search
     let search_results = perform_probe(&options).unwrap();
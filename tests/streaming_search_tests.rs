@@ -0,0 +1,128 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use tempfile::TempDir;
+
+use probe::search::{perform_probe_with_events, SearchEvent, SearchProgress, SearchRequest};
+
+/// Create a few files, two of which match "session"
+fn create_test_files(root: &Path) {
+    for name in ["login", "logout"] {
+        fs::write(
+            root.join(format!("{name}.rs")),
+            format!(
+                r#"
+pub fn {name}_session(token: &str) -> String {{
+    token.to_string()
+}}
+"#
+            ),
+        )
+        .unwrap();
+    }
+    fs::write(root.join("other.rs"), "pub fn unrelated() {}\n").unwrap();
+}
+
+#[derive(Debug)]
+enum Recorded {
+    Progress(SearchProgress),
+    FileResults(String, usize, SearchProgress),
+    Ranked(usize),
+}
+
+#[test]
+fn test_search_events_arrive_in_order() {
+    let temp_dir = TempDir::new().unwrap();
+    create_test_files(temp_dir.path());
+
+    let paths = vec![temp_dir.path().to_path_buf()];
+    let options = SearchRequest::new("session")
+        .paths(&paths)
+        .exclude_filenames(true)
+        .max_results(1)
+        .allow_tests(true)
+        .no_merge(true);
+
+    let mut events = Vec::new();
+    let limited = perform_probe_with_events(&options, &mut |event| {
+        events.push(match event {
+            SearchEvent::Progress(progress) => Recorded::Progress(*progress),
+            SearchEvent::FileResults {
+                file,
+                results,
+                progress,
+            } => Recorded::FileResults(file.to_string(), results.len(), *progress),
+            SearchEvent::Ranked(limited) => Recorded::Ranked(limited.results.len()),
+        })
+    })
+    .unwrap();
+
+    // Scanning reports every searched file and the two matches
+    let Recorded::Progress(first) = &events[0] else {
+        panic!("Expected a progress event first, got {:?}", events[0]);
+    };
+    assert_eq!(first.files_scanned, 3);
+    assert_eq!(first.files_matched, 2);
+
+    // Both matching files stream their unranked candidates before the final batch
+    let file_events: Vec<_> = events
+        .iter()
+        .filter_map(|event| match event {
+            Recorded::FileResults(file, count, progress) => Some((file, *count, *progress)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(file_events.len(), 2);
+    assert!(file_events.iter().all(|(file, count, _)| {
+        (file.ends_with("login.rs") || file.ends_with("logout.rs")) && *count > 0
+    }));
+    let last_progress = file_events[1].2;
+    assert_eq!(last_progress.files_processed, 2);
+    assert_eq!(
+        last_progress.blocks_extracted,
+        file_events.iter().map(|(_, count, _)| count).sum::<usize>()
+    );
+
+    // The ranked batch comes last and honours the limits
+    assert!(matches!(events.last(), Some(Recorded::Ranked(1))));
+    assert_eq!(limited.results.len(), 1);
+}
+
+#[test]
+fn test_cli_stream_outputs_json_lines() {
+    let temp_dir = TempDir::new().unwrap();
+    create_test_files(temp_dir.path());
+
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "search",
+            "session",
+            temp_dir.path().to_str().unwrap(),
+            "--stream",
+            // Temp dir names can match the query by filename
+            "--exclude-filenames",
+        ])
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+
+    let events: Vec<serde_json::Value> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("Every line should be JSON"))
+        .collect();
+
+    assert_eq!(events[0]["event"], "progress");
+    assert_eq!(
+        events
+            .iter()
+            .filter(|event| event["event"] == "file_results")
+            .count(),
+        2
+    );
+    let ranked = events.last().unwrap();
+    assert_eq!(ranked["event"], "ranked");
+    assert_eq!(ranked["summary"]["count"], 2);
+    assert_eq!(ranked["timed_out"], false);
+}
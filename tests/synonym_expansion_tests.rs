@@ -0,0 +1,160 @@
+mod common;
+
+use std::fs;
+use tempfile::TempDir;
+
+use common::{create_project, file_name, search_request, write_files};
+use probe::config::{configure_search, ConfigOverrides, ProjectConfig};
+use probe::search::synonyms::SynonymDictionary;
+use probe::{perform_probe, SearchRequest, SearchResult};
+
+/// Files that spell the same concepts as abbreviations and in full
+fn create_test_project() -> TempDir {
+    create_project(&[
+        (
+            "settings.rs",
+            "fn load_cfg(path: &str) -> Settings {\n    Settings::read(path)\n}\n",
+        ),
+        (
+            "loader.rs",
+            "fn load_config(path: &str) -> Settings {\n    Settings::parse(path)\n}\n",
+        ),
+        (
+            "ledger.rs",
+            "fn ledger_total(entries: &[Entry]) -> u64 {\n    entries.len() as u64\n}\n",
+        ),
+    ])
+}
+
+fn search(
+    temp_dir: &TempDir,
+    query: &str,
+    synonyms: Option<SynonymDictionary>,
+) -> Vec<SearchResult> {
+    let mut request = SearchRequest::new(query).path(temp_dir.path());
+    if let Some(synonyms) = synonyms {
+        request = request.synonyms(synonyms);
+    }
+    search_request(request)
+}
+
+#[test]
+fn test_abbreviation_matches_full_word() {
+    let temp_dir = create_test_project();
+    let builtin = SynonymDictionary::builtin().clone();
+
+    let without = search(&temp_dir, "cfg", None);
+    assert_eq!(without.len(), 1);
+
+    let with = search(&temp_dir, "cfg", Some(builtin.clone()));
+    let mut files: Vec<&str> = with.iter().map(file_name).collect();
+    files.sort();
+    assert_eq!(files, vec!["loader.rs", "settings.rs"]);
+
+    // Synonyms are down-weighted, so the word as written ranks first either way
+    assert_eq!(file_name(&with[0]), "settings.rs");
+    let config = search(&temp_dir, "config", Some(builtin));
+    assert_eq!(config.len(), 2);
+    assert_eq!(file_name(&config[0]), "loader.rs");
+}
+
+#[test]
+fn test_required_words_are_not_expanded() {
+    let temp_dir = create_test_project();
+    let results = search(
+        &temp_dir,
+        "+cfg",
+        Some(SynonymDictionary::builtin().clone()),
+    );
+    assert_eq!(results.len(), 1);
+    assert_eq!(file_name(&results[0]), "settings.rs");
+}
+
+#[test]
+fn test_synonyms_file_from_config() {
+    let temp_dir = create_test_project();
+    fs::write(temp_dir.path().join("synonyms.txt"), "journal = ledger\n").unwrap();
+    fs::write(
+        temp_dir.path().join(".probe.json"),
+        r#"{"synonyms": {"builtin": false, "file": "synonyms.txt"}}"#,
+    )
+    .unwrap();
+
+    let config = ProjectConfig::for_path(temp_dir.path()).unwrap();
+    let dictionary = config.synonym_dictionary(None, false).unwrap();
+    assert_eq!(dictionary.expansions("journal"), ["ledger"]);
+    assert!(dictionary.expansions("cfg").is_empty());
+
+    let results = search(&temp_dir, "journal", Some(dictionary));
+    assert_eq!(results.len(), 1);
+    assert_eq!(file_name(&results[0]), "ledger.rs");
+}
+
+#[test]
+fn test_configure_search_applies_project_config() {
+    let temp_dir = create_test_project();
+    let request = SearchRequest::new("cfg")
+        .path(temp_dir.path())
+        .exclude_filenames(true);
+
+    // Built-in synonyms are opt-in, in the CLI and servers alike
+    let configured = configure_search(request.clone(), &ConfigOverrides::default()).unwrap();
+    assert!(configured.synonyms.unwrap().expansions("cfg").is_empty());
+    let overrides = ConfigOverrides {
+        builtin_synonyms: true,
+        ..Default::default()
+    };
+    let configured = configure_search(request.clone(), &overrides).unwrap();
+    assert_eq!(perform_probe(&configured).unwrap().results.len(), 2);
+
+    let overrides = ConfigOverrides {
+        no_synonyms: true,
+        bm25_k1: Some(0.8),
+        ..Default::default()
+    };
+    let configured = configure_search(request.clone(), &overrides).unwrap();
+    assert!(configured.synonyms.is_none());
+    assert_eq!(configured.ranking.bm25.k1, 0.8);
+
+    fs::write(
+        temp_dir.path().join(".probe.json"),
+        r#"{"synonyms": {"builtin": true}, "vocabulary": {"exception_terms": ["ledgers"]}}"#,
+    )
+    .unwrap();
+    let configured = configure_search(request, &ConfigOverrides::default()).unwrap();
+    assert!(!configured.synonyms.unwrap().expansions("cfg").is_empty());
+    assert_eq!(
+        configured.vocabulary.unwrap().exception_terms,
+        vec!["ledgers"]
+    );
+}
+
+#[test]
+fn test_file_names_match_the_query_as_written() {
+    let temp_dir = create_test_project();
+    write_files(
+        temp_dir.path(),
+        &[
+            ("src/widgets.rs", "fn draw_widget() {}\n"),
+            (
+                "reader.rs",
+                "fn read_source(path: &str) -> String {\n    path.to_string()\n}\n",
+            ),
+        ],
+    );
+    assert!(SynonymDictionary::builtin()
+        .expansions("source")
+        .contains(&"src".to_string()));
+
+    // `source` is expanded to `src` for the file contents only, so `src/` is no file name hit
+    let request = SearchRequest::new("source")
+        .path(temp_dir.path())
+        .synonyms(SynonymDictionary::builtin().clone())
+        .explain(true);
+    let results = perform_probe(&request).unwrap().results;
+    assert!(!results.is_empty());
+    assert!(results.iter().all(|r| !r.file.ends_with("widgets.rs")));
+    assert!(results
+        .iter()
+        .all(|r| r.explanation.as_ref().unwrap().query == "source"));
+}
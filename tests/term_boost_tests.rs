@@ -0,0 +1,70 @@
+mod common;
+
+use tempfile::TempDir;
+
+use common::{create_project, file_name, search};
+use probe::search::format_search_results;
+use probe::search::query::create_query_plan;
+use probe::SearchResult;
+
+/// One file about tokens and one about oauth, each mentioning its word equally often
+fn create_test_project() -> TempDir {
+    create_project(&[
+        (
+            "tokens.rs",
+            "fn rotate_token(token: &str) -> String {\n    format!(\"{}-next\", token)\n}\n",
+        ),
+        (
+            "provider.rs",
+            "fn oauth_client(oauth: &Config) -> Client {\n    Client::new(oauth)\n}\n",
+        ),
+    ])
+}
+
+fn top_file(results: &[SearchResult]) -> &str {
+    file_name(&results[0])
+}
+
+#[test]
+fn test_boost_changes_ranking() {
+    let temp_dir = create_test_project();
+
+    let oauth_boosted = search(&temp_dir, "oauth^3 token");
+    assert_eq!(oauth_boosted.len(), 2);
+    assert_eq!(top_file(&oauth_boosted), "provider.rs");
+
+    let token_boosted = search(&temp_dir, "oauth token^3");
+    assert_eq!(top_file(&token_boosted), "tokens.rs");
+}
+
+#[test]
+fn test_boost_multiplies_score() {
+    let temp_dir = create_test_project();
+    let score = |query: &str| {
+        search(&temp_dir, query)
+            .into_iter()
+            .find(|r| r.file.ends_with("provider.rs"))
+            .and_then(|r| r.bm25_score)
+            .unwrap()
+    };
+
+    let plain = score("oauth");
+    let boosted = score("oauth^2.5");
+    assert!((boosted - plain * 2.5).abs() < 1e-9);
+}
+
+#[test]
+fn test_boost_in_query_plan_output() {
+    let plan = create_query_plan("oauth^3 token", false).unwrap();
+
+    let json = format_search_results(&[], false, "json", Some(&plan));
+    let document: serde_json::Value = serde_json::from_str(&json).unwrap();
+    let terms = document["query_plan"]["terms"].as_array().unwrap();
+    assert_eq!(terms[0]["keywords"][0], "oauth");
+    assert_eq!(terms[0]["boost"], 3.0);
+    assert_eq!(terms[1]["boost"], 1.0);
+    assert_eq!(document["query_plan"]["query"], "(oauth^3 OR token)");
+
+    let xml = format_search_results(&[], false, "xml", Some(&plan));
+    assert!(xml.contains("<term required=\"false\" excluded=\"false\" boost=\"3\">oauth</term>"));
+}
@@ -1,8 +1,10 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::time::Instant;
 use tempfile::TempDir;
 
+use probe::search::{perform_probe, SearchRequest};
+
 /// Create a large test file that will take time to process
 fn create_large_test_file(temp_dir: &Path) {
     let file_path = temp_dir.join("large_file.rs");
@@ -23,8 +25,8 @@ fn create_large_test_file(temp_dir: &Path) {
 }
 
 /// Test that the search operation times out after the specified timeout
-/// This test runs the search command in a separate process since our timeout
-/// implementation calls std::process::exit(1)
+/// This test runs the search command in a separate process since the CLI
+/// exits with a non-zero code after printing the partial results
 #[test]
 fn test_search_timeout() {
     // Create a temporary directory
@@ -82,3 +84,41 @@ fn test_search_timeout() {
 
     println!("✓ Search timed out correctly after {:?}", elapsed);
 }
+
+fn search_with_timeout(path: &Path, timeout: u64) -> probe::LimitedSearchResults {
+    let paths = [path.to_path_buf()];
+
+    let options = SearchRequest::new("search_term")
+        .paths(&paths)
+        .exclude_filenames(true)
+        .allow_tests(true)
+        .no_merge(true)
+        .timeout(timeout);
+
+    perform_probe(&options).expect("search should not fail on timeout")
+}
+
+/// Test that a timeout inside the library returns (partial) results instead of
+/// terminating the calling process
+#[test]
+fn test_library_timeout_returns_partial_results() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    for i in 0..200 {
+        let file: PathBuf = temp_dir.path().join(format!("file_{}.rs", i));
+        std::fs::write(
+            file,
+            format!("fn function_{}() {{\n    let search_term = {};\n}}\n", i, i),
+        )
+        .unwrap();
+    }
+
+    // A zero-second timeout cancels the search right away
+    let timed_out = search_with_timeout(temp_dir.path(), 0);
+    assert!(timed_out.timed_out);
+    assert!(timed_out.results.len() < 200);
+
+    // We are still running, and a search with enough time completes normally
+    let complete = search_with_timeout(temp_dir.path(), 30);
+    assert!(!complete.timed_out);
+    assert_eq!(complete.results.len(), 200);
+}
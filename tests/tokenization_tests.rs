@@ -21,7 +21,7 @@ fn test_camel_case_splitting() {
 fn test_compound_word_splitting() {
     // Test compound word splitting with "whitelist"
     let vocab = load_vocabulary();
-    let mut enhanced_vocab = vocab.clone();
+    let mut enhanced_vocab = (*vocab).clone();
 
     // Add specific programming terms to the vocabulary
     for term in [
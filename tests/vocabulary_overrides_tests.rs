@@ -0,0 +1,122 @@
+mod common;
+
+use tempfile::TempDir;
+
+use common::{create_project, files, search_request};
+use probe::config::ProjectConfig;
+use probe::search::tokenization::{is_stop_word, tokenize, with_vocabulary, VocabularyOverrides};
+use probe::SearchRequest;
+
+fn create_test_project() -> TempDir {
+    create_project(&[
+        (
+            "router.rs",
+            "fn route_multitenant(request: Request) {\n    dispatch(request)\n}\n",
+        ),
+        (
+            "tenants.rs",
+            "fn tenant_lookup(id: u64) -> Tenant {\n    find(id)\n}\n",
+        ),
+        (
+            "types.rs",
+            "fn parse_type(input: &str) -> Kind {\n    classify(input)\n}\n",
+        ),
+    ])
+}
+
+fn search(temp_dir: &TempDir, query: &str, vocabulary: &VocabularyOverrides) -> Vec<String> {
+    files(&search_request(
+        SearchRequest::new(query)
+            .path(temp_dir.path())
+            .vocabulary(vocabulary.clone()),
+    ))
+}
+
+fn compound_vocabulary() -> VocabularyOverrides {
+    let config: ProjectConfig = serde_json::from_str(
+        r#"{"vocabulary": {"compound_words": ["multi", "tenant"], "not_stop_words": ["type"]}}"#,
+    )
+    .unwrap();
+    config.vocabulary
+}
+
+#[test]
+fn test_default_vocabulary() {
+    let temp_dir = create_test_project();
+    let defaults = VocabularyOverrides::default();
+    assert_eq!(search(&temp_dir, "tenant", &defaults), vec!["tenants.rs"]);
+    assert!(search(&temp_dir, "type", &defaults).is_empty());
+    assert_eq!(tokenize("ledgers"), vec!["ledger"]);
+}
+
+#[test]
+fn test_compound_words_and_not_stop_words() {
+    let temp_dir = create_test_project();
+    let vocabulary = compound_vocabulary();
+
+    // Compound words are split into the added words, in queries and code alike
+    with_vocabulary(&vocabulary, || {
+        assert_eq!(tokenize("multitenant"), vec!["multi", "tenant"]);
+        // Built-in stop words can be searched for
+        assert!(!is_stop_word("type"));
+        assert!(is_stop_word("func"));
+    });
+    assert_eq!(
+        search(&temp_dir, "tenant", &vocabulary),
+        vec!["router.rs", "tenants.rs"]
+    );
+    assert_eq!(search(&temp_dir, "type", &vocabulary), vec!["types.rs"]);
+
+    // Only the search given the overrides uses them
+    assert!(is_stop_word("type"));
+    assert_eq!(tokenize("multitenant"), vec!["multiten"]);
+}
+
+#[test]
+fn test_stop_words_and_exception_terms() {
+    // Added stop words are ignored, and exception terms are kept whole and unstemmed
+    let vocabulary = VocabularyOverrides {
+        stop_words: vec!["Tenant".to_string()],
+        exception_terms: vec!["ledgers".to_string()],
+        ..Default::default()
+    };
+    with_vocabulary(&vocabulary, || {
+        assert!(is_stop_word("tenant"));
+        assert!(tokenize("tenant lookup")
+            .iter()
+            .all(|token| token != "tenant"));
+        assert_eq!(tokenize("ledgers"), vec!["ledgers", "ledger"]);
+    });
+    assert_eq!(tokenize("ledgers"), vec!["ledger"]);
+}
+
+#[test]
+fn test_concurrent_searches_keep_their_vocabularies() {
+    let temp_dir = create_test_project();
+    let vocabulary = compound_vocabulary();
+    std::thread::scope(|scope| {
+        let with_overrides = scope.spawn(|| {
+            (0..5)
+                .map(|_| search(&temp_dir, "tenant", &vocabulary))
+                .collect::<Vec<_>>()
+        });
+        let with_defaults = scope.spawn(|| {
+            (0..5)
+                .map(|_| search(&temp_dir, "tenant", &VocabularyOverrides::default()))
+                .collect::<Vec<_>>()
+        });
+        for files in with_overrides.join().unwrap() {
+            assert_eq!(files, vec!["router.rs", "tenants.rs"]);
+        }
+        for files in with_defaults.join().unwrap() {
+            assert_eq!(files, vec!["tenants.rs"]);
+        }
+    });
+}
+
+#[test]
+fn test_unknown_vocabulary_keys_are_rejected() {
+    assert!(
+        serde_json::from_str::<ProjectConfig>(r#"{"vocabulary": {"stopwords": ["a"]}}"#).is_err()
+    );
+}